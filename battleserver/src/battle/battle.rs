@@ -20,27 +20,32 @@ use crate::task_timer::{Task, TaskCmd};
 use crossbeam::channel::Sender;
 use log::error;
 use std::borrow::BorrowMut;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
 use tools::protos::base::{ActionUnitPt, SummaryDataPt};
+use tools::rating::Rating;
 use tools::templates::skill_temp::SkillTemp;
+use tools::templates::tile_map_temp::TileMapTemp;
 
 use super::battle_cter::BattleCharacter;
 use super::battle_enum::skill_type::{ORDER_MINON_ATTACK, SUMMON_MINON, TRANSFORM};
 use super::battle_enum::BattlePlayerState;
 use super::battle_player::BattlePlayer;
+use super::battle_record::BattleRecord;
+use super::battle_rng::BattleRng;
 use super::battle_skill::{order_minon_attack, summon_minon, transform};
 
 ///物品结构体
 #[derive(Clone, Debug)]
 pub struct Item {
-    pub id: u32,                        //物品id
-    pub skill_temp: &'static SkillTemp, //物品带的技能
+    pub id: u32,                    //物品id
+    pub skill_temp: Arc<SkillTemp>, //物品带的技能
 }
 
 ///方向结构体,用于aoe技能范围计算
 #[derive(Debug, Clone)]
 pub struct Direction {
-    pub direction: &'static Vec<isize>,
+    pub direction: Vec<isize>,
 }
 
 ///技能函数指针map,返回结果数组中的元组
@@ -122,12 +127,15 @@ pub struct BattleData {
     pub next_turn_index: usize,                    //下个turn的下标,从0-4循环
     pub turn_orders: [u32; MEMBER_MAX],            //turn行动队列，里面放玩家id
     pub reflash_map_turn: Option<usize>,           //刷新地图时的turn下标
-    pub battle_player: HashMap<u32, BattlePlayer>, //玩家战斗数据
-    pub cter_player: HashMap<u32, u32>,            //角色对应玩家,cter_id user_id
+    pub battle_player: BTreeMap<u32, BattlePlayer>, //玩家战斗数据
+    pub cter_player: BTreeMap<u32, u32>,            //角色对应玩家,cter_id user_id
     pub summary_vec: Vec<Vec<SummaryUser>>,        //排名  user_id
     pub summary_vec_temp: Vec<SummaryUser>,        //同一批挂掉的人
     pub leave_user: (u32, bool),                   //离开玩家id,是否惩罚
     pub leave_map: HashMap<u32, i8>,               //段位快照
+    pub rating_map: HashMap<u32, Rating>,          //赛前评分快照,只有匹配房的真人,结算都按这个算
+    pub new_ratings: HashMap<u32, Rating>,         //结算后的评分
+    pub rating_time: i64,                          //评分快照时间,结算都按这个时间算
     pub turn_limit_time: u64,                      //战斗turn时间限制
     pub turn: u32,                                 //turn
     pub round: u16,                                //round
@@ -140,6 +148,9 @@ pub struct BattleData {
     pub tcp_sender: Sender<Vec<u8>>,               //sender
     pub cter_id: u32,                              //角色id动态id,从101开始
     pub state: RoomState,
+    pub rng: BattleRng,                      //战斗随机数生成器
+    pub record: BattleRecord,                //战斗录像
+    pub fixed_tile_map: Option<TileMapTemp>, //指定地图(模拟战斗用),为None则随机
 }
 
 tools::get_mut_ref!(BattleData);
@@ -255,8 +266,61 @@ impl BattleData {
         }
     }
 
+    ///按评分计算同一批死掉的玩家的积分变化
+    ///先死的名次比自己差算赢，同一批死的算平，还活着的算输
+    ///每一批都按赛前评分快照算，结果放到new_ratings，不影响后面批次的结算
+    pub fn calc_rating_scores(&mut self, batch: &[u32]) -> HashMap<u32, i32> {
+        let mut res = HashMap::new();
+        let conf = crate::rating_conf();
+        let now = self.rating_time;
+        //机器人按真人平均分算，偏差取最大，对结果影响最小
+        let mut robot_rating = Rating::new(0, &conf);
+        if !self.rating_map.is_empty() {
+            let sum: f64 = self.rating_map.values().map(|x| x.rating).sum();
+            robot_rating.rating = sum / self.rating_map.len() as f64;
+        }
+        robot_rating.deviation = conf.max_deviation;
+
+        for user_id in batch.iter() {
+            let rating = self.rating_map.get(user_id);
+            if rating.is_none() {
+                continue;
+            }
+            let rating = rating.unwrap();
+            let mut opponents = vec![];
+            for other_id in self.leave_map.keys() {
+                if other_id == user_id {
+                    continue;
+                }
+                let outcome;
+                if batch.contains(other_id) {
+                    outcome = 0.5;
+                } else if self
+                    .summary_vec
+                    .iter()
+                    .flatten()
+                    .any(|x| x.user_id == *other_id)
+                {
+                    outcome = 1_f64;
+                } else {
+                    outcome = 0_f64;
+                }
+                let other = match self.rating_map.get(other_id) {
+                    Some(other) => *other,
+                    None => robot_rating,
+                };
+                opponents.push((other, outcome));
+            }
+            let new_rating = rating.update(&conf, opponents.as_slice(), now);
+            res.insert(*user_id, new_rating.get_score() - rating.get_score());
+            self.new_ratings.insert(*user_id, new_rating);
+        }
+        res
+    }
+
     pub fn get_world_boss_ref(&self) -> Option<&BattlePlayer> {
-        let worldboss_temps = &crate::TEMPLATES.worldboss_temp_mgr().temps;
+        let temps = crate::TEMPLATES.load();
+        let worldboss_temps = &temps.worldboss_temp_mgr().temps;
         self.battle_player
             .values()
             .find(|x| x.is_robot() && worldboss_temps.contains_key(&x.get_cter_temp_id()))
@@ -407,12 +471,15 @@ impl BattleData {
             next_turn_index: 0,
             turn_orders: [0; MEMBER_MAX],
             reflash_map_turn: None,
-            battle_player: HashMap::new(),
-            cter_player: HashMap::new(),
+            battle_player: BTreeMap::new(),
+            cter_player: BTreeMap::new(),
             summary_vec: v,
             summary_vec_temp: Vec::new(),
             leave_user: (0, false),
             leave_map: HashMap::new(),
+            rating_map: HashMap::new(),
+            new_ratings: HashMap::new(),
+            rating_time: 0,
             turn_limit_time: 60000, //默认一分钟
             turn: 0,
             round: 0,
@@ -425,6 +492,9 @@ impl BattleData {
             tcp_sender,
             cter_id: 100,
             state: RoomState::ChoiceIndex,
+            rng: BattleRng::default(),
+            record: BattleRecord::default(),
+            fixed_tile_map: None,
         };
 
         //初始化函数指针，封装到map里
@@ -646,3 +716,49 @@ impl BattleData {
         v
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_battle_data() -> BattleData {
+        let (task_sender, _) = crossbeam::channel::unbounded();
+        let (tcp_sender, _) = crossbeam::channel::unbounded();
+        let mut battle_data =
+            BattleData::new(RoomType::OneVOneVOneVOneMatch, task_sender, tcp_sender);
+        let conf = crate::rating_conf();
+        for (user_id, score) in [(1, 1000), (2, 1200), (3, 1400), (4, 1600)].iter() {
+            battle_data.leave_map.insert(*user_id, 0);
+            battle_data
+                .rating_map
+                .insert(*user_id, Rating::new(*score, &conf));
+        }
+        battle_data
+    }
+
+    #[test]
+    fn rating_scores_use_pre_match_snapshot() {
+        let mut battle_data = new_battle_data();
+        let snapshot = battle_data.rating_map.clone();
+        let first = battle_data.calc_rating_scores(&[1]);
+        let mut su = SummaryUser::default();
+        su.user_id = 1;
+        battle_data.summary_vec.push(vec![su.clone()]);
+        let second = battle_data.calc_rating_scores(&[2, 3]);
+        assert_eq!(battle_data.rating_map.len(), snapshot.len());
+        for (user_id, rating) in snapshot.iter() {
+            assert_eq!(
+                battle_data.rating_map.get(user_id).unwrap().rating,
+                rating.rating
+            );
+        }
+        assert!(first[&1] < 0);
+        assert_eq!(battle_data.new_ratings.len(), 3);
+
+        //后面批次的结算和前面批次有没有结算过无关
+        let mut other = new_battle_data();
+        other.summary_vec.push(vec![su]);
+        let res = other.calc_rating_scores(&[2, 3]);
+        assert_eq!(res, second);
+    }
+}
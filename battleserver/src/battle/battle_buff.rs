@@ -11,6 +11,7 @@ use crate::TEMPLATES;
 use log::{error, warn};
 use std::collections::HashSet;
 use std::convert::TryFrom;
+use std::sync::Arc;
 use tools::protos::base::{ActionUnitPt, EffectPt, TargetPt, TriggerEffectPt};
 use tools::templates::buff_temp::BuffTemp;
 
@@ -23,7 +24,7 @@ use super::battle_enum::DamageType;
 pub struct Buff {
     id: u32,
     pub function_id: u32, //功能id
-    pub buff_temp: &'static BuffTemp,
+    pub buff_temp: Arc<BuffTemp>,
     pub trigger_timesed: i8,           //已经触发过的次数
     pub keep_times: i8,                //剩余持续轮数
     pub scope: Vec<Direction>,         //buff的作用范围
@@ -36,7 +37,7 @@ pub struct Buff {
 
 impl Buff {
     pub fn new(
-        temp: &'static BuffTemp,
+        temp: &Arc<BuffTemp>,
         turn_index: Option<usize>,
         from_cter: Option<u32>,
         from_skill: Option<u32>,
@@ -71,14 +72,14 @@ impl Buff {
     }
 }
 
-impl From<&'static BuffTemp> for Buff {
-    fn from(bt: &'static BuffTemp) -> Self {
+impl From<&Arc<BuffTemp>> for Buff {
+    fn from(bt: &Arc<BuffTemp>) -> Self {
         let mut b = Buff {
             id: bt.id,
             function_id: bt.function_id,
             trigger_timesed: bt.trigger_times as i8,
             keep_times: bt.keep_time as i8,
-            buff_temp: bt,
+            buff_temp: bt.clone(),
             scope: Vec::new(),
             permanent: bt.keep_time == 0 && bt.trigger_times == 0,
             from_cter: None,
@@ -87,12 +88,13 @@ impl From<&'static BuffTemp> for Buff {
             trap_view_users: HashSet::new(),
         };
         let mut v = Vec::new();
-        let scope_temp = TEMPLATES.skill_scope_temp_mgr().get_temp(&bt.scope);
+        let temps = TEMPLATES.load();
+        let scope_temp = temps.skill_scope_temp_mgr().get_temp(&bt.scope);
         if let Ok(scope_temp) = scope_temp {
             if !scope_temp.scope.is_empty() {
                 for direction in scope_temp.scope.iter() {
                     let dir = Direction {
-                        direction: &direction.direction,
+                        direction: direction.direction.clone(),
                     };
                     v.push(dir);
                 }
@@ -113,7 +115,8 @@ impl BattleData {
         last_map_cell_cter_id: u32,
         au: &mut ActionUnitPt,
     ) {
-        let buff_temp = TEMPLATES.buff_temp_mgr().get_temp(&buff_id);
+        let temps = TEMPLATES.load();
+        let buff_temp = temps.buff_temp_mgr().get_temp(&buff_id);
         if let Err(e) = buff_temp {
             error!("{:?}", e);
             return;
@@ -156,13 +159,14 @@ impl BattleData {
     ) {
         let next_turn_index = self.next_turn_index;
         let mut target_pt = TargetPt::new();
-        let buff_temp = TEMPLATES.buff_temp_mgr().get_temp(&buff_id);
+        let temps = TEMPLATES.load();
+        let buff_temp = temps.buff_temp_mgr().get_temp(&buff_id);
         if let Err(e) = buff_temp {
             error!("{:?}", e);
             return;
         }
         let buff_temp = buff_temp.unwrap();
-        let new_buff_temp = TEMPLATES.buff_temp_mgr().get_temp(&buff_temp.par1);
+        let new_buff_temp = temps.buff_temp_mgr().get_temp(&buff_temp.par1);
         if let Err(e) = new_buff_temp {
             error!("{:?}", e);
             return;
@@ -208,7 +212,8 @@ impl BattleData {
 
     ///给附近的人添加技能cd
     fn near_add_cd(&mut self, cter_id: u32, index: u32, buff_id: u32, au: &mut ActionUnitPt) {
-        let buff_temp = TEMPLATES.buff_temp_mgr().get_temp(&buff_id);
+        let temps = TEMPLATES.load();
+        let buff_temp = temps.buff_temp_mgr().get_temp(&buff_id);
         if let Err(e) = buff_temp {
             error!("{:?}", e);
             return;
@@ -256,14 +261,15 @@ impl BattleData {
 
     ///附近造成技能伤害
     fn near_skill_damage(&mut self, cter_id: u32, index: u32, buff_id: u32, au: &mut ActionUnitPt) {
-        let buff_temp = TEMPLATES.buff_temp_mgr().get_temp(&buff_id);
+        let temps = TEMPLATES.load();
+        let buff_temp = temps.buff_temp_mgr().get_temp(&buff_id);
         if let Err(e) = buff_temp {
             error!("{:?}", e);
             return;
         }
         let buff_temp = buff_temp.unwrap();
 
-        let scope_temp = TEMPLATES.skill_scope_temp_mgr().get_temp(&buff_temp.scope);
+        let scope_temp = temps.skill_scope_temp_mgr().get_temp(&buff_temp.scope);
         if let Err(e) = scope_temp {
             error!("{:?}", e);
             return;
@@ -337,7 +343,8 @@ impl BattleData {
         let mut target_pt = TargetPt::new();
         target_pt.target_value.push(cter_index);
         let mut tep = TriggerEffectPt::new();
-        let buff_temp = TEMPLATES.buff_temp_mgr().get_temp(&buff_id);
+        let temps = TEMPLATES.load();
+        let buff_temp = temps.buff_temp_mgr().get_temp(&buff_id);
         if let Err(e) = buff_temp {
             warn!("{:?}", e);
             return;
@@ -419,7 +426,8 @@ impl BattleData {
             }
         }
 
-        let buff_temp = TEMPLATES.buff_temp_mgr().get_temp(&buff_id);
+        let temps = TEMPLATES.load();
+        let buff_temp = temps.buff_temp_mgr().get_temp(&buff_id);
         if let Err(e) = buff_temp {
             error!("{:?}", e);
             return;
@@ -451,7 +459,8 @@ impl BattleData {
         is_pair: bool,
         au: &mut ActionUnitPt,
     ) {
-        let buff_temp = TEMPLATES.buff_temp_mgr().get_temp(&buff_id);
+        let temps = TEMPLATES.load();
+        let buff_temp = temps.buff_temp_mgr().get_temp(&buff_id);
         if let Err(e) = buff_temp {
             error!("{:?}", e);
             return;
@@ -1,5 +1,5 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     convert::TryFrom,
 };
 
@@ -133,7 +133,7 @@ pub struct BattleCharacter {
     pub index_data: IndexData,                             //角色位置数据
     pub state: BattleCterState,                            //角色状态
     pub revenge_user_id: u32,                              //复仇角色
-    pub skills: HashMap<u32, Skill>,                       //玩家选择的主动技能id
+    pub skills: BTreeMap<u32, Skill>,                      //玩家选择的主动技能id
     pub items: HashMap<u32, Item>,                         //角色身上的道具
     pub self_transform_cter: Option<Box<BattleCharacter>>, //自己变身的角色
     pub self_cter: Option<Box<BattleCharacter>>,           //原本的角色
@@ -152,8 +152,9 @@ impl BattleCharacter {
         let mut battle_cter = BattleCharacter::default();
         let cter_temp_id = cter.cter_temp_id;
 
-        let skill_ref = TEMPLATES.skill_temp_mgr();
-        let buff_ref = TEMPLATES.buff_temp_mgr();
+        let temps = TEMPLATES.load();
+        let skill_ref = temps.skill_temp_mgr();
+        let buff_ref = temps.buff_temp_mgr();
         for skill_id in cter.skills.iter() {
             let res = skill_ref.temps.get(skill_id);
             if res.is_none() {
@@ -168,7 +169,7 @@ impl BattleCharacter {
             let skill = Skill::from_skill_temp(skill_temp, true);
             battle_cter.skills.insert(*skill_id, skill);
         }
-        let cter_temp = TEMPLATES.character_temp_mgr().get_temp_ref(&cter_temp_id);
+        let cter_temp = temps.character_temp_mgr().get_temp_ref(&cter_temp_id);
         if cter_temp.is_none() {
             let str = format!("cter_temp is none for cter_id:{}!", cter_temp_id);
             warn!("{:?}", str.as_str());
@@ -206,7 +207,8 @@ impl BattleCharacter {
         index: usize,
         turn_index: usize,
     ) -> anyhow::Result<Self> {
-        let cter_temp = TEMPLATES.character_temp_mgr().get_temp_ref(&cter_temp_id);
+        let temps = TEMPLATES.load();
+        let cter_temp = temps.character_temp_mgr().get_temp_ref(&cter_temp_id);
         if cter_temp.is_none() {
             let str = format!("cter_temp is none for cter_id:{}!", cter_temp_id);
             warn!("{:?}", str.as_str());
@@ -215,8 +217,8 @@ impl BattleCharacter {
         let cter_temp = cter_temp.unwrap();
         let mut battle_cter = BattleCharacter::default();
 
-        let buff_ref = TEMPLATES.buff_temp_mgr();
-        let skill_ref = TEMPLATES.skill_temp_mgr();
+        let buff_ref = temps.buff_temp_mgr();
+        let skill_ref = temps.skill_temp_mgr();
         for skill_group in cter_temp.skills.iter() {
             for skill_id in skill_group.group.iter() {
                 let res = skill_ref.temps.get(skill_id);
@@ -368,7 +370,8 @@ impl BattleCharacter {
         self.state = BattleCterState::Alive;
         for skill_group in cter_temp.skills.iter() {
             for skill_id in skill_group.group.iter() {
-                let skill_temp = TEMPLATES.skill_temp_mgr().get_temp(&skill_id);
+                let temps = TEMPLATES.load();
+                let skill_temp = temps.skill_temp_mgr().get_temp(skill_id);
                 if let Err(e) = skill_temp {
                     warn!("{:?}", e);
                     continue;
@@ -379,7 +382,8 @@ impl BattleCharacter {
             }
         }
         cter_temp.passive_buff.iter().for_each(|buff_id| {
-            let buff_temp = TEMPLATES.buff_temp_mgr().get_temp(buff_id);
+            let temps = TEMPLATES.load();
+            let buff_temp = temps.buff_temp_mgr().get_temp(buff_id);
             if let Ok(buff_temp) = buff_temp {
                 let buff = Buff::from(buff_temp);
                 self.battle_buffs.init(buff);
@@ -493,7 +497,8 @@ impl BattleCharacter {
         buff_id: u32,
         turn_index: Option<usize>,
     ) {
-        let buff_temp = TEMPLATES.buff_temp_mgr().get_temp(&buff_id);
+        let temps = TEMPLATES.load();
+        let buff_temp = temps.buff_temp_mgr().get_temp(&buff_id);
         if let Err(e) = buff_temp {
             error!("{:?}", e);
             return;
@@ -568,7 +573,8 @@ impl BattleCharacter {
                 id if id == CHANGE_SKILL || CHARGE.contains(&id) => {
                     let skill_id = buff.buff_temp.par1;
 
-                    let skill_temp = TEMPLATES.skill_temp_mgr().temps.get(&skill_id);
+                    let temps = TEMPLATES.load();
+                    let skill_temp = temps.skill_temp_mgr().temps.get(&skill_id);
                     match skill_temp {
                         None => {
                             error!(
@@ -694,7 +700,8 @@ impl BattleCharacter {
         buff_id: u32,
         next_turn_index: Option<usize>,
     ) -> anyhow::Result<TargetPt> {
-        let cter_temp = TEMPLATES.character_temp_mgr().get_temp_ref(&cter_temp_id);
+        let temps = TEMPLATES.load();
+        let cter_temp = temps.character_temp_mgr().get_temp_ref(&cter_temp_id);
         if cter_temp.is_none() {
             anyhow::bail!("cter_temp can not find!cter_id:{}", cter_temp_id)
         }
@@ -717,7 +724,7 @@ impl BattleCharacter {
         self.transform_inherit(transform_inherits, transform_buff_inherits);
 
         //给新变身加变身buff
-        let buff_temp = TEMPLATES.buff_temp_mgr().get_temp(&buff_id);
+        let buff_temp = temps.buff_temp_mgr().get_temp(&buff_id);
         if let Err(e) = buff_temp {
             warn!("{:?}", e);
             anyhow::bail!("")
@@ -727,7 +734,7 @@ impl BattleCharacter {
 
         //添加变身附带的攻击buff
         let attack_buff_id = buff_temp.par1;
-        let attack_buff = TEMPLATES.buff_temp_mgr().get_temp(&attack_buff_id);
+        let attack_buff = temps.buff_temp_mgr().get_temp(&attack_buff_id);
         if let Ok(attack_buff) = attack_buff {
             let attack_buff_function_id = attack_buff.function_id;
             if ADD_ATTACK.contains(&attack_buff_function_id) {
@@ -788,7 +795,8 @@ pub fn transform_att_inherit_copy(
     battle_cter: &BattleCharacter,
     target_cter_temp_id: u32,
 ) -> Vec<TransformInherit> {
-    let target_cter_temp = crate::TEMPLATES
+    let temps = crate::TEMPLATES.load();
+    let target_cter_temp = temps
         .character_temp_mgr()
         .get_temp_ref(&target_cter_temp_id)
         .unwrap();
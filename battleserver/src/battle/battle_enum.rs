@@ -1,5 +1,6 @@
 use num_enum::IntoPrimitive;
 use num_enum::TryFromPrimitive;
+pub use tools::templates::cell_temp::ElementType;
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub enum DamageType {
@@ -475,20 +476,3 @@ impl TargetType {
         res
     }
 }
-
-///元素类型
-#[derive(Debug, Clone, Copy, Eq, PartialEq, TryFromPrimitive, IntoPrimitive)]
-#[repr(u8)]
-pub enum ElementType {
-    Nature = 1, //生命元素
-    Earth = 2,  //土元素
-    Water = 3,  //水元素
-    Fire = 4,   //火元素
-}
-
-impl ElementType {
-    pub fn into_u8(self) -> u8 {
-        let res: u8 = self.into();
-        res
-    }
-}
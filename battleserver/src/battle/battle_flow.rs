@@ -77,6 +77,14 @@ impl BattleData {
                     let smp: SummaryDataPt = su.clone().into();
                     ssn.summary_datas.push(smp.clone());
                     if !su.push_to_server && self.room_type.is_match_type() {
+                        //积分可能被段位保护改过，以积分为准保存评分
+                        if let Some(rating) = self.new_ratings.get_mut(&su.user_id) {
+                            rating.sync_score(su.league.score);
+                            //回放不能改线上的评分
+                            if !self.record.is_replay {
+                                crate::save_rating(su.user_id, rating);
+                            }
+                        }
                         let mut bg = B_S_SUMMARY::new();
                         bg.set_room_type(self.room_type.into_u32());
                         bg.set_summary_data(smp);
@@ -136,7 +144,8 @@ impl BattleData {
             let mut skill_s;
             let skill;
             if is_item {
-                let res = TEMPLATES.skill_temp_mgr().get_temp(&skill_id);
+                let temps = TEMPLATES.load();
+                let res = temps.skill_temp_mgr().get_temp(&skill_id);
                 if let Err(e) = res {
                     error!("{:?}", e);
                     anyhow::bail!("")
@@ -250,12 +259,13 @@ impl BattleData {
         target_v.push((target_cter_id, DamageType::Attack(0)));
         //检查aoebuff
         if let Some(buff) = aoe_buff {
-            let buff = TEMPLATES.buff_temp_mgr().get_temp(&buff);
+            let temps = TEMPLATES.load();
+            let buff = temps.buff_temp_mgr().get_temp(&buff);
             if let Err(e) = buff {
                 warn!("{:?}", e);
                 anyhow::bail!("")
             }
-            let scope_temp = TEMPLATES
+            let scope_temp = temps
                 .skill_scope_temp_mgr()
                 .get_temp(&TRIGGER_SCOPE_NEAR_TEMP_ID);
             if let Err(e) = scope_temp {
@@ -12,7 +12,6 @@ use crate::task_timer::{Task, TaskCmd};
 use crate::TEMPLATES;
 use log::{error, info, warn};
 use protobuf::Message;
-use rand::Rng;
 use std::borrow::BorrowMut;
 use std::collections::HashMap;
 use std::convert::TryFrom;
@@ -88,8 +87,9 @@ impl BattleData {
                                 match end_turn_fire_buff {
                                     Some(end_turn_fire_buff) => {
                                         let buff_id = end_turn_fire_buff.buff_temp.par1;
+                                        let temps = TEMPLATES.load();
                                         let buff_temp =
-                                            TEMPLATES.buff_temp_mgr().get_temp(&buff_id).unwrap();
+                                            temps.buff_temp_mgr().get_temp(&buff_id).unwrap();
                                         let buff_function_id = buff_temp.function_id;
                                         let mut buff = Buff::new(
                                             buff_temp,
@@ -159,7 +159,8 @@ impl BattleData {
 
         match from_type {
             FromType::Skill(skill_id) => {
-                let skill_temp = crate::TEMPLATES.skill_temp_mgr().get_temp(&skill_id);
+                let temps = crate::TEMPLATES.load();
+                let skill_temp = temps.skill_temp_mgr().get_temp(&skill_id);
                 if let Err(e) = skill_temp {
                     anyhow::bail!("{:?}", e)
                 }
@@ -169,7 +170,8 @@ impl BattleData {
                 battle_cter.skills.get_mut(&skill_id).unwrap().is_active = true;
             }
             FromType::Buff(buff_id) => {
-                let buff_temp = crate::TEMPLATES.buff_temp_mgr().get_temp(&buff_id).unwrap();
+                let temps = crate::TEMPLATES.load();
+                let buff_temp = temps.buff_temp_mgr().get_temp(&buff_id).unwrap();
                 minon_temp_id = buff_temp.par1;
                 minon_count = buff_temp.par2;
             }
@@ -199,7 +201,7 @@ impl BattleData {
             }
         }
 
-        let mut random = rand::thread_rng();
+        let random = &battle_data_mut.rng;
         loop {
             //如果满了就break
             if targets_res.len() == minon_count as usize {
@@ -600,7 +602,8 @@ impl BattleData {
         target_cter: &mut BattleCharacter,
         damage: i16,
     ) -> i16 {
-        let scope_temp = TEMPLATES
+        let temps = TEMPLATES.load();
+        let scope_temp = temps
             .skill_scope_temp_mgr()
             .get_temp(&TRIGGER_SCOPE_NEAR_TEMP_ID);
         if let Err(_) = scope_temp {
@@ -976,10 +979,8 @@ impl BattleData {
         map_cell.open_cter = cter_id;
         let element = map_cell.element as u32;
         let res;
-        let temp = crate::TEMPLATES
-            .constant_temp_mgr()
-            .temps
-            .get("reward_gold_open_cell");
+        let temps = crate::TEMPLATES.load();
+        let temp = temps.constant_temp_mgr().temps.get("reward_gold_open_cell");
         match temp {
             Some(temp) => {
                 let value = u32::from_str(temp.value.as_str());
@@ -1238,7 +1239,8 @@ impl BattleData {
 
     ///获取目标数组
     pub fn get_target_array(&self, cter_id: u32, skill_id: u32) -> anyhow::Result<Vec<usize>> {
-        let res = TEMPLATES.skill_temp_mgr().get_temp(&skill_id);
+        let temps = TEMPLATES.load();
+        let res = temps.skill_temp_mgr().get_temp(&skill_id);
         if let Err(_) = res {
             anyhow::bail!("could not find skill temp of {}", skill_id)
         }
@@ -1613,7 +1615,7 @@ impl BattleData {
         center_index: isize,
         target_type: TargetType,
         targets: Option<Vec<u32>>,
-        mut scope_temp: Option<&SkillScopeTemp>,
+        scope_temp: Option<&SkillScopeTemp>,
     ) -> (Vec<usize>, Vec<u32>) {
         let mut v_u = Vec::new();
         let mut v = Vec::new();
@@ -1623,8 +1625,10 @@ impl BattleData {
             return (Vec::new(), Vec::new());
         }
         let center_map_cell = center_map_cell.unwrap();
+        let temps = TEMPLATES.load();
+        let mut scope_temp = scope_temp;
         if targets.is_none() && scope_temp.is_none() {
-            let res = TEMPLATES
+            let res = temps
                 .skill_scope_temp_mgr()
                 .get_temp(&TRIGGER_SCOPE_NEAR_TEMP_ID);
             if let Err(e) = res {
@@ -1877,7 +1881,8 @@ impl BattleData {
         if skill_judge == 0 {
             return Ok(());
         }
-        let judge_temp = TEMPLATES.skill_judge_temp_mgr().get_temp(&skill_judge)?;
+        let temps = TEMPLATES.load();
+        let judge_temp = temps.skill_judge_temp_mgr().get_temp(&skill_judge)?;
         let target_type = TargetType::try_from(judge_temp.target);
         if let Err(e) = target_type {
             anyhow::bail!("{:?}", e)
@@ -2022,8 +2027,9 @@ impl BattleData {
         unsafe {
             //找范围
             let mut au = build_action_unit_pt(cter_id, ActionType::Buff, Some(buff_id));
-            let buff_temp = crate::TEMPLATES.buff_temp_mgr().get_temp(&buff_id).unwrap();
-            let scope_temp = crate::TEMPLATES
+            let temps = crate::TEMPLATES.load();
+            let buff_temp = temps.buff_temp_mgr().get_temp(&buff_id).unwrap();
+            let scope_temp = temps
                 .skill_scope_temp_mgr()
                 .get_temp(&buff_temp.par1)
                 .unwrap();
@@ -2094,17 +2100,15 @@ impl BattleData {
         let self_ptr = self as *mut BattleData;
         unsafe {
             let self_mut = self_ptr.as_mut().unwrap();
-            let buff_temp = crate::TEMPLATES.buff_temp_mgr().get_temp(&buff_id).unwrap();
+            let temps = crate::TEMPLATES.load();
+            let buff_temp = temps.buff_temp_mgr().get_temp(&buff_id).unwrap();
             let cter = self.get_battle_cter_mut(cter_id, true).unwrap();
             let minon_count = cter.minons.iter().count();
             if minon_count == 0 {
                 return;
             }
             let add_buff_id = buff_temp.par1;
-            let add_buff_temp = crate::TEMPLATES
-                .buff_temp_mgr()
-                .get_temp(&add_buff_id)
-                .unwrap();
+            let add_buff_temp = temps.buff_temp_mgr().get_temp(&add_buff_id).unwrap();
             //给宠物加
             for &id in cter.minons.iter() {
                 let minon = self_mut.get_battle_cter_mut(id, true);
@@ -2163,10 +2167,8 @@ impl BattleData {
             if let Some(old_skill) = old_skill {
                 let cd = old_skill.cd_times;
                 let is_active = old_skill.is_active;
-                let new_skill_temp = crate::TEMPLATES
-                    .skill_temp_mgr()
-                    .get_temp(&new_skill_id)
-                    .unwrap();
+                let temps = crate::TEMPLATES.load();
+                let new_skill_temp = temps.skill_temp_mgr().get_temp(&new_skill_id).unwrap();
                 let mut new_skill = Skill::from_skill_temp(new_skill_temp, true);
                 new_skill.cd_times = cd;
                 new_skill.is_active = is_active;
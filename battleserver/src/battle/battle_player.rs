@@ -12,8 +12,8 @@ use crate::room::member::Member;
 use crate::TEMPLATES;
 use crossbeam::channel::Sender;
 use log::{error, info, warn};
-use std::collections::HashMap;
 use std::collections::HashSet;
+use std::collections::{BTreeMap, HashMap};
 use std::str::FromStr;
 use tools::macros::GetMutRef;
 use tools::protos::base::BattlePlayerPt;
@@ -31,6 +31,7 @@ pub struct BattleStatus {
     attack_state: AttackState,               //是否可以攻击
     pub attack_reward_movement_points: bool, //配对攻击后奖励翻地图块次数,表示是否奖励过翻拍次数
     pub battle_state: BattlePlayerState,     //玩家战斗状态
+    pub is_offline: bool,                    //是否暂时掉线(等待重连)
 }
 
 ///角色战斗流程相关数据
@@ -94,21 +95,21 @@ impl MerchandiseData {
 ///战斗玩家数据
 #[derive(Clone, Default)]
 pub struct BattlePlayer {
-    pub user_id: u32,                         //玩家ID
-    pub name: String,                         //名称
-    pub gold: i32,                            //金币
-    pub grade: u8,                            //玩家grade
-    pub league: League,                       //段位数据
-    pub mission_data: MissionData,            //任务数据
-    pub merchandise_data: MerchandiseData,    //商品数据
-    pub cters: HashMap<u32, BattleCharacter>, //玩家的战斗角色
-    pub major_cter: (u32, u32),               //主角色(动态id,配置id)
-    pub current_cter: (u32, u32),             //当前角色(动态id,配置id)
-    pub flow_data: TurnFlowData,              //战斗流程相关数据
-    pub status: BattleStatus,                 //战斗状态
-    pub robot_data: Option<RobotData>,        //机器人数据;如果有值，则是机器人，没有则是玩家
-    pub team_id: u8,                          //队伍id
-    pub is_world_boss: bool,                  //是否是worldboss
+    pub user_id: u32,                          //玩家ID
+    pub name: String,                          //名称
+    pub gold: i32,                             //金币
+    pub grade: u8,                             //玩家grade
+    pub league: League,                        //段位数据
+    pub mission_data: MissionData,             //任务数据
+    pub merchandise_data: MerchandiseData,     //商品数据
+    pub cters: BTreeMap<u32, BattleCharacter>, //玩家的战斗角色
+    pub major_cter: (u32, u32),                //主角色(动态id,配置id)
+    pub current_cter: (u32, u32),              //当前角色(动态id,配置id)
+    pub flow_data: TurnFlowData,               //战斗流程相关数据
+    pub status: BattleStatus,                  //战斗状态
+    pub robot_data: Option<RobotData>,         //机器人数据;如果有值，则是机器人，没有则是玩家
+    pub team_id: u8,                           //队伍id
+    pub is_world_boss: bool,                   //是否是worldboss
 }
 
 tools::get_mut_ref!(BattlePlayer);
@@ -144,7 +145,8 @@ impl BattlePlayer {
         }
         battle_player.reset_residue_movement_points();
         battle_player.team_id = member.team_id;
-        battle_player.is_world_boss = crate::TEMPLATES
+        let temps = crate::TEMPLATES.load();
+        battle_player.is_world_boss = temps
             .worldboss_temp_mgr()
             .temps
             .contains_key(&battle_player.major_cter.1);
@@ -324,7 +326,8 @@ impl BattlePlayer {
 
     ///重制翻块次数
     pub fn reset_residue_movement_points(&mut self) {
-        let temp = TEMPLATES.constant_temp_mgr();
+        let temps = TEMPLATES.load();
+        let temp = temps.constant_temp_mgr();
         let res = temp.temps.get("turn_default_movement_points");
         let mut reward_count;
         match res {
@@ -408,6 +411,15 @@ impl BattlePlayer {
         self.robot_data.is_some()
     }
 
+    ///turn超时是否需要T出房间
+    ///啥都没做的T出去，暂时掉线等待重连的不T，帮他跳过turn
+    pub fn need_kick_on_turn_timeout(&self) -> bool {
+        if self.status.is_offline {
+            return false;
+        }
+        self.flow_data.open_map_cell_vec.is_empty()
+    }
+
     ///重制角色数据
     pub fn round_reset(&mut self) -> Vec<u32> {
         self.status.is_attacked = false;
@@ -500,3 +512,19 @@ impl TransformInheritValue {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offline_player_is_not_kicked_on_turn_timeout() {
+        let mut battle_player = BattlePlayer::default();
+        assert!(battle_player.need_kick_on_turn_timeout());
+        battle_player.status.is_offline = true;
+        assert!(!battle_player.need_kick_on_turn_timeout());
+        battle_player.status.is_offline = false;
+        battle_player.flow_data.open_map_cell_vec.push(1);
+        assert!(!battle_player.need_kick_on_turn_timeout());
+    }
+}
@@ -0,0 +1,265 @@
+use crate::handlers::battle_handler::start_battle;
+use crate::mgr::battle_mgr::BattleMgr;
+use crate::task_timer::{task_fn, Task, TaskCmd};
+use crate::{JsonValue, Lock};
+use async_std::sync::{Arc, Mutex};
+use async_std::task::block_on;
+use log::{info, warn};
+use protobuf::Message;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::io::Write;
+use tools::cmd_code::ClientCode;
+use tools::protos::battle::{S_ACTION_NOTICE, S_SUMMARY_NOTICE};
+use tools::rating::Rating;
+use tools::util::packet::Packet;
+
+///录像事件类型
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum RecordEventType {
+    Packet, //玩家(包括机器人)发过来的消息
+    Task,   //定时器任务
+}
+
+///录像事件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordEvent {
+    pub event_type: RecordEventType, //事件类型
+    pub cmd: u32,                    //命令号,定时器任务时为TaskCmd
+    pub user_id: u32,                //玩家id
+    pub turn: u32,                   //发生时的turn
+    pub data: Vec<u8>,               //消息体
+}
+
+///战斗录像
+///记录开战数据、随机种子和所有会影响战斗结果的输入,用于离线复现战斗
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BattleRecord {
+    pub room_id: u32,        //房间id
+    pub battle_id: u32,      //战斗id
+    pub seed: u64,           //随机种子
+    pub season_id: i32,      //开战时的赛季id
+    pub start_data: Vec<u8>, //R_B_START消息体
+    #[serde(default)]
+    pub rating_map: HashMap<u32, Rating>, //赛前评分快照
+    #[serde(default)]
+    pub rating_time: i64, //评分快照时间
+    pub events: Vec<RecordEvent>, //按顺序记录的事件
+    #[serde(skip)]
+    pub is_replay: bool, //是否在回放,回放时不读写redis里的评分
+}
+
+impl BattleRecord {
+    ///开战时初始化录像
+    pub fn init(&mut self, room_id: u32, battle_id: u32, seed: u64, start_data: &[u8]) {
+        self.room_id = room_id;
+        self.battle_id = battle_id;
+        self.seed = seed;
+        unsafe {
+            self.season_id = crate::SEASON.season_id;
+        }
+        self.start_data = start_data.to_vec();
+        self.rating_map.clear();
+        self.rating_time = 0;
+        self.events.clear();
+    }
+
+    ///回放时沿用录像里的赛前评分快照
+    pub fn init_replay(&mut self, record: &BattleRecord) {
+        self.rating_map = record.rating_map.clone();
+        self.rating_time = record.rating_time;
+        self.is_replay = true;
+    }
+
+    ///记录赛前评分快照
+    pub fn record_rating(&mut self, rating_map: &HashMap<u32, Rating>, rating_time: i64) {
+        self.rating_map = rating_map.clone();
+        self.rating_time = rating_time;
+    }
+
+    ///记录消息
+    pub fn record_packet(&mut self, cmd: u32, user_id: u32, turn: u32, data: &[u8]) {
+        let event = RecordEvent {
+            event_type: RecordEventType::Packet,
+            cmd,
+            user_id,
+            turn,
+            data: data.to_vec(),
+        };
+        self.events.push(event);
+    }
+
+    ///记录定时器任务
+    pub fn record_task(&mut self, task_cmd: TaskCmd, user_id: u32, turn: u32) {
+        let cmd: u16 = task_cmd.into();
+        let event = RecordEvent {
+            event_type: RecordEventType::Task,
+            cmd: cmd as u32,
+            user_id,
+            turn,
+            data: Vec::new(),
+        };
+        self.events.push(event);
+    }
+
+    ///保存到目录下,文件名为room_id_battle_id.json
+    pub fn save(&self, dir: &str) -> anyhow::Result<String> {
+        std::fs::create_dir_all(dir)?;
+        let path = format!("{}/{}_{}.json", dir, self.room_id, self.battle_id);
+        let str = serde_json::to_string(self)?;
+        let mut file = std::fs::File::create(path.as_str())?;
+        file.write_all(str.as_bytes())?;
+        Ok(path)
+    }
+
+    ///从文件读取录像
+    pub fn load(path: &str) -> anyhow::Result<BattleRecord> {
+        let str = std::fs::read_to_string(path)?;
+        let record: BattleRecord = serde_json::from_str(str.as_str())?;
+        Ok(record)
+    }
+}
+
+///回放结果
+#[derive(Debug, Default)]
+pub struct ReplayResult {
+    pub action_notices: Vec<(u32, S_ACTION_NOTICE)>, //推送给玩家的行动通知,user_id
+    pub summary_notices: Vec<(u32, S_SUMMARY_NOTICE)>, //推送给玩家的结算通知,user_id
+    pub ranks: Vec<(u32, u8)>,                       //结算排名,user_id,名次(0为第一名),包括机器人
+    pub events: Vec<RecordEvent>,                    //回放过程中重新记录的事件,应该和录像一致
+}
+
+impl ReplayResult {
+    ///从发给客户端的消息里收集行动通知和结算通知
+    pub fn collect(&mut self, bytes: Vec<u8>) -> anyhow::Result<()> {
+        let packets = Packet::build_array_from_server(bytes)?;
+        for packet in packets {
+            let user_id = packet.get_user_id();
+            let cmd = packet.get_cmd();
+            if cmd == ClientCode::ActionNotice as u32 {
+                let mut proto = S_ACTION_NOTICE::new();
+                proto.merge_from_bytes(packet.get_data())?;
+                self.action_notices.push((user_id, proto));
+            } else if cmd == ClientCode::SummaryNotice as u32 {
+                let mut proto = S_SUMMARY_NOTICE::new();
+                proto.merge_from_bytes(packet.get_data())?;
+                self.summary_notices.push((user_id, proto));
+            }
+        }
+        Ok(())
+    }
+}
+
+///离线回放战斗录像
+///不需要网络和定时器,按录像顺序把事件重新投递给战斗逻辑,收集推送给玩家的消息
+pub fn replay(record: &BattleRecord) -> anyhow::Result<ReplayResult> {
+    let (tcp_sender, tcp_rec) = crossbeam::channel::unbounded();
+    let (task_sender, _task_rec) = crossbeam::channel::unbounded();
+    let (robot_sender, _robot_rec) = crossbeam::channel::unbounded();
+    let mut bm = BattleMgr::new();
+    bm.set_game_center_channel(tcp_sender);
+    bm.task_sender = Some(task_sender);
+    bm.robot_task_sender = Some(robot_sender);
+    //回放不需要再生成录像
+    bm.record_path.clear();
+    bm.finished_rooms = Some(Vec::new());
+    unsafe {
+        crate::SEASON.season_id = record.season_id;
+    }
+    let bm: Lock = Arc::new(Mutex::new(bm));
+
+    let mut lock = block_on(bm.lock());
+    start_battle(&mut lock, record.start_data.as_slice(), Some(record));
+    std::mem::drop(lock);
+
+    for event in record.events.iter() {
+        match event.event_type {
+            RecordEventType::Packet => {
+                let mut packet = Packet::new(event.cmd, 0, event.user_id);
+                packet.set_data(event.data.as_slice());
+                let mut lock = block_on(bm.lock());
+                lock.invok(packet);
+            }
+            RecordEventType::Task => {
+                let task_cmd = TaskCmd::try_from(event.cmd as u16);
+                if let Err(e) = task_cmd {
+                    anyhow::bail!("{:?}", e)
+                }
+                let mut map = serde_json::Map::new();
+                map.insert("user_id".to_owned(), JsonValue::from(event.user_id));
+                let task = Task {
+                    cmd: task_cmd.unwrap(),
+                    turn: event.turn,
+                    battle_id: record.battle_id,
+                    data: JsonValue::from(map),
+                    ..Default::default()
+                };
+                let f = task_fn(task.cmd);
+                f(bm.clone(), task);
+            }
+        }
+    }
+
+    let mut res = ReplayResult::default();
+    for bytes in tcp_rec.try_iter() {
+        res.collect(bytes)?;
+    }
+    let mut lock = block_on(bm.lock());
+    if let Some(room) = lock.finished_rooms.as_mut().and_then(|x| x.pop()) {
+        let battle_data = room.battle_data;
+        for su in battle_data.summary_vec.iter().flatten() {
+            res.ranks.push((su.user_id, su.summary_rank));
+        }
+        res.events = battle_data.record.events;
+    }
+    info!(
+        "replay finish!room_id:{},battle_id:{},seed:{},events:{},action_notices:{},summary_notices:{}",
+        record.room_id,
+        record.battle_id,
+        record.seed,
+        record.events.len(),
+        res.action_notices.len(),
+        res.summary_notices.len()
+    );
+    if res.ranks.is_empty() {
+        warn!(
+            "replay has no summary!room_id:{},battle_id:{}",
+            record.room_id, record.battle_id
+        );
+    }
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim;
+
+    #[test]
+    fn replay_matches_recorded_battle() {
+        //按正式战斗的流程随机选地图,录像开着
+        let rp = sim::robot_room_pt(&[1001, 1002]).unwrap();
+        let mut notices = ReplayResult::default();
+        let room = sim::play(rp, None, 42, Some(&mut notices)).unwrap();
+        let battle_data = &room.battle_data;
+        let record = &battle_data.record;
+        assert!(!record.events.is_empty());
+        let ranks: Vec<(u32, u8)> = battle_data
+            .summary_vec
+            .iter()
+            .flatten()
+            .map(|x| (x.user_id, x.summary_rank))
+            .collect();
+        assert!(!ranks.is_empty());
+
+        //录像存盘再读出来回放
+        let str = serde_json::to_string(record).unwrap();
+        let record: BattleRecord = serde_json::from_str(str.as_str()).unwrap();
+        let res = replay(&record).unwrap();
+        assert_eq!(res.ranks, ranks);
+        assert_eq!(res.events, record.events);
+        assert_eq!(res.action_notices, notices.action_notices);
+        assert_eq!(res.summary_notices, notices.summary_notices);
+    }
+}
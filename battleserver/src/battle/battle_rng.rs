@@ -0,0 +1,47 @@
+use rand::distributions::uniform::{SampleRange, SampleUniform};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::cell::RefCell;
+
+///战斗随机数生成器
+///每场战斗持有一个，由种子决定全部随机结果，用于复现战斗
+#[derive(Clone, Debug)]
+pub struct BattleRng {
+    seed: u64,            //随机种子
+    rng: RefCell<StdRng>, //随机数生成器
+}
+
+impl Default for BattleRng {
+    fn default() -> Self {
+        BattleRng::new(rand::random())
+    }
+}
+
+impl BattleRng {
+    ///根据种子创建
+    pub fn new(seed: u64) -> Self {
+        BattleRng {
+            seed,
+            rng: RefCell::new(StdRng::seed_from_u64(seed)),
+        }
+    }
+
+    pub fn get_seed(&self) -> u64 {
+        self.seed
+    }
+
+    ///重置种子,从头开始生成
+    pub fn reseed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng = RefCell::new(StdRng::seed_from_u64(seed));
+    }
+
+    ///在范围内随机一个数
+    pub fn gen_range<T, R>(&self, range: R) -> T
+    where
+        T: SampleUniform,
+        R: SampleRange<T>,
+    {
+        self.rng.borrow_mut().gen_range(range)
+    }
+}
@@ -13,10 +13,10 @@ use crate::robot::robot_trigger::RobotTriggerType;
 use crate::room::map_data::MapCell;
 use crate::TEMPLATES;
 use log::{error, warn};
-use rand::Rng;
 use std::borrow::BorrowMut;
 use std::collections::HashMap;
 use std::convert::TryFrom;
+use std::sync::Arc;
 use tools::protos::base::{ActionUnitPt, EffectPt, SkillPt, TargetPt};
 use tools::templates::skill_temp::SkillTemp;
 
@@ -30,7 +30,7 @@ use super::battle_enum::{FromType, SkillConsumeType, TRIGGER_SCOPE_CENTER_NEAR_T
 pub struct Skill {
     pub id: u32,
     pub function_id: u32, //功能id
-    pub skill_temp: &'static SkillTemp,
+    pub skill_temp: Arc<SkillTemp>,
     pub cd_times: i8,    //剩余cd,如果是消耗能量则无视这个值
     pub is_active: bool, //是否激活
     pub last_target_cter: u32,
@@ -56,7 +56,7 @@ impl Skill {
         self.cd_times = self.skill_temp.cd as i8;
     }
 
-    pub fn from_skill_temp(skill_temp: &'static SkillTemp, need_clear_cd: bool) -> Self {
+    pub fn from_skill_temp(skill_temp: &Arc<SkillTemp>, need_clear_cd: bool) -> Self {
         let mut skill = Skill::from(skill_temp);
         if need_clear_cd {
             skill.cd_times = 0;
@@ -65,13 +65,13 @@ impl Skill {
     }
 }
 
-impl From<&'static SkillTemp> for Skill {
-    fn from(skill_temp: &'static SkillTemp) -> Self {
+impl From<&Arc<SkillTemp>> for Skill {
+    fn from(skill_temp: &Arc<SkillTemp>) -> Self {
         Skill {
             id: skill_temp.id,
             function_id: skill_temp.function_id,
             cd_times: skill_temp.cd as i8,
-            skill_temp,
+            skill_temp: skill_temp.clone(),
             is_active: false,
             last_target_cter: 0,
             turn_use_times: 0,
@@ -240,7 +240,8 @@ pub fn show_index(
     target_array: Vec<u32>,
     au: &mut ActionUnitPt,
 ) -> Option<Vec<(u32, ActionUnitPt)>> {
-    let skill_temp = TEMPLATES.skill_temp_mgr().get_temp(&skill_id).unwrap();
+    let temps = TEMPLATES.load();
+    let skill_temp = temps.skill_temp_mgr().get_temp(&skill_id).unwrap();
     let skill_function_id = skill_temp.function_id;
     let show_index;
     if SHOW_INDEX_SAME_ELEMENT == skill_function_id {
@@ -312,7 +313,8 @@ pub fn show_map_cell(
     target_array: Vec<u32>,
     au: &mut ActionUnitPt,
 ) -> Option<Vec<(u32, ActionUnitPt)>> {
-    let temp = TEMPLATES.skill_temp_mgr().get_temp(&skill_id).unwrap();
+    let temps = TEMPLATES.load();
+    let temp = temps.skill_temp_mgr().get_temp(&skill_id).unwrap();
     let skill_function_id = temp.function_id;
     if skill_function_id != SHOW_ALL_USERS_CELL && target_array.is_empty() {
         warn!(
@@ -378,8 +380,7 @@ pub fn show_map_cell(
             if let Some(nature_index) = nature_index {
                 index = nature_index;
             } else if !v.is_empty() {
-                let mut rand = rand::thread_rng();
-                index = rand.gen_range(0..v.len());
+                index = battle_data_ref.rng.gen_range(0..v.len());
                 let res = v.get(index);
                 if let None = res {
                     warn!("there is no map_cell can show!");
@@ -402,7 +403,7 @@ pub fn show_map_cell(
             let element = map_cell.element;
             let map_cell_id = map_cell.id;
             let map_cell_index = map_cell.index;
-            let skill_temp = TEMPLATES.skill_temp_mgr().get_temp(&skill_id);
+            let skill_temp = temps.skill_temp_mgr().get_temp(&skill_id);
             if let Err(e) = skill_temp {
                 warn!("{:?}", e);
                 return None;
@@ -511,10 +512,11 @@ pub unsafe fn add_buff(
     let battle_cter = battle_cter.unwrap();
 
     let cter_index = battle_cter.get_map_cell_index();
-    let skill_temp = TEMPLATES.skill_temp_mgr().get_temp(&skill_id).unwrap();
+    let temps = TEMPLATES.load();
+    let skill_temp = temps.skill_temp_mgr().get_temp(&skill_id).unwrap();
     //先计算单体的
     let buff_id = skill_temp.buff as u32;
-    let buff_function_res = crate::TEMPLATES.buff_temp_mgr().get_temp(&buff_id);
+    let buff_function_res = temps.buff_temp_mgr().get_temp(&buff_id);
     let buff_function_id;
     match buff_function_res {
         Ok(buff_temp) => buff_function_id = buff_temp.function_id,
@@ -575,7 +577,7 @@ pub unsafe fn add_buff(
                     .map_cells
                     .get_mut(index as usize)
                     .unwrap();
-                let buff_temp = TEMPLATES.buff_temp_mgr().get_temp(&buff_id).unwrap();
+                let buff_temp = temps.buff_temp_mgr().get_temp(&buff_id).unwrap();
                 let mut buff = Buff::new(
                     buff_temp,
                     Some(battle_data_mut.next_turn_index),
@@ -730,7 +732,8 @@ pub fn skill_open_map_cell(
     target_array: Vec<u32>,
     au: &mut ActionUnitPt,
 ) -> Option<Vec<(u32, ActionUnitPt)>> {
-    let skill = TEMPLATES.skill_temp_mgr().get_temp(&skill_id).unwrap();
+    let temps = TEMPLATES.load();
+    let skill = temps.skill_temp_mgr().get_temp(&skill_id).unwrap();
     let skill_function_id = skill.function_id;
     if SKILL_OPEN_NEAR_CELL == skill_function_id {
         if target_array.is_empty() {
@@ -796,7 +799,8 @@ pub unsafe fn auto_pair_map_cell(
         warn!("{:?}", e);
         return None;
     }
-    let skill_temp = TEMPLATES.skill_temp_mgr().get_temp(&skill_id);
+    let temps = TEMPLATES.load();
+    let skill_temp = temps.skill_temp_mgr().get_temp(&skill_id);
     if let Err(e) = skill_temp {
         warn!("{:?}", e);
         return None;
@@ -804,7 +808,7 @@ pub unsafe fn auto_pair_map_cell(
     let skill_temp = skill_temp.unwrap();
     let buff_id = skill_temp.buff;
 
-    let buff_temp = TEMPLATES.buff_temp_mgr().get_temp(&buff_id);
+    let buff_temp = temps.buff_temp_mgr().get_temp(&buff_id);
     if let Err(e) = buff_temp {
         warn!("{:?}", e);
         return None;
@@ -976,7 +980,8 @@ pub unsafe fn skill_damage_and_cure(
     let cter_index = battle_cter.get_map_cell_index() as isize;
     let skill = battle_cter.skills.get_mut(&skill_id).unwrap();
 
-    let res = TEMPLATES
+    let temps = TEMPLATES.load();
+    let res = temps
         .skill_scope_temp_mgr()
         .get_temp(&skill.skill_temp.scope);
     if let Err(e) = res {
@@ -1082,7 +1087,8 @@ pub unsafe fn skill_aoe_damage(
         }
     }
 
-    let scope_temp = TEMPLATES.skill_scope_temp_mgr().get_temp(&scope_id);
+    let temps = TEMPLATES.load();
+    let scope_temp = temps.skill_scope_temp_mgr().get_temp(&scope_id);
     if let Err(e) = scope_temp {
         error!("{:?}", e);
         return None;
@@ -1193,7 +1199,8 @@ pub unsafe fn single_skill_damage(
     let target_index = target_cter.get_map_cell_index();
     let target_cter_id = target_cter.get_cter_id();
 
-    let skill_temp = TEMPLATES.skill_temp_mgr().get_temp(&skill_id).unwrap();
+    let temps = TEMPLATES.load();
+    let skill_temp = temps.skill_temp_mgr().get_temp(&skill_id).unwrap();
     let target_type = TargetType::try_from(skill_temp.target).unwrap();
     let skill_function_id = skill_temp.function_id;
     let mut skill_damage = skill_temp.par1 as i16;
@@ -1217,7 +1224,7 @@ pub unsafe fn single_skill_damage(
         || skill_function_id == DAMAGE_DIFFUSION
     {
         let battle_data_mut = battle_data_ptr.as_mut().unwrap();
-        let skill_scopt_temp = crate::TEMPLATES
+        let skill_scopt_temp = temps
             .skill_scope_temp_mgr()
             .get_temp(&TRIGGER_SCOPE_CENTER_NEAR_TEMP_ID)
             .unwrap();
@@ -1271,7 +1278,8 @@ pub unsafe fn sub_cd(
         return None;
     }
 
-    let skill_temp = TEMPLATES.skill_temp_mgr().get_temp(&skill_id).unwrap();
+    let temps = TEMPLATES.load();
+    let skill_temp = temps.skill_temp_mgr().get_temp(&skill_id).unwrap();
 
     let battle_cter = battle_cter.unwrap();
     let battle_player_index = battle_cter.get_map_cell_index();
@@ -1314,7 +1322,8 @@ pub unsafe fn scope_cure(
         return None;
     }
     let target_type = target_type.unwrap();
-    let scope_temp = TEMPLATES.skill_scope_temp_mgr().get_temp(&scope_id);
+    let temps = TEMPLATES.load();
+    let scope_temp = temps.skill_scope_temp_mgr().get_temp(&scope_id);
     if let Err(e) = scope_temp {
         warn!("{:?}", e);
         return None;
@@ -1371,14 +1380,12 @@ pub unsafe fn transform(
     let target_cter = target_cter.unwrap();
     let cter_temp_id = target_cter.get_cter_temp_id();
 
-    let skill_temp = crate::TEMPLATES
-        .skill_temp_mgr()
-        .get_temp(&skill_id)
-        .unwrap();
+    let temps = crate::TEMPLATES.load();
+    let skill_temp = temps.skill_temp_mgr().get_temp(&skill_id).unwrap();
     let buff_id = skill_temp.buff;
     let mut new_cter_temp_id = skill_temp.par1;
     if new_cter_temp_id == 0 {
-        let cter_temp = crate::TEMPLATES
+        let cter_temp = temps
             .character_temp_mgr()
             .get_temp_ref(&cter_temp_id)
             .unwrap();
@@ -1493,7 +1500,8 @@ pub unsafe fn transform_self(
     }
 
     //计算范围
-    let scope_temp = TEMPLATES.skill_scope_temp_mgr().get_temp(&scope_id);
+    let temps = TEMPLATES.load();
+    let scope_temp = temps.skill_scope_temp_mgr().get_temp(&scope_id);
     if let Err(e) = scope_temp {
         warn!("{:?}", e);
         return None;
@@ -1549,7 +1557,8 @@ pub unsafe fn summon_minon(
     _: Vec<u32>,
     au: &mut ActionUnitPt,
 ) -> Option<Vec<(u32, ActionUnitPt)>> {
-    let skill_temp = crate::TEMPLATES.skill_temp_mgr().get_temp(&skill_id);
+    let temps = crate::TEMPLATES.load();
+    let skill_temp = temps.skill_temp_mgr().get_temp(&skill_id);
     if let Err(e) = skill_temp {
         error!("{:?}", e);
         return None;
@@ -1658,7 +1667,8 @@ pub unsafe fn order_minon_attack(
             other_aus.push((0, other_au));
         }
     } else {
-        let skill_scope_temp = crate::TEMPLATES
+        let temps = crate::TEMPLATES.load();
+        let skill_scope_temp = temps
             .skill_scope_temp_mgr()
             .get_temp(&skill.skill_temp.scope)
             .unwrap();
@@ -16,6 +16,7 @@ use crate::TEMPLATES;
 use crate::{battle::battle::BattleData, room::map_data::MapCell};
 use log::{error, warn};
 use protobuf::Message;
+use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::str::FromStr;
 use tools::cmd_code::ClientCode;
@@ -147,7 +148,8 @@ impl BattleData {
                 //判断是否是上buff的陷阱
                 if TRAP_ADD_BUFF.contains(&buff_function_id) {
                     let buff_id = buff.buff_temp.par1;
-                    let buff_temp = TEMPLATES.buff_temp_mgr().get_temp(&buff_id);
+                    let temps = TEMPLATES.load();
+                    let buff_temp = temps.buff_temp_mgr().get_temp(&buff_id);
                     if let Err(e) = buff_temp {
                         warn!("{:?}", e);
                         continue;
@@ -265,10 +267,8 @@ impl TriggerEvent for BattleData {
                 }
                 //配对了奖励金币
                 let res;
-                let temp = crate::TEMPLATES
-                    .constant_temp_mgr()
-                    .temps
-                    .get("reward_gold_pair_cell");
+                let temps = crate::TEMPLATES.load();
+                let temp = temps.constant_temp_mgr().temps.get("reward_gold_pair_cell");
                 match temp {
                     Some(temp) => {
                         let value = u32::from_str(temp.value.as_str());
@@ -313,7 +313,8 @@ impl TriggerEvent for BattleData {
                     buff_function_id = buff.function_id;
                     buff_id = buff.get_id();
                     if buff_function_id == OPEN_ELEMENT_CELL_CLEAR_CD {
-                        let temp = crate::TEMPLATES.buff_temp_mgr().get_temp(&buff.get_id())?;
+                        let temps = crate::TEMPLATES.load();
+                        let temp = temps.buff_temp_mgr().get_temp(&buff.get_id())?;
                         if temp.par1 == element {
                             let mut target_pt = TargetPt::new();
                             target_pt.target_value.push(cter_index as u32);
@@ -514,7 +515,8 @@ impl TriggerEvent for BattleData {
             let self_mut = self_ptr.as_mut().unwrap();
             let battle_player = self_mut.get_battle_player_mut(None, true).unwrap();
             //添加技能限制条件
-            let skill_temp = TEMPLATES.skill_temp_mgr().get_temp(&skill_id).unwrap();
+            let temps = TEMPLATES.load();
+            let skill_temp = temps.skill_temp_mgr().get_temp(&skill_id).unwrap();
             if skill_temp.skill_judge == LIMIT_TURN_TIMES as u16 {
                 battle_player.flow_data.turn_limit_skills.push(skill_id);
             } else if skill_temp.skill_judge == LIMIT_ROUND_TIMES as u16 {
@@ -525,7 +527,7 @@ impl TriggerEvent for BattleData {
             let mut skill_s;
             let mut skill = None;
             if is_item {
-                let res = TEMPLATES.skill_temp_mgr().get_temp(&skill_id);
+                let res = temps.skill_temp_mgr().get_temp(&skill_id);
                 if let Err(e) = res {
                     error!("{:?}", e);
                     return;
@@ -548,11 +550,9 @@ impl TriggerEvent for BattleData {
                     WATER_TURRET | CHARGE_SKILL_DAMGE_ABSORPTION | FULL_MAP_DAMAGE => {
                         let skill_temp;
                         if skill_function_id == FULL_MAP_DAMAGE {
-                            skill_temp =
-                                TEMPLATES.skill_temp_mgr().get_temp(&skill.skill_temp.par3);
+                            skill_temp = temps.skill_temp_mgr().get_temp(&skill.skill_temp.par3);
                         } else {
-                            skill_temp =
-                                TEMPLATES.skill_temp_mgr().get_temp(&skill.skill_temp.par2);
+                            skill_temp = temps.skill_temp_mgr().get_temp(&skill.skill_temp.par2);
                         }
                         battle_cter.skills.remove(&skill_id);
                         if let Err(e) = skill_temp {
@@ -785,7 +785,8 @@ impl TriggerEvent for BattleData {
             return;
         }
 
-        let buff_temp = TEMPLATES.buff_temp_mgr().get_temp(&buff_id).unwrap();
+        let temps = TEMPLATES.load();
+        let buff_temp = temps.buff_temp_mgr().get_temp(&buff_id).unwrap();
         let buff_function_id = buff_temp.function_id;
         let battle_cter = battle_cter.unwrap();
         //如果是变身buff,那就变回来
@@ -846,10 +847,8 @@ impl TriggerEvent for BattleData {
 
                 for buff in owner_battle_cter.battle_buffs.buffs().values() {
                     if buff.function_id == SUMMON_CROW {
-                        let crow_buff = crate::TEMPLATES
-                            .buff_temp_mgr()
-                            .get_temp(&buff.get_id())
-                            .unwrap();
+                        let temps = crate::TEMPLATES.load();
+                        let crow_buff = temps.buff_temp_mgr().get_temp(&buff.get_id()).unwrap();
                         let crow_temp_id = crow_buff.par1;
 
                         //判断是否是小乌鸦
@@ -940,14 +939,11 @@ impl TriggerEvent for BattleData {
                 .unwrap();
             die_battle_player.player_die(str);
 
-            let self_league = die_battle_player.league.get_league_id();
             let player_name = die_battle_player.name.clone();
             let mut punishment_score = -50;
             let mut reward_score;
-            let con_temp = crate::TEMPLATES
-                .constant_temp_mgr()
-                .temps
-                .get("punishment_summary");
+            let temps = crate::TEMPLATES.load();
+            let con_temp = temps.constant_temp_mgr().temps.get("punishment_summary");
             if let Some(con_temp) = con_temp {
                 let reward_score_temp = f64::from_str(con_temp.value.as_str());
                 match reward_score_temp {
@@ -983,6 +979,12 @@ impl TriggerEvent for BattleData {
             }
             //处理结算
             if is_last_one {
+                //匹配房按评分结算积分
+                let mut rating_scores = HashMap::new();
+                if self.room_type.is_match_type() && !is_punishment {
+                    let batch: Vec<u32> = rank_vec_temp.iter().map(|x| x.user_id).collect();
+                    rating_scores = self_mut.calc_rating_scores(batch.as_slice());
+                }
                 let index = player_count as usize;
                 let res = self.summary_vec.get_mut(index);
                 if let None = res {
@@ -994,9 +996,7 @@ impl TriggerEvent for BattleData {
                     return;
                 }
                 let rank_vec = res.unwrap();
-                let count = rank_vec_temp.len();
-                let summary_award_temp_mgr = crate::TEMPLATES.summary_award_temp_mgr();
-                let con_temp_mgr = crate::TEMPLATES.constant_temp_mgr();
+                let con_temp_mgr = temps.constant_temp_mgr();
                 let res = con_temp_mgr.temps.get("max_grade");
                 let mut max_grade = 2;
                 if self.room_type.is_match_type() {
@@ -1030,8 +1030,7 @@ impl TriggerEvent for BattleData {
                             sp.grade = 1;
                         }
                     } else {
-                        //计算基础分
-                        let mut rank = sp.summary_rank + 1;
+                        let rank = sp.summary_rank + 1;
                         if rank == 1 {
                             sp.grade += 1;
                             if sp.grade > 2 {
@@ -1043,35 +1042,11 @@ impl TriggerEvent for BattleData {
                                 sp.grade = 1;
                             }
                         }
-                        let mut base_score = 0;
-                        for index in 0..count {
-                            rank += index as u8;
-                            let score_temp = summary_award_temp_mgr.get_score_by_rank(rank);
-                            if let Err(e) = score_temp {
-                                warn!("{:?}", e);
-                                continue;
-                            }
-                            base_score += score_temp.unwrap();
-                        }
-                        base_score /= count as i16;
-                        //计算浮动分
-                        let mut average_league = 0;
-                        let mut league_count = 0;
-                        for (&id, league_id) in self.leave_map.iter() {
-                            if id == die_user_id {
-                                continue;
-                            }
-                            league_count += 1;
-                            average_league += *league_id;
-                        }
-                        average_league /= league_count;
-                        let mut unstable = 0;
-                        if self_league >= average_league {
-                            unstable = 0;
-                        } else if average_league > self_league {
-                            unstable = (average_league - self_league) * 10;
-                        }
-                        reward_score = (base_score + unstable as i16) as i32;
+                        //按评分计算积分变化
+                        reward_score = match rating_scores.get(&sp.user_id) {
+                            Some(&score) => score,
+                            None => 0,
+                        };
                     }
                     sp.reward_score = reward_score;
                     let res = sp.league.update_score(reward_score);
@@ -1119,10 +1094,8 @@ impl TriggerEvent for BattleData {
             let self_mut = self_ptr.as_mut().unwrap();
             let mut buff;
             let mut crow_count = 0;
-            let buff_temp = crate::TEMPLATES
-                .buff_temp_mgr()
-                .get_temp(&SUMMON_CROW)
-                .unwrap();
+            let temps = crate::TEMPLATES.load();
+            let buff_temp = temps.buff_temp_mgr().get_temp(&SUMMON_CROW).unwrap();
             let count = buff_temp.par2;
             for cter in battle_player.cters.values_mut() {
                 cter.skills.values_mut().for_each(|x| x.turn_use_times = 0);
@@ -1218,10 +1191,9 @@ impl TriggerEvent for BattleData {
                         let cd = skill.cd_times;
                         let is_active = skill.is_active;
                         cter.skills.remove(&skill_id);
-                        let change_skill_temp = crate::TEMPLATES
-                            .skill_temp_mgr()
-                            .get_temp(&change_skill_id)
-                            .unwrap();
+                        let temps = crate::TEMPLATES.load();
+                        let change_skill_temp =
+                            temps.skill_temp_mgr().get_temp(&change_skill_id).unwrap();
                         let mut change_skill = Skill::from_skill_temp(change_skill_temp, true);
                         change_skill.cd_times = cd;
                         change_skill.is_active = is_active;
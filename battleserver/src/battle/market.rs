@@ -29,7 +29,8 @@ pub enum MerchandisType {
 pub fn handler_buy(battle_data: &mut BattleData, user_id: u32, merchandise_id: u32) {
     let battle_data_ptr = battle_data as *mut BattleData;
     let battle_player = battle_data.battle_player.get_mut(&user_id).unwrap();
-    let merchandise_temp = crate::TEMPLATES.merchandise_temp_mgr();
+    let temps = crate::TEMPLATES.load();
+    let merchandise_temp = temps.merchandise_temp_mgr();
     let temp = merchandise_temp.get_temp(&merchandise_id).unwrap();
     //扣金币
     battle_player.add_gold(-temp.price);
@@ -1,8 +1,8 @@
 use std::convert::TryFrom;
+use std::sync::Arc;
 
 use log::error;
 use protobuf::Message;
-use rand::Rng;
 use tools::cmd_code::ClientCode;
 use tools::protos::battle::S_MISSION_NOTICE;
 use tools::templates::mission_temp::MissionTemp;
@@ -83,9 +83,9 @@ pub struct MissionData {
 
 #[derive(Clone)]
 pub struct Mission {
-    pub progress: u16,                      //任务进度
-    pub is_complete: bool,                  //是否完成
-    pub mission_temp: &'static MissionTemp, //任务id
+    pub progress: u16,                  //任务进度
+    pub is_complete: bool,              //是否完成
+    pub mission_temp: Arc<MissionTemp>, //任务id
 }
 
 impl MissionData {
@@ -109,11 +109,11 @@ impl MissionData {
     }
 
     ///新任务
-    pub fn new_mission(&mut self, mission_temp: &'static MissionTemp) {
+    pub fn new_mission(&mut self, mission_temp: &Arc<MissionTemp>) {
         let mission = Mission {
             progress: 0,
             is_complete: false,
-            mission_temp,
+            mission_temp: mission_temp.clone(),
         };
         self.mission = Some(mission);
         self.history_list.push(mission_temp.id);
@@ -205,8 +205,9 @@ impl MissionData {
 ///随机任务
 pub fn random_mission(battle_data: &mut BattleData, user_id: u32) {
     let battle_player = battle_data.battle_player.get_mut(&user_id).unwrap();
-    let mission_temp_mgr = crate::TEMPLATES.mission_temp_mgr();
-    let mut random = rand::thread_rng();
+    let temps = crate::TEMPLATES.load();
+    let mission_temp_mgr = temps.mission_temp_mgr();
+    let random = &battle_data.rng;
     let no_condition_missions = mission_temp_mgr.no_condition_mission();
     let mut mission_list = vec![];
     let history_list = &battle_player.mission_data.history_list;
@@ -5,6 +5,8 @@ pub mod battle_enum;
 pub mod battle_flow;
 pub mod battle_helper;
 pub mod battle_player;
+pub mod battle_record;
+pub mod battle_rng;
 pub mod battle_skill;
 pub mod battle_trigger;
 pub mod market;
@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+use tools::conf::{check_address, BaseConfig, Validate};
+use tools::game_key::GameKey;
+use tools::rating::RatingConf;
+use tools::reconnect::ReconnectConfig;
+use tools::registry::RegistryConfig;
+
+///战斗服配置，启动时从config/config.conf读取并校验
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BattleConfig {
+    #[serde(flatten)]
+    pub game: GameKey, //游戏和区服
+    #[serde(flatten)]
+    pub base: BaseConfig, //公共配置
+    #[serde(flatten)]
+    pub registry: RegistryConfig, //注册中心
+    #[serde(flatten)]
+    pub reconnect: ReconnectConfig, //断线重连
+    #[serde(flatten)]
+    pub rating: RatingConf, //评分参数
+    pub tcp_port: String,          //游戏中心地址(file模式)
+    pub record_path: String,       //战斗录像目录
+    pub drain_timeout: u64,        //停服时等待战斗结束的时间(秒)
+    pub load_report_interval: u64, //负载上报间隔(秒)
+}
+
+impl Default for BattleConfig {
+    fn default() -> Self {
+        BattleConfig {
+            game: GameKey::default(),
+            base: BaseConfig::default(),
+            registry: RegistryConfig::default(),
+            reconnect: ReconnectConfig::default(),
+            rating: RatingConf::default(),
+            tcp_port: String::new(),
+            record_path: String::new(),
+            drain_timeout: 300,
+            load_report_interval: 5,
+        }
+    }
+}
+
+impl Validate for BattleConfig {
+    fn validate(&self) -> anyhow::Result<()> {
+        self.base.validate()?;
+        self.registry.validate()?;
+        self.reconnect.validate()?;
+        self.rating.validate()?;
+        if self.registry.registry_mode == "file" {
+            check_address("tcp_port", self.tcp_port.as_str())?;
+        }
+        if self.load_report_interval == 0 {
+            anyhow::bail!("load_report_interval不能为0!");
+        }
+        Ok(())
+    }
+}
@@ -2,6 +2,7 @@ use crate::battle::battle::BattleData;
 use crate::battle::battle_cter::BattleCharacter;
 use crate::battle::battle_enum::{ActionType, PosType, SkillConsumeType};
 use crate::battle::battle_helper::build_action_unit_pt;
+use crate::battle::battle_record::BattleRecord;
 use crate::battle::battle_skill::Skill;
 use crate::battle::market::handler_buy;
 use crate::mgr::battle_mgr::BattleMgr;
@@ -26,8 +27,18 @@ use tools::protos::server_protocol::{R_B_START, R_S_UPDATE_SEASON};
 use tools::templates::emoji_temp::EmojiTemp;
 use tools::util::packet::Packet;
 
+///停服排空，等当前战斗都打完再回复确认
+pub fn drain(bm: &mut BattleMgr, _: Packet) {
+    tools::shutdown::set_draining();
+    info!("战斗服开始停服排空!剩余战斗数量:{}", bm.rooms.len());
+    if bm.rooms.is_empty() {
+        bm.drain_ack(0);
+    }
+}
+
 ///购买
 pub fn buy(bm: &mut BattleMgr, packet: Packet) {
+    bm.record_packet(&packet);
     let user_id = packet.get_user_id();
     let mut buy_proto = C_BUY::new();
     let res = buy_proto.merge_from_bytes(packet.get_data());
@@ -89,7 +100,8 @@ pub fn buy(bm: &mut BattleMgr, packet: Packet) {
             return;
         }
     }
-    let merchandise_temp = crate::TEMPLATES.merchandise_temp_mgr();
+    let temps = crate::TEMPLATES.load();
+    let merchandise_temp = temps.merchandise_temp_mgr();
     let temp = merchandise_temp.get_temp(&merchandise_id);
     if let Err(e) = temp {
         error!("{:?}", e);
@@ -119,7 +131,7 @@ pub fn buy(bm: &mut BattleMgr, packet: Packet) {
         );
         return;
     }
-    let cter_temp_mgr = crate::TEMPLATES.character_temp_mgr();
+    let cter_temp_mgr = temps.character_temp_mgr();
     let cter_temp = cter_temp_mgr.temps.get(&cter_id).unwrap();
 
     //匹配角色类型是否相同
@@ -148,6 +160,7 @@ pub fn buy(bm: &mut BattleMgr, packet: Packet) {
 ///行动请求
 #[track_caller]
 pub fn action(bm: &mut BattleMgr, packet: Packet) {
+    bm.record_packet(&packet);
     let rm_ptr = bm as *mut BattleMgr;
     let user_id = packet.get_user_id();
     let res = bm.get_room_mut(&user_id);
@@ -337,8 +350,14 @@ pub unsafe fn process_summary(bm: &mut BattleMgr, room_id: u32) -> bool {
 
 ///开始战斗
 pub fn start(bm: &mut BattleMgr, packet: Packet) {
+    start_battle(bm, packet.get_data(), None);
+}
+
+///创建战斗房间并开始战斗
+///record不为空时为回放录像,沿用录像里的随机种子和战斗id
+pub fn start_battle(bm: &mut BattleMgr, data: &[u8], record: Option<&BattleRecord>) {
     let mut rbs = R_B_START::new();
-    let res = rbs.merge_from_bytes(packet.get_data());
+    let res = rbs.merge_from_bytes(data);
     if let Err(e) = res {
         warn!("{:?}", e);
         return;
@@ -355,6 +374,30 @@ pub fn start(bm: &mut BattleMgr, packet: Packet) {
         return;
     }
     let mut room = room.unwrap();
+    if let Some(record) = record {
+        room.battle_data.rng.reseed(record.seed);
+        room.battle_data.battle_id = record.battle_id;
+    }
+    //初始化战斗录像
+    let seed = room.battle_data.rng.get_seed();
+    let battle_id = room.battle_data.battle_id;
+    room.battle_data
+        .record
+        .init(room.get_room_id(), battle_id, seed, data);
+    if let Some(record) = record {
+        room.battle_data.record.init_replay(record);
+    }
+    info!(
+        "战斗随机种子!room_id:{},battle_id:{},seed:{}",
+        room.get_room_id(),
+        battle_id,
+        seed
+    );
+    begin_battle(bm, room);
+}
+
+///开始战斗，并把房间交给战斗管理器
+pub fn begin_battle(bm: &mut BattleMgr, mut room: Room) {
     let room_type = room.get_room_type();
     //开始战斗
     room.start();
@@ -385,6 +428,7 @@ pub fn start(bm: &mut BattleMgr, packet: Packet) {
 
 ///处理pos
 pub fn pos(rm: &mut BattleMgr, packet: Packet) {
+    rm.record_packet(&packet);
     let user_id = packet.get_user_id();
     let res = rm.get_room_mut(&user_id);
     if let None = res {
@@ -708,7 +752,8 @@ pub fn emoji(bm: &mut BattleMgr, packet: Packet) {
         return;
     }
     let emoji_id = ce.emoji_id;
-    let res: Option<&EmojiTemp> = crate::TEMPLATES.emoji_temp_mgr().temps.get(&emoji_id);
+    let temps = crate::TEMPLATES.load();
+    let res: Option<&EmojiTemp> = temps.emoji_temp_mgr().temps.get(&emoji_id);
     if res.is_none() {
         warn!("there is no temp for emoji_id:{}", emoji_id);
         return;
@@ -731,6 +776,7 @@ pub fn emoji(bm: &mut BattleMgr, packet: Packet) {
 
 ///离线
 pub fn off_line(bm: &mut BattleMgr, packet: Packet) {
+    bm.record_packet(&packet);
     let user_id = packet.get_user_id();
 
     //校验用户不在战斗房间里
@@ -744,8 +790,37 @@ pub fn off_line(bm: &mut BattleMgr, packet: Packet) {
     bm.send_2_server(GameCode::UnloadUser.into_u32(), user_id, Vec::new());
 }
 
+///玩家暂时掉线，等待重连，不离开战斗
+pub fn disconnect(bm: &mut BattleMgr, packet: Packet) {
+    set_offline(bm, packet.get_user_id(), true);
+}
+
+///玩家断线重连成功
+pub fn reconnect(bm: &mut BattleMgr, packet: Packet) {
+    set_offline(bm, packet.get_user_id(), false);
+}
+
+fn set_offline(bm: &mut BattleMgr, user_id: u32, is_offline: bool) {
+    let room = bm.get_room_mut(&user_id);
+    if room.is_none() {
+        return;
+    }
+    let room = room.unwrap();
+    let room_id = room.get_room_id();
+    let battle_player = room.get_battle_player_mut_ref(&user_id);
+    if battle_player.is_none() {
+        return;
+    }
+    battle_player.unwrap().status.is_offline = is_offline;
+    info!(
+        "玩家战斗中掉线状态变化!room_id:{},user_id:{},is_offline:{}",
+        room_id, user_id, is_offline
+    );
+}
+
 ///离开房间
 pub fn leave_room(bm: &mut BattleMgr, packet: Packet) {
+    bm.record_packet(&packet);
     let user_id = packet.get_user_id();
 
     //校验用户不在战斗房间里
@@ -759,25 +834,15 @@ pub fn leave_room(bm: &mut BattleMgr, packet: Packet) {
 }
 
 pub fn reload_temps(_: &mut BattleMgr, _: Packet) {
-    let path = std::env::current_dir();
-    if let Err(e) = path {
-        error!("{:?}", e);
-        return;
-    }
-    let path = path.unwrap();
-    let str = path.as_os_str().to_str();
-    if let None = str {
-        error!("reload_temps can not path to_str!");
-        return;
-    }
-    let str = str.unwrap();
-    let res = str.to_string() + "/template";
-    let res = crate::TEMPLATES.reload_temps(res.as_str());
-    if let Err(e) = res {
-        error!("{:?}", e);
-        return;
+    let res = crate::TEMPLATES.reload();
+    match res {
+        Ok(report) => {
+            info!("reload_temps success!{}", report);
+        }
+        Err(e) => {
+            error!("reload_temps fail,keep old templates!{:?}", e);
+        }
     }
-    info!("reload_temps success!");
 }
 
 ///更新赛季
@@ -796,7 +861,8 @@ pub fn update_season(bm: &mut BattleMgr, packet: Packet) {
     }
 
     //处理更新内存
-    let mgr = crate::TEMPLATES.constant_temp_mgr();
+    let temps = crate::TEMPLATES.load();
+    let mgr = temps.constant_temp_mgr();
     let round_season_id = mgr.temps.get("round_season_id");
     if let None = round_season_id {
         warn!("the constant temp is None!key:round_season_id");
@@ -812,7 +878,6 @@ pub fn update_season(bm: &mut BattleMgr, packet: Packet) {
     if round_season_id != season_id {
         return;
     }
-    let mut redis_lock = crate::REDIS_POOL.lock().unwrap();
     //更新所有内存数据
     for &user_id in bm.player_room.clone().keys() {
         let room = bm.get_room_mut(&user_id);
@@ -825,7 +890,7 @@ pub fn update_season(bm: &mut BattleMgr, packet: Packet) {
             continue;
         }
         let member = member.unwrap();
-        let rank: Option<String> = redis_lock.hget(
+        let rank: Option<String> = crate::REDIS_POOL.hget(
             crate::REDIS_INDEX_RANK,
             crate::REDIS_KEY_CURRENT_RANK,
             user_id.to_string().as_str(),
@@ -839,6 +904,7 @@ pub fn update_season(bm: &mut BattleMgr, packet: Packet) {
 
 ///选择初始占位
 pub fn choice_index(bm: &mut BattleMgr, packet: Packet) {
+    bm.record_packet(&packet);
     let user_id = packet.get_user_id();
     let mut ccl = C_CHOOSE_INDEX::new();
     let res = ccl.merge_from_bytes(packet.get_data());
@@ -0,0 +1,291 @@
+pub mod battle;
+pub mod config;
+pub mod handlers;
+pub mod mgr;
+pub mod net;
+pub mod robot;
+pub mod room;
+pub mod sim;
+pub mod task_timer;
+
+use crate::config::BattleConfig;
+use crate::mgr::battle_mgr::BattleMgr;
+use crate::net::tcp_client;
+use async_std::sync::Mutex;
+use lazy_static::lazy_static;
+use log::{error, info, warn};
+use scheduled_thread_pool::ScheduledThreadPool;
+use std::env;
+use std::sync::Arc;
+use tools::conf::Conf;
+use tools::rating::{Rating, RatingConf};
+use tools::redis_pool::RedisPoolTool;
+use tools::registry::{Registry, ROLE_GAME_CENTER_BATTLE};
+use tools::templates::template::{init_temps_mgr, Templates};
+
+//初始化全局线程池
+lazy_static! {
+
+    ///定时器任务队列
+    static ref SCHEDULED_MGR : ScheduledThreadPool = {
+        let stp = ScheduledThreadPool::with_name("TASK_TIMER",8);
+        stp
+    };
+
+    ///静态配置文件
+    pub static ref TEMPLATES: Templates = {
+        init_templates_mgr()
+    };
+
+    ///机器人定时器任务队列
+    static ref ROBOT_SCHEDULED_MGR : ScheduledThreadPool = {
+        let stp = ScheduledThreadPool::with_name("ROBOT_TASK_TIMER",8);
+        stp
+    };
+
+    ///配置文件
+    static ref CONF_MAP: Conf = {
+        let path = env::current_dir().unwrap();
+        let str = path.as_os_str().to_str().unwrap();
+        let res = str.to_string()+"/config/config.conf";
+        let conf = Conf::init(res.as_str());
+        conf
+    };
+
+    ///校验过的配置
+    static ref CONFIG: BattleConfig = {
+        match CONF_MAP.parse() {
+            Ok(config) => config,
+            Err(e) => panic!("{:?}", e),
+        }
+    };
+
+    ///reids客户端
+    static ref REDIS_POOL: RedisPoolTool = {
+        let mut redis = match RedisPoolTool::init(&CONFIG.base.redis_add, &CONFIG.base.redis_pass) {
+            Ok(redis) => redis,
+            Err(e) => {
+                error!("初始化redis失败!{:?}", e);
+                std::process::exit(1);
+            }
+        };
+        //按游戏和区服隔离redis数据
+        redis.set_namespace(CONFIG.game.namespace().as_str(), &[]);
+        redis
+    };
+}
+
+pub static mut SEASON: Season = Season::new();
+
+pub struct Season {
+    season_id: i32,
+    next_update_time: u64,
+}
+
+impl Season {
+    const fn new() -> Self {
+        Season {
+            season_id: 0,
+            next_update_time: 0,
+        }
+    }
+}
+
+fn init_templates_mgr() -> Templates {
+    let path = env::current_dir().unwrap();
+    let str = path.as_os_str().to_str().unwrap();
+    let res = str.to_string() + "/template";
+    let conf = init_temps_mgr(res.as_str());
+    conf
+}
+
+const REDIS_INDEX_GAME_SEASON: u32 = 1;
+///排行榜redis索引
+const REDIS_INDEX_RANK: u32 = 2;
+const REDIS_KEY_GAME_SEASON: &str = "game_season";
+///当前赛季排行
+const REDIS_KEY_CURRENT_RANK: &str = "current_rank";
+///玩家评分
+const REDIS_KEY_RATING: &str = "rating";
+
+pub type Lock = Arc<Mutex<BattleMgr>>;
+type JsonValue = serde_json::Value;
+
+pub fn init_log() {
+    let info_log = &CONFIG.base.info_log_path;
+    let error_log = &CONFIG.base.error_log_path;
+    tools::my_log::init_log(info_log, error_log);
+    //打印生效的配置
+    info!("{}", CONF_MAP.dump(&*CONFIG));
+}
+
+///初始化数据包压缩
+pub fn init_compress() {
+    tools::compress::init(
+        CONFIG.base.compress_threshold,
+        CONFIG.base.compress_type.as_str(),
+    );
+}
+
+pub fn init_temps() {
+    let time = std::time::SystemTime::now();
+    lazy_static::initialize(&TEMPLATES);
+    let spend_time = time.elapsed().unwrap().as_millis();
+    info!("初始化templates成功!耗时:{}ms", spend_time);
+}
+
+///初始化tcp客户端
+pub fn init_tcp_client(bm: Lock) {
+    tcp_client::new(bm);
+}
+
+///发现游戏中心地址，还没有注册就一直等
+pub fn discover_game_center() -> String {
+    let registry = Registry::new(&CONFIG.registry);
+    let static_addresses = vec![CONFIG.tcp_port.clone()];
+    loop {
+        let res = registry.discover(&REDIS_POOL, ROLE_GAME_CENTER_BATTLE, &static_addresses);
+        if let Some(info) = res.first() {
+            return info.address.clone();
+        }
+        warn!("注册中心还没有游戏中心,等待游戏中心启动!");
+        std::thread::sleep(registry.heartbeat_interval());
+    }
+}
+
+///定时上报负载给游戏中心
+pub fn init_load_report(bm: Lock) {
+    let interval = CONFIG.load_report_interval;
+    let m = move || loop {
+        std::thread::sleep(std::time::Duration::from_secs(interval));
+        async_std::task::block_on(bm.lock()).report_load();
+    };
+    std::thread::spawn(m);
+}
+
+///收到退出信号后等当前战斗打完，超过drain_timeout秒还没打完就保存战斗录像再退出
+pub fn init_shutdown(bm: Lock) {
+    let m = move || {
+        let timeout = CONFIG.drain_timeout;
+        let time = std::time::SystemTime::now();
+        loop {
+            let room_num = async_std::task::block_on(bm.lock()).rooms.len();
+            if room_num == 0 {
+                break;
+            }
+            if time.elapsed().unwrap().as_secs() >= timeout {
+                let mut lock = async_std::task::block_on(bm.lock());
+                let snapshot_num = lock.snapshot_rooms();
+                warn!(
+                    "等待战斗结束超时!剩余战斗数量:{},保存录像数量:{}",
+                    room_num, snapshot_num
+                );
+                lock.drain_ack(snapshot_num);
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_secs(1));
+        }
+        //等确认消息发出去
+        std::thread::sleep(std::time::Duration::from_secs(1));
+    };
+    tools::shutdown::on_signal(m);
+}
+
+///评分参数
+pub fn rating_conf() -> RatingConf {
+    CONFIG.rating
+}
+
+///从redis加载玩家评分，没有就按积分初始化
+pub fn load_rating(user_id: u32, score: i32, conf: &RatingConf) -> Rating {
+    let res: Option<String> = REDIS_POOL.hget(
+        REDIS_INDEX_RANK,
+        REDIS_KEY_RATING,
+        user_id.to_string().as_str(),
+    );
+    if res.is_none() {
+        return Rating::new(score, conf);
+    }
+    let res = serde_json::from_str::<Rating>(res.unwrap().as_str());
+    if let Err(e) = res {
+        warn!("{:?}", e);
+        return Rating::new(score, conf);
+    }
+    let mut rating = res.unwrap();
+    rating.sync_score(score);
+    rating
+}
+
+///保存玩家评分到redis
+pub fn save_rating(user_id: u32, rating: &Rating) {
+    let res = serde_json::to_string(rating);
+    if let Err(e) = res {
+        error!("{:?}", e);
+        return;
+    }
+    let _: Option<String> = REDIS_POOL.hset(
+        REDIS_INDEX_RANK,
+        REDIS_KEY_RATING,
+        user_id.to_string().as_str(),
+        res.unwrap().as_str(),
+    );
+}
+
+///初始化赛季信息
+pub fn init_season() {
+    let game_id = CONFIG.game.game_id.to_string();
+
+    let res: Option<String> = REDIS_POOL.hget(
+        REDIS_INDEX_GAME_SEASON,
+        REDIS_KEY_GAME_SEASON,
+        game_id.as_str(),
+    );
+    if let None = res {
+        error!("redis do not has season data about game:{}", game_id);
+        return;
+    }
+    let str = res.unwrap();
+    let value = serde_json::from_str(str.as_str());
+    if let Err(e) = value {
+        error!("{:?}", e);
+        return;
+    }
+
+    let value: JsonValue = value.unwrap();
+    let map = value.as_object();
+    if map.is_none() {
+        warn!("the map is None for JsonValue!");
+        return;
+    }
+    let map = map.unwrap();
+
+    let season_id = map.get("season_id");
+    if season_id.is_none() {
+        warn!("the season_id is None!");
+        return;
+    }
+    let season_id = season_id.unwrap();
+    let season_id = season_id.as_u64();
+    if season_id.is_none() {
+        warn!("the season_id is None!");
+        return;
+    }
+    let season_id = season_id.unwrap();
+    unsafe {
+        SEASON.season_id = season_id as i32;
+        let next_update_time = map.get("next_update_time");
+        if next_update_time.is_none() {
+            warn!("the next_update_time is None!");
+            return;
+        }
+        let next_update_time = next_update_time.unwrap();
+        let next_update_time = next_update_time.as_u64();
+        if next_update_time.is_none() {
+            warn!("the next_update_time is None!");
+            return;
+        }
+
+        let next_update_time = next_update_time.unwrap();
+        SEASON.next_update_time = next_update_time;
+    }
+}
@@ -1,103 +1,37 @@
-mod battle;
-mod handlers;
-mod mgr;
-mod net;
-mod robot;
-mod room;
-mod task_timer;
-
-use crate::mgr::battle_mgr::BattleMgr;
-use crate::net::tcp_client;
-use crate::robot::robot_task_mgr::robot_init_timer;
-use crate::task_timer::init_timer;
 use async_std::sync::Mutex;
-use lazy_static::lazy_static;
-use log::{error, info, warn};
-use scheduled_thread_pool::ScheduledThreadPool;
+use battleserver::battle::battle_record::{self, BattleRecord};
+use battleserver::mgr::battle_mgr::BattleMgr;
+use battleserver::robot::robot_task_mgr::robot_init_timer;
+use battleserver::sim::{simulate, SimResult};
+use battleserver::task_timer::init_timer;
+use battleserver::{
+    init_compress, init_load_report, init_log, init_season, init_shutdown, init_tcp_client,
+    init_temps, TEMPLATES,
+};
+use rayon::prelude::*;
+use std::collections::BTreeMap;
 use std::env;
+use std::io::Write;
 use std::sync::Arc;
-use tools::conf::Conf;
-use tools::redis_pool::RedisPoolTool;
-use tools::templates::template::{init_temps_mgr, TemplatesMgr};
 
-//初始化全局线程池
-lazy_static! {
-
-    ///定时器任务队列
-    static ref SCHEDULED_MGR : ScheduledThreadPool = {
-        let stp = ScheduledThreadPool::with_name("TASK_TIMER",8);
-        stp
-    };
-
-    ///静态配置文件
-    static ref TEMPLATES: TemplatesMgr = {
-        init_templates_mgr()
-    };
-
-    ///机器人定时器任务队列
-    static ref ROBOT_SCHEDULED_MGR : ScheduledThreadPool = {
-        let stp = ScheduledThreadPool::with_name("ROBOT_TASK_TIMER",8);
-        stp
-    };
-
-    ///配置文件
-    static ref CONF_MAP: Conf = {
-        let path = env::current_dir().unwrap();
-        let str = path.as_os_str().to_str().unwrap();
-        let res = str.to_string()+"/config/config.conf";
-        let conf = Conf::init(res.as_str());
-        conf
-    };
-
-    ///reids客户端
-    static ref REDIS_POOL:Arc<std::sync::Mutex<RedisPoolTool>>={
-        let add: &str = &CONF_MAP.get_str("redis_add","");
-        let pass: &str = &CONF_MAP.get_str("redis_pass","");
-        let redis = RedisPoolTool::init(add,pass);
-        let redis:Arc<std::sync::Mutex<RedisPoolTool>> = Arc::new(std::sync::Mutex::new(redis));
-        redis
-    };
-}
-
-pub static mut SEASON: Season = Season::new();
-
-pub struct Season {
-    season_id: i32,
-    next_update_time: u64,
-}
-
-impl Season {
-    const fn new() -> Self {
-        Season {
-            season_id: 0,
-            next_update_time: 0,
-        }
+fn main() -> anyhow::Result<()> {
+    //回放模式: battleserver replay 录像文件路径
+    let args: Vec<String> = env::args().collect();
+    if args.len() > 2 && args[1] == "replay" {
+        return replay(args[2].as_str());
+    }
+    //模拟模式: battleserver sim 场数 角色id(逗号分隔) 地图id 输出csv路径 [起始种子]
+    if args.len() > 5 && args[1] == "sim" {
+        return sim(&args[2..]);
     }
-}
-
-fn init_templates_mgr() -> TemplatesMgr {
-    let path = env::current_dir().unwrap();
-    let str = path.as_os_str().to_str().unwrap();
-    let res = str.to_string() + "/template";
-    let conf = init_temps_mgr(res.as_str());
-    conf
-}
-
-const REDIS_INDEX_GAME_SEASON: u32 = 1;
-///排行榜redis索引
-const REDIS_INDEX_RANK: u32 = 2;
-const REDIS_KEY_GAME_SEASON: &str = "game_season";
-///当前赛季排行
-const REDIS_KEY_CURRENT_RANK: &str = "current_rank";
-
-type Lock = Arc<Mutex<BattleMgr>>;
-type JsonValue = serde_json::Value;
 
-fn main() -> anyhow::Result<()> {
     let bm = Arc::new(Mutex::new(BattleMgr::new()));
     //初始化日志模块
     init_log();
 
+    //初始化数据包压缩
+    init_compress();
+
     //初始化配置
     init_temps();
 
@@ -110,82 +44,131 @@ fn main() -> anyhow::Result<()> {
     //初始化赛季
     init_season();
 
+    //初始化负载上报
+    init_load_report(bm.clone());
+
+    //初始化退出信号处理
+    init_shutdown(bm.clone());
+
     //初始化网络
     init_tcp_client(bm);
 
     Ok(())
 }
 
-fn init_log() {
-    let info_log = &CONF_MAP.get_str("info_log_path", "");
-    let error_log = &CONF_MAP.get_str("error_log_path", "");
-    tools::my_log::init_log(info_log, error_log);
-}
-
-fn init_temps() {
-    let time = std::time::SystemTime::now();
-    lazy_static::initialize(&TEMPLATES);
-    let spend_time = time.elapsed().unwrap().as_millis();
-    info!("初始化templates成功!耗时:{}ms", spend_time);
-}
-
-///初始化tcp服务端
-fn init_tcp_client(bm: Lock) {
-    let tcp_port: &str = &CONF_MAP.get_str("tcp_port", "");
-    tcp_client::new(tcp_port, bm);
-}
-
-///初始化赛季信息
-fn init_season() {
-    let mut lock = REDIS_POOL.lock().unwrap();
-
-    let res: Option<String> = lock.hget(REDIS_INDEX_GAME_SEASON, REDIS_KEY_GAME_SEASON, "101");
-    if let None = res {
-        error!("redis do not has season data about game:{}", 101);
-        return;
-    }
-    let str = res.unwrap();
-    let value = serde_json::from_str(str.as_str());
-    if let Err(e) = value {
-        error!("{:?}", e);
-        return;
+///离线回放战斗录像,打印结算结果
+fn replay(path: &str) -> anyhow::Result<()> {
+    init_log();
+    init_temps();
+    let record = BattleRecord::load(path)?;
+    let res = battle_record::replay(&record)?;
+    println!(
+        "room_id:{},battle_id:{},seed:{},events:{},action_notices:{}",
+        record.room_id,
+        record.battle_id,
+        record.seed,
+        record.events.len(),
+        res.action_notices.len()
+    );
+    for (user_id, rank) in res.ranks.iter() {
+        println!("rank => user_id:{},rank:{}", user_id, rank);
     }
-
-    let value: JsonValue = value.unwrap();
-    let map = value.as_object();
-    if map.is_none() {
-        warn!("the map is None for JsonValue!");
-        return;
+    for (user_id, ssn) in res.summary_notices.iter() {
+        for sd in ssn.summary_datas.iter() {
+            println!(
+                "summary to user_id:{} => user_id:{},cter_id:{},rank:{},reward_score:{}",
+                user_id, sd.user_id, sd.cter_id, sd.rank, sd.reward_score
+            );
+        }
     }
-    let map = map.unwrap();
+    Ok(())
+}
 
-    let season_id = map.get("season_id");
-    if season_id.is_none() {
-        warn!("the season_id is None!");
-        return;
-    }
-    let season_id = season_id.unwrap();
-    let season_id = season_id.as_u64();
-    if season_id.is_none() {
-        warn!("the season_id is None!");
-        return;
+///批量模拟全机器人战斗,统计角色胜率、技能使用次数和平均turn数,输出csv
+fn sim(args: &[String]) -> anyhow::Result<()> {
+    init_log();
+    init_temps();
+    let count: u64 = args[0].parse()?;
+    let mut cter_ids = Vec::new();
+    for id in args[1].split(',') {
+        cter_ids.push(id.trim().parse::<u32>()?);
     }
-    let season_id = season_id.unwrap();
-    unsafe {
-        SEASON.season_id = season_id as i32;
-        let next_update_time = map.get("next_update_time");
-        if next_update_time.is_none() {
-            warn!("the next_update_time is None!");
-            return;
+    let map_id: u32 = args[2].parse()?;
+    let out_path = args[3].as_str();
+    let base_seed: u64 = match args.get(4) {
+        Some(seed) => seed.parse()?,
+        None => 0,
+    };
+    let temps = TEMPLATES.load();
+    let tile_map_temp = temps.tile_map_temp_mgr().get_temp(map_id)?;
+
+    let results: Vec<anyhow::Result<SimResult>> = (0..count)
+        .into_par_iter()
+        .map(|index| simulate(cter_ids.as_slice(), tile_map_temp, base_seed + index))
+        .collect();
+
+    //角色配置id,(场数,胜场,平局)
+    let mut cter_stat: BTreeMap<u32, (u32, u32, u32)> = BTreeMap::new();
+    let mut skill_use: BTreeMap<(u32, u32), u32> = BTreeMap::new();
+    let mut total_turn_times = 0_u64;
+    let mut finish_count = 0_u64;
+    for res in results {
+        let res = match res {
+            Ok(res) => res,
+            Err(e) => {
+                println!("{:?}", e);
+                continue;
+            }
+        };
+        finish_count += 1;
+        total_turn_times += res.total_turn_times as u64;
+        for (cter_temp_id, rank) in res.ranks.iter() {
+            let stat = cter_stat.entry(*cter_temp_id).or_insert((0, 0, 0));
+            stat.0 += 1;
+            match rank {
+                Some(0) => stat.1 += 1,
+                None => stat.2 += 1,
+                _ => {}
+            }
         }
-        let next_update_time = next_update_time.unwrap();
-        let next_update_time = next_update_time.as_u64();
-        if next_update_time.is_none() {
-            warn!("the next_update_time is None!");
-            return;
+        for (key, times) in res.skill_use.iter() {
+            *skill_use.entry(*key).or_insert(0) += *times;
         }
-
-        let next_update_time = next_update_time.unwrap();
-        SEASON.next_update_time = next_update_time;
     }
+    if finish_count == 0 {
+        anyhow::bail!("no simulate finished!")
+    }
+    let avg_turn_times = total_turn_times as f64 / finish_count as f64;
+
+    let mut file = std::fs::File::create(out_path)?;
+    writeln!(file, "cter_id,battles,wins,draws,win_rate,avg_turn_times")?;
+    for (cter_temp_id, (battles, wins, draws)) in cter_stat.iter() {
+        writeln!(
+            file,
+            "{},{},{},{},{:.4},{:.2}",
+            cter_temp_id,
+            battles,
+            wins,
+            draws,
+            *wins as f64 / *battles as f64,
+            avg_turn_times
+        )?;
+    }
+    writeln!(file)?;
+    writeln!(file, "cter_id,skill_id,use_times,avg_use_times")?;
+    for ((cter_temp_id, skill_id), times) in skill_use.iter() {
+        writeln!(
+            file,
+            "{},{},{},{:.2}",
+            cter_temp_id,
+            skill_id,
+            times,
+            *times as f64 / finish_count as f64
+        )?;
+    }
+    println!(
+        "simulate finish!count:{},finish:{},avg_turn_times:{:.2},out:{}",
+        count, finish_count, avg_turn_times, out_path
+    );
+    Ok(())
 }
@@ -1,5 +1,6 @@
 use crate::handlers::battle_handler::{
-    action, buy, choice_index, emoji, leave_room, off_line, pos, reload_temps, start, update_season,
+    action, buy, choice_index, disconnect, drain, emoji, leave_room, off_line, pos, reconnect,
+    reload_temps, start, update_season,
 };
 use crate::robot::robot_task_mgr::RobotTask;
 use crate::room::room::Room;
@@ -10,6 +11,7 @@ use log::{info, warn};
 use std::collections::hash_map::RandomState;
 use std::collections::HashMap;
 use tools::cmd_code::{BattleCode, ServerCommonCode};
+use tools::util::bytebuf::ByteBuf;
 use tools::util::packet::Packet;
 
 type CmdFn = HashMap<u32, fn(&mut BattleMgr, Packet), RandomState>;
@@ -23,6 +25,9 @@ pub struct BattleMgr {
     pub game_center_channel: Option<Sender<Vec<u8>>>, //tcp客户的
     pub task_sender: Option<Sender<Task>>,            //task channel的发送方
     pub robot_task_sender: Option<Sender<RobotTask>>, //机器人task channel的发送方
+    pub record_path: String,                          //战斗录像保存目录,为空则不保存
+    pub finished_rooms: Option<Vec<Room>>,            //已结束的房间(模拟战斗统计用),为None则不保留
+    pub drain_acked: bool,                            //是否已回复停服排空确认
 }
 
 tools::get_mut_ref!(BattleMgr);
@@ -33,7 +38,10 @@ impl BattleMgr {
     }
 
     pub fn new() -> BattleMgr {
-        let mut bm = BattleMgr::default();
+        let mut bm = BattleMgr {
+            record_path: crate::CONFIG.record_path.clone(),
+            ..Default::default()
+        };
         bm.cmd_init();
         bm
     }
@@ -96,6 +104,21 @@ impl BattleMgr {
         self.rooms.get_mut(&room_id)
     }
 
+    ///记录战斗录像事件
+    pub fn record_packet(&mut self, packet: &Packet) {
+        let user_id = packet.get_user_id();
+        let room = self.get_room_mut(&user_id);
+        if let Some(room) = room {
+            let turn = room.battle_data.turn;
+            room.battle_data.record.record_packet(
+                packet.get_cmd(),
+                user_id,
+                turn,
+                packet.get_data(),
+            );
+        }
+    }
+
     #[allow(dead_code)]
     pub fn get_room_ref(&self, user_id: &u32) -> Option<&Room> {
         let res = self.player_room.get(user_id);
@@ -116,12 +139,90 @@ impl BattleMgr {
             for user_id in room.members.keys() {
                 self.player_room.remove(user_id);
             }
+            //保存战斗录像
+            if !self.record_path.is_empty() {
+                let res = room.battle_data.record.save(self.record_path.as_str());
+                match res {
+                    Ok(path) => info!("保存战斗录像成功!room_id:{},path:{}", room_id, path),
+                    Err(e) => warn!("{:?}", e),
+                }
+            }
 
             info!(
                 "房间战斗结束！删除房间，释放内存！room_type:{:?},room_id:{}",
                 room_type, room_id
             );
+            if let Some(finished_rooms) = self.finished_rooms.as_mut() {
+                finished_rooms.push(room);
+            }
+            //停服排空中，最后一场战斗打完了就回复确认
+            if tools::shutdown::is_draining() && self.rooms.is_empty() {
+                self.drain_ack(0);
+            }
+        }
+    }
+
+    ///停服排空超时，把还没打完的战斗录像保存下来，返回保存的数量
+    pub fn snapshot_rooms(&mut self) -> usize {
+        let mut count = 0;
+        if self.record_path.is_empty() {
+            warn!("record_path is empty,can not snapshot rooms!");
+            return count;
+        }
+        for room in self.rooms.values() {
+            let res = room.battle_data.record.save(self.record_path.as_str());
+            match res {
+                Ok(path) => {
+                    count += 1;
+                    info!(
+                        "停服保存未结束的战斗录像!room_id:{},path:{}",
+                        room.get_room_id(),
+                        path
+                    );
+                }
+                Err(e) => warn!("{:?}", e),
+            }
         }
+        count
+    }
+
+    ///回复停服排空确认，只回复一次
+    pub fn drain_ack(&mut self, snapshot_num: usize) {
+        if self.drain_acked {
+            return;
+        }
+        self.drain_acked = true;
+        let value = serde_json::json!({
+            "server_type":"battle",
+            "room_num":self.rooms.len(),
+            "snapshot_num":snapshot_num,
+        });
+        info!("战斗服停服排空完成!{}", value);
+        self.send_2_server(
+            ServerCommonCode::DrainAck.into_u32(),
+            0,
+            value.to_string().into_bytes(),
+        );
+    }
+
+    ///上报负载给游戏中心，游戏中心按房间数分配新的战斗
+    pub fn report_load(&mut self) {
+        if self.game_center_channel.is_none() {
+            return;
+        }
+        let bytes = self.build_load_report_bytes();
+        let res = self.get_game_center_channel_mut().send(bytes);
+        if let Err(e) = res {
+            warn!("{:?}", e);
+        }
+    }
+
+    ///负载上报消息,body:u32房间数量
+    pub fn build_load_report_bytes(&self) -> Vec<u8> {
+        let mut bb = ByteBuf::new();
+        bb.push_u32(self.rooms.len() as u32);
+        let cmd = ServerCommonCode::LoadReport.into_u32();
+        Packet::build_packet_bytes(cmd, 0, bb.into_bytes(), true, false)
     }
 
     ///处理玩家离开战斗
@@ -165,6 +266,9 @@ impl BattleMgr {
         //热更静态配置
         self.cmd_map
             .insert(ServerCommonCode::ReloadTemps.into_u32(), reload_temps);
+        //停服排空
+        self.cmd_map
+            .insert(ServerCommonCode::Drain.into_u32(), drain);
         //更新赛季信息
         self.cmd_map
             .insert(BattleCode::UpdateSeasonPush.into_u32(), update_season);
@@ -174,6 +278,12 @@ impl BattleMgr {
         //离开房间
         self.cmd_map
             .insert(BattleCode::LeaveRoom.into_u32(), leave_room);
+        //暂时掉线
+        self.cmd_map
+            .insert(BattleCode::Disconnect.into_u32(), disconnect);
+        //断线重连
+        self.cmd_map
+            .insert(BattleCode::Reconnect.into_u32(), reconnect);
         //开始战斗
         self.cmd_map.insert(BattleCode::Start.into_u32(), start);
 
@@ -39,7 +39,8 @@ impl League {
             self.score = 0;
             return 0;
         }
-        let mgr = crate::TEMPLATES.league_temp_mgr();
+        let temps = crate::TEMPLATES.load();
+        let mgr = temps.league_temp_mgr();
         if score < 0 {
             let league_temp = mgr.get_temp(&self.league_id);
             if let Err(_) = league_temp {
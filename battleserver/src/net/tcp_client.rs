@@ -1,9 +1,8 @@
 use crate::Lock;
 use async_std::task::{block_on, spawn};
-use async_trait::async_trait;
 use crossbeam::channel::Sender;
 use log::{error, warn};
-use tools::tcp::ClientHandler;
+use tools::reconnect::{ReconnectConf, ReconnectHandler};
 use tools::util::packet::Packet;
 
 ///处理客户端所有请求,每个客户端单独分配一个handler
@@ -19,20 +18,25 @@ impl TcpClientHandler {
     }
 }
 
-#[async_trait]
-impl ClientHandler for TcpClientHandler {
-    async fn on_open(&mut self, ts: Sender<Vec<u8>>) {
-        let mut lock = block_on(self.bm.lock());
-        lock.set_game_center_channel(ts);
+impl ReconnectHandler for TcpClientHandler {
+    fn address(&mut self) -> Option<String> {
+        //游戏中心可能换了地址，重新发现
+        Some(crate::discover_game_center())
     }
 
-    async fn on_close(&mut self) {
-        let address = crate::CONF_MAP.get_str("tcp_port", "");
+    fn handshake(&mut self) -> Vec<Vec<u8>> {
+        //告诉游戏中心自己属于哪个游戏和区服,重连后马上上报负载
+        let register = crate::CONFIG.game.build_register_bytes();
+        let load = block_on(self.bm.lock()).build_load_report_bytes();
+        vec![register, load]
+    }
 
-        self.on_read(address.to_string()).await;
+    fn on_connect(&mut self, ts: Sender<Vec<u8>>, _reconnect: bool) {
+        let mut lock = block_on(self.bm.lock());
+        lock.set_game_center_channel(ts);
     }
 
-    async fn on_message(&mut self, mess: Vec<u8>) {
+    fn on_message(&mut self, mess: Vec<u8>) {
         let packet_array = Packet::build_array_from_server(mess);
 
         if let Err(e) = packet_array {
@@ -57,9 +61,12 @@ async fn handler_mess_s(bm: Lock, packet: Packet) {
     lock.invok(packet);
 }
 
-///创建新的tcp服务器,如果有问题，终端进程
-pub fn new(address: &str, bm: Lock) {
-    let mut tch = TcpClientHandler::new(bm);
-    let res = tch.on_read(address.to_string());
-    block_on(res);
+///连接游戏中心，断线后自动重连，阻塞到不再重连为止
+pub fn new(bm: Lock) {
+    let tch = TcpClientHandler::new(bm);
+    let conf = ReconnectConf::new(&crate::CONFIG.reconnect);
+    let res = tools::reconnect::spawn("GAME_CENTER_CLIENT", tch, conf).join();
+    if let Err(e) = res {
+        error!("{:?}", e);
+    }
 }
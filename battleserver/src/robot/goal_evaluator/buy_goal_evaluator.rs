@@ -6,7 +6,6 @@ use crate::robot::robot_status::buy_action::BuyRobotAction;
 use crate::robot::robot_task_mgr::RobotTask;
 // use crossbeam::atomic::AtomicCell;
 use crossbeam::channel::Sender;
-use rand::Rng;
 
 #[derive(Default)]
 pub struct BuyGoalEvaluator {
@@ -48,8 +47,7 @@ impl GoalEvaluator for BuyGoalEvaluator {
             let res = check_buy(robot, robot_data.temp_id);
             //只能买一个的时候，并且不在商店，并且有行动点数的时候
             if res.len() == 1 && !is_at_market && robot.flow_data.residue_movement_points > 0 {
-                let mut rand = rand::thread_rng();
-                let res = rand.gen_range(40..56);
+                let res = battle_data.rng.gen_range(40..56);
                 return res;
             } else if res.len() > 1 && !is_at_market && robot.flow_data.residue_movement_points > 0
             {
@@ -78,7 +76,8 @@ impl GoalEvaluator for BuyGoalEvaluator {
 }
 
 fn get_could_buy(battle_player: &BattlePlayer, merchandise_id: u32) -> bool {
-    let merchandise_temp = crate::TEMPLATES.merchandise_temp_mgr();
+    let temps = crate::TEMPLATES.load();
+    let merchandise_temp = temps.merchandise_temp_mgr();
     let temp = merchandise_temp.get_temp(&merchandise_id);
     if let Err(_) = temp {
         return false;
@@ -101,9 +100,8 @@ fn get_could_buy(battle_player: &BattlePlayer, merchandise_id: u32) -> bool {
 }
 
 pub fn check_buy(battle_player: &BattlePlayer, robot_temp_id: u32) -> Vec<u32> {
-    let temp = crate::TEMPLATES
-        .robot_temp_mgr()
-        .get_temp_ref(&robot_temp_id);
+    let temps = crate::TEMPLATES.load();
+    let temp = temps.robot_temp_mgr().get_temp_ref(&robot_temp_id);
     if let None = temp {
         return vec![];
     }
@@ -1,18 +1,14 @@
 use std::collections::HashMap;
 
 use log::{error, warn};
-use rand::Rng;
 use serde_json::{Map, Value};
 use tools::cmd_code::BattleCode;
 
 use crate::battle::battle_enum::SkillConsumeType;
+use crate::battle::{battle::BattleData, battle_skill::Skill};
 use crate::battle::{battle_enum::TargetType, battle_player::BattlePlayer};
 use crate::handlers::battle_handler::check_skill_useable;
 use crate::room::map_data::MapCellType;
-use crate::{
-    battle::{battle::BattleData, battle_skill::Skill},
-    room::map_data::TileMap,
-};
 
 use super::robot_task_mgr::RobotTask;
 use super::{RobotActionType, RobotData};
@@ -286,7 +282,7 @@ pub fn skill_target(
         }
         //随机不在记忆队列中的地图块
         i if [423].contains(&i) => {
-            let res = rand_not_remember_map_cell(&battle_data.tile_map, robot);
+            let res = rand_not_remember_map_cell(battle_data, robot);
             if let Some(index) = res {
                 targets.push(index);
             }
@@ -354,8 +350,7 @@ pub fn skill_target(
                 }
                 v.push(cter.get_map_cell_index());
             }
-            let mut random = rand::thread_rng();
-            let index = random.gen_range(0..v.len());
+            let index = battle_data.rng.gen_range(0..v.len());
             let &target_cter_index = v.get(index).unwrap();
             targets.push(target_cter_index);
         }
@@ -459,8 +454,7 @@ pub fn skill_target(
                     }
                 }
             }
-            let mut random = rand::thread_rng();
-            let random_index = random.gen_range(0..res_v.len());
+            let random_index = battle_data.rng.gen_range(0..res_v.len());
             let &index = res_v.get(random_index).unwrap();
             targets.push(index);
         }
@@ -554,7 +548,7 @@ pub fn skill_target(
             if res_v.is_empty() {
                 return Ok(targets);
             }
-            let mut random = rand::thread_rng();
+            let random = &battle_data.rng;
 
             for _ in 0..2 {
                 if res_v.is_empty() {
@@ -578,7 +572,8 @@ pub fn skill_target(
 }
 
 ///随机一个不在记忆队列中的地图块
-pub fn rand_not_remember_map_cell(tile_map: &TileMap, robot: &RobotData) -> Option<usize> {
+pub fn rand_not_remember_map_cell(battle_data: &BattleData, robot: &RobotData) -> Option<usize> {
+    let tile_map = &battle_data.tile_map;
     let remember_map_cell = &robot.remember_map_cell;
 
     let mut not_c_v = vec![];
@@ -593,7 +588,7 @@ pub fn rand_not_remember_map_cell(tile_map: &TileMap, robot: &RobotData) -> Opti
         }
         v.push(map_cell_index);
     }
-    let mut rand = rand::thread_rng();
+    let rand = &battle_data.rng;
 
     let index;
     let rand_index;
@@ -645,7 +640,7 @@ pub fn skill_open_near_cell_robot(
         return None;
     }
 
-    let mut rand = rand::thread_rng();
+    let rand = &battle_data.rng;
     //如果记忆队列中小于1个，直接返回
     let remember_map_cell = &robot_data.remember_map_cell;
     if remember_map_cell.is_empty() {
@@ -1186,7 +1181,7 @@ pub fn get_line_aoe(cter_id: u32, battle_data: &BattleData) -> Option<(u32, Vec<
         }
     }
     if !res_3.is_empty() {
-        let mut rand = rand::thread_rng();
+        let rand = &battle_data.rng;
         let mut index = rand.gen_range(0..res_3.len());
         let mut res = res_3.remove(index);
 
@@ -1202,7 +1197,7 @@ pub fn get_line_aoe(cter_id: u32, battle_data: &BattleData) -> Option<(u32, Vec<
 
         return Some((3, fin_res));
     } else if !res_2.is_empty() {
-        let mut rand = rand::thread_rng();
+        let rand = &battle_data.rng;
         let mut index = rand.gen_range(0..res_2.len());
         let mut res = res_2.remove(index);
 
@@ -1218,7 +1213,7 @@ pub fn get_line_aoe(cter_id: u32, battle_data: &BattleData) -> Option<(u32, Vec<
         }
         return Some((2, fin_res));
     } else if !res_1.is_empty() {
-        let mut rand = rand::thread_rng();
+        let rand = &battle_data.rng;
         let mut index = rand.gen_range(0..res_1.len());
         let mut res = res_1.remove(index);
 
@@ -57,7 +57,8 @@ impl RobotStatusAction for AttackRobotAction {
         let robot_id = self.robot_id;
         let robot = battle_data.battle_player.get(&robot_id).unwrap();
         let cter_id = robot.current_cter.0;
-        let skill_scope_temp = crate::TEMPLATES
+        let temps = crate::TEMPLATES.load();
+        let skill_scope_temp = temps
             .skill_scope_temp_mgr()
             .get_temp(&TRIGGER_SCOPE_CENTER_NEAR_TEMP_ID)
             .unwrap();
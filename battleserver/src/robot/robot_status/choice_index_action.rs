@@ -68,8 +68,7 @@ impl RobotStatusAction for ChoiceIndexRobotAction {
             }
             v.push(index);
         }
-        let mut rand = rand::thread_rng();
-        let res = rand.gen_range(0..v.len());
+        let res = battle_data.rng.gen_range(0..v.len());
         let index = v.remove(res);
         //创建机器人任务执行选择站位
         self.send_2_battle(index, RobotActionType::ChoiceIndex, BattleCode::ChoiceIndex);
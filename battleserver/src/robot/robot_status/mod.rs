@@ -11,7 +11,6 @@ use crate::battle::battle::BattleData;
 use crate::robot::robot_action::RobotStatusAction;
 use crate::robot::robot_task_mgr::RobotTask;
 use crossbeam::channel::Sender;
-use rand::Rng;
 
 use log::info;
 use num_enum::IntoPrimitive;
@@ -87,7 +87,7 @@ impl RobotStatusAction for OpenCellRobotAction {
             }
             v.push(*key);
         }
-        let mut rand = rand::thread_rng();
+        let rand = &battle_data.rng;
         let mut index = None;
 
         let mut action_type = RobotActionType::Open;
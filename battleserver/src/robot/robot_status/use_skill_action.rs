@@ -79,8 +79,7 @@ impl RobotStatusAction for UseSkillRobotAction {
             warn!("the targets is empty!");
             return;
         }
-        let mut rand = rand::thread_rng();
-        let index = rand.gen_range(0..v.len());
+        let index = battle_data.rng.gen_range(0..v.len());
         let skill = v.get(index).unwrap();
         let battle_data = self.get_battle_data_mut_ref().unwrap();
         let res = robot_use_skill(battle_data, skill, robot);
@@ -48,17 +48,7 @@ pub fn robot_init_timer(bm: Lock) {
 
             let task_cmd = task.action_type;
             let rm_clone = bm.clone();
-            let fnc = match task_cmd {
-                RobotActionType::Attack => attack,
-                RobotActionType::Skill => use_skill,
-                RobotActionType::Open => open_cell,
-                RobotActionType::Skip => skip_turn,
-                RobotActionType::UseItem => use_item,
-                RobotActionType::ChoiceIndex => choice_index,
-                RobotActionType::Buy => buy,
-                RobotActionType::Unlock => unlock,
-                RobotActionType::None => none,
-            };
+            let fnc = robot_task_fn(task_cmd);
             let m = move || fnc(rm_clone, task);
             ROBOT_SCHEDULED_MGR.execute_after(Duration::from_millis(delay), m);
         }
@@ -71,6 +61,21 @@ pub fn robot_init_timer(bm: Lock) {
     info!("初始化定时器任务执行器成功!");
 }
 
+///根据机器人行为类型获得执行函数
+pub fn robot_task_fn(action_type: RobotActionType) -> fn(Lock, RobotTask) {
+    match action_type {
+        RobotActionType::Attack => attack,
+        RobotActionType::Skill => use_skill,
+        RobotActionType::Open => open_cell,
+        RobotActionType::Skip => skip_turn,
+        RobotActionType::UseItem => use_item,
+        RobotActionType::ChoiceIndex => choice_index,
+        RobotActionType::Buy => buy,
+        RobotActionType::Unlock => unlock,
+        RobotActionType::None => none,
+    }
+}
+
 ///机器人选择站位函数
 pub fn choice_index(rm: Lock, task: RobotTask) {
     let json_value = task.data;
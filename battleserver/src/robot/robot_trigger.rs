@@ -1,7 +1,6 @@
 use crate::robot::{RememberCell, RobotData};
 use num_enum::IntoPrimitive;
 use num_enum::TryFromPrimitive;
-use rand::Rng;
 
 ///触发器类型
 #[derive(Debug, Clone, Copy, Eq, PartialEq, TryFromPrimitive, IntoPrimitive)]
@@ -42,8 +41,8 @@ impl RobotData {
         self.remember_map_cell.push_front(rc);
         //如果数量大于5则忘记尾端
         if size > max_size {
-            let mut rand = rand::thread_rng();
-            let res = rand.gen_range(0..100);
+            let battle_data = unsafe { self.battle_data.as_ref().unwrap() };
+            let res = battle_data.rng.gen_range(0..100);
             let forget = (size - 2) * 10;
             //50%机率忘记队列前面的
             if res < forget {
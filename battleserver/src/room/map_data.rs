@@ -6,40 +6,11 @@ use crate::battle::battle_enum::buff_type::TRAPS;
 use crate::battle::battle_enum::BattlePlayerState;
 use crate::room::RoomType;
 use crate::TEMPLATES;
-use num_enum::IntoPrimitive;
-use num_enum::TryFromPrimitive;
-use rand::Rng;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::convert::TryFrom;
 use std::slice::Iter;
-
-#[derive(Debug, Clone, Copy, Eq, PartialEq, TryFromPrimitive, IntoPrimitive)]
-#[repr(u8)]
-pub enum MapCellType {
-    InValid = 0,
-    UnUse = 1,
-    Valid = 2,
-    WorldCell = 3,
-    MarketCell = 4,
-}
-
-impl Default for MapCellType {
-    fn default() -> Self {
-        MapCellType::InValid
-    }
-}
-
-impl MapCellType {
-    pub fn into_u8(self) -> u8 {
-        let res: u8 = self.into();
-        res
-    }
-
-    pub fn into_u32(self) -> u32 {
-        let res = self.into_u8();
-        res as u32
-    }
-}
+pub use tools::templates::tile_map_temp::MapCellType;
+use tools::templates::tile_map_temp::TileMapTemp;
 
 ///地图
 #[derive(Debug, Default, Clone)]
@@ -50,7 +21,7 @@ pub struct TileMap {
     pub world_cell: (usize, u32),                  //世界块 index,世界块id
     pub market_cell: (usize, u32),                 //商店 index,商店id
     pub season_id: i32,                            //当前地图赛季id
-    pub un_pair_map: HashMap<usize, u32>,          //未配对的地图块map
+    pub un_pair_map: BTreeMap<usize, u32>,         //未配对的地图块map
     pub world_boss_init_index: usize,              //boss出场位置
 }
 
@@ -197,6 +168,15 @@ impl TileMap {
         season_is_open: bool,
         last_map_id: u32,
     ) -> anyhow::Result<Self> {
+        //指定了地图的话(模拟战斗)，直接用指定的地图
+        if let Some(tile_map_temp) = battle_data.fixed_tile_map.as_ref() {
+            let season_id = if tile_map_temp.world_cell > 0 {
+                unsafe { crate::SEASON.season_id }
+            } else {
+                0
+            };
+            return TileMap::init_by_temp(battle_data, tile_map_temp, season_id);
+        }
         let room_type = battle_data.room_type;
         let mut member_count = battle_data
             .battle_player
@@ -207,10 +187,11 @@ impl TileMap {
         if member_count == 3 {
             member_count += 1;
         }
-        //创建随机结构体实例
-        let mut rand = rand::thread_rng();
+        //拿到战斗随机数生成器
+        let rand = &battle_data.rng;
         //拿到地图配置管理器
-        let tile_map_mgr = TEMPLATES.tile_map_temp_mgr();
+        let temps = TEMPLATES.load();
+        let tile_map_mgr = temps.tile_map_temp_mgr();
         let mut season_id = 0;
         //第一次初始化地图
         if last_map_id == 0 {
@@ -278,6 +259,8 @@ impl TileMap {
                     for cell_index in tile_map_temp_map.keys() {
                         index_v.push(*cell_index);
                     }
+                    //排序，保证同一个种子随机结果一致
+                    index_v.sort();
                     let index = rand.gen_range(0..index_v.len());
                     world_cell_index = *index_v.get(index).unwrap();
                 }
@@ -304,6 +287,17 @@ impl TileMap {
         let map_random_index = rand.gen_range(0..tile_map_temp_v.len());
 
         let tile_map_temp = tile_map_temp_v.get(map_random_index).unwrap();
+        TileMap::init_by_temp(battle_data, tile_map_temp, season_id)
+    }
+
+    ///根据地图配置初始化战斗地图数据
+    pub fn init_by_temp(
+        battle_data: &BattleData,
+        tile_map_temp: &TileMapTemp,
+        season_id: i32,
+    ) -> anyhow::Result<Self> {
+        let room_type = battle_data.room_type;
+        let rand = &battle_data.rng;
         let mut tmp = TileMap::default();
         tmp.id = tile_map_temp.id;
 
@@ -339,10 +333,11 @@ impl TileMap {
             map[index_value] = (tile_map_temp.market_id, MapCellType::MarketCell);
             tmp.market_cell = (index_value, tile_map_temp.market_id);
         }
-        let mut random_vec = TEMPLATES.cell_temp_mgr().type_vec.clone();
+        let temps = TEMPLATES.load();
+        let mut random_vec = temps.cell_temp_mgr().type_vec.clone();
         //然后就是rare_map_cell
         for map_cell_rare in tile_map_temp.cell_rare.iter() {
-            let element_vec = TEMPLATES
+            let element_vec = temps
                 .cell_temp_mgr()
                 .rare_map
                 .get(&map_cell_rare.rare)
@@ -406,14 +401,10 @@ impl TileMap {
             x += 1;
             let mut buffs: Option<Iter<u32>> = None;
             if map_cell.is_world() {
-                let world_map_cell = TEMPLATES
-                    .world_cell_temp_mgr()
-                    .temps
-                    .get(map_cell_id)
-                    .unwrap();
+                let world_map_cell = temps.world_cell_temp_mgr().temps.get(map_cell_id).unwrap();
                 buffs = Some(world_map_cell.buff.iter());
             } else if map_cell.cell_type == MapCellType::Valid {
-                let map_cell_temp = TEMPLATES.cell_temp_mgr().temps.get(map_cell_id).unwrap();
+                let map_cell_temp = temps.cell_temp_mgr().temps.get(map_cell_id).unwrap();
                 buffs = Some(map_cell_temp.buff.iter());
                 map_cell.element = map_cell_temp.element;
                 tmp.un_pair_map.insert(index, map_cell.id);
@@ -422,7 +413,7 @@ impl TileMap {
             if let Some(buffs) = buffs {
                 let mut buff_map = HashMap::new();
                 for buff_id in buffs {
-                    let buff_temp = crate::TEMPLATES.buff_temp_mgr().get_temp(buff_id).unwrap();
+                    let buff_temp = temps.buff_temp_mgr().get_temp(buff_id).unwrap();
                     let buff = Buff::from(buff_temp);
                     buff_map.insert(buff.get_id(), buff);
                     map_cell.passive_buffs.push(*buff_id);
@@ -446,7 +437,8 @@ impl TileMap {
 
 fn hs_2_v(hs: &HashSet<u32>, rare: u16) -> Vec<u32> {
     let mut v = Vec::new();
-    let temp_mgr = TEMPLATES.cell_temp_mgr();
+    let temps = TEMPLATES.load();
+    let temp_mgr = temps.cell_temp_mgr();
     for i in hs.iter() {
         let temp = temp_mgr.get_temp(i).unwrap();
         if temp.rare != rare {
@@ -454,5 +446,7 @@ fn hs_2_v(hs: &HashSet<u32>, rare: u16) -> Vec<u32> {
         }
         v.push(*i);
     }
+    //排序，保证同一个种子随机结果一致
+    v.sort();
     v
 }
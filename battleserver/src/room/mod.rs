@@ -6,6 +6,7 @@ pub mod room;
 use num_enum::IntoPrimitive;
 use num_enum::TryFromPrimitive;
 use tools::protos::base::RoomSettingPt;
+pub use tools::templates::merchandise_temp::RoomType;
 
 ///最大成员数量
 pub const MEMBER_MAX: usize = 4;
@@ -18,40 +19,6 @@ pub enum TeamId {
     Max = 4, //最大teamid
 }
 
-///房间类型
-#[derive(Debug, Clone, Copy, Eq, PartialEq, TryFromPrimitive, IntoPrimitive)]
-#[repr(u8)]
-pub enum RoomType {
-    None = 0,                  //无效
-    OneVOneVOneVOneCustom = 1, //1v1v1v1自定义房间
-    OneVOneVOneVOneMatch = 2,  //1v1v1v1匹配房间
-    WorldBossCustom = 3,       //世界boss自定义房间
-    WorldBoseMatch = 4,        //世界boss匹配房间
-}
-
-impl RoomType {
-    pub fn is_match_type(self) -> bool {
-        self == RoomType::WorldBoseMatch || self == RoomType::OneVOneVOneVOneMatch
-    }
-
-    pub fn is_boss_type(self) -> bool {
-        self == RoomType::WorldBoseMatch || self == RoomType::WorldBossCustom
-    }
-
-    pub fn is_custom_type(self) -> bool {
-        self == RoomType::OneVOneVOneVOneCustom || self == RoomType::WorldBossCustom
-    }
-    pub fn into_u8(self) -> u8 {
-        let res: u8 = self.into();
-        res
-    }
-
-    pub fn into_u32(self) -> u32 {
-        let res: u8 = self.into();
-        res as u32
-    }
-}
-
 #[derive(Debug, Clone, Copy, Eq, PartialEq, TryFromPrimitive, IntoPrimitive)]
 #[repr(u8)]
 pub enum RoomState {
@@ -12,9 +12,8 @@ use chrono::{DateTime, Utc};
 use crossbeam::channel::Sender;
 use log::{error, info, warn};
 use protobuf::Message;
-use rand::Rng;
-use std::borrow::{Borrow, BorrowMut};
-use std::collections::HashMap;
+use std::borrow::BorrowMut;
+use std::collections::BTreeMap;
 use std::convert::TryFrom;
 use std::str::FromStr;
 use tools::cmd_code::{ClientCode, GameCode, RoomCode};
@@ -36,7 +35,7 @@ pub struct Room {
     room_type: RoomType,                 //房间类型
     owner_id: u32,                       //房主id
     pub state: RoomState,                //房间状态
-    pub members: HashMap<u32, Member>,   //玩家id对应角色id
+    pub members: BTreeMap<u32, Member>,  //玩家id对应角色id
     pub member_index: [u32; MEMBER_MAX], //玩家对应的位置
     pub setting: RoomSetting,            //房间设置
     pub battle_data: BattleData,         //战斗相关数据封装
@@ -63,7 +62,7 @@ impl Room {
             anyhow::bail!("{:?}", e)
         }
         let room_type = room_type.unwrap();
-        let mut members = HashMap::new();
+        let mut members = BTreeMap::new();
         let mut member_index: [u32; MEMBER_MAX] = [0; MEMBER_MAX];
         let mut index = 0;
         for member_pt in rp.members.iter() {
@@ -502,8 +501,6 @@ impl Room {
             }
             index_v.push(index);
         }
-        let mut rand = rand::thread_rng();
-
         //检测出场顺位，没有选的，系统进行随机
         let room = self as *mut Room;
         unsafe {
@@ -511,25 +508,25 @@ impl Room {
             if self.room_type.is_boss_type() {
                 let world_boss = self.battle_data.get_world_boss_ref().unwrap();
                 let cter_temp_id = world_boss.get_major_cter().get_cter_temp_id();
-                let world_boss_temp = crate::TEMPLATES
-                    .worldboss_temp_mgr()
-                    .temps
-                    .get(&cter_temp_id)
-                    .unwrap();
+                let temps = crate::TEMPLATES.load();
+                let world_boss_temp = temps.worldboss_temp_mgr().temps.get(&cter_temp_id).unwrap();
                 let index = world_boss_temp.turn_order_index;
                 let res;
                 match index {
                     0 => {
-                        res = rand.gen_range(0..index_v.len());
+                        res = self.battle_data.rng.gen_range(0..index_v.len());
                     }
                     1 => {
-                        res = rand.gen_range(0..1);
+                        res = self.battle_data.rng.gen_range(0..1);
                     }
                     2 => {
-                        res = rand.gen_range(index_v.len() - 1..index_v.len());
+                        res = self
+                            .battle_data
+                            .rng
+                            .gen_range(index_v.len() - 1..index_v.len());
                     }
                     _ => {
-                        res = rand.gen_range(0..index_v.len());
+                        res = self.battle_data.rng.gen_range(0..index_v.len());
                     }
                 }
                 let res_index = index_v.remove(res);
@@ -543,7 +540,7 @@ impl Room {
                     continue;
                 }
                 //系统帮忙选
-                let remove_index = rand.gen_range(0..index_v.len());
+                let remove_index = self.battle_data.rng.gen_range(0..index_v.len());
                 let index = index_v.get(remove_index).unwrap();
                 let turn_order = *index as usize;
                 self.insert_turn_orders(turn_order, member_id);
@@ -896,10 +893,29 @@ impl Room {
     }
 
     pub fn init_league_map(&mut self) {
+        let is_match_type = self.room_type.is_match_type();
+        let rating_conf = crate::rating_conf();
+        let battle_data = &mut self.battle_data;
+        //回放用录像里的赛前评分，不读redis
+        let is_replay = battle_data.record.is_replay;
         for member in self.members.values() {
             let user_id = member.user_id;
             let league_id = member.league.get_league_id();
-            self.battle_data.leave_map.insert(user_id, league_id);
+            battle_data.leave_map.insert(user_id, league_id);
+            //匹配房真人才有评分
+            if !is_match_type || member.robot_temp_id > 0 || is_replay {
+                continue;
+            }
+            let rating = crate::load_rating(user_id, member.league.score, &rating_conf);
+            battle_data.rating_map.insert(user_id, rating);
+        }
+        if is_replay {
+            battle_data.rating_map = battle_data.record.rating_map.clone();
+            battle_data.rating_time = battle_data.record.rating_time;
+        } else {
+            battle_data.rating_time = chrono::Local::now().timestamp_millis();
+            let record = &mut battle_data.record;
+            record.record_rating(&battle_data.rating_map, battle_data.rating_time);
         }
     }
 
@@ -921,14 +937,15 @@ impl Room {
         if self.battle_data.tile_map.world_cell.1 > 0 && self.battle_data.reflash_map_turn.is_none()
         {
             let world_cell_id = self.battle_data.tile_map.world_cell.1;
-            let world_cell_temp = TEMPLATES
+            let temps = TEMPLATES.load();
+            let world_cell_temp = temps
                 .world_cell_temp_mgr()
                 .temps
                 .get(&world_cell_id)
                 .unwrap();
 
             for buff_id in world_cell_temp.buff.iter() {
-                let buff = TEMPLATES.buff_temp_mgr().get_temp(&buff_id);
+                let buff = temps.buff_temp_mgr().get_temp(buff_id);
                 if let Err(e) = buff {
                     error!("{:?}", e);
                     continue;
@@ -947,7 +964,7 @@ impl Room {
             }
         }
         let mut sbsn = S_BATTLE_START_NOTICE::new();
-        let debug = crate::CONF_MAP.borrow().get_bool("debug", false);
+        let debug = crate::CONFIG.base.debug;
         for battle_player in self.battle_data.battle_player.values() {
             let battle_player_pt = battle_player.convert_to_battle_player_pt();
             sbsn.battle_players.push(battle_player_pt);
@@ -985,7 +1002,8 @@ impl Room {
         }
 
         let user_id = user_id.unwrap();
-        let time_limit = TEMPLATES.constant_temp_mgr().temps.get("choice_index_time");
+        let temps = TEMPLATES.load();
+        let time_limit = temps.constant_temp_mgr().temps.get("choice_index_time");
         let mut task = Task::default();
 
         if self.room_type.is_custom_type() {
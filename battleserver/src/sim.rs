@@ -0,0 +1,211 @@
+use crate::battle::battle_enum::ActionType;
+use crate::battle::battle_record::ReplayResult;
+use crate::handlers::battle_handler::begin_battle;
+use crate::mgr::battle_mgr::BattleMgr;
+use crate::robot::robot_task_mgr::robot_task_fn;
+use crate::room::room::Room;
+use crate::room::RoomType;
+use crate::task_timer::task_fn;
+use crate::{Lock, TEMPLATES};
+use async_std::sync::{Arc, Mutex};
+use async_std::task::block_on;
+use log::warn;
+use protobuf::Message;
+use std::collections::{BTreeMap, HashMap};
+use tools::cmd_code::BattleCode;
+use tools::protos::base::{CharacterPt, MemberPt, RoomPt, RoomSettingPt};
+use tools::protos::battle::C_ACTION;
+use tools::protos::server_protocol::R_B_START;
+use tools::templates::tile_map_temp::TileMapTemp;
+
+///单场模拟最多执行的任务数量，防止死循环
+const SIM_MAX_STEPS: usize = 100000;
+
+///单场模拟战斗结果
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SimResult {
+    pub seed: u64,                            //随机种子
+    pub ranks: Vec<(u32, Option<u8>)>,        //角色配置id,排名(0为第一名),None为没有结算(平局)
+    pub total_turn_times: u16,                //总的turn次数
+    pub skill_use: BTreeMap<(u32, u32), u32>, //(角色配置id,技能id),发起次数
+}
+
+///组装全机器人自定义房间数据,每个角色由一个机器人操作
+pub fn robot_room_pt(cter_temp_ids: &[u32]) -> anyhow::Result<RoomPt> {
+    if cter_temp_ids.is_empty() || cter_temp_ids.len() > crate::room::MEMBER_MAX {
+        anyhow::bail!("invalid cter count:{}", cter_temp_ids.len())
+    }
+    let mut rp = RoomPt::new();
+    rp.room_id = 1;
+    rp.room_type = RoomType::OneVOneVOneVOneCustom as u32;
+    let mut setting = RoomSettingPt::new();
+    setting.ai_level = 3;
+    rp.set_setting(setting);
+    let temps = TEMPLATES.load();
+    let robot_temp_mgr = temps.robot_temp_mgr();
+    for (index, cter_temp_id) in cter_temp_ids.iter().enumerate() {
+        let robot_temp = robot_temp_mgr
+            .temps
+            .values()
+            .find(|x| x.cter_id == *cter_temp_id);
+        if robot_temp.is_none() {
+            anyhow::bail!("there is no robot temp for cter_id:{}", cter_temp_id)
+        }
+        let robot_temp = robot_temp.unwrap();
+        let mut cter_pt = CharacterPt::new();
+        cter_pt.cter_temp_id = *cter_temp_id;
+        cter_pt.set_skills(robot_temp.skills.clone());
+        let mut mp = MemberPt::new();
+        mp.user_id = index as u32 + 1;
+        mp.nick_name = "robot".to_owned();
+        mp.grade = 1;
+        mp.team_id = index as u32 + 1;
+        mp.robot_temp_id = robot_temp.get_id();
+        mp.index = index as u32;
+        mp.set_cter(cter_pt);
+        rp.members.push(mp);
+    }
+    Ok(rp)
+}
+
+///无网络、无定时器地把房间的战斗跑完，返回结束的房间(带完整录像)
+///tile_map_temp:指定的地图,为None则和正式战斗一样按随机种子选地图
+///notices:不为None时收集推送给玩家的行动通知和结算通知
+pub fn play(
+    rp: RoomPt,
+    tile_map_temp: Option<&TileMapTemp>,
+    seed: u64,
+    mut notices: Option<&mut ReplayResult>,
+) -> anyhow::Result<Room> {
+    let (tcp_sender, tcp_rec) = crossbeam::channel::unbounded();
+    let (task_sender, task_rec) = crossbeam::channel::unbounded();
+    let (robot_sender, robot_rec) = crossbeam::channel::unbounded();
+    let mut bm = BattleMgr::new();
+    bm.set_game_center_channel(tcp_sender.clone());
+    bm.task_sender = Some(task_sender.clone());
+    bm.robot_task_sender = Some(robot_sender.clone());
+    bm.record_path.clear();
+    bm.finished_rooms = Some(Vec::new());
+
+    let mut room = Room::new(&rp, tcp_sender, task_sender, robot_sender)?;
+    let mut rbs = R_B_START::new();
+    rbs.set_room_pt(rp);
+    room.battle_data.rng.reseed(seed);
+    room.battle_data.fixed_tile_map = tile_map_temp.cloned();
+    let room_id = room.get_room_id();
+    let battle_id = room.battle_data.battle_id;
+    room.battle_data
+        .record
+        .init(room_id, battle_id, seed, rbs.write_to_bytes()?.as_slice());
+
+    let bm: Lock = Arc::new(Mutex::new(bm));
+    let mut lock = block_on(bm.lock());
+    begin_battle(&mut lock, room);
+    std::mem::drop(lock);
+
+    let mut steps = 0;
+    loop {
+        for bytes in tcp_rec.try_iter() {
+            if let Some(notices) = notices.as_mut() {
+                notices.collect(bytes)?;
+            }
+        }
+        let lock = block_on(bm.lock());
+        let is_over = !lock.rooms.contains_key(&room_id);
+        std::mem::drop(lock);
+        if is_over {
+            break;
+        }
+        if steps >= SIM_MAX_STEPS {
+            anyhow::bail!("simulate over max steps!seed:{}", seed)
+        }
+        steps += 1;
+        //优先执行机器人行为，没有了再执行定时器任务(超时等)
+        if let Ok(robot_task) = robot_rec.try_recv() {
+            let f = robot_task_fn(robot_task.action_type);
+            f(bm.clone(), robot_task);
+            continue;
+        }
+        if let Ok(task) = task_rec.try_recv() {
+            let f = task_fn(task.cmd);
+            f(bm.clone(), task);
+            continue;
+        }
+        anyhow::bail!("simulate stalled!seed:{}", seed)
+    }
+
+    let mut lock = block_on(bm.lock());
+    let room = lock.finished_rooms.as_mut().unwrap().pop();
+    if room.is_none() {
+        anyhow::bail!("there is no finished room!seed:{}", seed)
+    }
+    Ok(room.unwrap())
+}
+
+///无网络、无定时器地跑一场全机器人战斗
+///cter_temp_ids:参战角色配置id,每个角色由一个机器人操作
+///tile_map_temp:指定的地图
+///seed:随机种子,相同的输入和种子结果一致
+pub fn simulate(
+    cter_temp_ids: &[u32],
+    tile_map_temp: &TileMapTemp,
+    seed: u64,
+) -> anyhow::Result<SimResult> {
+    let rp = robot_room_pt(cter_temp_ids)?;
+    let room = play(rp, Some(tile_map_temp), seed, None)?;
+    let battle_data = &room.battle_data;
+
+    let mut res = SimResult {
+        seed,
+        total_turn_times: battle_data.total_turn_times,
+        ..Default::default()
+    };
+    let mut rank_map = HashMap::new();
+    for su in battle_data.summary_vec.iter().flatten() {
+        rank_map.insert(su.user_id, su.summary_rank);
+    }
+    let mut user_cter = HashMap::new();
+    for (user_id, member) in room.members.iter() {
+        let cter_temp_id = member.chose_cter.cter_temp_id;
+        user_cter.insert(*user_id, cter_temp_id);
+        res.ranks
+            .push((cter_temp_id, rank_map.get(user_id).copied()));
+    }
+    //从录像里统计技能发起次数
+    for event in battle_data.record.events.iter() {
+        if event.cmd != BattleCode::Action as u32 {
+            continue;
+        }
+        let mut proto = C_ACTION::new();
+        if let Err(e) = proto.merge_from_bytes(event.data.as_slice()) {
+            warn!("{:?}", e);
+            continue;
+        }
+        if proto.action_type != ActionType::Skill.into_u32() {
+            continue;
+        }
+        let cter_temp_id = user_cter.get(&event.user_id).copied().unwrap_or(0);
+        let count = res
+            .skill_use
+            .entry((cter_temp_id, proto.value))
+            .or_insert(0);
+        *count += 1;
+    }
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn simulate_is_deterministic() {
+        let temps = TEMPLATES.load();
+        let tile_map_temp = temps.tile_map_temp_mgr().get_temp(2001).unwrap();
+        let cter_ids = [1001, 1002];
+        let first = simulate(&cter_ids, tile_map_temp, 42).unwrap();
+        let second = simulate(&cter_ids, tile_map_temp, 42).unwrap();
+        assert_eq!(first, second);
+        assert_eq!(first.ranks.len(), cter_ids.len());
+    }
+}
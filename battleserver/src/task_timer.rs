@@ -52,12 +52,7 @@ pub fn init_timer(bm: Lock) {
 
             let task_cmd = TaskCmd::from(task.cmd);
             let rm_clone = bm.clone();
-            let f = match task_cmd {
-                TaskCmd::ChoiceIndex => choice_index,
-                TaskCmd::BattleTurnTime => battle_turn_time,
-                TaskCmd::MaxBattleTurnTimes => max_battle_turn_limit,
-                _ => none,
-            };
+            let f = task_fn(task_cmd);
             let m = move || f(rm_clone, task);
             SCHEDULED_MGR.execute_after(Duration::from_millis(delay), m);
         }
@@ -70,6 +65,17 @@ pub fn init_timer(bm: Lock) {
     }
     info!("初始化定时器任务执行器成功!");
 }
+
+///根据任务类型获得执行函数
+pub fn task_fn(task_cmd: TaskCmd) -> fn(Lock, Task) {
+    match task_cmd {
+        TaskCmd::ChoiceIndex => choice_index,
+        TaskCmd::BattleTurnTime => battle_turn_time,
+        TaskCmd::MaxBattleTurnTimes => max_battle_turn_limit,
+        _ => none,
+    }
+}
+
 fn none(_: Arc<Mutex<BattleMgr>>, _: Task) {}
 
 ///占位任务，没选的直接t出房间
@@ -117,6 +123,10 @@ fn choice_index(bm: Arc<Mutex<BattleMgr>>, task: Task) {
         return;
     }
 
+    let turn = room.battle_data.turn;
+    room.battle_data
+        .record
+        .record_task(TaskCmd::ChoiceIndex, user_id, turn);
     //移除玩家
     room.remove_member(MemberLeaveNoticeType::Kicked, &user_id, true);
 
@@ -204,8 +214,11 @@ fn battle_turn_time(bm: Arc<Mutex<BattleMgr>>, task: Task) {
         return;
     }
 
-    //如果玩家啥都没做，就T出房间
-    let need_kick = battle_player.flow_data.open_map_cell_vec.is_empty();
+    //如果玩家啥都没做，就T出房间，掉线等待重连的帮他跳过turn
+    let need_kick = battle_player.need_kick_on_turn_timeout();
+    room.battle_data
+        .record
+        .record_task(TaskCmd::BattleTurnTime, user_id, turn);
     if need_kick {
         room.remove_member(MemberLeaveNoticeType::Kicked.into(), &user_id, true);
         info!("定时检turn任务,没有翻的人T出去,user_id:{}", user_id);
@@ -256,6 +269,10 @@ fn max_battle_turn_limit(bm: Arc<Mutex<BattleMgr>>, task: Task) {
     }
     let room = room.unwrap();
     let room_id = room.get_room_id();
+    let turn = room.battle_data.turn;
+    room.battle_data
+        .record
+        .record_task(TaskCmd::MaxBattleTurnTimes, user_id, turn);
 
     lock.rm_room(room_id);
 }
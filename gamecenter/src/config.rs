@@ -0,0 +1,100 @@
+use crate::entity::game_conf::GameConf;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use tools::conf::{check_address, BaseConfig, Validate};
+use tools::reconnect::ReconnectConfig;
+use tools::registry::RegistryConfig;
+
+///游戏中心配置，启动时从config/config.conf读取并校验
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CenterConfig {
+    #[serde(flatten)]
+    pub base: BaseConfig, //公共配置
+    #[serde(flatten)]
+    pub registry: RegistryConfig, //注册中心
+    #[serde(flatten)]
+    pub reconnect: ReconnectConfig, //断线重连
+    pub games: Vec<GameConf>,       //所有游戏区服
+    pub game_id: u32,               //没有配置games时的游戏id
+    pub region_id: u32,             //没有配置games时的区服id
+    pub room_port: String,          //没有配置games时的房间服地址
+    pub rank_port: String,          //没有配置games时的排行榜服地址
+    pub mysql: String,              //mysql地址
+    pub http_port: u16,             //http监听端口
+    pub user_center_state: String,  //用户中心地址
+    pub tcp_port_gate: String,      //给gate连的监听地址
+    pub tcp_port_battle: String,    //给战斗服连的监听地址
+    pub net_module: String,         //通信方式
+    pub drain_timeout: u64,         //停服时等待战斗服排空的时间(秒)
+    pub season_check_interval: u64, //赛季检查间隔(毫秒)
+    pub template_path: String,      //配置表目录，相对路径从启动目录开始算
+}
+
+impl Default for CenterConfig {
+    fn default() -> Self {
+        CenterConfig {
+            base: BaseConfig::default(),
+            registry: RegistryConfig::default(),
+            reconnect: ReconnectConfig::default(),
+            games: Vec::new(),
+            game_id: 101,
+            region_id: 0,
+            room_port: String::new(),
+            rank_port: String::new(),
+            mysql: String::new(),
+            http_port: 0,
+            user_center_state: String::new(),
+            tcp_port_gate: String::new(),
+            tcp_port_battle: String::new(),
+            net_module: "tcp".to_owned(),
+            drain_timeout: 300,
+            season_check_interval: 1000,
+            template_path: "template".to_owned(),
+        }
+    }
+}
+
+impl CenterConfig {
+    ///所有游戏区服配置
+    ///没有配置games的，用顶层的game_id,region_id,room_port,rank_port
+    pub fn games(&self) -> Vec<GameConf> {
+        if !self.games.is_empty() {
+            return self.games.clone();
+        }
+        vec![GameConf {
+            game_id: self.game_id,
+            region_id: self.region_id,
+            room_port: self.room_port.clone(),
+            rank_port: self.rank_port.clone(),
+        }]
+    }
+}
+
+impl Validate for CenterConfig {
+    fn validate(&self) -> anyhow::Result<()> {
+        self.base.validate()?;
+        self.registry.validate()?;
+        self.reconnect.validate()?;
+        check_address("tcp_port_gate", self.tcp_port_gate.as_str())?;
+        check_address("tcp_port_battle", self.tcp_port_battle.as_str())?;
+        let mut keys = HashSet::new();
+        for game in self.games() {
+            if !keys.insert(game.game_key()) {
+                anyhow::bail!("games里有重复的游戏区服!{}", game.game_key());
+            }
+            if self.registry.registry_mode == "file"
+                && (game.room_port.is_empty() || game.rank_port.is_empty())
+            {
+                anyhow::bail!("file模式下room_port和rank_port不能为空!{}", game.game_key());
+            }
+        }
+        if self.season_check_interval == 0 {
+            anyhow::bail!("season_check_interval不能为0!");
+        }
+        if self.template_path.is_empty() {
+            anyhow::bail!("template_path不能为空!");
+        }
+        Ok(())
+    }
+}
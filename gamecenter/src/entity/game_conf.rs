@@ -0,0 +1,18 @@
+use serde::{Deserialize, Serialize};
+use tools::game_key::GameKey;
+
+///游戏区服配置，每个游戏区服有自己的房间服和排行榜服
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GameConf {
+    pub game_id: u32, //游戏id
+    #[serde(default)]
+    pub region_id: u32, //区服id
+    pub room_port: String, //房间服地址
+    pub rank_port: String, //排行榜服地址
+}
+
+impl GameConf {
+    pub fn game_key(&self) -> GameKey {
+        GameKey::new(self.game_id, self.region_id)
+    }
+}
@@ -1 +1,2 @@
+pub mod game_conf;
 pub mod league;
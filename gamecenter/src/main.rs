@@ -1,19 +1,33 @@
+mod config;
 mod entity;
 mod mgr;
 mod net;
 
+use crate::config::CenterConfig;
+use crate::entity::game_conf::GameConf;
 use crate::mgr::game_center_mgr::GameCenterMgr;
 use crate::net::http::{ReloadTempsHandler, UpdateSeasonHandler};
 use crate::net::room_tcp_client::RoomTcpClientHandler;
 use crate::net::{battle_tcp_server, gate_tcp_server};
 use async_std::sync::Mutex;
-use net::http::{KickPlayerHandler, StopAllServerHandler, UpdateWorldBossHandler};
+use async_std::task::block_on;
+use log::{error, info, warn};
+use net::http::{
+    DrainStatusHandler, KickPlayerHandler, StopAllServerHandler, UpdateWorldBossHandler,
+};
 use net::rank_tcp_client::RankTcpClientHandler;
 use std::env;
 use std::sync::Arc;
 use std::time::Duration;
 use tools::conf::Conf;
-use tools::tcp::ClientHandler;
+use tools::game_key::GameKey;
+use tools::reconnect::ReconnectConf;
+use tools::redis_pool::RedisPoolTool;
+use tools::registry::{
+    Registry, ServiceInfo, ROLE_GAME_CENTER_BATTLE, ROLE_GAME_CENTER_GATE,
+    ROLE_RANK, ROLE_ROOM,
+};
+use tools::templates::template::{init_temps_mgr, Templates};
 
 #[macro_use]
 extern crate lazy_static;
@@ -28,64 +42,246 @@ lazy_static! {
         let conf = Conf::init(res.as_str());
         conf
     };
+
+    ///校验过的配置
+    static ref CONFIG: CenterConfig = {
+        match CONF_MAP.parse() {
+            Ok(config) => config,
+            Err(e) => panic!("{:?}", e),
+        }
+    };
+
+    ///所有游戏区服配置
+    static ref GAMES: Vec<GameConf> = {
+        CONFIG.games()
+    };
+
+    ///静态配置文件
+    static ref TEMPLATES: Templates = {
+        init_templates_mgr()
+    };
+
+    ///reids客户端
+    static ref REDIS_POOL: RedisPoolTool = {
+        match RedisPoolTool::init(&CONFIG.base.redis_add, &CONFIG.base.redis_pass) {
+            Ok(redis) => redis,
+            Err(e) => {
+                error!("初始化redis失败!{:?}", e);
+                std::process::exit(1);
+            }
+        }
+    };
 }
 
+///游戏中心只用到赛季，worldboss和常量表，直接读游戏服的配置表目录，不再单独拷贝一份
+fn init_templates_mgr() -> Templates {
+    let path = env::current_dir()
+        .unwrap()
+        .join(CONFIG.template_path.as_str());
+    let str = path.as_os_str().to_str().unwrap();
+    init_temps_mgr(str)
+}
+
+///redis 赛季index
+const REDIS_INDEX_GAME_SEASON: u32 = 1;
+///redis 赛季key
+const REDIS_KEY_GAME_SEASON: &str = "game_season";
+///redis worldboss
+const REDIS_KEY_WORLD_BOSS: &str = "world_boss";
+
 type Lock = Arc<Mutex<GameCenterMgr>>;
 
+///游戏区服自己的redis客户端，和REDIS_POOL共用一组连接，key带上游戏区服的命名空间
+///每次调用都按game_key新建，多个游戏区服同时读写也不会串key
+fn game_redis(game_key: GameKey) -> RedisPoolTool {
+    REDIS_POOL.with_namespace(game_key.namespace().as_str(), &[])
+}
+
 fn main() {
     let game_center = Arc::new(Mutex::new(GameCenterMgr::new()));
 
     //初始化日志模块
     init_log();
 
+    //打印生效的配置
+    info!("{}", CONF_MAP.dump(&*CONFIG));
+
+    //初始化数据包压缩
+    tools::compress::init(
+        CONFIG.base.compress_threshold,
+        CONFIG.base.compress_type.as_str(),
+    );
+
     //初始化tcp服务端
     init_tcp_server(game_center.clone());
 
     //初始化http服务器
     init_http_server(game_center.clone());
 
+    //初始化赛季定时器
+    init_season_timer(game_center.clone());
+
+    //注册到注册中心
+    init_registry(game_center.clone());
+
+    //初始化退出信号处理
+    init_shutdown(game_center.clone());
+
     //初始化tcp客户端
     init_tcp_client(game_center);
 }
 
 fn init_log() {
-    let info_log = CONF_MAP.get_str("info_log_path", "");
-    let error_log = CONF_MAP.get_str("error_log_path", "");
+    let info_log = &CONFIG.base.info_log_path;
+    let error_log = &CONFIG.base.error_log_path;
     tools::my_log::init_log(info_log.as_str(), error_log.as_str());
 }
 
 ///初始化tcp服务端
 fn init_tcp_server(gm: Lock) {
-    let tcp_port: &str = &CONF_MAP.get_str("tcp_port_gate", "");
+    let tcp_port: &str = &CONFIG.tcp_port_gate;
     gate_tcp_server::new(tcp_port.to_string(), gm.clone());
 
-    let tcp_port: &str = &CONF_MAP.get_str("tcp_port_battle", "");
+    let tcp_port: &str = &CONFIG.tcp_port_battle;
     battle_tcp_server::new(tcp_port.to_owned(), gm);
 }
 
-///初始化tcp客户端
+///初始化tcp客户端，每个游戏区服连接自己的房间服和排行榜服，断线后自动重连
 fn init_tcp_client(gm: Lock) {
-    let mut rth = RoomTcpClientHandler { gm: gm.clone() };
-    let address = CONF_MAP.get_str("room_port", "");
-    let m = async move {
-        rth.on_read(address).await;
+    let mut clients = vec![];
+    for game_conf in GAMES.iter() {
+        let game_key = game_conf.game_key();
+        let rth = RoomTcpClientHandler {
+            gm: gm.clone(),
+            game_key,
+            address: String::new(),
+        };
+        let conf = ReconnectConf::new(&CONFIG.reconnect);
+        clients.push(tools::reconnect::spawn("ROOM_CLIENT", rth, conf));
+
+        let rth = RankTcpClientHandler {
+            gm: gm.clone(),
+            game_key,
+            address: String::new(),
+        };
+        let conf = ReconnectConf::new(&CONFIG.reconnect);
+        clients.push(tools::reconnect::spawn("RANK_CLIENT", rth, conf));
+    }
+    for client in clients {
+        let res = client.join();
+        if let Err(e) = res {
+            error!("{:?}", e);
+        }
+    }
+}
+
+///发现游戏区服的房间服或者排行榜服地址，还没有注册就一直等
+fn discover_address(game_key: GameKey, role: &str) -> String {
+    let registry = Registry::new(&CONFIG.registry);
+    let game_conf = GAMES.iter().find(|x| x.game_key() == game_key);
+    let static_address = match (game_conf, role) {
+        (Some(game_conf), ROLE_ROOM) => game_conf.room_port.clone(),
+        (Some(game_conf), ROLE_RANK) => game_conf.rank_port.clone(),
+        _ => String::new(),
     };
-    async_std::task::spawn(m);
-    let address: &str = &CONF_MAP.get_str("rank_port", "");
-    let mut rth = RankTcpClientHandler { gm };
-    let res = rth.on_read(address.to_string());
-    async_std::task::block_on(res);
+    let static_addresses = vec![static_address];
+    loop {
+        let redis = game_redis(game_key);
+        let res = registry.discover(&redis, role, &static_addresses);
+        if let Some(info) = res.first() {
+            return info.address.clone();
+        }
+        warn!("注册中心还没有{},等待启动!game:{}", role, game_key);
+        std::thread::sleep(registry.heartbeat_interval());
+    }
+}
+
+///注册到每个游戏区服的注册中心，定时心跳上报连接数量
+fn init_registry(gm: Lock) {
+    let registry = Registry::new(&CONFIG.registry);
+    let gate_address = CONFIG.registry.advertise_address(&CONFIG.tcp_port_gate);
+    let battle_address = CONFIG.registry.advertise_address(&CONFIG.tcp_port_battle);
+    let heartbeat = registry.clone();
+    let m = move || {
+        let lock = block_on(gm.lock());
+        let mut infos = vec![];
+        for game_conf in GAMES.iter() {
+            let game_key = game_conf.game_key();
+            let gate_num = lock
+                .gate_clients
+                .values()
+                .filter(|x| x.game_key == game_key)
+                .count();
+            let battle_num = lock
+                .battle_clients
+                .values()
+                .filter(|x| x.game_key == game_key)
+                .count();
+            let gate = ServiceInfo::new(
+                ROLE_GAME_CENTER_GATE,
+                gate_address.clone(),
+                0,
+                gate_num as u32,
+            );
+            let battle = ServiceInfo::new(
+                ROLE_GAME_CENTER_BATTLE,
+                battle_address.clone(),
+                0,
+                battle_num as u32,
+            );
+            infos.push((game_key, gate, battle));
+        }
+        std::mem::drop(lock);
+        for (game_key, gate, battle) in infos {
+            let redis = game_redis(game_key);
+            heartbeat.register(&redis, &gate);
+            heartbeat.register(&redis, &battle);
+        }
+    };
+    registry.spawn_heartbeat(m);
 }
 
 ///初始化http服务端
 fn init_http_server(gm: Lock) {
     std::thread::sleep(Duration::from_millis(10));
-    let http_port = CONF_MAP.get_usize("http_port", 0) as u16;
+    let http_port = CONFIG.http_port;
     tools::http::Builder::new()
         .route(Box::new(ReloadTempsHandler::new(gm.clone())))
         .route(Box::new(UpdateSeasonHandler::new(gm.clone())))
         .route(Box::new(StopAllServerHandler::new(gm.clone())))
         .route(Box::new(KickPlayerHandler::new(gm.clone())))
         .route(Box::new(UpdateWorldBossHandler::new(gm.clone())))
+        .route(Box::new(DrainStatusHandler::new(gm.clone())))
         .bind(http_port);
 }
+
+///加载赛季数据，然后定时检查赛季和worldboss轮换
+fn init_season_timer(gm: Lock) {
+    block_on(gm.lock()).load_season();
+    let interval = CONFIG.season_check_interval;
+    let m = move || loop {
+        std::thread::sleep(Duration::from_millis(interval));
+        block_on(gm.lock()).check_season(None, false, false);
+    };
+    std::thread::spawn(m);
+}
+
+///收到退出信号时通知所有服务器停服排空，等待确认后退出
+fn init_shutdown(gm: Lock) {
+    let m = move || {
+        block_on(gm.lock()).stop_all_server_handler();
+        let timeout = CONFIG.drain_timeout;
+        let time = std::time::SystemTime::now();
+        loop {
+            if block_on(gm.lock()).is_drain_finish() {
+                break;
+            }
+            if time.elapsed().unwrap().as_secs() >= timeout {
+                info!("等待停服排空确认超时!");
+                break;
+            }
+            std::thread::sleep(Duration::from_secs(1));
+        }
+    };
+    tools::shutdown::on_signal(m);
+}
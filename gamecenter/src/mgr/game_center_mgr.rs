@@ -1,21 +1,28 @@
+use super::season_mgr::{SeasonMgr, SeasonState, WorldBossState};
 use crossbeam::channel::Sender;
-use log::warn;
+use log::{error, info, warn};
 use protobuf::Message;
 use serde_json::Value;
 use std::collections::HashMap;
+use std::str::FromStr;
 use tools::cmd_code::{BattleCode, GameCode, GateCode, RankCode, RoomCode, ServerCommonCode};
+use tools::game_key::GameKey;
 use tools::net_message_io::NetHandler;
 use tools::protos::server_protocol::{R_B_START, R_S_UPDATE_SEASON, UPDATE_WORLD_BOSS_PUSH};
+use tools::util::bytebuf::ByteBuf;
 use tools::util::packet::Packet;
 
 #[derive(Default)]
 pub struct GameCenterMgr {
-    pub rank_server: Option<Sender<Vec<u8>>>,         //排行榜服
-    pub room_center: Option<Sender<Vec<u8>>>,         //房间中心
-    pub gate_clients: HashMap<usize, GateClient>,     //gate路由服客户端,key:token,value:GateClient
+    pub rank_servers: HashMap<GameKey, Sender<Vec<u8>>>, //排行榜服,key:游戏区服
+    pub room_centers: HashMap<GameKey, Sender<Vec<u8>>>, //房间中心,key:游戏区服
+    pub gate_clients: HashMap<usize, GateClient>, //gate路由服客户端,key:token,value:GateClient
     pub battle_clients: HashMap<usize, BattleClient>, //战斗服客户端,key:token,value:BattleClient
-    pub user_w_gate: HashMap<u32, usize>,             //玩家对应gate
-    pub user_w_battle: HashMap<u32, usize>,           //玩家对应战斗服
+    pub user_w_gate: HashMap<u32, usize>,         //玩家对应gate
+    pub user_w_battle: HashMap<u32, usize>,       //玩家对应战斗服
+    pub drain_wait_num: usize,                    //停服排空需要等待确认的服务器数量
+    pub drain_acks: Vec<Value>,                   //已收到的停服排空确认
+    pub season_mgrs: HashMap<GameKey, SeasonMgr>, //赛季日历,key:游戏区服
 }
 
 impl GameCenterMgr {
@@ -23,111 +30,333 @@ impl GameCenterMgr {
         GameCenterMgr::default()
     }
 
-    ///通知更新服务器更新赛季
-    pub fn notice_update_season(&mut self, value: Value) {
-        let map = value.as_object();
-        if let None = map {
-            return;
+    ///没有注册的服务器默认属于第一个配置的游戏区服
+    pub fn default_game_key() -> GameKey {
+        crate::GAMES
+            .first()
+            .map(|x| x.game_key())
+            .unwrap_or_default()
+    }
+
+    ///获得gate或者战斗服所属的游戏区服
+    pub fn get_game_key(&self, gate_token: Option<usize>, battle_token: Option<usize>) -> GameKey {
+        if let Some(gate_client) = gate_token.and_then(|x| self.gate_clients.get(&x)) {
+            return gate_client.game_key;
         }
-        let map = map.unwrap();
-        let season_id = map.get("season_id");
-        if season_id.is_none() {
-            warn!("the season_id is None!");
-            return;
+        if let Some(battle_client) = battle_token.and_then(|x| self.battle_clients.get(&x)) {
+            return battle_client.game_key;
         }
-        let season_id = season_id.unwrap();
+        GameCenterMgr::default_game_key()
+    }
 
-        let round = map.get("round");
-        if round.is_none() {
-            warn!("the round is None!");
+    ///gate或者战斗服注册所属的游戏区服
+    pub fn register(
+        &mut self,
+        packet: &Packet,
+        gate_token: Option<usize>,
+        battle_token: Option<usize>,
+    ) {
+        let res = GameKey::from_register_bytes(packet.get_data());
+        if let Err(e) = res {
+            warn!("{:?}", e);
             return;
         }
-        let round = round.unwrap();
-
-        let next_update_time = map.get("next_update_time");
-        if next_update_time.is_none() {
-            warn!("the next_update_time is None!");
-            return;
+        let game_key = res.unwrap();
+        if !crate::GAMES.iter().any(|x| x.game_key() == game_key) {
+            warn!("the game:{} is not in config!", game_key);
         }
-        let next_update_time = next_update_time.unwrap().as_u64();
-        if next_update_time.is_none() {
-            warn!("the next_update_time is None!");
-            return;
+        if let Some(gate_client) = gate_token.and_then(|x| self.gate_clients.get_mut(&x)) {
+            gate_client.game_key = game_key;
+            info!(
+                "gate_client register!token:{},game:{}",
+                gate_token.unwrap(),
+                game_key
+            );
         }
-        let next_update_time = next_update_time.unwrap();
+        if let Some(battle_client) = battle_token.and_then(|x| self.battle_clients.get_mut(&x)) {
+            battle_client.game_key = game_key;
+            info!(
+                "battle_client register!token:{},game:{}",
+                battle_token.unwrap(),
+                game_key
+            );
+        }
+    }
 
-        let mut usn = R_S_UPDATE_SEASON::new();
-        usn.set_season_id(season_id.as_u64().unwrap() as i32);
-        usn.set_round(round.as_u64().unwrap() as u32);
-        usn.set_next_update_time(next_update_time);
+    ///从redis加载所有游戏区服的赛季和worldboss状态
+    pub fn load_season(&mut self) {
+        for game_conf in crate::GAMES.iter() {
+            let game_key = game_conf.game_key();
+            let game_id = game_key.game_id.to_string();
+            let redis = crate::game_redis(game_key);
+            let mut season_mgr = SeasonMgr::default();
+            let res: Option<String> = redis.hget(
+                crate::REDIS_INDEX_GAME_SEASON,
+                crate::REDIS_KEY_GAME_SEASON,
+                game_id.as_str(),
+            );
+            if let Some(res) = res {
+                match serde_json::from_str::<SeasonState>(res.as_str()) {
+                    Ok(season) => season_mgr.season = Some(season),
+                    Err(e) => error!("{:?}", e),
+                }
+            }
+            let res: Option<String> = redis.hget(
+                crate::REDIS_INDEX_GAME_SEASON,
+                crate::REDIS_KEY_WORLD_BOSS,
+                game_id.as_str(),
+            );
+            if let Some(res) = res {
+                match serde_json::from_str::<WorldBossState>(res.as_str()) {
+                    Ok(world_boss) => season_mgr.world_boss = Some(world_boss),
+                    Err(e) => error!("{:?}", e),
+                }
+            }
+            info!(
+                "加载赛季数据完成!game:{},season:{:?},world_boss:{:?}",
+                game_key, season_mgr.season, season_mgr.world_boss
+            );
+            self.season_mgrs.insert(game_key, season_mgr);
+        }
+    }
 
-        let cmd = RankCode::UpdateSeasonPush.into_u32();
-        let mut packet = Packet::new(cmd, 0, 0);
-        packet.set_is_client(false);
-        packet.set_is_broad(true);
-        packet.set_data(&usn.write_to_bytes().unwrap()[..]);
-        let bytes = packet.build_server_bytes();
-        //通知排行榜服
-        let res = self.get_rank_center_mut().send(bytes);
-        if let Err(e) = res {
-            warn!("{:?}", e);
+    ///检查赛季和worldboss是否需要轮换
+    ///game_key为None则检查所有游戏区服
+    pub fn check_season(
+        &mut self,
+        game_key: Option<GameKey>,
+        force_season: bool,
+        force_world_boss: bool,
+    ) {
+        let game_keys: Vec<GameKey> = match game_key {
+            Some(game_key) => vec![game_key],
+            None => self.season_mgrs.keys().copied().collect(),
+        };
+        for game_key in game_keys {
+            self.check_game_season(game_key, force_season, force_world_boss);
         }
     }
 
-    ///通知更新服务器更新赛季
-    pub fn notice_update_worldboss(&mut self, value: Value) {
-        let map = value.as_object();
-        if let None = map {
+    ///检查一个游戏区服的赛季和worldboss是否需要轮换，需要就持久化并推送给该游戏区服的所有服务器
+    ///先把新状态标记为未推送存到redis再推送，推送完再标记为已推送
+    ///推送过程中停服的，重启后会把未推送的状态重新推送一次
+    fn check_game_season(&mut self, game_key: GameKey, force_season: bool, force_world_boss: bool) {
+        //排行榜服和房间服还没连上，等连上再处理
+        if !self.rank_servers.contains_key(&game_key) || !self.room_centers.contains_key(&game_key)
+        {
             return;
         }
-        let map = map.unwrap();
-        let world_boss_id = map.get("cter_id");
-        if world_boss_id.is_none() {
-            warn!("the worldboss_id is None!");
+        let mut season_mgr = match self.season_mgrs.remove(&game_key) {
+            Some(season_mgr) => season_mgr,
+            None => {
+                warn!("could not find season_mgr by game:{}!", game_key);
+                return;
+            }
+        };
+        let now = chrono::Local::now().timestamp() as u64;
+        let temps = crate::TEMPLATES.load();
+        let round_season_id = temps
+            .constant_temp_mgr()
+            .temps
+            .get("round_season_id")
+            .and_then(|x| i32::from_str(x.value.as_str()).ok())
+            .unwrap_or(1001);
+
+        //上次没推送完的，先补推送，有新的赛季就只推送新的
+        let mut season = None;
+        if let Some(current) = season_mgr.season.as_ref() {
+            if !current.pushed {
+                season = Some(current.clone());
+            }
+        }
+        let temp_mgr = temps.season_temp_mgr();
+        let res = season_mgr.rotate_season(temp_mgr, round_season_id, now, force_season);
+        if res.is_some() {
+            season = res;
+        }
+        if let Some(mut season) = season {
+            self.save_season(game_key, &season);
+            self.push_update_season(game_key, &season);
+            season.pushed = true;
+            self.save_season(game_key, &season);
+            info!("赛季轮换完成!game:{},{:?}", game_key, season);
+        }
+
+        let mut world_boss = None;
+        if let Some(boss) = season_mgr.world_boss.as_ref() {
+            if !boss.pushed {
+                world_boss = Some(boss.clone());
+            }
+        }
+        let temp_mgr = temps.worldboss_temp_mgr();
+        let res = season_mgr.rotate_world_boss(temp_mgr, now, force_world_boss);
+        if res.is_some() {
+            world_boss = res;
+        }
+        if let Some(mut world_boss) = world_boss {
+            self.save_world_boss(game_key, &world_boss);
+            self.push_update_worldboss(game_key, &world_boss);
+            world_boss.pushed = true;
+            self.save_world_boss(game_key, &world_boss);
+            info!("worldboss轮换完成!game:{},{:?}", game_key, world_boss);
+        }
+        //内存里的状态同步为已推送
+        if let Some(season) = season_mgr.season.as_mut() {
+            season.pushed = true;
+        }
+        if let Some(boss) = season_mgr.world_boss.as_mut() {
+            boss.pushed = true;
+        }
+        self.season_mgrs.insert(game_key, season_mgr);
+    }
+
+    fn save_season(&mut self, game_key: GameKey, season: &SeasonState) {
+        let res = serde_json::to_string(season);
+        if let Err(e) = res {
+            error!("{:?}", e);
             return;
         }
-        let world_boss_id = world_boss_id.unwrap();
-        let world_boss_id = world_boss_id.as_u64().unwrap() as i32;
+        let game_id = game_key.game_id.to_string();
+        let redis = crate::game_redis(game_key);
+        let _: Option<String> = redis.hset(
+            crate::REDIS_INDEX_GAME_SEASON,
+            crate::REDIS_KEY_GAME_SEASON,
+            game_id.as_str(),
+            res.unwrap().as_str(),
+        );
+    }
 
-        let next_update_time = map.get("next_update_time");
-        if next_update_time.is_none() {
-            warn!("the next_update_time is None!");
+    fn save_world_boss(&mut self, game_key: GameKey, world_boss: &WorldBossState) {
+        let res = serde_json::to_string(world_boss);
+        if let Err(e) = res {
+            error!("{:?}", e);
             return;
         }
-        let next_update_time = next_update_time.unwrap().as_u64();
-        if next_update_time.is_none() {
-            warn!("the next_update_time is None!");
+        let game_id = game_key.game_id.to_string();
+        let redis = crate::game_redis(game_key);
+        let _: Option<String> = redis.hset(
+            crate::REDIS_INDEX_GAME_SEASON,
+            crate::REDIS_KEY_WORLD_BOSS,
+            game_id.as_str(),
+            res.unwrap().as_str(),
+        );
+    }
+
+    ///通知该游戏区服的排行榜服，房间服和战斗服更新赛季
+    ///游戏服要等排行榜服处理完赛季结算再重新加载排行榜，由排行榜服通知
+    pub fn push_update_season(&mut self, game_key: GameKey, season: &SeasonState) {
+        let mut usn = R_S_UPDATE_SEASON::new();
+        usn.set_season_id(season.season_id);
+        usn.set_round(season.round);
+        usn.set_next_update_time(season.next_update_time);
+        let bytes = usn.write_to_bytes();
+        if let Err(e) = bytes {
+            error!("{:?}", e);
             return;
         }
-        let next_update_time = next_update_time.unwrap();
+        let bytes = bytes.unwrap();
 
+        let mut packet = Packet::new(RankCode::UpdateSeasonPush.into_u32(), 0, 0);
+        packet.set_is_client(false);
+        packet.set_is_broad(true);
+        packet.set_data(&bytes[..]);
+        //通知排行榜服
+        let bytes = packet.build_server_bytes();
+        self.send_2_rank(&game_key, bytes);
+        //通知房间服
+        packet.set_cmd(RoomCode::UpdateSeasonPush.into_u32());
+        let bytes = packet.build_server_bytes();
+        self.send_2_room(&game_key, bytes);
+        //推送给所有战斗服
+        packet.set_cmd(BattleCode::UpdateSeasonPush.into_u32());
+        let bytes = packet.build_server_bytes();
+        for battle_client in self.battle_clients.values() {
+            if battle_client.game_key == game_key {
+                battle_client.send(bytes.as_slice());
+            }
+        }
+    }
+
+    ///通知该游戏区服的所有服务器更新worldboss
+    pub fn push_update_worldboss(&mut self, game_key: GameKey, world_boss: &WorldBossState) {
         let mut usn = UPDATE_WORLD_BOSS_PUSH::new();
-        usn.set_world_boss_id(world_boss_id);
-        usn.set_next_update_time(next_update_time);
+        usn.set_world_boss_id(world_boss.cter_id);
+        usn.set_next_update_time(world_boss.next_update_time);
 
         let mut packet = Packet::new(RoomCode::UpdateWorldBossPush.into_u32(), 0, 0);
         packet.set_is_client(false);
         packet.set_is_broad(true);
         packet.set_data(&usn.write_to_bytes().unwrap()[..]);
         let bytes = packet.build_server_bytes();
-        let _ = self.get_room_center_mut().send(bytes);
+        self.send_2_room(&game_key, bytes);
 
         packet.set_cmd(GameCode::UpdateWorldBossPush.into_u32());
         let bytes = packet.build_server_bytes();
         //推送给所有游戏服
         for gate_client in self.gate_clients.values_mut() {
-            gate_client.send(bytes.as_slice());
+            if gate_client.game_key == game_key {
+                gate_client.send(bytes.as_slice());
+            }
         }
     }
 
     ///停服
+    ///广播停服排空命令:gate停止登录并踢人，游戏服保存玩家数据，房间服拒绝新房间，战斗服等战斗打完
+    ///各服处理完后回复DrainAck
     pub fn stop_all_server_handler(&mut self) {
-        let bytes =
-            Packet::build_packet_bytes(GateCode::StopServer.into_u32(), 0, Vec::new(), true, false);
-        let bytes = bytes.as_slice();
+        tools::shutdown::set_draining();
+        self.drain_acks.clear();
+        let bytes = Packet::build_packet_bytes(
+            ServerCommonCode::Drain.into_u32(),
+            0,
+            Vec::new(),
+            true,
+            false,
+        );
+        let bytes_slice = bytes.as_slice();
+        //通知gate,gate再通知游戏服
         for gate_client in self.gate_clients.values() {
-            gate_client.send(bytes);
+            gate_client.send(bytes_slice);
+        }
+        //通知战斗服
+        for battle_client in self.battle_clients.values() {
+            battle_client.send(bytes_slice);
+        }
+        //通知所有游戏区服的房间服
+        let mut wait_num = self.gate_clients.len() + self.battle_clients.len();
+        for room_center in self.room_centers.values() {
+            let res = room_center.send(bytes.clone());
+            match res {
+                Ok(_) => wait_num += 1,
+                Err(e) => warn!("{:?}", e),
+            }
         }
+        self.drain_wait_num = wait_num;
+        info!("开始停服排空!需要等待确认的服务器数量:{}", wait_num);
+    }
+
+    ///停服排空是否已经全部确认
+    pub fn is_drain_finish(&self) -> bool {
+        self.drain_acks.len() >= self.drain_wait_num
+    }
+
+    ///处理停服排空确认
+    pub fn drain_ack(&mut self, packet: &Packet) {
+        let value = serde_json::from_slice::<Value>(packet.get_data());
+        let value = match value {
+            Ok(value) => value,
+            Err(e) => {
+                warn!("{:?}", e);
+                Value::Null
+            }
+        };
+        info!(
+            "收到停服排空确认!{},进度:{}/{}",
+            value,
+            self.drain_acks.len() + 1,
+            self.drain_wait_num
+        );
+        self.drain_acks.push(value);
     }
 
     pub fn kick_player_handler(&mut self, user_id: u32) {
@@ -167,18 +396,41 @@ impl GameCenterMgr {
             battle_client.send(bytes_slice);
         }
 
-        //通知房间服
-        let res = self.get_room_center_mut().send(bytes);
-        if let Err(e) = res {
-            warn!("{:?}", e);
+        //通知所有游戏区服的房间服
+        for room_center in self.room_centers.values() {
+            let res = room_center.send(bytes.clone());
+            if let Err(e) = res {
+                warn!("{:?}", e);
+            }
         }
     }
 
-    pub fn handler(&mut self, packet: &Packet, gate_token: Option<usize>) {
+    pub fn handler(
+        &mut self,
+        packet: &Packet,
+        gate_token: Option<usize>,
+        battle_token: Option<usize>,
+        game_key: GameKey,
+    ) {
         let cmd = packet.get_cmd();
-        //开始战斗,负载均衡，分配战斗服务器
+        //停服排空确认
+        if cmd == ServerCommonCode::DrainAck.into_u32() {
+            self.drain_ack(packet);
+            return;
+        }
+        //服务器注册
+        if cmd == ServerCommonCode::Register.into_u32() {
+            self.register(packet, gate_token, battle_token);
+            return;
+        }
+        //战斗服上报负载
+        if cmd == ServerCommonCode::LoadReport.into_u32() {
+            self.load_report(packet, battle_token);
+            return;
+        }
+        //开始战斗,负载均衡，在同一个游戏区服里分配战斗服务器
         if cmd == BattleCode::Start.into_u32() {
-            self.slb(packet.clone());
+            self.slb(packet.clone(), game_key);
         }
         //绑定玩家到gate
         let user_id = packet.get_user_id();
@@ -211,8 +463,23 @@ impl GameCenterMgr {
         }
     }
 
-    ///负载均衡
-    pub fn slb(&mut self, packet: Packet) {
+    ///战斗服上报负载，以上报的房间数为准
+    pub fn load_report(&mut self, packet: &Packet, battle_token: Option<usize>) {
+        let battle_client = battle_token.and_then(|x| self.battle_clients.get_mut(&x));
+        if battle_client.is_none() {
+            return;
+        }
+        let mut bb = ByteBuf::from(packet.get_data());
+        let res = bb.read_u32();
+        if let Err(e) = res {
+            warn!("{:?}", e);
+            return;
+        }
+        battle_client.unwrap().room_num = res.unwrap();
+    }
+
+    ///负载均衡，新的战斗分配给同一个游戏区服里房间数最少的战斗服
+    pub fn slb(&mut self, packet: Packet, game_key: GameKey) {
         let mut proto = R_B_START::new();
         let res = proto.merge_from_bytes(packet.get_data());
         if let Err(e) = res {
@@ -224,9 +491,13 @@ impl GameCenterMgr {
         let bc_res = self
             .battle_clients
             .values_mut()
+            .filter(|x| x.game_key == game_key)
             .min_by(|x, y| x.room_num.cmp(&y.room_num));
         if let None = bc_res {
-            warn!("could not find min room num of battle server!");
+            warn!(
+                "could not find min room num of battle server!game:{}",
+                game_key
+            );
             return;
         }
         let bc_res = bc_res.unwrap();
@@ -258,12 +529,52 @@ impl GameCenterMgr {
         }
     }
 
-    pub fn get_room_center_mut(&mut self) -> &mut Sender<Vec<u8>> {
-        self.room_center.as_mut().unwrap()
+    pub fn get_room_center_mut(
+        &mut self,
+        game_key: &GameKey,
+    ) -> anyhow::Result<&mut Sender<Vec<u8>>> {
+        let res = self.room_centers.get_mut(game_key);
+        if res.is_none() {
+            anyhow::bail!("could not find room center by game:{}!", game_key)
+        }
+        Ok(res.unwrap())
     }
 
-    pub fn get_rank_center_mut(&mut self) -> &mut Sender<Vec<u8>> {
-        self.rank_server.as_mut().unwrap()
+    pub fn get_rank_center_mut(
+        &mut self,
+        game_key: &GameKey,
+    ) -> anyhow::Result<&mut Sender<Vec<u8>>> {
+        let res = self.rank_servers.get_mut(game_key);
+        if res.is_none() {
+            anyhow::bail!("could not find rank server by game:{}!", game_key)
+        }
+        Ok(res.unwrap())
+    }
+
+    ///发送给游戏区服的房间服
+    pub fn send_2_room(&mut self, game_key: &GameKey, bytes: Vec<u8>) {
+        let res = self.get_room_center_mut(game_key);
+        match res {
+            Ok(rc) => {
+                if let Err(e) = rc.send(bytes) {
+                    warn!("{:?}", e);
+                }
+            }
+            Err(e) => warn!("{:?}", e),
+        }
+    }
+
+    ///发送给游戏区服的排行榜服
+    pub fn send_2_rank(&mut self, game_key: &GameKey, bytes: Vec<u8>) {
+        let res = self.get_rank_center_mut(game_key);
+        match res {
+            Ok(rs) => {
+                if let Err(e) = rs.send(bytes) {
+                    warn!("{:?}", e);
+                }
+            }
+            Err(e) => warn!("{:?}", e),
+        }
     }
 
     pub fn get_gate_client(&self, user_id: u32) -> anyhow::Result<&GateClient> {
@@ -294,12 +605,12 @@ impl GameCenterMgr {
         Ok(gc)
     }
 
-    pub fn set_room_sender(&mut self, sender: Sender<Vec<u8>>) {
-        self.room_center = Some(sender);
+    pub fn set_room_sender(&mut self, game_key: GameKey, sender: Sender<Vec<u8>>) {
+        self.room_centers.insert(game_key, sender);
     }
 
-    pub fn set_rank_sender(&mut self, sender: Sender<Vec<u8>>) {
-        self.rank_server = Some(sender);
+    pub fn set_rank_sender(&mut self, game_key: GameKey, sender: Sender<Vec<u8>>) {
+        self.rank_servers.insert(game_key, sender);
     }
 
     pub fn add_gate_client(&mut self, net_handler: NetHandler) {
@@ -317,11 +628,15 @@ impl GameCenterMgr {
 
 pub struct GateClient {
     net_handler: NetHandler,
+    pub game_key: GameKey, //所属游戏区服
 }
 
 impl GateClient {
     pub fn new(net_handler: NetHandler) -> Self {
-        let gc = GateClient { net_handler };
+        let gc = GateClient {
+            net_handler,
+            game_key: GameCenterMgr::default_game_key(),
+        };
         gc
     }
 
@@ -337,6 +652,7 @@ impl GateClient {
 pub struct BattleClient {
     net_handler: NetHandler,
     room_num: u32,
+    pub game_key: GameKey, //所属游戏区服
 }
 
 impl BattleClient {
@@ -344,6 +660,7 @@ impl BattleClient {
         let rc = BattleClient {
             net_handler,
             room_num: 0,
+            game_key: GameCenterMgr::default_game_key(),
         };
         rc
     }
@@ -0,0 +1,226 @@
+use serde::{Deserialize, Serialize};
+use tools::templates::season_temp::SeasonTempMgr;
+use tools::templates::world_boss_temp::WorldBossTempMgr;
+
+///一次最多补多少次轮换，停服太久的直接从当前时间开始
+const MAX_CATCH_UP_TIMES: usize = 100;
+
+///赛季状态，持久化到redis，游戏服，房间服，战斗服启动时也从这里读取
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SeasonState {
+    pub season_id: i32,        //赛季id
+    pub round: u32,            //第几轮赛季
+    pub next_update_time: u64, //下次更新时间(秒)
+    #[serde(default)]
+    pub pushed: bool, //是否已经推送给所有服务器，没推送就停服的，重启后重新推送
+}
+
+///worldboss状态，持久化到redis
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WorldBossState {
+    pub cter_id: i32,          //worldboss角色id
+    pub next_update_time: u64, //下次更新时间(秒)
+    #[serde(default)]
+    pub pushed: bool, //是否已经推送给所有服务器
+}
+
+///赛季日历，按配置id顺序轮换赛季和worldboss
+#[derive(Debug, Clone, Default)]
+pub struct SeasonMgr {
+    pub season: Option<SeasonState>,        //当前赛季
+    pub world_boss: Option<WorldBossState>, //当前worldboss
+}
+
+impl SeasonMgr {
+    ///计算下一个赛季
+    ///current为None则从round_season_id开始第一轮
+    ///start_time:下一个赛季的开始时间(秒)
+    pub fn next_season(
+        temp_mgr: &SeasonTempMgr,
+        round_season_id: i32,
+        current: Option<&SeasonState>,
+        start_time: u64,
+    ) -> Option<SeasonState> {
+        let mut ids: Vec<u32> = temp_mgr.temps.keys().copied().collect();
+        if ids.is_empty() {
+            return None;
+        }
+        ids.sort_unstable();
+        let (season_id, round) = match current {
+            Some(current) => {
+                let index = ids.iter().position(|x| *x as i32 == current.season_id);
+                let season_id = match index {
+                    Some(index) => ids[(index + 1) % ids.len()],
+                    None => ids[0],
+                };
+                let mut round = current.round;
+                //轮回到第一个赛季，开始新的一轮
+                if season_id as i32 == round_season_id {
+                    round += 1;
+                }
+                (season_id, round)
+            }
+            None => {
+                let season_id = ids
+                    .iter()
+                    .find(|x| **x as i32 == round_season_id)
+                    .copied()
+                    .unwrap_or(ids[0]);
+                (season_id, 1)
+            }
+        };
+        let keep_time = temp_mgr.temps.get(&season_id).unwrap().keep_time / 1000;
+        if keep_time == 0 {
+            return None;
+        }
+        Some(SeasonState {
+            season_id: season_id as i32,
+            round,
+            next_update_time: start_time + keep_time,
+            pushed: false,
+        })
+    }
+
+    ///计算下一个worldboss
+    pub fn next_world_boss(
+        temp_mgr: &WorldBossTempMgr,
+        current: Option<&WorldBossState>,
+        start_time: u64,
+    ) -> Option<WorldBossState> {
+        let mut ids: Vec<u32> = temp_mgr.temps.keys().copied().collect();
+        if ids.is_empty() {
+            return None;
+        }
+        ids.sort_unstable();
+        let index = current.and_then(|x| ids.iter().position(|id| *id as i32 == x.cter_id));
+        let cter_id = match index {
+            Some(index) => ids[(index + 1) % ids.len()],
+            None => ids[0],
+        };
+        let keep_time = temp_mgr.temps.get(&cter_id).unwrap().keep_time / 1000;
+        if keep_time == 0 {
+            return None;
+        }
+        Some(WorldBossState {
+            cter_id: cter_id as i32,
+            next_update_time: start_time + keep_time,
+            pushed: false,
+        })
+    }
+
+    ///到时间了就轮换赛季，返回新的赛季
+    ///中间错过的赛季按时间补算轮次，但只推送最新的这个
+    ///force为true则不管时间立刻轮换
+    pub fn rotate_season(
+        &mut self,
+        temp_mgr: &SeasonTempMgr,
+        round_season_id: i32,
+        now: u64,
+        force: bool,
+    ) -> Option<SeasonState> {
+        let mut res = None;
+        let mut times = 0;
+        loop {
+            let start_time = match self.season.as_ref() {
+                None => now,
+                Some(season) if force && res.is_none() => now.min(season.next_update_time),
+                Some(season) if season.next_update_time > now => break,
+                //停服太久，不再一个个补
+                Some(_) if times >= MAX_CATCH_UP_TIMES => now,
+                Some(season) => season.next_update_time,
+            };
+            let next =
+                SeasonMgr::next_season(temp_mgr, round_season_id, self.season.as_ref(), start_time);
+            if next.is_none() {
+                break;
+            }
+            let next = next.unwrap();
+            self.season = Some(next.clone());
+            res = Some(next);
+            times += 1;
+        }
+        res
+    }
+
+    ///到时间了就轮换worldboss，返回新的worldboss
+    ///中间错过的worldboss不需要补推送，直接跳到当前这个
+    pub fn rotate_world_boss(
+        &mut self,
+        temp_mgr: &WorldBossTempMgr,
+        now: u64,
+        force: bool,
+    ) -> Option<WorldBossState> {
+        let mut res = None;
+        let mut times = 0;
+        loop {
+            let start_time = match self.world_boss.as_ref() {
+                None => now,
+                Some(boss) if force && res.is_none() => now.min(boss.next_update_time),
+                Some(boss) if boss.next_update_time > now => break,
+                Some(_) if times >= MAX_CATCH_UP_TIMES => now,
+                Some(boss) => boss.next_update_time,
+            };
+            let next = SeasonMgr::next_world_boss(temp_mgr, self.world_boss.as_ref(), start_time);
+            if next.is_none() {
+                break;
+            }
+            let next = next.unwrap();
+            self.world_boss = Some(next.clone());
+            res = Some(next);
+            times += 1;
+        }
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tools::templates::season_temp::SeasonTemp;
+
+    fn season_temp_mgr() -> SeasonTempMgr {
+        let mut mgr = SeasonTempMgr::default();
+        let mut v = vec![];
+        for id in 1001..1004 {
+            v.push(SeasonTemp {
+                id,
+                element: 1,
+                keep_time: 10_000,
+            });
+        }
+        mgr.init(v);
+        mgr
+    }
+
+    #[test]
+    fn rotate_season_in_order_and_catch_up() {
+        let temp_mgr = season_temp_mgr();
+        let mut mgr = SeasonMgr::default();
+        let res = mgr.rotate_season(&temp_mgr, 1001, 100, false).unwrap();
+        assert_eq!(res.season_id, 1001);
+        assert_eq!(res.round, 1);
+        assert_eq!(res.next_update_time, 110);
+
+        //没到时间不轮换
+        assert!(mgr.rotate_season(&temp_mgr, 1001, 109, false).is_none());
+
+        //停服错过了两次，按时间补算，只返回最新的1001，开始新一轮
+        let res = mgr.rotate_season(&temp_mgr, 1001, 135, false).unwrap();
+        assert_eq!(res.season_id, 1001);
+        assert_eq!(res.round, 2);
+        assert_eq!(res.next_update_time, 140);
+    }
+
+    #[test]
+    fn rotate_season_catch_up_is_capped() {
+        let temp_mgr = season_temp_mgr();
+        let mut mgr = SeasonMgr::default();
+        mgr.rotate_season(&temp_mgr, 1001, 100, false);
+
+        //停服太久，补算到上限后从当前时间开始
+        let now = 100 + 10 * (MAX_CATCH_UP_TIMES as u64 + 50);
+        let res = mgr.rotate_season(&temp_mgr, 1001, now, false).unwrap();
+        assert_eq!(res.next_update_time, now + 10);
+        assert!(mgr.rotate_season(&temp_mgr, 1001, now, false).is_none());
+    }
+}
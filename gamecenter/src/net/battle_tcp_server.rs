@@ -3,6 +3,7 @@ use crate::Lock;
 use async_trait::async_trait;
 use log::error;
 use log::info;
+use tools::game_key::GameKey;
 use tools::net_message_io::NetHandler;
 use tools::util::packet::Packet;
 
@@ -27,6 +28,11 @@ impl Forward for BattleTcpServerHandler {
         None
     }
 
+    fn get_game_key(&self) -> Option<GameKey> {
+        //注册后才知道，从GameCenterMgr里取
+        None
+    }
+
     fn get_game_center_mut(&mut self) -> &mut Lock {
         &mut self.gm
     }
@@ -57,12 +63,16 @@ impl tools::net_message_io::MessageHandler for BattleTcpServerHandler {
             }
             remove_vec.push(user_id);
         }
-        for user_id in remove_vec {
-            lock.user_w_battle.remove(&user_id);
+        for user_id in remove_vec.iter() {
+            lock.user_w_battle.remove(user_id);
         }
-        //删除battle服
+        //删除battle服，新的战斗会分配到其他战斗服
         lock.battle_clients.remove(&token);
-        info!("battle_client is closed!token:{}", token);
+        info!(
+            "battle_client is closed!token:{},drain user num:{}",
+            token,
+            remove_vec.len()
+        );
     }
 
     ///客户端读取事件
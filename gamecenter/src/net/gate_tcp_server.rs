@@ -2,6 +2,7 @@ use crate::net::Forward;
 use crate::Lock;
 use async_trait::async_trait;
 use log::{error, info};
+use tools::game_key::GameKey;
 use tools::net_message_io::NetHandler;
 use tools::util::packet::Packet;
 
@@ -27,6 +28,11 @@ impl Forward for GateTcpServerHandler {
         Some(self.token)
     }
 
+    fn get_game_key(&self) -> Option<GameKey> {
+        //注册后才知道，从GameCenterMgr里取
+        None
+    }
+
     fn get_game_center_mut(&mut self) -> &mut Lock {
         &mut self.gm
     }
@@ -3,12 +3,28 @@ use std::collections::HashMap;
 use crate::Lock;
 
 use async_std::task::block_on;
+use log::info;
 use serde_json::json;
 use tools::{
+    game_key::GameKey,
     http::HttpServerHandler,
     json::{JsonValue, JsonValueTrait},
 };
 
+///从uri参数解析游戏区服,没有传game_id返回None,表示所有游戏区服
+fn parse_game_key(uri_params: &HashMap<String, String>) -> anyhow::Result<Option<GameKey>> {
+    let game_id = uri_params.get("game_id");
+    if game_id.is_none() {
+        return Ok(None);
+    }
+    let game_id = game_id.unwrap().parse::<u32>()?;
+    let region_id = match uri_params.get("region_id") {
+        Some(region_id) => region_id.parse::<u32>()?,
+        None => 0,
+    };
+    Ok(Some(GameKey::new(game_id, region_id)))
+}
+
 pub struct StopAllServerHandler {
     gm: Lock,
 }
@@ -112,9 +128,18 @@ impl HttpServerHandler for ReloadTempsHandler {
         _uri_params: HashMap<String, String>,
         _json_params: &[u8],
     ) -> anyhow::Result<serde_json::Value> {
+        //先热更自己的，解析或者校验失败就不通知其他服务器
+        let report = crate::TEMPLATES.reload()?;
+        info!("reload_temps success!{}", report);
         let mut lock = block_on(self.gm.lock());
         lock.notice_reload_temps();
-        let value = json!({ "status":"OK" });
+        let value = json!({
+            "status":"OK",
+            "version":report.version,
+            "added":report.added,
+            "changed":report.changed,
+            "removed":report.removed,
+        });
         Ok(value)
     }
 }
@@ -137,19 +162,14 @@ impl HttpServerHandler for UpdateSeasonHandler {
     fn do_post(
         &mut self,
         _uri: String,
-        _uri_params: HashMap<String, String>,
+        uri_params: HashMap<String, String>,
         _json_params: &[u8],
     ) -> anyhow::Result<serde_json::Value> {
-        log::info!("收到modify_server,uri:{:?}", _uri);
-        let _json_params = JsonValue::from_bytes(_json_params);
-        if let Err(err) = _json_params {
-            log::warn!("{:?}", err);
-            return Ok(json!(r#"{"result": "fail!","errMessage":"参数有问题!"}"#));
-        }
-        let params = _json_params.unwrap();
-
+        log::info!("收到update_season,uri:{:?}", _uri);
+        let game_key = parse_game_key(&uri_params)?;
+        //立刻轮换到下一个赛季
         let mut lock = block_on(self.gm.lock());
-        lock.notice_update_season(params);
+        lock.check_season(game_key, true, false);
         let value = json!({ "status":"OK" });
         Ok(value)
     }
@@ -173,18 +193,47 @@ impl HttpServerHandler for UpdateWorldBossHandler {
     fn do_post(
         &mut self,
         _uri: String,
-        _uri_params: HashMap<String, String>,
+        uri_params: HashMap<String, String>,
         _json_params: &[u8],
     ) -> anyhow::Result<JsonValue> {
-        let _json_params = JsonValue::from_bytes(_json_params);
-        if let Err(err) = _json_params {
-            log::warn!("{:?}", err);
-            return Ok(json!(r#"{"result": "fail!","errMessage":"参数有问题!"}"#));
-        }
-        let params = _json_params.unwrap();
+        let game_key = parse_game_key(&uri_params)?;
+        //立刻轮换到下一个worldboss
         let mut lock = block_on(self.gm.lock());
-        lock.notice_update_worldboss(params);
+        lock.check_season(game_key, false, true);
         let value = json!({ "status":"OK" });
         Ok(value)
     }
 }
+
+///查询停服排空进度
+pub struct DrainStatusHandler {
+    gm: Lock,
+}
+
+impl DrainStatusHandler {
+    pub fn new(gm: Lock) -> Self {
+        DrainStatusHandler { gm }
+    }
+}
+
+impl HttpServerHandler for DrainStatusHandler {
+    fn get_path(&self) -> &str {
+        "/drain_status"
+    }
+
+    fn do_get(
+        &mut self,
+        _uri: String,
+        _uri_params: HashMap<String, String>,
+    ) -> anyhow::Result<String> {
+        let lock = block_on(self.gm.lock());
+        let value = json!({
+            "status":"OK",
+            "draining":tools::shutdown::is_draining(),
+            "finish":lock.is_drain_finish(),
+            "wait_num":lock.drain_wait_num,
+            "acks":lock.drain_acks,
+        });
+        Ok(value.to_string())
+    }
+}
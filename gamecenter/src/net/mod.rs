@@ -9,7 +9,8 @@ use std::collections::VecDeque;
 use crate::Lock;
 use async_trait::async_trait;
 use log::warn;
-use tools::cmd_code::{BattleCode, ClientCode, GameCode, RankCode, RoomCode};
+use tools::cmd_code::{BattleCode, ClientCode, GameCode, RankCode, RoomCode, ServerCommonCode};
+use tools::game_key::GameKey;
 use tools::net_message_io::TransportWay;
 use tools::util::packet::Packet;
 
@@ -22,12 +23,16 @@ trait Forward {
 
     fn get_gate_token(&self) -> Option<usize>;
 
+    ///房间服和排行榜服连接所属的游戏区服，gate和战斗服的在注册时记录在GameCenterMgr里
+    fn get_game_key(&self) -> Option<GameKey>;
+
     fn get_game_center_mut(&mut self) -> &mut Lock;
 
     ///数据包转发
     async fn forward_packet(&mut self, packet_array: VecDeque<Packet>) {
         let gate_token = self.get_gate_token();
         let battle_token = self.get_battle_token();
+        let self_game_key = self.get_game_key();
         let lock = self.get_game_center_mut();
         let mut lock = lock.lock().await;
         for mut packet in packet_array {
@@ -35,15 +40,28 @@ trait Forward {
             let user_id = packet.get_user_id();
             let mut bytes = packet.build_server_bytes();
             let is_broad = packet.is_broad();
+            //数据来自哪个游戏区服
+            let game_key = match self_game_key {
+                Some(game_key) => game_key,
+                None => lock.get_game_key(gate_token, battle_token),
+            };
             //需要自己处理的数据
-            lock.handler(&packet, gate_token);
+            lock.handler(&packet, gate_token, battle_token, game_key);
             let bytes_slice = bytes.as_slice();
             //处理公共的命令
-            if cmd > ClientCode::Min.into_u32() && cmd < ClientCode::Max.into_u32() {
+            if cmd == ServerCommonCode::DrainAck.into_u32()
+                || cmd == ServerCommonCode::Register.into_u32()
+                || cmd == ServerCommonCode::LoadReport.into_u32()
+            {
+                //停服排空确认，服务器注册，负载上报，已经在handler里处理
+                continue;
+            } else if cmd > ClientCode::Min.into_u32() && cmd < ClientCode::Max.into_u32() {
                 //发送给客户端
                 if is_broad {
                     for client in lock.gate_clients.values() {
-                        client.send(bytes_slice);
+                        if client.game_key == game_key {
+                            client.send(bytes_slice);
+                        }
                     }
                 } else {
                     let res = lock.get_gate_client(user_id);
@@ -59,11 +77,8 @@ trait Forward {
                     packet.set_server_token(gate_token as u32);
                     bytes = packet.build_server_bytes();
                 }
-                //发消息到房间服
-                let res = lock.get_room_center_mut().send(bytes);
-                if let Err(e) = res {
-                    warn!("{:?}", e);
-                }
+                //发消息到该游戏区服的房间服
+                lock.send_2_room(&game_key, bytes);
             } else if cmd > GameCode::Min.into_u32()//转发给游戏服
                 && cmd < GameCode::Max.into_u32()
             {
@@ -80,9 +95,11 @@ trait Forward {
                         }
                     }
                 } else if is_broad {
-                    //推送给所有游戏服
+                    //推送给该游戏区服的所有游戏服
                     for gate_client in lock.gate_clients.values() {
-                        gate_client.send(bytes_slice);
+                        if gate_client.game_key == game_key {
+                            gate_client.send(bytes_slice);
+                        }
                     }
                 } else {
                     //发给玩家所在游戏服
@@ -97,12 +114,18 @@ trait Forward {
             {
                 if is_broad {
                     for battle_client in lock.battle_clients.values() {
-                        battle_client.send(bytes_slice);
+                        if battle_client.game_key == game_key {
+                            battle_client.send(bytes_slice);
+                        }
                     }
                 } else {
                     let res = lock.get_battle_client(user_id);
                     match res {
                         Ok(gc) => gc.send(bytes_slice),
+                        //不在战斗中的玩家掉线、重连不需要通知战斗服
+                        Err(_)
+                            if cmd == BattleCode::Disconnect.into_u32()
+                                || cmd == BattleCode::Reconnect.into_u32() => {}
                         Err(e) => warn!("{:?},cmd:{:?}", e, cmd),
                     }
                 }
@@ -114,12 +137,8 @@ trait Forward {
                     bytes = packet.build_server_bytes();
                 }
 
-                let rs = lock.get_rank_center_mut();
-                let res = rs.send(bytes);
-                match res {
-                    Ok(_) => {}
-                    Err(e) => warn!("{:?},cmd:{:?}", e, cmd),
-                }
+                //发消息到该游戏区服的排行榜服
+                lock.send_2_rank(&game_key, bytes);
             } else {
                 warn!("could not find cmd {}!", cmd);
                 return;
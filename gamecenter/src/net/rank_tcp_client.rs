@@ -1,16 +1,19 @@
 use crate::net::Forward;
 use crate::Lock;
 use async_std::task::block_on;
-use async_trait::async_trait;
 use crossbeam::channel::Sender;
 use log::error;
-use tools::tcp::ClientHandler;
+use tools::game_key::GameKey;
+use tools::reconnect::ReconnectHandler;
+use tools::registry::ROLE_RANK;
 use tools::util::packet::Packet;
 
 ///处理排行榜服所有请求,每个客户端单独分配一个handler
 #[derive(Clone)]
 pub struct RankTcpClientHandler {
     pub gm: Lock,
+    pub game_key: GameKey, //所属游戏区服
+    pub address: String,   //服务器地址
 }
 
 impl Forward for RankTcpClientHandler {
@@ -22,25 +25,28 @@ impl Forward for RankTcpClientHandler {
         None
     }
 
+    fn get_game_key(&self) -> Option<GameKey> {
+        Some(self.game_key)
+    }
+
     fn get_game_center_mut(&mut self) -> &mut Lock {
         &mut self.gm
     }
 }
 
-#[async_trait]
-impl ClientHandler for RankTcpClientHandler {
-    async fn on_open(&mut self, ts: Sender<Vec<u8>>) {
-        let mut lock = block_on(self.gm.lock());
-        lock.set_rank_sender(ts);
+impl ReconnectHandler for RankTcpClientHandler {
+    fn address(&mut self) -> Option<String> {
+        //可能换了地址，重新发现
+        self.address = crate::discover_address(self.game_key, ROLE_RANK);
+        Some(self.address.clone())
     }
 
-    async fn on_close(&mut self) {
-        let address = crate::CONF_MAP.get_str("rank_port", "");
-
-        self.on_read(address.to_string()).await;
+    fn on_connect(&mut self, ts: Sender<Vec<u8>>, _reconnect: bool) {
+        let mut lock = block_on(self.gm.lock());
+        lock.set_rank_sender(self.game_key, ts);
     }
 
-    async fn on_message(&mut self, mess: Vec<u8>) {
+    fn on_message(&mut self, mess: Vec<u8>) {
         let packet_array = Packet::build_array_from_server(mess);
 
         if let Err(e) = packet_array {
@@ -49,6 +55,6 @@ impl ClientHandler for RankTcpClientHandler {
         }
         let packet_array = packet_array.unwrap();
         //转发消息
-        self.forward_packet(packet_array).await;
+        block_on(self.forward_packet(packet_array));
     }
 }
@@ -2,7 +2,7 @@ use std::str::FromStr;
 
 //use isahc::prelude::*;
 
-use crate::{net::query_user_id_from_redis, CONF_MAP};
+use crate::{net::query_user_id_from_redis, CONFIG};
 
 pub fn auth_account(ticket: &str) -> anyhow::Result<u32> {
     let steam_id = auth_user_ticket(ticket)?;
@@ -11,21 +11,14 @@ pub fn auth_account(ticket: &str) -> anyhow::Result<u32> {
 }
 
 pub fn auth_user_ticket(ticket: &str) -> anyhow::Result<u64> {
-    let res = CONF_MAP.conf.get(&"steam".to_owned());
-    if let None = res {
+    let steam = CONFIG.steam.first();
+    if steam.is_none() {
         anyhow::bail!("steam auth fail!steam key is not find!")
     }
+    let steam = steam.unwrap();
 
-    let json_value = res.unwrap();
-
-    let res = json_value.as_array().unwrap().get(0).unwrap();
-    let json_value = res.as_object().unwrap();
-
-    let web_api_key = json_value.get("web_api_key").unwrap();
-    let web_api_key = web_api_key.as_str().unwrap();
-
-    let app_id = json_value.get("player_test_app_id").unwrap();
-    let app_id = app_id.as_u64().unwrap();
+    let web_api_key = steam.web_api_key.as_str();
+    let app_id = steam.player_test_app_id;
 
     let url = format!("https://partner.steam-api.com/ISteamUserAuth/AuthenticateUserTicket/v1/?key={:?}&appid={}&ticket={:?}",web_api_key,app_id,ticket);
     let url = url.replace(r#"""#, "");
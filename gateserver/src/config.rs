@@ -0,0 +1,117 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use tools::conf::{check_address, BaseConfig, Validate};
+use tools::game_key::GameKey;
+use tools::reconnect::ReconnectConfig;
+use tools::registry::RegistryConfig;
+
+///steam平台配置,web_api_key可以用web_api_key_file从单独的文件读取
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SteamConfig {
+    pub web_api_key: String,     //web api key
+    pub url: String,             //steam接口地址
+    pub demo_app_id: u64,        //demo的app id
+    pub player_test_app_id: u64, //玩家测试的app id
+    pub app_id: u64,             //正式的app id
+}
+
+///gate配置，启动时从config/config.conf读取并校验
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GateConfig {
+    #[serde(flatten)]
+    pub game: GameKey, //游戏和区服
+    #[serde(flatten)]
+    pub base: BaseConfig, //公共配置
+    #[serde(flatten)]
+    pub registry: RegistryConfig, //注册中心
+    #[serde(flatten)]
+    pub reconnect: ReconnectConfig, //断线重连
+    pub mysql: String,             //mysql地址
+    pub game_ports: Vec<String>,   //游戏服地址(file模式)
+    pub game_port: String,         //单个游戏服地址，没有配置game_ports时使用
+    pub game_center_port: String,  //游戏中心地址(file模式)
+    pub http_port: u16,            //http监听端口
+    pub user_center_state: String, //用户中心地址
+    pub net_module: String,        //和客户端通信的方式:"tcp"或者"ws"
+    pub tcp_port: String,          //tcp监听地址
+    pub web_socket_port: String,   //websocket监听地址
+    pub steam: Vec<SteamConfig>,   //steam平台配置
+    pub resume_window: usize,      //断线后保留会话等待重连的时间(秒),0为不保留
+    pub login_timeout: usize,      //连上后多久没登录断开(秒),0为不检查
+    pub heartbeat_timeout: usize,  //多久没收到消息断开(秒),0为不检查
+    pub rate_limit: JsonValue,     //限流配置
+    pub rpc_timeout: u64,          //rpc超时时间(毫秒)
+    pub capacity: u32,             //容量(玩家数),0为不限制
+}
+
+impl Default for GateConfig {
+    fn default() -> Self {
+        GateConfig {
+            game: GameKey::default(),
+            base: BaseConfig::default(),
+            registry: RegistryConfig::default(),
+            reconnect: ReconnectConfig::default(),
+            mysql: String::new(),
+            game_ports: Vec::new(),
+            game_port: String::new(),
+            game_center_port: String::new(),
+            http_port: 0,
+            user_center_state: String::new(),
+            net_module: "tcp".to_owned(),
+            tcp_port: String::new(),
+            web_socket_port: String::new(),
+            steam: Vec::new(),
+            resume_window: 0,
+            login_timeout: 30,
+            heartbeat_timeout: 60,
+            rate_limit: JsonValue::Null,
+            rpc_timeout: 5000,
+            capacity: 0,
+        }
+    }
+}
+
+impl GateConfig {
+    ///和客户端通信的监听地址
+    pub fn listen_address(&self) -> &str {
+        match self.net_module.as_str() {
+            "ws" => self.web_socket_port.as_str(),
+            _ => self.tcp_port.as_str(),
+        }
+    }
+
+    ///所有游戏服地址，没有配置game_ports的用game_port
+    pub fn game_ports(&self) -> Vec<String> {
+        if self.game_ports.is_empty() {
+            return vec![self.game_port.clone()];
+        }
+        self.game_ports.clone()
+    }
+}
+
+impl Validate for GateConfig {
+    fn validate(&self) -> anyhow::Result<()> {
+        self.base.validate()?;
+        self.registry.validate()?;
+        self.reconnect.validate()?;
+        match self.net_module.as_str() {
+            "tcp" => check_address("tcp_port", self.tcp_port.as_str())?,
+            "ws" => check_address("web_socket_port", self.web_socket_port.as_str())?,
+            _ => anyhow::bail!("net_module只能是tcp或者ws!{:?}", self.net_module),
+        }
+        if self.registry.registry_mode == "file" {
+            if self.game_ports().iter().any(|x| x.is_empty()) {
+                anyhow::bail!("file模式下game_ports或者game_port不能为空!");
+            }
+            if self.game_center_port.is_empty() {
+                anyhow::bail!("file模式下game_center_port不能为空!");
+            }
+        }
+        if self.rpc_timeout == 0 {
+            anyhow::bail!("rpc_timeout不能为0!");
+        }
+        Ok(())
+    }
+}
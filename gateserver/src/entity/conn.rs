@@ -0,0 +1,94 @@
+use crate::mgr::rate_limit::RateLimiter;
+use tools::net_message_io::NetHandler;
+
+///客户端连接，记录连上和最后一次收到有效消息的时间，用于清理不登录、不发心跳的连接
+pub struct ClientConn {
+    pub net: NetHandler,      //连接句柄
+    pub time: ConnTime,       //连接时间
+    pub limiter: RateLimiter, //限流器
+}
+
+impl ClientConn {
+    pub fn new(net: NetHandler, now: i64) -> Self {
+        ClientConn {
+            net,
+            time: ConnTime::new(now),
+            limiter: RateLimiter::default(),
+        }
+    }
+
+    ///关闭连接
+    pub fn close(&self) {
+        let endpoint = self.net.endpoint;
+        self.net
+            .node_handler
+            .network()
+            .remove(endpoint.resource_id());
+    }
+}
+
+///连接时间
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ConnTime {
+    pub connected_at: i64, //连上的时间(毫秒)
+    pub last_seen: i64,    //最后一次收到有效消息的时间(毫秒)
+}
+
+impl ConnTime {
+    pub fn new(now: i64) -> Self {
+        ConnTime {
+            connected_at: now,
+            last_seen: now,
+        }
+    }
+
+    ///收到有效消息，刷新最后活跃时间
+    pub fn touch(&mut self, now: i64) {
+        self.last_seen = now;
+    }
+
+    ///是否超时，超时时间为0则不检查
+    ///没登录的从连上开始算，发什么包都不能续命；登录了的从最后一次收到有效消息开始算
+    pub fn is_timeout(
+        &self,
+        is_login: bool,
+        now: i64,
+        login_timeout: i64,
+        heartbeat_timeout: i64,
+    ) -> bool {
+        let (timeout, from) = if is_login {
+            (heartbeat_timeout, self.last_seen)
+        } else {
+            (login_timeout, self.connected_at)
+        };
+        timeout > 0 && now - from >= timeout
+    }
+}
+
+///清理空闲连接统计
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ReapStats {
+    pub login_timeout_num: u64,     //登录超时清理的连接数量
+    pub heartbeat_timeout_num: u64, //心跳超时清理的连接数量
+    pub last_reap_time: i64,        //最近一次清理到连接的时间(毫秒)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conn_timeout() {
+        let mut time = ConnTime::new(0);
+        //没登录的刷新活跃时间也不能续命
+        time.touch(29000);
+        assert!(!time.is_timeout(false, 29999, 30000, 60000));
+        assert!(time.is_timeout(false, 30000, 30000, 60000));
+        //登录了的按最后活跃时间算
+        assert!(!time.is_timeout(true, 60000, 30000, 60000));
+        assert!(time.is_timeout(true, 89000, 30000, 60000));
+        //为0不检查
+        assert!(!time.is_timeout(false, i64::MAX, 0, 0));
+        assert!(!time.is_timeout(true, i64::MAX, 0, 0));
+    }
+}
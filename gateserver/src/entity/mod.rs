@@ -1 +1,3 @@
+pub mod conn;
 pub mod gateuser;
+pub mod session;
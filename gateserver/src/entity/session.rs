@@ -0,0 +1,52 @@
+use std::collections::VecDeque;
+use tools::util::packet::Packet;
+
+///断线期间最多缓存的推送数量，超过则放弃重连
+pub const MAX_SUSPENDED_PACKETS: usize = 512;
+
+///断线后等待重连的会话
+pub struct SuspendedSession {
+    pub session_token: u64,        //会话token
+    pub off_time: i64,             //断线时间(毫秒)
+    pub packets: VecDeque<Packet>, //断线期间缓存的推送
+}
+
+impl SuspendedSession {
+    pub fn new(session_token: u64, off_time: i64) -> Self {
+        SuspendedSession {
+            session_token,
+            off_time,
+            packets: VecDeque::new(),
+        }
+    }
+
+    ///缓存推送，超过上限返回false
+    pub fn push(&mut self, packet: Packet) -> bool {
+        if self.packets.len() >= MAX_SUSPENDED_PACKETS {
+            return false;
+        }
+        self.packets.push_back(packet);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push_until_full() {
+        let mut session = SuspendedSession::new(1, 0);
+        for cmd in 0..MAX_SUSPENDED_PACKETS as u32 {
+            assert!(session.push(Packet::new(cmd, 0, 1011)));
+        }
+        //满了不再缓存，已经缓存的保持顺序
+        assert!(!session.push(Packet::new(0, 0, 1011)));
+        assert_eq!(session.packets.len(), MAX_SUSPENDED_PACKETS);
+        assert_eq!(session.packets.front().unwrap().get_cmd(), 0);
+        assert_eq!(
+            session.packets.back().unwrap().get_cmd(),
+            MAX_SUSPENDED_PACKETS as u32 - 1
+        );
+    }
+}
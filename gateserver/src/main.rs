@@ -1,26 +1,34 @@
 mod auth;
+mod config;
 mod entity;
 mod mgr;
 mod net;
+use crate::config::GateConfig;
 use crate::mgr::channel_mgr::ChannelMgr;
 use crate::net::tcp_client::TcpClientHandler;
 use async_std::path;
 use async_std::sync::Mutex;
-use log::info;
+use async_std::task::block_on;
+use log::{error, info, warn};
 use net::websocket;
 use std::fs::{DirEntry, File};
 use std::sync::Arc;
+use tools::cmd_code::ServerCommonCode;
 use tools::conf::Conf;
 
-use crate::net::http::KickPlayerHttpHandler;
+use crate::net::http::{CompressMetricsHttpHandler, KickPlayerHttpHandler, ReapStatsHttpHandler};
 use crate::net::tcp_client::TcpClientType;
 use crate::net::tcp_server;
 use std::env;
 use std::time::Duration;
 use tools::http::HttpServerHandler;
+use tools::reconnect::ReconnectConf;
 use tools::redis_pool::RedisPoolTool;
-use tools::tcp::ClientHandler;
+use tools::registry::{
+    Registry, ServiceInfo, ROLE_GAME, ROLE_GAME_CENTER_GATE, ROLE_GATE,
+};
 use tools::thread_pool::MyThreadPool;
+use tools::util::packet::Packet;
 
 #[macro_use]
 extern crate lazy_static;
@@ -43,12 +51,25 @@ lazy_static! {
         conf
     };
 
+    ///校验过的配置
+    static ref CONFIG: GateConfig = {
+        match CONF_MAP.parse() {
+            Ok(config) => config,
+            Err(e) => panic!("{:?}", e),
+        }
+    };
+
    ///reids客户端
-    static ref REDIS_POOL:Arc<std::sync::Mutex<RedisPoolTool>>={
-        let add: &str = &CONF_MAP.get_str("redis_add","");
-        let pass: &str = &CONF_MAP.get_str("redis_pass","");
-        let redis = RedisPoolTool::init(add,pass);
-        let redis:Arc<std::sync::Mutex<RedisPoolTool>> = Arc::new(std::sync::Mutex::new(redis));
+    static ref REDIS_POOL: RedisPoolTool = {
+        let mut redis = match RedisPoolTool::init(&CONFIG.base.redis_add, &CONFIG.base.redis_pass) {
+            Ok(redis) => redis,
+            Err(e) => {
+                error!("初始化redis失败!{:?}", e);
+                std::process::exit(1);
+            }
+        };
+        //和游戏服同一个命名空间，注册中心才能发现同一个游戏区服的游戏服和游戏中心
+        redis.set_namespace(CONFIG.game.namespace().as_str(), &[REDIS_KEY_USERS, REDIS_KEY_UID_2_PID]);
         redis
     };
 }
@@ -65,6 +86,15 @@ fn main() {
     //初始化日志
     init_log();
 
+    //打印生效的配置
+    info!("{}", CONF_MAP.dump(&*CONFIG));
+
+    //初始化数据包压缩
+    tools::compress::init(
+        CONFIG.base.compress_threshold,
+        CONFIG.base.compress_type.as_str(),
+    );
+
     //连接游戏中心服
     init_game_center_tcp_connect(cm.clone());
 
@@ -74,34 +104,62 @@ fn main() {
     //初始化http服务
     init_http_server(cm.clone());
 
+    //注册到注册中心
+    init_registry(cm.clone());
+
+    //初始化退出信号处理
+    init_shutdown(cm.clone());
+
+    //初始化定时检查(断线重连超时、空闲连接清理)
+    init_timer(cm.clone());
+
     //初始化与客户端通信的模块
     init_net_server(cm);
 }
 
 fn init_log() {
-    let info_log = &CONF_MAP.get_str("info_log_path", "");
-    let error_log = &CONF_MAP.get_str("error_log_path", "");
+    let info_log = &CONFIG.base.info_log_path;
+    let error_log = &CONFIG.base.error_log_path;
     tools::my_log::init_log(info_log, error_log);
 }
 
 ///初始化http服务端
 fn init_http_server(cm: Arc<Mutex<ChannelMgr>>) {
     std::thread::sleep(Duration::from_millis(10));
-    let http_port = CONF_MAP.get_usize("http_port", 0);
+    let http_port = CONFIG.http_port;
     tools::http::Builder::new()
-        .route(Box::new(KickPlayerHttpHandler::new(cm)))
-        .bind(http_port as u16);
+        .route(Box::new(KickPlayerHttpHandler::new(cm.clone())))
+        .route(Box::new(ReapStatsHttpHandler::new(cm)))
+        .route(Box::new(CompressMetricsHttpHandler))
+        .bind(http_port);
+}
+
+///收到退出信号时停服排空:不再接收登录，踢掉所有玩家，通知游戏服保存数据
+fn init_shutdown(cm: Arc<Mutex<ChannelMgr>>) {
+    let m = move || {
+        let packet = Packet::new(ServerCommonCode::Drain.into_u32(), 0, 0);
+        async_std::task::block_on(cm.lock()).drain(packet);
+        //等待消息发出去
+        std::thread::sleep(Duration::from_secs(3));
+    };
+    tools::shutdown::on_signal(m);
+}
+
+///每秒检查一次断线等待重连的会话和空闲连接，超时的按下线处理
+fn init_timer(cm: Arc<Mutex<ChannelMgr>>) {
+    let m = move || loop {
+        std::thread::sleep(Duration::from_secs(1));
+        let mut lock = async_std::task::block_on(cm.lock());
+        lock.reap_suspended();
+        lock.reap_idle();
+    };
+    std::thread::spawn(m);
 }
 
 ///初始化网络服务这块
 fn init_net_server(cm: Arc<Mutex<ChannelMgr>>) {
     //获取通信模块
-    let net_module = CONF_MAP.get_str("net_module", "");
-    match net_module.as_str() {
-        "tcp" => {
-            //初始化tcp服务端
-            init_tcp_server(cm);
-        }
+    match CONFIG.net_module.as_str() {
         "ws" => {
             init_ws_server(cm);
         }
@@ -113,35 +171,79 @@ fn init_net_server(cm: Arc<Mutex<ChannelMgr>>) {
 }
 
 ///初始化游戏服务器tcp客户端链接
+///定时从注册中心发现游戏服，连接新上线的游戏服
 fn init_game_tcp_connect(cp: Arc<Mutex<ChannelMgr>>) {
-    let game = async {
-        let mut tch = TcpClientHandler::new(cp, TcpClientType::GameServer);
-        let address = CONF_MAP.get_str("game_port", "");
-        info!("开始链接游戏服:{:?}...", address);
-        tch.on_read(address.to_string()).await;
+    let registry = Registry::new(&CONFIG.registry);
+    let m = move || loop {
+        for info in discover_games(&registry) {
+            let address = info.address;
+            //已经连上或者正在连接的跳过
+            if !block_on(cp.lock()).game_addresses.insert(address.clone()) {
+                continue;
+            }
+            info!("开始链接游戏服:{:?}...", address);
+            let tch = TcpClientHandler::new(cp.clone(), TcpClientType::GameServer, address);
+            tools::reconnect::spawn("GAME_CLIENT", tch, ReconnectConf::new(&CONFIG.reconnect));
+        }
+        //本地配置的地址不会变，不需要再检查
+        if registry.is_file_mode() {
+            break;
+        }
+        std::thread::sleep(registry.heartbeat_interval());
+    };
+    std::thread::spawn(m);
+}
+
+///发现所有游戏服
+fn discover_games(registry: &Registry) -> Vec<ServiceInfo> {
+    registry.discover(&REDIS_POOL, ROLE_GAME, &CONFIG.game_ports())
+}
+
+///发现游戏中心地址，还没有注册就一直等
+fn discover_game_center() -> String {
+    let registry = Registry::new(&CONFIG.registry);
+    let static_addresses = vec![CONFIG.game_center_port.clone()];
+    loop {
+        let res = registry.discover(&REDIS_POOL, ROLE_GAME_CENTER_GATE, &static_addresses);
+        if let Some(info) = res.first() {
+            return info.address.clone();
+        }
+        warn!("注册中心还没有游戏中心,等待游戏中心启动!");
+        std::thread::sleep(registry.heartbeat_interval());
+    }
+}
+
+///注册到注册中心，定时心跳上报在线玩家数量
+fn init_registry(cm: Arc<Mutex<ChannelMgr>>) {
+    let registry = Registry::new(&CONFIG.registry);
+    let address = CONFIG.registry.advertise_address(CONFIG.listen_address());
+    let capacity = CONFIG.capacity;
+    let heartbeat = registry.clone();
+    let m = move || {
+        let load = block_on(cm.lock()).user_channel.len() as u32;
+        let info = ServiceInfo::new(ROLE_GATE, address.clone(), capacity, load);
+        heartbeat.register(&REDIS_POOL, &info);
     };
-    async_std::task::spawn(game);
+    registry.spawn_heartbeat(m);
 }
 
 ///初始化房间服务器tcp客户端链接
+///断线后自动重连，每次重连前重新发现游戏中心地址
 fn init_game_center_tcp_connect(cp: Arc<Mutex<ChannelMgr>>) {
-    let room = async {
-        let mut tch = TcpClientHandler::new(cp, TcpClientType::GameCenter);
-        let address = CONF_MAP.get_str("game_center_port", "");
-        info!("开始链接游戏中心服:{:?}...", address);
-        tch.on_read(address.to_string()).await;
-    };
-    async_std::task::spawn(room);
+    info!("开始链接游戏中心服...");
+    let tch = TcpClientHandler::new(cp, TcpClientType::GameCenter, String::new());
+    let conf = ReconnectConf::new(&CONFIG.reconnect);
+    tools::reconnect::spawn("GAME_CENTER_CLIENT", tch, conf);
 }
 
 ///初始化tcp服务端
 fn init_tcp_server(cm: Arc<Mutex<ChannelMgr>>) {
-    let str = &CONF_MAP.get_str("tcp_port", "");
+    let str = &CONFIG.tcp_port;
     tcp_server::new(str, cm);
 }
 
 ///初始化tcp服务端
 fn init_ws_server(cm: Arc<Mutex<ChannelMgr>>) {
-    let str = &CONF_MAP.get_str("web_socket_port", "");
+    let str = &CONFIG.web_socket_port;
     websocket::new(str, cm);
 }
@@ -1,18 +1,35 @@
+use crate::entity::conn::{ClientConn, ReapStats};
 use crate::entity::gateuser::GateUser;
+use crate::entity::session::SuspendedSession;
+use crate::mgr::rate_limit::{RateLimitConf, RateLimitResult};
 use crate::net::http::notice_user_center;
 use crate::net::http::UserCenterNoticeType;
+use chrono::Local;
 use crossbeam::channel::Sender;
 use log::warn;
 use log::{error, info};
-use std::collections::HashMap;
-use tools::cmd_code::RoomCode;
+use protobuf::Message;
+use std::collections::{HashMap, HashSet};
+use tools::cmd_code::{BattleCode, ClientCode, GameCode, RoomCode};
+use tools::consistent_hash::HashRing;
 use tools::net_message_io::NetHandler;
-use tools::util::packet::Packet;
+use tools::protos::protocol::S_USER_LOGIN;
+use tools::rpc::RpcClient;
+use tools::util::bytebuf::ByteBuf;
+use tools::util::packet::{ClientFrame, Packet};
 
 ///channel管理结构体
 pub struct ChannelMgr {
-    //游戏服tcpstream
-    pub game_client_channel: Option<Sender<Vec<u8>>>,
+    //游戏服tcpstream,key:游戏服地址
+    pub game_client_channels: HashMap<String, Sender<Vec<u8>>>,
+    //已经连上或者正在连接的游戏服地址
+    pub game_addresses: HashSet<String>,
+    //游戏服一致性哈希环，按user_id分配游戏服
+    pub game_ring: HashRing,
+    //user_id,玩家所在游戏服地址(卸载玩家数据前不变)
+    pub user_w_game: HashMap<u32, String>,
+    //停服排空还在等待确认的游戏服数量
+    pub game_drain_wait_num: usize,
     //房间服stream
     // pub room_client_channel: Option<TcpStream>,
     //游戏中心stream
@@ -23,6 +40,20 @@ pub struct ChannelMgr {
     pub channels: HashMap<usize, u32>,
     //临时会话map
     pub temp_channels: HashMap<u32, Option<NetHandler>>,
+    //token,登录时协商好的包头格式
+    pub frames: HashMap<usize, ClientFrame>,
+    //user_id,会话token(断线重连用)
+    pub session_tokens: HashMap<u32, u64>,
+    //user_id,断线后等待重连的会话
+    pub suspended: HashMap<u32, SuspendedSession>,
+    //token,所有客户端连接(包括还没登录的)
+    pub conns: HashMap<usize, ClientConn>,
+    //清理空闲连接统计
+    pub reap_stats: ReapStats,
+    //限流配置
+    pub rate_limit_conf: RateLimitConf,
+    //发给游戏服的rpc请求
+    pub rpc: RpcClient,
 }
 
 impl ChannelMgr {
@@ -30,18 +61,59 @@ impl ChannelMgr {
     pub fn new() -> Self {
         let players: HashMap<u32, GateUser> = HashMap::new();
         let cm = ChannelMgr {
-            game_client_channel: None,
+            game_client_channels: HashMap::new(),
+            game_addresses: HashSet::new(),
+            game_ring: HashRing::default(),
+            user_w_game: HashMap::new(),
+            game_drain_wait_num: 0,
             // room_client_channel: None,
             game_center_client_channel: None,
             user_channel: players,
             channels: HashMap::new(),
             temp_channels: HashMap::new(),
+            frames: HashMap::new(),
+            session_tokens: HashMap::new(),
+            suspended: HashMap::new(),
+            conns: HashMap::new(),
+            reap_stats: ReapStats::default(),
+            rate_limit_conf: RateLimitConf::from_json(Some(&crate::CONFIG.rate_limit)),
+            rpc: RpcClient::new(),
         };
         cm
     }
 
-    pub fn set_game_client_channel(&mut self, ts: Sender<Vec<u8>>) {
-        self.game_client_channel = Some(ts);
+    ///游戏服连上了，加入哈希环
+    pub fn set_game_client_channel(&mut self, address: &str, ts: Sender<Vec<u8>>) {
+        self.game_client_channels.insert(address.to_owned(), ts);
+        self.game_ring.add(address);
+        info!(
+            "游戏服加入路由!address:{},游戏服数量:{}",
+            address,
+            self.game_ring.nodes().len()
+        );
+    }
+
+    ///游戏服断开了，移出哈希环，在这个游戏服上的玩家全部踢下线，重新登录时分配到其他游戏服
+    pub fn remove_game_client_channel(&mut self, address: &str) {
+        self.game_client_channels.remove(address);
+        self.game_ring.remove(address);
+        let mut users = Vec::new();
+        for (&user_id, game_address) in self.user_w_game.iter() {
+            if game_address == address {
+                users.push(user_id);
+            }
+        }
+        for user_id in users.iter() {
+            self.user_w_game.remove(user_id);
+            if !self.kick_player(*user_id) {
+                self.drop_suspended(*user_id);
+            }
+        }
+        warn!(
+            "游戏服断开，移出路由!address:{},踢下线玩家数量:{}",
+            address,
+            users.len()
+        );
     }
 
     // pub fn set_room_client_channel(&mut self, ts: TcpStream) {
@@ -59,7 +131,12 @@ impl ChannelMgr {
         match user_id {
             Some(user_id) => {
                 let user_id = *user_id;
+                //有重连窗口则挂起会话，等待重连
+                if self.suspend(token, user_id) {
+                    return;
+                }
                 self.notice_off_line(user_id);
+                self.session_tokens.remove(&user_id);
                 //关闭连接
                 self.close_remove(&token);
 
@@ -67,8 +144,225 @@ impl ChannelMgr {
             }
             None => {
                 //warn!("user_id is none for token:{},so nothing to do!", token);
+                self.frames.remove(&token);
+                self.conns.remove(&token);
+            }
+        }
+    }
+
+    ///新连接
+    pub fn on_open(&mut self, net: NetHandler) {
+        let token = net.endpoint.resource_id().raw();
+        let conn = ClientConn::new(net, Local::now().timestamp_millis());
+        self.conns.insert(token, conn);
+    }
+
+    ///收到客户端的有效消息，刷新最后活跃时间
+    pub fn touch(&mut self, token: usize) {
+        if let Some(conn) = self.conns.get_mut(&token) {
+            conn.time.touch(Local::now().timestamp_millis());
+        }
+    }
+
+    ///限流检查，超过限制丢包，连续违规太多次断开连接
+    pub fn check_rate_limit(&mut self, token: usize, user_id: u32, cmd: u32) -> RateLimitResult {
+        let conn = self.conns.get_mut(&token);
+        if conn.is_none() {
+            return RateLimitResult::Pass;
+        }
+        let limiter = &mut conn.unwrap().limiter;
+        let now = Local::now().timestamp_millis();
+        let res = limiter.check(&self.rate_limit_conf, cmd, now);
+        match res {
+            RateLimitResult::Pass => {}
+            RateLimitResult::Drop => {
+                //只在开始违规时打印，防止刷屏
+                if limiter.get_violations() == 1 {
+                    warn!(
+                        "client packet is rate limited!user_id:{},cmd:{}",
+                        user_id, cmd
+                    );
+                }
+            }
+            RateLimitResult::Disconnect => {
+                warn!(
+                    "client packet is rate limited too many times,disconnect!user_id:{},cmd:{},violations:{},total_drop_num:{}",
+                    user_id,
+                    cmd,
+                    limiter.get_violations(),
+                    limiter.total_drop_num
+                );
+            }
+        }
+        res
+    }
+
+    ///清理空闲连接:没登录的连上超过login_timeout秒，登录了的超过heartbeat_timeout秒没有有效消息，走正常的下线流程
+    pub fn reap_idle(&mut self) {
+        let login_timeout = crate::CONFIG.login_timeout as i64 * 1000;
+        let heartbeat_timeout = crate::CONFIG.heartbeat_timeout as i64 * 1000;
+        let now = Local::now().timestamp_millis();
+        let mut v = Vec::new();
+        for (token, conn) in self.conns.iter() {
+            let is_login = self.channels.contains_key(token);
+            if conn
+                .time
+                .is_timeout(is_login, now, login_timeout, heartbeat_timeout)
+            {
+                v.push((*token, is_login));
+            }
+        }
+        if v.is_empty() {
+            return;
+        }
+        for (token, is_login) in v {
+            if is_login {
+                self.reap_stats.heartbeat_timeout_num += 1;
+            } else {
+                self.reap_stats.login_timeout_num += 1;
+            }
+            //先关掉连接，已经登录的再走正常的下线流程
+            if let Some(conn) = self.conns.remove(&token) {
+                conn.close();
+            }
+            self.off_line(token);
+            warn!(
+                "客户端连接超时没有消息，清理连接!token:{},is_login:{}",
+                token, is_login
+            );
+        }
+        self.reap_stats.last_reap_time = now;
+    }
+
+    ///断线重连窗口(秒)，为0则不支持重连
+    fn resume_window() -> i64 {
+        crate::CONFIG.resume_window as i64
+    }
+
+    ///挂起会话:关闭连接，保留路由，断线期间的推送缓存起来，通知战斗服玩家暂时掉线
+    fn suspend(&mut self, token: usize, user_id: u32) -> bool {
+        if ChannelMgr::resume_window() <= 0 || tools::shutdown::is_draining() {
+            return false;
+        }
+        let session_token = self.session_tokens.get(&user_id);
+        if session_token.is_none() {
+            return false;
+        }
+        let session_token = *session_token.unwrap();
+        self.close_remove(&token);
+        let session = SuspendedSession::new(session_token, Local::now().timestamp_millis());
+        self.suspended.insert(user_id, session);
+        let mut packet = Packet::new(BattleCode::Disconnect.into_u32(), 0, user_id);
+        packet.set_is_client(false);
+        self.write_to_game_center(packet);
+        info!("玩家断线，等待重连!user_id:{}", user_id);
+        true
+    }
+
+    ///缓存断线期间的推送，玩家不在等待重连中返回false
+    pub fn buffer_suspended(&mut self, user_id: u32, packet: Packet) -> bool {
+        let session = self.suspended.get_mut(&user_id);
+        if session.is_none() {
+            return false;
+        }
+        let session = session.unwrap();
+        if !session.push(packet) {
+            //缓存满了，下次检查时直接当下线处理
+            warn!("suspended packets is full!user_id:{}", user_id);
+            session.off_time = 0;
+        }
+        true
+    }
+
+    ///断线重连，body:user_id(u32)|session_token(u64)
+    pub fn resume(&mut self, net: Option<NetHandler>, bytes: &[u8]) -> anyhow::Result<u32> {
+        let mut bb = ByteBuf::from(bytes);
+        let user_id = bb.read_u32()?;
+        let session_token = bb.read_u64()?;
+        let session = self.suspended.remove(&user_id);
+        if session.is_none() {
+            anyhow::bail!("there is no suspended session!user_id:{}", user_id)
+        }
+        let session = session.unwrap();
+        if session.session_token != session_token {
+            self.suspended.insert(user_id, session);
+            anyhow::bail!("session token is wrong!user_id:{}", user_id)
+        }
+        self.add_gate_user(user_id, net);
+        //回复重连成功，然后补发断线期间的推送
+        let mut packet = Packet::new(ClientCode::Resume.into_u32(), 0, user_id);
+        packet.set_data_from_vec(vec![1]);
+        self.write_to_client(user_id, packet);
+        let count = session.packets.len();
+        for packet in session.packets {
+            self.write_to_client(user_id, packet);
+        }
+        let mut packet = Packet::new(BattleCode::Reconnect.into_u32(), 0, user_id);
+        packet.set_is_client(false);
+        self.write_to_game_center(packet);
+        info!(
+            "玩家断线重连成功!user_id:{},补发推送数量:{}",
+            user_id, count
+        );
+        Ok(user_id)
+    }
+
+    ///重连窗口过了还没重连的，按下线处理
+    pub fn reap_suspended(&mut self) {
+        if self.suspended.is_empty() {
+            return;
+        }
+        let window = ChannelMgr::resume_window() * 1000;
+        let now = Local::now().timestamp_millis();
+        let mut v = Vec::new();
+        for (user_id, session) in self.suspended.iter() {
+            if now - session.off_time >= window {
+                v.push(*user_id);
             }
         }
+        for user_id in v {
+            self.drop_suspended(user_id);
+        }
+    }
+
+    ///放弃等待重连，通知其他服玩家下线
+    pub fn drop_suspended(&mut self, user_id: u32) {
+        if self.suspended.remove(&user_id).is_none() {
+            return;
+        }
+        self.session_tokens.remove(&user_id);
+        self.notice_off_line(user_id);
+        info!(
+            "玩家断线重连超时，通知其他服卸载玩家数据!user_id:{}",
+            user_id
+        );
+    }
+
+    ///生成会话token并推送给客户端
+    pub fn push_session_token(&mut self, user_id: u32) {
+        let window = ChannelMgr::resume_window();
+        if window <= 0 {
+            return;
+        }
+        let session_token: u64 = rand::random();
+        self.session_tokens.insert(user_id, session_token);
+        let mut bb = ByteBuf::new();
+        bb.push_u64(session_token);
+        bb.push_u32(window as u32);
+        let mut packet = Packet::new(ClientCode::SessionToken.into_u32(), 0, user_id);
+        packet.set_data_from_vec(bb.into_bytes());
+        self.write_to_client(user_id, packet);
+    }
+
+    ///按协商好的包头格式写到客户端
+    pub fn write_to_client(&mut self, user_id: u32, mut packet: Packet) {
+        let bytes = self.build_client_bytes(&user_id, &mut packet);
+        let gate_user = self.get_mut_user_channel(&user_id);
+        if let Some(gate_user) = gate_user {
+            let tcp = gate_user.get_net_mut_ref();
+            let endpoint = tcp.endpoint;
+            tcp.node_handler.network().send(endpoint, bytes.as_slice());
+        }
     }
 
     ///通知下线
@@ -88,24 +382,109 @@ impl ChannelMgr {
         async_std::task::spawn(notice_user_center(user_id, UserCenterNoticeType::OffLine));
     }
 
+    ///校验客户端发来的包头，连接上的第一个登录包按其包头版本协商该连接的格式
+    ///协商过的连接再发登录包也要按协商好的格式校验，序列号必须递增，防止重放登录包重置序列号
+    pub fn check_client_frame(&mut self, token: usize, packet: &Packet) -> anyhow::Result<()> {
+        let cmd = packet.get_cmd();
+        if cmd == GameCode::Login.into_u32() || cmd == GameCode::Resume.into_u32() {
+            self.frames
+                .entry(token)
+                .or_insert_with(|| ClientFrame::new(packet));
+        }
+        //还没登录的交给后面的登录校验处理
+        match self.frames.get_mut(&token) {
+            Some(frame) => frame.check_recv(packet),
+            None => Ok(()),
+        }
+    }
+
+    ///按玩家连接协商好的包头格式转换成发给客户端的byte数组
+    pub fn build_client_bytes(&mut self, user_id: &u32, packet: &mut Packet) -> Vec<u8> {
+        let token = match self.user_channel.get(user_id) {
+            Some(gate_user) => gate_user.get_token(),
+            None => 0,
+        };
+        self.build_token_bytes(token, packet)
+    }
+
+    ///按连接协商好的包头格式转换成发给客户端的byte数组，还没登录成功的连接也可以用
+    pub fn build_token_bytes(&mut self, token: usize, packet: &mut Packet) -> Vec<u8> {
+        match self.frames.get_mut(&token) {
+            Some(frame) => frame.build_client_bytes(packet),
+            None => packet.build_client_bytes(),
+        }
+    }
+
     ///写到游戏服
+    ///广播的发给所有游戏服，其他的发给玩家所在游戏服
     pub fn write_to_game(&mut self, packet: Packet) {
-        if self.game_client_channel.is_none() {
+        if self.game_client_channels.is_empty() {
             error!("disconnect with Game-Server,pls connect Game-Server before send packet!");
             return;
         }
-        let gc = self.game_client_channel.as_mut().unwrap();
-        let size = gc.send(packet.build_server_bytes());
+        let user_id = packet.get_user_id();
+        let cmd = packet.get_cmd();
+        let bytes = packet.build_server_bytes();
+        if user_id == 0 || packet.is_broad() {
+            for gc in self.game_client_channels.values() {
+                let size = gc.send(bytes.clone());
+                if let Err(e) = size {
+                    error!("{:?}", e);
+                }
+            }
+            return;
+        }
+        let address = self.get_game_address(user_id);
+        if address.is_none() {
+            error!("could not find Game-Server for user_id:{}!", user_id);
+            return;
+        }
+        let address = address.unwrap();
+        //卸载玩家数据后解除绑定，下次登录重新分配
+        if cmd == GameCode::UnloadUser.into_u32() {
+            self.user_w_game.remove(&user_id);
+        }
+        let gc = self.game_client_channels.get(&address).unwrap();
+        let size = gc.send(bytes);
         if let Err(e) = size {
             error!("{:?}", e);
         }
     }
 
+    ///获得玩家所在游戏服地址，还没分配的按user_id一致性哈希分配
+    fn get_game_address(&mut self, user_id: u32) -> Option<String> {
+        if let Some(address) = self.user_w_game.get(&user_id) {
+            if self.game_client_channels.contains_key(address) {
+                return Some(address.clone());
+            }
+        }
+        let address = self.game_ring.get(user_id)?.clone();
+        self.user_w_game.insert(user_id, address.clone());
+        Some(address)
+    }
+
+    ///游戏服停服排空确认，所有游戏服都确认了返回true
+    pub fn game_drain_ack(&mut self) -> bool {
+        if self.game_drain_wait_num > 0 {
+            self.game_drain_wait_num -= 1;
+        }
+        self.game_drain_wait_num == 0
+    }
+
     ///停服
     pub fn stop_server(&mut self) {
         self.kick_all();
     }
 
+    ///停服排空:不再接收登录，踢掉所有玩家，然后通知游戏服保存数据
+    pub fn drain(&mut self, packet: Packet) {
+        tools::shutdown::set_draining();
+        self.kick_all();
+        self.game_drain_wait_num = self.game_client_channels.len();
+        self.write_to_game(packet);
+        info!("gate开始停服排空,已通知游戏服!");
+    }
+
     pub fn kick_player(&mut self, user_id: u32) -> bool {
         let gate_user = self.get_mut_user_channel(&user_id);
         if let None = gate_user {
@@ -114,6 +493,8 @@ impl ChannelMgr {
         let gate_user = gate_user;
         if let Some(gate_user) = gate_user {
             let token = gate_user.get_token();
+            //T人不等待重连
+            self.session_tokens.remove(&user_id);
             self.off_line(token);
             return true;
         }
@@ -133,6 +514,38 @@ impl ChannelMgr {
         }
     }
 
+    ///游戏服登录失败或者超时，回给客户端登录失败，然后断开连接
+    ///游戏服可能在超时后才加载完玩家，通知游戏服卸载，游戏服按顺序处理登录和卸载，不会留下没人管的玩家
+    pub fn login_fail(&mut self, user_id: u32, err_mess: String) {
+        let mut packet = Packet::new(GameCode::UnloadUser.into_u32(), 0, user_id);
+        packet.set_is_client(false);
+        self.write_to_game(packet);
+        self.user_w_game.remove(&user_id);
+        let net = self.temp_channels.remove(&user_id);
+        let net = match net {
+            Some(Some(net)) => net,
+            _ => {
+                warn!("temp_channels could not find net for user_id:{}", user_id);
+                return;
+            }
+        };
+        let mut sul = S_USER_LOGIN::new();
+        sul.set_is_succ(false);
+        sul.set_err_mess(err_mess);
+        let res = sul.write_to_bytes();
+        if let Err(e) = res {
+            error!("{:?}", e);
+            return;
+        }
+        let mut packet = Packet::new(ClientCode::Login.into_u32(), 0, user_id);
+        packet.set_data_from_vec(res.unwrap());
+        let token = net.endpoint.resource_id().raw();
+        let bytes = self.build_token_bytes(token, &mut packet);
+        let endpoint = net.endpoint;
+        net.node_handler.network().send(endpoint, bytes.as_slice());
+        GateUser::new(Some(net)).close();
+    }
+
     ///将临时到tcpsender转化到gateuser
     pub fn temp_channel_2_gate_user(&mut self, user_id: u32) {
         let res = self.temp_channels.remove(&user_id);
@@ -184,6 +597,8 @@ impl ChannelMgr {
 
     ///关闭channel句柄，并从内存中删除
     pub fn close_remove(&mut self, token: &usize) {
+        self.frames.remove(token);
+        self.conns.remove(token);
         let user_id = self.channels.remove(token);
         if user_id.is_none() {
             return;
@@ -203,6 +618,181 @@ impl ChannelMgr {
         for (_, &user_id) in res.iter() {
             self.kick_player(user_id);
         }
+        let suspended: Vec<u32> = self.suspended.keys().copied().collect();
+        for user_id in suspended {
+            self.drop_suspended(user_id);
+        }
         info!("kick all finish!");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use message_io::network::{Endpoint, NetEvent, Transport};
+    use message_io::node::{self, NodeHandler};
+    use std::io::Read;
+    use std::net::{TcpListener, TcpStream};
+    use std::time::Duration;
+    use tools::util::packet::{PROTOCOL_VERSION_V1, PROTOCOL_VERSION_V2};
+
+    ///测试用的网络节点，连到本地监听的端口，服务端写给客户端的数据从TcpStream读出来
+    ///同一个节点建立的连接token不会重复
+    struct TestNet {
+        node_handler: NodeHandler<()>,
+        listener: TcpListener,
+        connected: crossbeam::channel::Receiver<Endpoint>,
+    }
+
+    impl TestNet {
+        fn new() -> Self {
+            let (node_handler, node_listener) = node::split::<()>();
+            let (sender, connected) = crossbeam::channel::unbounded();
+            std::thread::spawn(move || {
+                node_listener.for_each(move |event| {
+                    if let NetEvent::Connected(endpoint, true) = event.network() {
+                        let _ = sender.send(endpoint);
+                    }
+                })
+            });
+            TestNet {
+                node_handler,
+                listener: TcpListener::bind("127.0.0.1:0").unwrap(),
+                connected,
+            }
+        }
+
+        fn connect(&self) -> (NetHandler, TcpStream) {
+            let address = self.listener.local_addr().unwrap();
+            self.node_handler
+                .network()
+                .connect(Transport::Tcp, address)
+                .unwrap();
+            let (stream, _) = self.listener.accept().unwrap();
+            stream
+                .set_read_timeout(Some(Duration::from_secs(5)))
+                .unwrap();
+            let endpoint = self.connected.recv_timeout(Duration::from_secs(5)).unwrap();
+            (NetHandler::new(self.node_handler.clone(), endpoint), stream)
+        }
+    }
+
+    ///从客户端连接读出count个包
+    fn read_packets(stream: &mut TcpStream, count: usize) -> Vec<Packet> {
+        let mut bytes = Vec::new();
+        let mut buf = [0_u8; 1024];
+        loop {
+            if let Ok(v) = Packet::build_array_from_client(bytes.clone()) {
+                if v.len() >= count {
+                    return v;
+                }
+            }
+            let size = stream.read(&mut buf).unwrap();
+            assert!(size > 0);
+            bytes.extend_from_slice(&buf[..size]);
+        }
+    }
+
+    fn resume_bytes(user_id: u32, session_token: u64) -> Vec<u8> {
+        let mut bb = ByteBuf::new();
+        bb.push_u32(user_id);
+        bb.push_u64(session_token);
+        bb.into_bytes()
+    }
+
+    ///登录后推送会话token，断线挂起
+    fn login_and_suspend(cm: &mut ChannelMgr, net: &TestNet, user_id: u32) -> u64 {
+        let (handler, mut stream) = net.connect();
+        let token = handler.endpoint.resource_id().raw();
+        cm.add_gate_user(user_id, Some(handler));
+        cm.push_session_token(user_id);
+        let packets = read_packets(&mut stream, 1);
+        assert_eq!(packets[0].get_cmd(), ClientCode::SessionToken.into_u32());
+        let session_token = ByteBuf::from(packets[0].get_data()).read_u64().unwrap();
+        cm.off_line(token);
+        assert!(cm.suspended.contains_key(&user_id));
+        assert!(!cm.channels.contains_key(&token));
+        session_token
+    }
+
+    #[test]
+    fn resume_and_replay_buffered_packets() {
+        let net = TestNet::new();
+        let mut cm = ChannelMgr::new();
+        let session_token = login_and_suspend(&mut cm, &net, 1011);
+
+        //断线期间的推送缓存起来
+        for cmd in [1001_u32, 1002, 1003].iter() {
+            assert!(cm.buffer_suspended(1011, Packet::new(*cmd, 0, 1011)));
+        }
+        assert!(!cm.buffer_suspended(1012, Packet::new(1001, 0, 1012)));
+
+        //token不对的不能重连，会话还在
+        let (handler, _) = net.connect();
+        let bytes = resume_bytes(1011, session_token.wrapping_add(1));
+        assert!(cm.resume(Some(handler), bytes.as_slice()).is_err());
+        assert!(cm.suspended.contains_key(&1011));
+
+        //重连成功，先回复重连结果，再按顺序补发
+        let (handler, mut stream) = net.connect();
+        let bytes = resume_bytes(1011, session_token);
+        assert_eq!(cm.resume(Some(handler), bytes.as_slice()).unwrap(), 1011);
+        assert!(cm.suspended.is_empty());
+        let packets = read_packets(&mut stream, 4);
+        let cmds: Vec<u32> = packets.iter().map(|x| x.get_cmd()).collect();
+        assert_eq!(cmds, vec![ClientCode::Resume.into_u32(), 1001, 1002, 1003]);
+        assert_eq!(packets[0].get_data(), &[1]);
+    }
+
+    #[test]
+    fn reap_suspended_after_window() {
+        let net = TestNet::new();
+        let mut cm = ChannelMgr::new();
+        let session_token = login_and_suspend(&mut cm, &net, 1011);
+
+        //还在重连窗口内的不清理
+        cm.reap_suspended();
+        assert!(cm.suspended.contains_key(&1011));
+
+        //过了重连窗口按下线处理，token也跟着失效
+        let window = ChannelMgr::resume_window() * 1000;
+        cm.suspended.get_mut(&1011).unwrap().off_time -= window;
+        cm.reap_suspended();
+        assert!(cm.suspended.is_empty());
+        assert!(!cm.session_tokens.contains_key(&1011));
+        let (handler, _) = net.connect();
+        let bytes = resume_bytes(1011, session_token);
+        assert!(cm.resume(Some(handler), bytes.as_slice()).is_err());
+    }
+
+    #[test]
+    fn login_fail_unload_user() {
+        let mut cm = ChannelMgr::new();
+        let (sender, rec) = crossbeam::channel::unbounded();
+        cm.set_game_client_channel("127.0.0.1:16801", sender);
+        cm.get_game_address(1011);
+
+        //超时后游戏服的回复才到，通知游戏服卸载，解除绑定
+        cm.login_fail(1011, "rpc timeout!".to_owned());
+        let packet = Packet::build_array_from_server(rec.try_recv().unwrap()).unwrap();
+        assert_eq!(packet[0].get_cmd(), GameCode::UnloadUser.into_u32());
+        assert_eq!(packet[0].get_user_id(), 1011);
+        assert!(!cm.user_w_game.contains_key(&1011));
+    }
+
+    #[test]
+    fn reply_before_login_use_negotiated_frame() {
+        let mut cm = ChannelMgr::new();
+        let mut login = Packet::new(GameCode::Login.into_u32(), 0, 0);
+        login.set_version(PROTOCOL_VERSION_V2);
+        login.set_seq(1);
+        cm.check_client_frame(1, &login).unwrap();
+        //登录和重连失败时还没绑定玩家，也要按连接协商好的格式回
+        let mut packet = Packet::new(ClientCode::Login.into_u32(), 0, 0);
+        let bytes = cm.build_token_bytes(1, &mut packet);
+        assert_eq!(Packet::client_version(&bytes), PROTOCOL_VERSION_V2);
+        let mut packet = Packet::new(ClientCode::Login.into_u32(), 0, 0);
+        let bytes = cm.build_token_bytes(2, &mut packet);
+        assert_eq!(Packet::client_version(&bytes), PROTOCOL_VERSION_V1);
+    }
+}
@@ -1 +1,2 @@
 pub mod channel_mgr;
+pub mod rate_limit;
@@ -0,0 +1,248 @@
+use log::warn;
+use std::collections::HashMap;
+use tools::cmd_code::{BattleCode, GameCode, RankCode, RoomCode};
+
+type JsonValue = serde_json::Value;
+
+///连续违规的计数多久没有新违规就清零(毫秒)
+const VIOLATION_RESET_TIME: i64 = 10000;
+
+///限流检查结果
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum RateLimitResult {
+    Pass,       //通过
+    Drop,       //丢弃这个包
+    Disconnect, //违规次数太多，断开连接
+}
+
+///令牌桶参数
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BucketConf {
+    pub rate: f64,  //每秒补充的令牌数
+    pub burst: f64, //桶容量(允许的突发数量)
+}
+
+impl BucketConf {
+    fn from_json(value: &JsonValue) -> Option<Self> {
+        let rate = value.get("rate")?.as_f64()?;
+        let burst = value.get("burst")?.as_f64()?;
+        Some(BucketConf { rate, burst })
+    }
+}
+
+///限流配置，按命令号段配置，也可以给单个命令单独配置
+///"rate_limit":{"game":{"rate":10,"burst":20},"room":{...},"battle":{...},"rank":{...},"cmds":{"30006":{...}},"max_violations":30}
+///没有配置的号段不限流
+#[derive(Debug, Clone, Default)]
+pub struct RateLimitConf {
+    pub game: Option<BucketConf>,       //GameCode号段
+    pub room: Option<BucketConf>,       //RoomCode号段
+    pub battle: Option<BucketConf>,     //BattleCode号段
+    pub rank: Option<BucketConf>,       //RankCode号段
+    pub cmds: HashMap<u32, BucketConf>, //单个命令的配置，优先于号段配置
+    pub max_violations: u32,            //连续违规多少次断开连接，为0则只丢包不断开
+}
+
+impl RateLimitConf {
+    pub fn from_json(value: Option<&JsonValue>) -> Self {
+        let mut conf = RateLimitConf::default();
+        let value = match value {
+            Some(value) => value,
+            None => return conf,
+        };
+        conf.game = value.get("game").and_then(BucketConf::from_json);
+        conf.room = value.get("room").and_then(BucketConf::from_json);
+        conf.battle = value.get("battle").and_then(BucketConf::from_json);
+        conf.rank = value.get("rank").and_then(BucketConf::from_json);
+        if let Some(cmds) = value.get("cmds").and_then(|x| x.as_object()) {
+            for (cmd, bucket) in cmds.iter() {
+                let cmd = cmd.parse::<u32>();
+                let bucket = BucketConf::from_json(bucket);
+                match (cmd, bucket) {
+                    (Ok(cmd), Some(bucket)) => {
+                        conf.cmds.insert(cmd, bucket);
+                    }
+                    _ => warn!("rate_limit cmds config is wrong!{:?}", cmds),
+                }
+            }
+        }
+        conf.max_violations = value
+            .get("max_violations")
+            .and_then(|x| x.as_u64())
+            .unwrap_or(0) as u32;
+        conf
+    }
+
+    ///获得命令对应的令牌桶参数，None表示不限流
+    ///号段和转发时的一样，包括Min和Max
+    fn get_bucket_conf(&self, cmd: u32) -> Option<BucketConf> {
+        if let Some(bucket) = self.cmds.get(&cmd) {
+            return Some(*bucket);
+        }
+        if (GameCode::Min.into_u32()..=GameCode::Max.into_u32()).contains(&cmd) {
+            return self.game;
+        }
+        if (RoomCode::Min.into_u32()..=RoomCode::Max.into_u32()).contains(&cmd) {
+            return self.room;
+        }
+        if (BattleCode::Min.into_u32()..=BattleCode::Max.into_u32()).contains(&cmd) {
+            return self.battle;
+        }
+        if (RankCode::Min.into_u32()..=RankCode::Max.into_u32()).contains(&cmd) {
+            return self.rank;
+        }
+        None
+    }
+}
+
+///令牌桶
+#[derive(Debug, Clone, Copy)]
+struct TokenBucket {
+    tokens: f64,    //当前令牌数
+    last_time: i64, //上次补充令牌的时间(毫秒)
+}
+
+impl TokenBucket {
+    fn new(conf: &BucketConf, now: i64) -> Self {
+        TokenBucket {
+            tokens: conf.burst,
+            last_time: now,
+        }
+    }
+
+    ///补充令牌后尝试拿一个
+    fn try_take(&mut self, conf: &BucketConf, now: i64) -> bool {
+        let elapsed = (now - self.last_time).max(0) as f64 / 1000_f64;
+        self.tokens = (self.tokens + elapsed * conf.rate).min(conf.burst);
+        self.last_time = now;
+        if self.tokens < 1_f64 {
+            return false;
+        }
+        self.tokens -= 1_f64;
+        true
+    }
+}
+
+///单个连接的限流器，每个命令一个令牌桶
+#[derive(Debug, Clone, Default)]
+pub struct RateLimiter {
+    buckets: HashMap<u32, TokenBucket>, //cmd,令牌桶
+    violations: u32,                    //连续违规次数
+    last_violation_time: i64,           //最近一次违规时间(毫秒)
+    pub total_drop_num: u64,            //总共丢弃的包数量
+}
+
+impl RateLimiter {
+    ///检查这个命令是否超过限制
+    pub fn check(&mut self, conf: &RateLimitConf, cmd: u32, now: i64) -> RateLimitResult {
+        let bucket_conf = conf.get_bucket_conf(cmd);
+        if bucket_conf.is_none() {
+            return RateLimitResult::Pass;
+        }
+        let bucket_conf = bucket_conf.unwrap();
+        let bucket = self
+            .buckets
+            .entry(cmd)
+            .or_insert_with(|| TokenBucket::new(&bucket_conf, now));
+        if bucket.try_take(&bucket_conf, now) {
+            return RateLimitResult::Pass;
+        }
+        if now - self.last_violation_time > VIOLATION_RESET_TIME {
+            self.violations = 0;
+        }
+        self.violations += 1;
+        self.last_violation_time = now;
+        self.total_drop_num += 1;
+        if conf.max_violations > 0 && self.violations >= conf.max_violations {
+            return RateLimitResult::Disconnect;
+        }
+        RateLimitResult::Drop
+    }
+
+    ///当前连续违规次数
+    pub fn get_violations(&self) -> u32 {
+        self.violations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conf() -> RateLimitConf {
+        let value = serde_json::json!({
+            "cmds":{"1003":{"rate":0.2,"burst":3}},
+            "max_violations":5,
+        });
+        RateLimitConf::from_json(Some(&value))
+    }
+
+    #[test]
+    fn burst_and_refill() {
+        let conf = conf();
+        let cmd = GameCode::Login.into_u32();
+        let mut limiter = RateLimiter::default();
+        for _ in 0..3 {
+            assert_eq!(limiter.check(&conf, cmd, 0), RateLimitResult::Pass);
+        }
+        assert_eq!(limiter.check(&conf, cmd, 0), RateLimitResult::Drop);
+        //0.2个每秒，5秒补充一个
+        assert_eq!(limiter.check(&conf, cmd, 4000), RateLimitResult::Drop);
+        assert_eq!(limiter.check(&conf, cmd, 5000), RateLimitResult::Pass);
+        assert_eq!(limiter.check(&conf, cmd, 5000), RateLimitResult::Drop);
+        //补满也不会超过burst
+        for _ in 0..3 {
+            assert_eq!(limiter.check(&conf, cmd, 100000), RateLimitResult::Pass);
+        }
+        assert_eq!(limiter.check(&conf, cmd, 100000), RateLimitResult::Drop);
+        //没配置的命令不限流
+        for _ in 0..100 {
+            assert_eq!(limiter.check(&conf, 1, 0), RateLimitResult::Pass);
+        }
+    }
+
+    #[test]
+    fn range_include_min_and_max() {
+        let value = serde_json::json!({
+            "game":{"rate":1,"burst":1},
+            "rank":{"rate":2,"burst":2},
+        });
+        let conf = RateLimitConf::from_json(Some(&value));
+        for cmd in [GameCode::Min.into_u32(), GameCode::Max.into_u32()].iter() {
+            assert_eq!(conf.get_bucket_conf(*cmd).unwrap().burst, 1_f64);
+        }
+        for cmd in [RankCode::Min.into_u32(), RankCode::Max.into_u32()].iter() {
+            assert_eq!(conf.get_bucket_conf(*cmd).unwrap().burst, 2_f64);
+        }
+        assert!(conf.get_bucket_conf(RoomCode::Min.into_u32()).is_none());
+    }
+
+    #[test]
+    fn violations_escalate_and_reset() {
+        let conf = conf();
+        let cmd = GameCode::Login.into_u32();
+        let mut limiter = RateLimiter::default();
+        for _ in 0..3 {
+            limiter.check(&conf, cmd, 0);
+        }
+        for i in 1..conf.max_violations {
+            assert_eq!(limiter.check(&conf, cmd, 0), RateLimitResult::Drop);
+            assert_eq!(limiter.get_violations(), i);
+        }
+        assert_eq!(limiter.check(&conf, cmd, 0), RateLimitResult::Disconnect);
+        assert_eq!(limiter.total_drop_num, conf.max_violations as u64);
+
+        //一段时间没有违规，连续违规次数清零
+        let mut limiter = RateLimiter::default();
+        for _ in 0..3 {
+            limiter.check(&conf, cmd, 0);
+        }
+        assert_eq!(limiter.check(&conf, cmd, 0), RateLimitResult::Drop);
+        assert_eq!(limiter.check(&conf, cmd, 1000), RateLimitResult::Drop);
+        assert_eq!(limiter.get_violations(), 2);
+        let now = 1000 + VIOLATION_RESET_TIME + 1;
+        //补充了令牌的先用掉
+        while limiter.check(&conf, cmd, now) == RateLimitResult::Pass {}
+        assert_eq!(limiter.get_violations(), 1);
+    }
+}
@@ -23,8 +23,8 @@ pub async fn notice_user_center(user_id: u32, notice_type: UserCenterNoticeType)
         UserCenterNoticeType::OffLine => is_login = false,
     }
     //通知用户中心
-    let http_port: &str = &CONF_MAP.get_str("user_center_state", "");
-    let game_id: usize = CONF_MAP.get_usize("game_id", 0);
+    let http_port: &str = &CONFIG.user_center_state;
+    let game_id = CONFIG.game.game_id;
     let mut map: Map<String, JsonValue> = Map::new();
     map.insert("user_id".to_owned(), JsonValue::from(user_id));
     map.insert("game_id".to_owned(), JsonValue::from(game_id));
@@ -69,3 +69,65 @@ impl HttpServerHandler for KickPlayerHttpHandler {
         Ok(value)
     }
 }
+
+///数据包压缩统计
+pub struct CompressMetricsHttpHandler;
+
+impl HttpServerHandler for CompressMetricsHttpHandler {
+    fn get_path(&self) -> &str {
+        "/compress_metrics"
+    }
+
+    fn do_get(
+        &mut self,
+        _uri: String,
+        _uri_params: HashMap<String, String>,
+    ) -> anyhow::Result<String> {
+        let metrics = tools::compress::metrics();
+        let value = json!({
+            "status":"OK",
+            "compress_times":metrics.compress_times,
+            "raw_bytes":metrics.raw_bytes,
+            "compressed_bytes":metrics.compressed_bytes,
+            "saved_bytes":metrics.saved_bytes,
+            "decompress_times":metrics.decompress_times,
+        });
+        Ok(value.to_string())
+    }
+}
+
+///空闲连接清理统计
+pub struct ReapStatsHttpHandler {
+    gm: Arc<Mutex<ChannelMgr>>,
+}
+
+impl ReapStatsHttpHandler {
+    pub fn new(gm: Arc<Mutex<ChannelMgr>>) -> Self {
+        ReapStatsHttpHandler { gm }
+    }
+}
+
+impl HttpServerHandler for ReapStatsHttpHandler {
+    fn get_path(&self) -> &str {
+        "/reap_stats"
+    }
+
+    fn do_get(
+        &mut self,
+        _uri: String,
+        _uri_params: HashMap<String, String>,
+    ) -> anyhow::Result<String> {
+        let lock = block_on(self.gm.lock());
+        let stats = lock.reap_stats;
+        let value = json!({
+            "status":"OK",
+            "login_timeout_num":stats.login_timeout_num,
+            "heartbeat_timeout_num":stats.heartbeat_timeout_num,
+            "last_reap_time":stats.last_reap_time,
+            "conn_num":lock.conns.len(),
+            "online_num":lock.user_channel.len(),
+            "suspended_num":lock.suspended.len(),
+        });
+        Ok(value.to_string())
+    }
+}
@@ -2,12 +2,11 @@ pub mod http;
 pub mod tcp_client;
 pub mod tcp_server;
 pub mod websocket;
-use crate::{CONF_MAP, REDIS_INDEX_USERS, REDIS_KEY_UID_2_PID};
+use crate::{CONFIG, REDIS_INDEX_USERS, REDIS_KEY_UID_2_PID};
 use crate::{REDIS_KEY_USERS, REDIS_POOL};
 use log::{debug, error, info, warn};
 use protobuf::Message;
 use std::sync::Arc;
-use tools::tcp::ClientHandler;
 
 use crate::mgr::channel_mgr::ChannelMgr;
 
@@ -26,9 +25,8 @@ type Lock = Arc<Mutex<ChannelMgr>>;
 pub fn query_pid_from_redis(user_id: u32) -> anyhow::Result<String> {
     let user_id_str = user_id.to_string();
     //校验用户中心是否登陆过，如果有，则不往下执行
-    let mut redis_write = REDIS_POOL.lock().unwrap();
     let res: Option<String> =
-        redis_write.hget(REDIS_INDEX_USERS, REDIS_KEY_UID_2_PID, user_id_str.as_str());
+        REDIS_POOL.hget(REDIS_INDEX_USERS, REDIS_KEY_UID_2_PID, user_id_str.as_str());
     if res.is_none() {
         anyhow::bail!("this account is invalid!user_id:{:?}", user_id)
     }
@@ -39,8 +37,7 @@ pub fn query_pid_from_redis(user_id: u32) -> anyhow::Result<String> {
 ///从redis查找user_id
 pub fn query_user_id_from_redis(platform_value: &str) -> anyhow::Result<u32> {
     //校验用户中心是否登陆过，如果有，则不往下执行
-    let mut redis_write = REDIS_POOL.lock().unwrap();
-    let res: Option<String> = redis_write.hget(REDIS_INDEX_USERS, REDIS_KEY_USERS, platform_value);
+    let res: Option<String> = REDIS_POOL.hget(REDIS_INDEX_USERS, REDIS_KEY_USERS, platform_value);
     if res.is_none() {
         anyhow::bail!(
             "this account is invalid!platform_value:{:?}",
@@ -72,8 +69,7 @@ pub fn query_user_id_from_redis(platform_value: &str) -> anyhow::Result<u32> {
 ///校验用户中心是否在线
 fn check_uc_online(user_id: &u32) -> anyhow::Result<bool> {
     //校验用户中心是否登陆过，如果有，则不往下执行
-    let mut redis_write = REDIS_POOL.lock().unwrap();
-    let pid: Option<String> = redis_write.hget(
+    let pid: Option<String> = REDIS_POOL.hget(
         REDIS_INDEX_USERS,
         REDIS_KEY_UID_2_PID,
         user_id.to_string().as_str(),
@@ -82,7 +78,7 @@ fn check_uc_online(user_id: &u32) -> anyhow::Result<bool> {
         anyhow::bail!("this user_id is invalid!user_id:{}", user_id)
     }
     let pid = pid.unwrap();
-    let res: Option<String> = redis_write.hget(0, REDIS_KEY_USERS, pid.as_str());
+    let res: Option<String> = REDIS_POOL.hget(0, REDIS_KEY_USERS, pid.as_str());
     if res.is_none() {
         anyhow::bail!("this user_id is invalid!user_id:{}", user_id)
     }
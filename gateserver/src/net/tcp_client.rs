@@ -1,12 +1,14 @@
 use std::collections::VecDeque;
+use std::time::Duration;
 
 use super::*;
 use async_std::sync::Mutex;
 use async_std::task::block_on;
-use async_trait::async_trait;
 use crossbeam::channel::Sender;
 use log::error;
 use tools::cmd_code::{ClientCode, GateCode, RankCode, RoomCode, ServerCommonCode};
+use tools::reconnect::ReconnectHandler;
+use tools::registry::Registry;
 
 pub enum TcpClientType {
     GameServer,
@@ -15,52 +17,73 @@ pub enum TcpClientType {
 
 pub struct TcpClientHandler {
     client_type: TcpClientType,
-    ts: Option<Sender<Vec<u8>>>,
+    address: String, //服务器地址
     cp: Arc<Mutex<ChannelMgr>>,
 }
 
 impl TcpClientHandler {
-    pub fn new(cp: Lock, client_type: TcpClientType) -> TcpClientHandler {
+    pub fn new(cp: Lock, client_type: TcpClientType, address: String) -> TcpClientHandler {
         let tch = TcpClientHandler {
-            ts: None,
             cp,
             client_type,
+            address,
         };
         tch
     }
 }
 
-#[async_trait]
-impl ClientHandler for TcpClientHandler {
-    async fn on_open(&mut self, ts: Sender<Vec<u8>>) {
+impl ReconnectHandler for TcpClientHandler {
+    fn address(&mut self) -> Option<String> {
         match self.client_type {
             TcpClientType::GameServer => {
-                block_on(self.cp.lock()).set_game_client_channel(ts.clone());
+                //已经从注册中心下线的游戏服不再重连，重新上线后会被重新发现
+                let registry = Registry::new(&CONFIG.registry);
+                if !registry.is_file_mode() {
+                    let games = crate::discover_games(&registry);
+                    if !games.iter().any(|x| x.address == self.address) {
+                        block_on(self.cp.lock())
+                            .game_addresses
+                            .remove(&self.address);
+                        warn!("游戏服已经下线，不再重连!address:{}", self.address);
+                        return None;
+                    }
+                }
             }
             TcpClientType::GameCenter => {
-                block_on(self.cp.lock()).set_game_center_client_channel(ts.clone());
+                //游戏中心可能换了地址，重新发现
+                self.address = crate::discover_game_center();
             }
         }
-        self.ts = Some(ts);
+        Some(self.address.clone())
+    }
+
+    fn handshake(&mut self) -> Vec<Vec<u8>> {
+        match self.client_type {
+            TcpClientType::GameServer => vec![],
+            //告诉游戏中心自己属于哪个游戏和区服
+            TcpClientType::GameCenter => vec![CONFIG.game.build_register_bytes()],
+        }
     }
 
-    async fn on_close(&mut self) {
-        let address: Option<String>;
+    fn on_connect(&mut self, ts: Sender<Vec<u8>>, _reconnect: bool) {
         match self.client_type {
             TcpClientType::GameServer => {
-                address = Some(CONF_MAP.get_str("game_port", ""));
+                block_on(self.cp.lock()).set_game_client_channel(self.address.as_str(), ts);
             }
             TcpClientType::GameCenter => {
-                address = Some(CONF_MAP.get_str("game_center_port", ""));
-            }
-            _ => {
-                address = None;
+                block_on(self.cp.lock()).set_game_center_client_channel(ts);
             }
         }
-        self.on_read(address.unwrap()).await;
     }
 
-    async fn on_message(&mut self, mess: Vec<u8>) {
+    fn on_disconnect(&mut self) {
+        //游戏中心断开期间的消息先缓存，游戏服断开的话玩家重新分配
+        if let TcpClientType::GameServer = self.client_type {
+            block_on(self.cp.lock()).remove_game_client_channel(self.address.as_str());
+        }
+    }
+
+    fn on_message(&mut self, mess: Vec<u8>) {
         let packet_array = Packet::build_array_from_server(mess);
 
         if let Err(e) = packet_array {
@@ -75,6 +98,10 @@ impl ClientHandler for TcpClientHandler {
 fn handler_mess_s(cp: Lock, packet_array: VecDeque<Packet>) {
     for mut packet in packet_array {
         let mut lock = async_std::task::block_on(cp.lock());
+        //rpc回复，唤醒等待的请求
+        if lock.rpc.on_reply(&packet) {
+            continue;
+        }
         let user_id = packet.get_user_id();
         let cmd = packet.get_cmd();
         //判断是否是发给客户端消息
@@ -83,17 +110,25 @@ fn handler_mess_s(cp: Lock, packet_array: VecDeque<Packet>) {
                 //封装成gateuser到管理器中
                 lock.temp_channel_2_gate_user(user_id);
             }
+            let login_succ = cmd == ClientCode::Login.into_u32() && is_login_succ(&packet);
+            let bytes = lock.build_client_bytes(&user_id, &mut packet);
             let gate_user = lock.get_mut_user_channel(&user_id);
             match gate_user {
                 Some(user) => {
                     let tcp = user.get_net_mut_ref();
                     let endpoint = tcp.endpoint;
-                    tcp.node_handler
-                        .network()
-                        .send(endpoint, packet.build_client_bytes().as_slice());
+                    tcp.node_handler.network().send(endpoint, bytes.as_slice());
                     info!("回给客户端消息,user_id:{},cmd:{}", user_id, cmd,);
+                    //登录成功，下发断线重连用的会话token
+                    if login_succ {
+                        lock.push_session_token(user_id);
+                    }
                 }
                 None => {
+                    //断线等待重连中，先缓存起来
+                    if lock.buffer_suspended(user_id, packet) {
+                        continue;
+                    }
                     if cmd == ClientCode::LeaveRoom.into_u32()
                         || cmd == ClientCode::MemberLeaveNotice.into_u32()
                     {
@@ -109,6 +144,48 @@ fn handler_mess_s(cp: Lock, packet_array: VecDeque<Packet>) {
     }
 }
 
+///登录请求发给游戏服，等待游戏服加载玩家数据
+///成功按游戏服的回复回给客户端，失败或者超时回给客户端登录失败
+pub fn login_2_game(cp: Lock, mut lock: MutexGuard<ChannelMgr>, mut packet: Packet) {
+    let timeout = Duration::from_millis(CONFIG.rpc_timeout);
+    let reply = lock.rpc.request(&mut packet, timeout);
+    let user_id = packet.get_user_id();
+    lock.write_to_game(packet);
+    std::mem::drop(lock);
+    let m = async move {
+        let res = reply.wait_message::<S_USER_LOGIN>().await;
+        match res {
+            Ok(sul) => {
+                let res = sul.write_to_bytes();
+                if let Err(e) = res {
+                    error!("{:?}", e);
+                    return;
+                }
+                let mut packet = Packet::new(ClientCode::Login.into_u32(), 0, user_id);
+                packet.set_data_from_vec(res.unwrap());
+                packet.set_is_client(true);
+                handler_mess_s(cp, VecDeque::from(vec![packet]));
+            }
+            Err(e) => {
+                warn!("login fail!user_id:{},{:?}", user_id, e);
+                cp.lock().await.login_fail(user_id, e.to_string());
+            }
+        }
+    };
+    async_std::task::spawn(m);
+}
+
+///登录是否成功
+fn is_login_succ(packet: &Packet) -> bool {
+    let mut sul = S_USER_LOGIN::new();
+    let res = sul.merge_from_bytes(packet.get_data());
+    if let Err(e) = res {
+        warn!("{:?}", e);
+        return false;
+    }
+    sul.is_succ
+}
+
 ///数据包转发
 fn arrange_packet(cp: async_std::sync::MutexGuard<ChannelMgr>, packet: Packet) {
     let cmd = packet.get_cmd();
@@ -132,6 +209,14 @@ fn arrange_packet(cp: async_std::sync::MutexGuard<ChannelMgr>, packet: Packet) {
     {
         //转发到房间服
         lock.write_to_game_center(packet);
+    } else if cmd == ServerCommonCode::Drain.into_u32() {
+        //停服排空
+        lock.drain(packet);
+    } else if cmd == ServerCommonCode::DrainAck.into_u32() {
+        //所有游戏服停服排空完成，转发给游戏中心
+        if lock.game_drain_ack() {
+            lock.write_to_game_center(packet);
+        }
     } else if cmd == GateCode::StopServer.into_u32() {
         lock.stop_server();
     } else if cmd == GateCode::KickPlayer.into_u32() {
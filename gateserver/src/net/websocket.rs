@@ -2,11 +2,13 @@ use crate::auth::steam_auth::auth_account;
 use crate::auth::STEAM;
 
 use super::*;
+use crate::mgr::rate_limit::RateLimitResult;
+use crate::net::tcp_client::login_2_game;
 use async_std::sync::{Mutex, MutexGuard};
 use async_std::task::block_on;
 use async_trait::async_trait;
 use chrono::Local;
-use tools::cmd_code::{BattleCode, ClientCode, RoomCode};
+use tools::cmd_code::{BattleCode, ClientCode, RankCode, RoomCode};
 use tools::net_message_io::NetHandler;
 use tools::net_message_io::TransportWay;
 use tools::protos::protocol::HEART_BEAT;
@@ -28,6 +30,7 @@ impl tools::net_message_io::MessageHandler for WsServerHandler {
     }
 
     async fn on_open(&mut self, tcp_handler: NetHandler) {
+        self.cm.lock().await.on_open(tcp_handler.clone());
         self.ws_handler = Some(tcp_handler);
     }
 
@@ -103,7 +106,36 @@ impl WsServerHandler {
     async fn handle_binary(&mut self, mut packet: Packet) -> bool {
         let token = self.get_token();
         let mut lock = self.cm.lock().await;
-        let user_id = lock.get_channels_user_id(&token);
+
+        //校验包头版本和序列号
+        if let Err(e) = lock.check_client_frame(token, &packet) {
+            warn!("{:?}", e);
+            return false;
+        }
+        //限流，放在登录和回心跳之前，刷登录包也打不到鉴权和用户中心
+        let user_id = lock.get_channels_user_id(&token).copied().unwrap_or(0);
+        let res = lock.check_rate_limit(token, user_id, packet.get_cmd());
+        match res {
+            RateLimitResult::Pass => {}
+            RateLimitResult::Drop => return true,
+            RateLimitResult::Disconnect => return false,
+        }
+        //断线重连
+        if packet.get_cmd() == GameCode::Resume.into_u32() {
+            let res = lock.resume(self.ws_handler.clone(), packet.get_data());
+            if let Err(e) = res {
+                warn!("{:?}", e);
+                packet.set_cmd(ClientCode::Resume.into_u32());
+                packet.set_data_from_vec(vec![0]);
+                let bytes = lock.build_token_bytes(token, &mut packet);
+                std::mem::drop(lock);
+                self.write_to_client(bytes.as_slice());
+                return false;
+            }
+            lock.touch(token);
+            return true;
+        }
+        let user_id = lock.get_channels_user_id(&token).copied();
 
         //如果内存不存在数据，请求的命令又不是登录命令,则判断未登录异常操作
         if user_id.is_none() && packet.get_cmd() != GameCode::Login.into_u32() {
@@ -115,6 +147,8 @@ impl WsServerHandler {
             warn!("{:?}", str.as_str());
             return true;
         }
+        //通过校验的包才刷新活跃时间
+        lock.touch(token);
 
         let u_id;
         //执行登录
@@ -143,14 +177,15 @@ impl WsServerHandler {
                     sul.set_err_mess(e.to_string());
                     packet.set_cmd(ClientCode::Login as u32);
                     packet.set_data_from_vec(sul.write_to_bytes().unwrap());
+                    let bytes = lock.build_token_bytes(token, &mut packet);
                     std::mem::drop(lock);
-                    self.write_to_client(packet.build_client_bytes().as_slice());
+                    self.write_to_client(bytes.as_slice());
                     return false;
                 }
             }
             lock.temp_channels.insert(u_id, self.ws_handler.clone());
         } else {
-            u_id = *user_id.unwrap();
+            u_id = user_id.unwrap();
         }
         packet.set_user_id(u_id);
 
@@ -160,8 +195,9 @@ impl WsServerHandler {
             hb.set_sys_time(time_stamp);
             let bytes = hb.write_to_bytes().unwrap();
 
-            let res =
-                Packet::build_packet_bytes(ClientCode::HeartBeat.into(), u_id, bytes, false, true);
+            let mut hb_packet = Packet::new(ClientCode::HeartBeat.into(), 0, u_id);
+            hb_packet.set_data_from_vec(bytes);
+            let res = lock.build_client_bytes(&u_id, &mut hb_packet);
             let gate_user = lock.user_channel.get_mut(&u_id);
             if let None = gate_user {
                 return true;
@@ -185,6 +221,11 @@ impl WsServerHandler {
     ///数据包转发
     fn arrange_packet(&mut self, packet: Packet) {
         let mut lock = block_on(self.cm.lock());
+        //登录，等待游戏服加载玩家数据
+        if packet.get_cmd() == GameCode::Login.into_u32() {
+            login_2_game(self.cm.clone(), lock, packet);
+            return;
+        }
         //转发到游戏服
         if packet.get_cmd() >= GameCode::Min as u32 && packet.get_cmd() <= GameCode::Max as u32 {
             lock.write_to_game(packet);
@@ -195,6 +236,8 @@ impl WsServerHandler {
             && packet.get_cmd() <= RoomCode::Max.into_u32())
             || (packet.get_cmd() >= BattleCode::Min.into_u32()
                 && packet.get_cmd() <= BattleCode::Max.into_u32())
+            || (packet.get_cmd() >= RankCode::Min.into_u32()
+                && packet.get_cmd() <= RankCode::Max.into_u32())
         {
             lock.write_to_game_center(packet);
             return;
@@ -218,7 +261,11 @@ fn handle_login(
     platform_value: &str,
     user_id: u32,
 ) -> anyhow::Result<u32> {
-    let debug = crate::CONF_MAP.get_bool("debug", false);
+    //停服排空中，不再接收登录
+    if tools::shutdown::is_draining() {
+        anyhow::bail!("server is stopping!user_id:{}", user_id)
+    }
+    let _debug = crate::CONFIG.base.debug;
 
     // if debug {
     //     query_user_id_from_redis(platform_value)?;
@@ -245,6 +292,9 @@ fn handle_login(
         anyhow::bail!("{:?}", e)
     }
 
+    //断线等待重连中又重新登录了，先按下线处理
+    lock.drop_suspended(user_id);
+
     // //校验内存
     let mem_res = check_mem_online(&user_id, lock);
     // //如果用户中心登陆了或者本地内存登陆了，直接错误返回
@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+use tools::conf::{de_string, Validate};
+
+///http服配置，启动时从server.config读取并校验
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HttpConfig {
+    pub mysql_ip: String,               //mysql的ip
+    pub mysql_user: String,             //mysql的用户
+    pub mysql_port: u16,                //mysql的端口
+    pub mysql_database_name: String,    //mysql数据库名字
+    pub http_listen_port: u16,          //http监听端口
+    pub app_id: String,                 //微信app_id
+    pub questionnaire: String,          //调查问卷接口
+    pub recharge_call_back_url: String, //充值回调接口
+    pub reload_http_list_url: String,   //httplist服中心加载url
+    #[serde(deserialize_with = "de_string")]
+    pub mysql_pass: String, //mysql的密码
+    #[serde(deserialize_with = "de_string")]
+    pub app_secret: String, //微信app_secret
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        HttpConfig {
+            mysql_ip: String::new(),
+            mysql_user: String::new(),
+            mysql_pass: String::new(),
+            mysql_port: 3306,
+            mysql_database_name: "slg_http".to_owned(),
+            http_listen_port: 8080,
+            app_id: String::new(),
+            app_secret: String::new(),
+            questionnaire: String::new(),
+            recharge_call_back_url: String::new(),
+            reload_http_list_url: String::new(),
+        }
+    }
+}
+
+impl HttpConfig {
+    pub fn database_url(&self) -> String {
+        format!(
+            "mysql://{}:{}@{}:{}/{}",
+            self.mysql_user,
+            self.mysql_pass,
+            self.mysql_ip,
+            self.mysql_port,
+            self.mysql_database_name
+        )
+    }
+}
+
+impl Validate for HttpConfig {
+    fn validate(&self) -> anyhow::Result<()> {
+        if self.mysql_ip.is_empty() || self.mysql_user.is_empty() {
+            anyhow::bail!("mysql_ip和mysql_user不能为空!");
+        }
+        Ok(())
+    }
+}
@@ -119,7 +119,7 @@ impl HttpServerHandler for AddServerHandler {
         };
         async_std::task::block_on(m);
 
-        let url = crate::CONF_MAP.get_str("reload_http_list_url", "");
+        let url = &crate::CONFIG.reload_http_list_url;
         let res = tools::http::send_get(url.as_str(), None, None);
         if let Err(err) = res {
             log::error!("{:?}", err);
@@ -37,7 +37,7 @@ impl HttpServerHandler for ModifyWhiteUserHandler {
         } else {
             white_user::delete(name);
         }
-        let url = crate::CONF_MAP.get_str("reload_http_list_url", "");
+        let url = &crate::CONFIG.reload_http_list_url;
         let res = tools::http::send_get(url.as_str(), None, None);
         if let Err(err) = res {
             log::error!("{:?}", err);
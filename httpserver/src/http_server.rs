@@ -10,7 +10,7 @@ use crate::handler::wx_game_message_handler::NoticeMessHandler;
 use crate::handler::wx_game_subscribe_handler::WxGameSubscribeHandler;
 
 pub fn init_server() {
-    let port = crate::CONF_MAP.get_usize("http_listen_port", 8080);
+    let port = crate::CONFIG.http_listen_port;
     tools::http::Builder::new()
         .route(Box::new(TestHandler))
         .route(Box::new(WxGameSubscribeHandler))
@@ -22,5 +22,5 @@ pub fn init_server() {
         .route(Box::new(GameYearlyHandler))
         .route(Box::new(ServerListHandler))
         .route(Box::new(ReloadHandler))
-        .bind(port as u16);
+        .bind(port);
 }
@@ -1,3 +1,4 @@
+pub mod config;
 pub mod db;
 pub mod entity;
 pub mod handler;
@@ -9,6 +10,7 @@ use std::{
     sync::{Arc, RwLock},
 };
 
+use crate::config::HttpConfig;
 use crate::entity::{
     server_info::{self, ServerInfo},
     white_user::{self, WhiteUserInfo},
@@ -28,14 +30,16 @@ lazy_static! {
             log::info!("初始化server.config完成!");
             conf
         };
+        ///校验过的配置
+        static ref CONFIG : HttpConfig = {
+            match CONF_MAP.parse() {
+                Ok(config) => config,
+                Err(e) => panic!("{:?}", e),
+            }
+        };
         ///初始化mysql数据库连接池
         static ref POOL : MySqlPool = {
-            let ip = CONF_MAP.get_str("mysql_ip","");
-            let user = CONF_MAP.get_str("mysql_user","");
-            let pass = CONF_MAP.get_str("mysql_pass","");
-            let port = CONF_MAP.get_usize("mysql_port",3306);
-            let name = CONF_MAP.get_str("mysql_database_name","slg_http");
-            let database_url = format!("mysql://{}:{}@{}:{}/{}", user, pass.as_str(), ip,port,name);
+            let database_url = CONFIG.database_url();
             let m = async{
                let res = sqlx::MySqlPool::connect(database_url.as_str()).await.unwrap();
                res
@@ -68,6 +72,8 @@ fn main() {
     let time = std::time::Instant::now();
     //初始化日志
     init_log();
+    //打印生效的配置
+    log::info!("{}", CONF_MAP.dump(&*CONFIG));
     //初始化数据库
     init_db();
     //初始化httpserver
@@ -7,8 +7,8 @@ use tools::http::{send_get, send_post};
 static SEND_SUBSCRIBE_URL: &str = "https://api.weixin.qq.com/cgi-bin/message/subscribe/send";
 
 pub fn get_access_token() -> Option<String> {
-    let app_id = crate::CONF_MAP.get_str("app_id", "");
-    let app_secret = crate::CONF_MAP.get_str("app_secret", "");
+    let app_id = &crate::CONFIG.app_id;
+    let app_secret = &crate::CONFIG.app_secret;
     let url = format!(
         "https://mp-weixin.q1.com/WXAPIService.asmx/GetAccessTokenS?appId={}&secret={}",
         app_id, app_secret
@@ -59,7 +59,7 @@ use std::ops::Deref;
 use std::sync::atomic::AtomicU32;
 use std::sync::{Arc, RwLock};
 
-use tools::templates::template::{init_temps_mgr, TemplatesMgr};
+use tools::templates::template::{init_temps_mgr, Templates};
 
 #[macro_use]
 extern crate lazy_static;
@@ -71,7 +71,7 @@ lazy_static! {
     };
 
     ///静态配置文件
-    static ref TEMPLATES: TemplatesMgr = {
+    static ref TEMPLATES: Templates = {
         let path = env::current_dir().unwrap();
         let str = path.as_os_str().to_str().unwrap();
         let res = str.to_string()+"/template";
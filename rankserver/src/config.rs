@@ -0,0 +1,56 @@
+use serde::{Deserialize, Serialize};
+use tools::conf::{check_address, BaseConfig, Validate};
+use tools::game_key::GameKey;
+use tools::rating::RatingConf;
+use tools::registry::RegistryConfig;
+
+///排行榜服配置，启动时从config/config.conf读取并校验
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RankConfig {
+    #[serde(flatten)]
+    pub game: GameKey, //游戏和区服
+    #[serde(flatten)]
+    pub base: BaseConfig, //公共配置
+    #[serde(flatten)]
+    pub registry: RegistryConfig, //注册中心
+    #[serde(flatten)]
+    pub rating: RatingConf, //评分参数
+    pub tcp_port: String,             //tcp监听地址
+    pub mysql: String,                //mysql地址
+    pub rank_around_max_range: usize, //查询附近排名最多前后多少名
+    pub rank_page_max_size: usize,    //分页查询每页最多多少条
+    pub rank_friends_max: usize,      //好友排行一次最多查询多少人
+}
+
+impl Default for RankConfig {
+    fn default() -> Self {
+        RankConfig {
+            game: GameKey::default(),
+            base: BaseConfig::default(),
+            registry: RegistryConfig::default(),
+            rating: RatingConf::default(),
+            tcp_port: String::new(),
+            mysql: String::new(),
+            rank_around_max_range: 50,
+            rank_page_max_size: 100,
+            rank_friends_max: 200,
+        }
+    }
+}
+
+impl Validate for RankConfig {
+    fn validate(&self) -> anyhow::Result<()> {
+        self.base.validate()?;
+        self.registry.validate()?;
+        self.rating.validate()?;
+        check_address("tcp_port", self.tcp_port.as_str())?;
+        if self.rank_around_max_range == 0
+            || self.rank_page_max_size == 0
+            || self.rank_friends_max == 0
+        {
+            anyhow::bail!("rank_around_max_range,rank_page_max_size和rank_friends_max不能为0!");
+        }
+        Ok(())
+    }
+}
@@ -2,17 +2,21 @@ use crate::mgr::rank_mgr::RankMgr;
 use crate::mgr::RankInfo;
 use crate::{
     REDIS_INDEX_HISTORY, REDIS_INDEX_RANK, REDIS_KEY_BEST_RANK, REDIS_KEY_CURRENT_RANK,
-    REDIS_KEY_HISTORY_RANK, REDIS_KEY_LAST_RANK, REDIS_POOL,
+    REDIS_KEY_HISTORY_RANK, REDIS_KEY_LAST_RANK, REDIS_KEY_RATING, REDIS_KEY_SEASON_ROUND,
+    REDIS_POOL,
 };
-use async_std::task::block_on;
 use log::{error, warn};
 use protobuf::Message;
-use rayon::iter::{IntoParallelRefMutIterator, ParallelIterator};
 use std::str::FromStr;
-use tools::cmd_code::{BattleCode, GameCode, RoomCode};
+use tools::cmd_code::{ClientCode, GameCode};
+use tools::protos::protocol::{
+    C_RANK_AROUND_ME, C_RANK_FRIENDS, C_RANK_PAGE, S_RANK_AROUND_ME, S_RANK_FRIENDS, S_RANK_PAGE,
+};
 use tools::protos::server_protocol::B_S_SUMMARY;
 use tools::protos::server_protocol::G_S_MODIFY_NICK_NAME;
 use tools::protos::server_protocol::R_S_UPDATE_SEASON;
+use tools::rating::{Rating, RatingConf};
+use tools::redis_pool::RedisPoolTool;
 use tools::util::packet::Packet;
 
 ///修改名字
@@ -25,10 +29,7 @@ pub fn modify_nick_name(rm: &mut RankMgr, packet: Packet) {
         return;
     }
     let nick_name = proto.nick_name;
-    rm.rank_vec
-        .par_iter_mut()
-        .filter(|x| x.user_id == user_id)
-        .for_each(|x| x.name = nick_name.clone());
+    rm.set_name(user_id, nick_name.clone());
 
     //同步最好排名
     let best_rank = rm.user_best_rank.get_mut(&user_id);
@@ -45,33 +46,41 @@ pub fn modify_nick_name(rm: &mut RankMgr, packet: Packet) {
 
 ///处理上一赛季
 ///先清空上一赛季数据库，然后插入当前赛季数据
-pub async fn handler_season_update(
+pub fn handler_season_update(
     rm: &mut RankMgr,
     round: u32,
     round_season_id: i32,
     proto: &mut R_S_UPDATE_SEASON,
 ) {
-    if round_season_id != proto.season_id {
-        return;
+    //游戏中心推送过程中停服，重启后会重新推送，处理过的轮次不再处理
+    //游戏中心停服太久会直接推送最新的赛季，所以按轮次判断，不要求是每轮的第一个赛季
+    let handled_round: Option<u32> =
+        REDIS_POOL.hget(REDIS_INDEX_RANK, REDIS_KEY_SEASON_ROUND, "round");
+    match handled_round {
+        Some(handled_round) if handled_round >= round => {
+            warn!("the season round is handled!round:{}", round);
+            return;
+        }
+        None if round_season_id != proto.season_id => return,
+        _ => {}
     }
 
-    let mut redis_lock = REDIS_POOL.lock().await;
+    //把没刷新的排名刷新一次
+    rm.flush(true);
 
     //先清空上一赛季的排行榜数据
-    let _: Option<String> = redis_lock.del(REDIS_INDEX_RANK, REDIS_KEY_LAST_RANK);
+    let _: Option<String> = REDIS_POOL.del(REDIS_INDEX_RANK, REDIS_KEY_LAST_RANK);
 
     //如果当前赛季到排行榜是空到，直接返回
-    if rm.rank_vec.is_empty() {
+    if rm.ranks.is_empty() {
+        save_season_round(&REDIS_POOL, round);
         return;
     }
 
-    //立刻进行排序一次
-    rm.sort(false);
-
     let mut index = 0;
     //刷新last_rank数据,并保存历史排行榜,并更新玩家历史最佳
-    for ri in rm.rank_vec.iter() {
-        let user_id = ri.user_id;
+    for user_id in rm.get_ordered_user_ids() {
+        let ri = rm.ranks.get(&user_id).unwrap();
         let json_value = serde_json::to_string(ri);
         if let Err(err) = json_value {
             error!("{:?}", err);
@@ -79,7 +88,7 @@ pub async fn handler_season_update(
         }
         let json_value = json_value.unwrap();
         //更新last_rank
-        let _: Option<String> = redis_lock.hset(
+        let _: Option<String> = REDIS_POOL.hset(
             REDIS_INDEX_RANK,
             REDIS_KEY_LAST_RANK,
             user_id.to_string().as_str(),
@@ -91,7 +100,7 @@ pub async fn handler_season_update(
             key.push_str(REDIS_KEY_HISTORY_RANK);
             key.push_str("-");
             key.push_str(round.to_string().as_str());
-            let _: Option<String> = redis_lock.hset(
+            let _: Option<String> = REDIS_POOL.hset(
                 REDIS_INDEX_HISTORY,
                 key.as_str(),
                 user_id.to_string().as_str(),
@@ -118,7 +127,7 @@ pub async fn handler_season_update(
             match best_rank_str {
                 Ok(best_rank_str) => {
                     //更新到redis
-                    let _: Option<String> = redis_lock.hset(
+                    let _: Option<String> = REDIS_POOL.hset(
                         REDIS_INDEX_RANK,
                         REDIS_KEY_BEST_RANK,
                         user_id.to_string().as_str(),
@@ -134,50 +143,74 @@ pub async fn handler_season_update(
     }
 
     //处理当前赛季数据
-    let mut redis_lock = async_std::task::block_on(REDIS_POOL.lock());
-    let mut need_rm_v = vec![];
+    let rating_conf = crate::CONFIG.rating;
+    let user_ids: Vec<u32> = rm.ranks.keys().copied().collect();
     //掉段处理
-    for ri in rm.rank_vec.iter_mut() {
-        let user_id = ri.user_id;
+    for user_id in user_ids {
+        let mut ri = rm.ranks.get(&user_id).unwrap().clone();
         let user_id_str = user_id.to_string();
 
         ri.league.id -= 1;
         let league_id = ri.league.id;
         //清除0段位处理
         if ri.league.id <= 0 {
-            need_rm_v.push(user_id);
-            let _: Option<String> = redis_lock.hdel(
+            rm.remove_rank_info(user_id);
+            let _: Option<String> = REDIS_POOL.hdel(
                 REDIS_INDEX_RANK,
                 REDIS_KEY_CURRENT_RANK,
                 user_id_str.as_str(),
             );
         } else {
             ri.update_league(league_id);
-            let json_value = serde_json::to_string(ri);
-            match json_value {
-                Ok(json_value) => {
-                    let _: Option<String> = redis_lock.hset(
-                        REDIS_INDEX_RANK,
-                        REDIS_KEY_CURRENT_RANK,
-                        user_id_str.as_str(),
-                        json_value.as_str(),
-                    );
-                }
-                Err(e) => {
-                    error!("{:?}", e);
-                }
-            }
+            //评分跟着段位重置
+            reset_rating(
+                &REDIS_POOL,
+                user_id_str.as_str(),
+                ri.get_score(),
+                &rating_conf,
+            );
+            rm.update_rank_info(ri);
         }
     }
-    for user_id in need_rm_v {
-        let mut index = 0;
-        for ri in rm.rank_vec.iter() {
-            if ri.user_id == user_id {
-                break;
-            };
-            index += 1;
+    //重新排名并持久化，游戏服需要全量同步
+    rm.flush(true);
+    rm.need_full_sync = true;
+
+    //全部处理完才标记，中途停服的重新推送过来会再处理一次
+    save_season_round(&REDIS_POOL, round);
+}
+
+///标记赛季轮次已经处理过
+fn save_season_round(redis: &RedisPoolTool, round: u32) {
+    let _: Option<String> = redis.hset(
+        REDIS_INDEX_RANK,
+        REDIS_KEY_SEASON_ROUND,
+        "round",
+        round.to_string().as_str(),
+    );
+}
+
+///新赛季重置玩家评分，积分跟段位走，偏差回到初始值
+fn reset_rating(redis: &RedisPoolTool, user_id: &str, score: i32, conf: &RatingConf) {
+    let res: Option<String> = redis.hget(REDIS_INDEX_RANK, REDIS_KEY_RATING, user_id);
+    let mut rating = match res {
+        Some(res) => match serde_json::from_str::<Rating>(res.as_str()) {
+            Ok(rating) => rating,
+            Err(e) => {
+                warn!("{:?}", e);
+                Rating::new(score, conf)
+            }
+        },
+        None => Rating::new(score, conf),
+    };
+    rating.reset_season(score, conf);
+    let res = serde_json::to_string(&rating);
+    match res {
+        Ok(res) => {
+            let _: Option<String> =
+                redis.hset(REDIS_INDEX_RANK, REDIS_KEY_RATING, user_id, res.as_str());
         }
-        rm.rank_vec.remove(index);
+        Err(e) => error!("{:?}", e),
     }
 }
 
@@ -190,7 +223,8 @@ pub fn update_season(rm: &mut RankMgr, packet: Packet) {
     }
     let round = usn.get_round();
 
-    let mgr = crate::TEMPLATES.constant_temp_mgr();
+    let temps = crate::TEMPLATES.load();
+    let mgr = temps.constant_temp_mgr();
     let round_season_id = mgr.temps.get("round_season_id");
     if let None = round_season_id {
         warn!("the constant temp is None!key:round_season_id");
@@ -205,7 +239,7 @@ pub fn update_season(rm: &mut RankMgr, packet: Packet) {
     let round_season_id = res.unwrap();
 
     //处理赛季更新
-    block_on(handler_season_update(rm, round, round_season_id, &mut usn));
+    handler_season_update(rm, round, round_season_id, &mut usn);
 
     let bytes = usn.write_to_bytes();
     if let Err(e) = bytes {
@@ -213,10 +247,9 @@ pub fn update_season(rm: &mut RankMgr, packet: Packet) {
         return;
     }
     let bytes = bytes.unwrap();
-    //通知其他服赛季更新
-    rm.push_2_server(GameCode::UpdateSeasonPush.into_u32(), 0, bytes.clone());
-    rm.push_2_server(RoomCode::UpdateSeasonPush.into_u32(), 0, bytes.clone());
-    rm.push_2_server(BattleCode::UpdateSeasonPush.into_u32(), 0, bytes);
+    //房间服和战斗服由游戏中心直接通知
+    //游戏服要重新加载排行榜，等这边处理完再通知
+    rm.push_2_server(GameCode::UpdateSeasonPush.into_u32(), 0, bytes);
 }
 
 ///更新排行榜请求指令
@@ -230,15 +263,112 @@ pub fn update_rank(rm: &mut RankMgr, packet: Packet) {
     }
     let sd = bss.get_summary_data();
     let cters = bss.cters.clone();
-    let res = rm.get_rank_mut(user_id);
-    match res {
+    let ri = match rm.get_rank_ref(user_id) {
         Some(ri) => {
+            let mut ri = ri.clone();
             ri.update(sd, cters);
+            ri
         }
-        None => {
-            let ri = RankInfo::new(sd, cters);
-            rm.rank_vec.push(ri);
+        None => RankInfo::new(sd, cters),
+    };
+    rm.update_rank_info(ri);
+}
+
+///请求自己附近的排行
+pub fn rank_around_me(rm: &mut RankMgr, packet: Packet) {
+    let user_id = packet.get_user_id();
+    let mut proto = C_RANK_AROUND_ME::new();
+    let res = proto.merge_from_bytes(packet.get_data());
+    if let Err(e) = res {
+        error!("{:?}", e);
+        return;
+    }
+    let max_range = crate::CONFIG.rank_around_max_range;
+    let range = (proto.range as usize).max(1).min(max_range);
+
+    let mut s_proto = S_RANK_AROUND_ME::new();
+    //不在排行榜上的只返回空数据
+    if let Some(rank) = rm.get_rank(user_id) {
+        let start = rank.saturating_sub(range);
+        let end = rank + range + 1;
+        for (index, ri) in rm.get_range(start, end).into_iter().enumerate() {
+            let rip = ri.to_rank_pt(start + index);
+            if ri.user_id == user_id {
+                s_proto.set_self_rank(rip.clone());
+            }
+            s_proto.ranks.push(rip);
         }
     }
-    rm.need_rank = true;
+    send_proto_2_client(rm, ClientCode::RankAroundMe, user_id, &s_proto);
+}
+
+///分页请求排行榜
+pub fn rank_page(rm: &mut RankMgr, packet: Packet) {
+    let user_id = packet.get_user_id();
+    let mut proto = C_RANK_PAGE::new();
+    let res = proto.merge_from_bytes(packet.get_data());
+    if let Err(e) = res {
+        error!("{:?}", e);
+        return;
+    }
+    let max_page_size = crate::CONFIG.rank_page_max_size;
+    let page_size = (proto.page_size as usize).max(1).min(max_page_size);
+    let start = (proto.page as usize).saturating_mul(page_size);
+
+    let mut s_proto = S_RANK_PAGE::new();
+    s_proto.page = proto.page;
+    s_proto.page_size = page_size as u32;
+    s_proto.total = rm.rank_index.len() as u32;
+    let end = start.saturating_add(page_size);
+    for (index, ri) in rm.get_range(start, end).into_iter().enumerate() {
+        s_proto.ranks.push(ri.to_rank_pt(start + index));
+    }
+    send_proto_2_client(rm, ClientCode::RankPage, user_id, &s_proto);
+}
+
+///请求好友排行，返回在排行榜上的好友和自己
+pub fn rank_friends(rm: &mut RankMgr, packet: Packet) {
+    let user_id = packet.get_user_id();
+    let mut proto = C_RANK_FRIENDS::new();
+    let res = proto.merge_from_bytes(packet.get_data());
+    if let Err(e) = res {
+        error!("{:?}", e);
+        return;
+    }
+    let mut user_ids = proto.take_user_ids();
+    let max_friends = crate::CONFIG.rank_friends_max;
+    if user_ids.len() > max_friends {
+        warn!(
+            "too many user_ids for rank friends!user_id:{},len:{}",
+            user_id,
+            user_ids.len()
+        );
+        user_ids.truncate(max_friends);
+    }
+    user_ids.push(user_id);
+    user_ids.sort_unstable();
+    user_ids.dedup();
+
+    let mut ranks = Vec::with_capacity(user_ids.len());
+    for id in user_ids {
+        let rank = rm.get_rank(id);
+        let ri = rm.get_rank_ref(id);
+        if let (Some(rank), Some(ri)) = (rank, ri) {
+            ranks.push(ri.to_rank_pt(rank));
+        }
+    }
+    ranks.sort_unstable_by_key(|x| x.rank);
+    let mut s_proto = S_RANK_FRIENDS::new();
+    s_proto.set_ranks(ranks.into());
+    send_proto_2_client(rm, ClientCode::RankFriends, user_id, &s_proto);
+}
+
+///序列化协议并发给客户端
+fn send_proto_2_client<M: Message>(rm: &RankMgr, cmd: ClientCode, user_id: u32, proto: &M) {
+    let bytes = proto.write_to_bytes();
+    if let Err(e) = bytes {
+        error!("{:?}", e);
+        return;
+    }
+    rm.send_2_client(cmd, user_id, bytes.unwrap());
 }
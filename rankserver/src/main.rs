@@ -1,3 +1,4 @@
+mod config;
 mod handler;
 mod mgr;
 mod net;
@@ -5,17 +6,18 @@ mod task_timer;
 
 use std::env;
 
+use crate::config::RankConfig;
 use crate::mgr::rank_mgr::RankMgr;
 use crate::net::tcp_server;
 use async_std::sync::Mutex;
-use log::error;
+use log::{error, info};
 use mgr::RankInfo;
-use rayon::slice::ParallelSliceMut;
 use std::sync::Arc;
 use task_timer::init_timer;
 use tools::conf::Conf;
 use tools::redis_pool::RedisPoolTool;
-use tools::templates::template::{init_temps_mgr, TemplatesMgr};
+use tools::registry::{Registry, ServiceInfo, ROLE_RANK};
+use tools::templates::template::{init_temps_mgr, Templates};
 
 #[macro_use]
 extern crate lazy_static;
@@ -31,22 +33,35 @@ lazy_static! {
         conf
     };
 
+    ///校验过的配置
+    static ref CONFIG: RankConfig = {
+        match CONF_MAP.parse() {
+            Ok(config) => config,
+            Err(e) => panic!("{:?}", e),
+        }
+    };
+
     ///静态配置文件
-    static ref TEMPLATES: TemplatesMgr = {
+    static ref TEMPLATES: Templates = {
         init_templates_mgr()
     };
 
         ///reids客户端
-    static ref REDIS_POOL:Arc<Mutex<RedisPoolTool>>={
-        let add: &str = &CONF_MAP.get_str("redis_add","");
-        let pass: &str = &CONF_MAP.get_str("redis_pass","");
-        let redis = RedisPoolTool::init(add,pass);
-        let redis:Arc<Mutex<RedisPoolTool>> = Arc::new(Mutex::new(redis));
+    static ref REDIS_POOL: RedisPoolTool = {
+        let mut redis = match RedisPoolTool::init(&CONFIG.base.redis_add, &CONFIG.base.redis_pass) {
+            Ok(redis) => redis,
+            Err(e) => {
+                error!("初始化redis失败!{:?}", e);
+                std::process::exit(1);
+            }
+        };
+        //按游戏和区服隔离redis数据
+        redis.set_namespace(CONFIG.game.namespace().as_str(), &[]);
         redis
     };
 }
 
-fn init_templates_mgr() -> TemplatesMgr {
+fn init_templates_mgr() -> Templates {
     let path = env::current_dir().unwrap();
     let str = path.as_os_str().to_str().unwrap();
     let res = str.to_string() + "/template";
@@ -72,41 +87,71 @@ const REDIS_KEY_HISTORY_RANK: &str = "history_rank";
 ///最佳排行
 const REDIS_KEY_BEST_RANK: &str = "best_rank";
 
+///玩家评分
+const REDIS_KEY_RATING: &str = "rating";
+
+///已经处理过的赛季轮次
+const REDIS_KEY_SEASON_ROUND: &str = "season_round";
+
 fn main() {
     let rm = Arc::new(Mutex::new(RankMgr::new()));
 
     //初始化日志模块
     init_log();
 
+    //打印生效的配置
+    info!("{}", CONF_MAP.dump(&*CONFIG));
+
+    //初始化数据包压缩
+    tools::compress::init(
+        CONFIG.base.compress_threshold,
+        CONFIG.base.compress_type.as_str(),
+    );
+
     //初始化排行榜
     init_rank(rm.clone());
 
     //初始化定时器
     init_timer(rm.clone());
 
+    //注册到注册中心
+    init_registry(rm.clone());
+
     //初始化网络
     init_tcp_server(rm.clone());
 }
 
 fn init_log() {
-    let info_log = &CONF_MAP.get_str("info_log_path", "");
-    let error_log = &CONF_MAP.get_str("error_log_path", "");
+    let info_log = &CONFIG.base.info_log_path;
+    let error_log = &CONFIG.base.error_log_path;
     tools::my_log::init_log(info_log, error_log);
 }
 
+///注册到注册中心，定时心跳上报排行榜人数
+fn init_registry(rm: Lock) {
+    let registry = Registry::new(&CONFIG.registry);
+    let address = CONFIG.registry.advertise_address(&CONFIG.tcp_port);
+    let heartbeat = registry.clone();
+    let m = move || {
+        let load = async_std::task::block_on(rm.lock()).ranks.len() as u32;
+        let info = ServiceInfo::new(ROLE_RANK, address.clone(), 0, load);
+        heartbeat.register(&REDIS_POOL, &info);
+    };
+    registry.spawn_heartbeat(m);
+}
+
 ///初始化tcp服务端
 fn init_tcp_server(rm: Lock) {
-    let tcp_port: &str = &CONF_MAP.get_str("tcp_port", "");
+    let tcp_port: &str = &CONFIG.tcp_port;
     tcp_server::new(tcp_port, rm);
 }
 
 ///初始化排行榜
 fn init_rank(rm: Lock) {
-    let mut redis_lock = async_std::task::block_on(REDIS_POOL.lock());
     let mut lock = async_std::task::block_on(rm.lock());
 
     //加载上一赛季排行榜
-    let last_ranks: Option<Vec<String>> = redis_lock.hvals(REDIS_INDEX_RANK, REDIS_KEY_LAST_RANK);
+    let last_ranks: Option<Vec<String>> = REDIS_POOL.hvals(REDIS_INDEX_RANK, REDIS_KEY_LAST_RANK);
     if let Some(last_ranks) = last_ranks {
         for last_rank in last_ranks {
             let ri = serde_json::from_str(last_rank.as_str());
@@ -134,7 +179,7 @@ fn init_rank(rm: Lock) {
     });
 
     //加载当前赛季排行榜
-    let ranks: Option<Vec<String>> = redis_lock.hvals(REDIS_INDEX_RANK, REDIS_KEY_CURRENT_RANK);
+    let ranks: Option<Vec<String>> = REDIS_POOL.hvals(REDIS_INDEX_RANK, REDIS_KEY_CURRENT_RANK);
     if ranks.is_none() {
         return;
     }
@@ -147,13 +192,13 @@ fn init_rank(rm: Lock) {
             continue;
         }
         let ri: RankInfo = ri.unwrap();
-        lock.rank_vec.push(ri);
+        lock.update_rank_info(ri);
     }
-    //进行排序
-    lock.rank_vec.par_sort_by(|a, b| a.rank.cmp(&b.rank));
+    //刷新排名
+    lock.flush(true);
 
     //加载最佳排行
-    let best_ranks: Option<Vec<String>> = redis_lock.hvals(REDIS_INDEX_RANK, REDIS_KEY_BEST_RANK);
+    let best_ranks: Option<Vec<String>> = REDIS_POOL.hvals(REDIS_INDEX_RANK, REDIS_KEY_BEST_RANK);
     if let None = best_ranks {
         return;
     }
@@ -1,4 +1,5 @@
-use tools::protos::base::{LeaguePt, SummaryDataPt};
+use tools::protos::base::{LeaguePt, RankInfoPt, SummaryDataPt};
+pub mod rank_index;
 pub mod rank_mgr;
 use serde::{Deserialize, Serialize};
 
@@ -21,7 +22,8 @@ unsafe impl Send for RankInfo {}
 impl RankInfo {
     ///更新段位
     pub fn update_league(&mut self, id: i8) {
-        let res = crate::TEMPLATES.league_temp_mgr().get_temp(&id).unwrap();
+        let temps = crate::TEMPLATES.load();
+        let res = temps.league_temp_mgr().get_temp(&id).unwrap();
         self.league.id = res.id;
         let time = chrono::Local::now();
         self.league.league_score = res.score;
@@ -49,6 +51,21 @@ impl RankInfo {
         self.league = League::from(sd_pt.get_league());
         self.cters = cters;
     }
+
+    ///转换成排行协议的数据，rank传实时排名
+    pub fn to_rank_pt(&self, rank: usize) -> RankInfoPt {
+        let mut rip = RankInfoPt::new();
+        rip.user_id = self.user_id;
+        rip.name = self.name.clone();
+        rip.rank = rank as i32;
+        rip.set_cters(self.cters.clone());
+        let mut l_pt = LeaguePt::new();
+        l_pt.set_league_id(self.league.get_league_id() as i32);
+        l_pt.set_league_score(self.league.league_score);
+        l_pt.set_league_time(self.league.league_time);
+        rip.set_league(l_pt);
+        rip
+    }
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
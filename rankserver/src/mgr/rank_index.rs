@@ -0,0 +1,265 @@
+use crate::mgr::RankInfo;
+use std::cmp::Ordering;
+
+///空节点
+const NIL: usize = usize::MAX;
+
+///排行排序key
+///段位高的在前，同段位先进段位的在前，再看积分，最后按玩家id保证唯一
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct RankKey {
+    pub league_id: i8,    //段位id
+    pub league_time: i64, //进入段位的时间
+    pub score: i32,       //积分
+    pub user_id: u32,     //玩家id
+}
+
+impl From<&RankInfo> for RankKey {
+    fn from(ri: &RankInfo) -> Self {
+        RankKey {
+            league_id: ri.league.id,
+            league_time: ri.league.league_time,
+            score: ri.league.league_score,
+            user_id: ri.user_id,
+        }
+    }
+}
+
+impl Ord for RankKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .league_id
+            .cmp(&self.league_id)
+            .then(self.league_time.cmp(&other.league_time))
+            .then(other.score.cmp(&self.score))
+            .then(self.user_id.cmp(&other.user_id))
+    }
+}
+
+impl PartialOrd for RankKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    key: RankKey,
+    priority: u32,
+    left: usize,
+    right: usize,
+    size: usize, //子树节点数量
+}
+
+///排行索引，带子树大小的treap
+///插入，删除，按key查排名，按排名查key都是O(log n)
+#[derive(Debug, Clone)]
+pub struct RankIndex {
+    nodes: Vec<Node>,
+    free: Vec<usize>, //删除后可以复用的节点
+    root: usize,
+    seed: u32, //随机优先级种子
+}
+
+impl Default for RankIndex {
+    fn default() -> Self {
+        RankIndex {
+            nodes: Vec::new(),
+            free: Vec::new(),
+            root: NIL,
+            seed: 0x9E37_79B9,
+        }
+    }
+}
+
+impl RankIndex {
+    pub fn len(&self) -> usize {
+        self.size(self.root)
+    }
+
+    ///插入，返回插入后的排名(从0开始)
+    pub fn insert(&mut self, key: RankKey) -> usize {
+        let node = self.new_node(key);
+        let (left, right) = self.split(self.root, &key, false);
+        let rank = self.size(left);
+        let left = self.merge(left, node);
+        self.root = self.merge(left, right);
+        rank
+    }
+
+    ///删除，返回删除前的排名，不存在返回None
+    pub fn remove(&mut self, key: &RankKey) -> Option<usize> {
+        let rank = self.rank_of(key)?;
+        let (left, right) = self.split(self.root, key, false);
+        let (mid, right) = self.split(right, key, true);
+        if mid != NIL {
+            self.free.push(mid);
+        }
+        self.root = self.merge(left, right);
+        Some(rank)
+    }
+
+    ///查排名(从0开始)
+    pub fn rank_of(&self, key: &RankKey) -> Option<usize> {
+        let mut index = self.root;
+        let mut rank = 0;
+        while index != NIL {
+            let node = &self.nodes[index];
+            match key.cmp(&node.key) {
+                Ordering::Less => index = node.left,
+                Ordering::Greater => {
+                    rank += self.size(node.left) + 1;
+                    index = node.right;
+                }
+                Ordering::Equal => return Some(rank + self.size(node.left)),
+            }
+        }
+        None
+    }
+
+    ///按排名取key(从0开始)
+    pub fn nth(&self, mut rank: usize) -> Option<&RankKey> {
+        let mut index = self.root;
+        while index != NIL {
+            let node = &self.nodes[index];
+            let left_size = self.size(node.left);
+            if rank < left_size {
+                index = node.left;
+            } else if rank == left_size {
+                return Some(&node.key);
+            } else {
+                rank -= left_size + 1;
+                index = node.right;
+            }
+        }
+        None
+    }
+
+    ///取排名在[start,end)之间的key
+    pub fn range(&self, start: usize, end: usize) -> Vec<RankKey> {
+        let end = end.min(self.len());
+        let mut res = Vec::with_capacity(end.saturating_sub(start));
+        for rank in start..end {
+            if let Some(key) = self.nth(rank) {
+                res.push(*key);
+            }
+        }
+        res
+    }
+
+    fn size(&self, index: usize) -> usize {
+        if index == NIL {
+            return 0;
+        }
+        self.nodes[index].size
+    }
+
+    fn update_size(&mut self, index: usize) {
+        let node = &self.nodes[index];
+        let size = self.size(node.left) + self.size(node.right) + 1;
+        self.nodes[index].size = size;
+    }
+
+    fn new_node(&mut self, key: RankKey) -> usize {
+        //xorshift
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 17;
+        self.seed ^= self.seed << 5;
+        let node = Node {
+            key,
+            priority: self.seed,
+            left: NIL,
+            right: NIL,
+            size: 1,
+        };
+        match self.free.pop() {
+            Some(index) => {
+                self.nodes[index] = node;
+                index
+            }
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            }
+        }
+    }
+
+    ///按key拆成两棵树，inclusive为true时等于key的分到左边
+    fn split(&mut self, index: usize, key: &RankKey, inclusive: bool) -> (usize, usize) {
+        if index == NIL {
+            return (NIL, NIL);
+        }
+        let node_key = self.nodes[index].key;
+        let go_left = if inclusive {
+            node_key <= *key
+        } else {
+            node_key < *key
+        };
+        if go_left {
+            let (left, right) = self.split(self.nodes[index].right, key, inclusive);
+            self.nodes[index].right = left;
+            self.update_size(index);
+            (index, right)
+        } else {
+            let (left, right) = self.split(self.nodes[index].left, key, inclusive);
+            self.nodes[index].left = right;
+            self.update_size(index);
+            (left, index)
+        }
+    }
+
+    ///合并两棵树，left的key都小于right
+    fn merge(&mut self, left: usize, right: usize) -> usize {
+        if left == NIL {
+            return right;
+        }
+        if right == NIL {
+            return left;
+        }
+        if self.nodes[left].priority > self.nodes[right].priority {
+            let merged = self.merge(self.nodes[left].right, right);
+            self.nodes[left].right = merged;
+            self.update_size(left);
+            left
+        } else {
+            let merged = self.merge(left, self.nodes[right].left);
+            self.nodes[right].left = merged;
+            self.update_size(right);
+            right
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(user_id: u32, score: i32) -> RankKey {
+        RankKey {
+            league_id: 1,
+            league_time: 0,
+            score,
+            user_id,
+        }
+    }
+
+    #[test]
+    fn insert_remove_and_rank() {
+        let mut index = RankIndex::default();
+        for user_id in 0..100 {
+            index.insert(key(user_id, user_id as i32));
+        }
+        assert_eq!(index.len(), 100);
+        //积分高的在前
+        assert_eq!(index.rank_of(&key(99, 99)), Some(0));
+        assert_eq!(index.nth(99).unwrap().user_id, 0);
+
+        assert_eq!(index.remove(&key(99, 99)), Some(0));
+        assert_eq!(index.remove(&key(99, 99)), None);
+        assert_eq!(index.insert(key(99, 50)), 49);
+        assert_eq!(index.rank_of(&key(98, 98)), Some(0));
+        let top: Vec<u32> = index.range(0, 3).iter().map(|x| x.user_id).collect();
+        assert_eq!(top, vec![98, 97, 96]);
+        assert_eq!(index.len(), 100);
+    }
+}
@@ -1,15 +1,15 @@
-use std::collections::HashMap;
-
+use super::rank_index::{RankIndex, RankKey};
 use super::RankInfo;
-use crate::handler::{modify_nick_name, update_rank, update_season};
+use crate::handler::{
+    modify_nick_name, rank_around_me, rank_friends, rank_page, update_rank, update_season,
+};
 use crate::task_timer::Task;
 use crate::{REDIS_INDEX_RANK, REDIS_KEY_CURRENT_RANK};
-use async_std::task::block_on;
 use crossbeam::channel::Sender;
-use log::warn;
-use rayon::slice::ParallelSliceMut;
+use log::{error, warn};
 use std::collections::hash_map::RandomState;
-use tools::cmd_code::RankCode;
+use std::collections::{HashMap, HashSet};
+use tools::cmd_code::{ClientCode, RankCode};
 use tools::net_message_io::NetHandler;
 use tools::util::packet::Packet;
 
@@ -17,13 +17,16 @@ type CmdFn = HashMap<u32, fn(&mut RankMgr, Packet), RandomState>;
 ///排行榜管理器
 #[derive(Default)]
 pub struct RankMgr {
-    pub rank_vec: Vec<RankInfo>,                //排行榜数据
-    pub cmd_map: CmdFn,                         //命令管理 key:cmd,value:函数指针
-    pub need_rank: bool,                        //是否需要排序
-    pub last_rank: Vec<RankInfo>,               //上一赛季排行榜数据
+    pub ranks: HashMap<u32, RankInfo>,   //当前赛季排行数据 key:玩家id
+    pub rank_index: RankIndex,           //当前赛季排行索引
+    dirty_range: Option<(usize, usize)>, //排名有变化的区间
+    dirty_users: HashSet<u32>,           //数据有变化的玩家
+    pub need_full_sync: bool,            //是否需要游戏服全量同步
+    pub cmd_map: CmdFn,                  //命令管理 key:cmd,value:函数指针
+    pub last_rank: Vec<RankInfo>,        //上一赛季排行榜数据
     pub user_best_rank: HashMap<u32, RankInfo>, //玩家历史最好排行数据
-    net_handler: Option<NetHandler>,            //tcp channel的发送方
-    pub task_sender: Option<Sender<Task>>,      //任务发送方
+    net_handler: Option<NetHandler>,     //tcp channel的发送方
+    pub task_sender: Option<Sender<Task>>, //任务发送方
 }
 
 impl RankMgr {
@@ -33,14 +36,8 @@ impl RankMgr {
         rm
     }
 
-    pub fn get_rank_mut(&mut self, user_id: u32) -> Option<&mut RankInfo> {
-        for ri in self.rank_vec.iter_mut() {
-            if ri.user_id != user_id {
-                continue;
-            }
-            return Some(ri);
-        }
-        None
+    pub fn get_rank_ref(&self, user_id: u32) -> Option<&RankInfo> {
+        self.ranks.get(&user_id)
     }
 
     ///转发到游戏中心服,然后推送给所有特定服务器
@@ -55,6 +52,17 @@ impl RankMgr {
             .send(endpoint, bytes.as_slice());
     }
 
+    ///通过游戏中心服发送给客户端
+    pub fn send_2_client(&self, cmd: ClientCode, user_id: u32, bytes: Vec<u8>) {
+        let bytes = Packet::build_packet_bytes(cmd.into_u32(), user_id, bytes, true, true);
+        let tcp_handler = self.net_handler.as_ref().unwrap();
+        let endpoint = tcp_handler.endpoint;
+        tcp_handler
+            .node_handler
+            .network()
+            .send(endpoint, bytes.as_slice());
+    }
+
     pub fn set_sender(&mut self, net_handler: NetHandler) {
         self.net_handler = Some(net_handler);
     }
@@ -70,6 +78,15 @@ impl RankMgr {
         //修改名字
         self.cmd_map
             .insert(RankCode::ModifyNickName.into_u32(), modify_nick_name);
+        //请求自己附近的排行
+        self.cmd_map
+            .insert(RankCode::RankAroundMe.into_u32(), rank_around_me);
+        //分页请求排行榜
+        self.cmd_map
+            .insert(RankCode::RankPage.into_u32(), rank_page);
+        //请求好友排行
+        self.cmd_map
+            .insert(RankCode::RankFriends.into_u32(), rank_friends);
     }
 
     ///执行函数，通过packet拿到cmd，然后从cmdmap拿到函数指针调用
@@ -85,40 +102,127 @@ impl RankMgr {
 }
 
 impl RankMgr {
-    pub fn sort(&mut self, need_save: bool) {
-        if !self.need_rank {
-            return;
+    ///更新或者插入排行数据，同时更新排行索引
+    ///没有段位的不参与排行
+    pub fn update_rank_info(&mut self, ri: RankInfo) {
+        let user_id = ri.user_id;
+        let mut old_rank = None;
+        if let Some(old) = self.ranks.get(&user_id) {
+            old_rank = self.rank_index.remove(&RankKey::from(old));
         }
-        self.rank_vec.par_sort_by(|a, b| {
-            //如果段位等级一样
-            if a.league.get_league_id() == b.league.get_league_id() {
-                if a.league.league_time != b.league.league_time {
-                    //看时间
-                    return a.league.league_time.cmp(&b.league.league_time);
-                }
+        let mut new_rank = None;
+        if ri.league.id > 0 {
+            new_rank = Some(self.rank_index.insert(RankKey::from(&ri)));
+        }
+        self.ranks.insert(user_id, ri);
+        self.dirty_users.insert(user_id);
+        match (old_rank, new_rank) {
+            (Some(old_rank), Some(new_rank)) => {
+                self.add_dirty_range(old_rank.min(new_rank), old_rank.max(new_rank))
+            }
+            (Some(rank), None) | (None, Some(rank)) => self.add_dirty_range(rank, usize::MAX),
+            (None, None) => {}
+        }
+    }
+
+    ///删除排行数据
+    pub fn remove_rank_info(&mut self, user_id: u32) -> Option<RankInfo> {
+        let ri = self.ranks.remove(&user_id)?;
+        if let Some(rank) = self.rank_index.remove(&RankKey::from(&ri)) {
+            self.add_dirty_range(rank, usize::MAX);
+        }
+        self.dirty_users.remove(&user_id);
+        Some(ri)
+    }
+
+    ///修改名字，不影响排名
+    pub fn set_name(&mut self, user_id: u32, name: String) {
+        if let Some(ri) = self.ranks.get_mut(&user_id) {
+            ri.name = name;
+            self.dirty_users.insert(user_id);
+        }
+    }
+
+    ///玩家排名(从0开始)，不在排行榜上返回None
+    pub fn get_rank(&self, user_id: u32) -> Option<usize> {
+        let ri = self.ranks.get(&user_id)?;
+        self.rank_index.rank_of(&RankKey::from(ri))
+    }
+
+    ///排名在[start,end)之间的排行数据
+    pub fn get_range(&self, start: usize, end: usize) -> Vec<&RankInfo> {
+        let mut res = vec![];
+        for key in self.rank_index.range(start, end) {
+            if let Some(ri) = self.ranks.get(&key.user_id) {
+                res.push(ri);
             }
-            //段位不一样直接看分数
-            b.get_score().cmp(&a.get_score())
-        });
-        let mut redis_lock = block_on(crate::REDIS_POOL.lock());
-        for (index, ri_mut) in self.rank_vec.iter_mut().enumerate() {
-            let user_id = ri_mut.user_id;
-            let rank = ri_mut.rank;
-            let league_id = ri_mut.league.id;
-            let index = index as i32;
-            if rank != index && league_id > 0 {
-                ri_mut.rank = index;
-                if need_save {
-                    let json_value = serde_json::to_string(ri_mut).unwrap();
-                    //持久化到redis
-                    let _: Option<u32> = redis_lock.hset(
-                        REDIS_INDEX_RANK,
-                        REDIS_KEY_CURRENT_RANK,
-                        user_id.to_string().as_str(),
-                        json_value.as_str(),
-                    );
+        }
+        res
+    }
+
+    ///按排名顺序的玩家id
+    pub fn get_ordered_user_ids(&self) -> Vec<u32> {
+        let res = self.rank_index.range(0, self.rank_index.len());
+        res.iter().map(|key| key.user_id).collect()
+    }
+
+    ///是否有没同步的变化
+    pub fn is_dirty(&self) -> bool {
+        self.dirty_range.is_some() || !self.dirty_users.is_empty()
+    }
+
+    fn add_dirty_range(&mut self, start: usize, end: usize) {
+        self.dirty_range = match self.dirty_range {
+            Some((old_start, old_end)) => Some((old_start.min(start), old_end.max(end))),
+            None => Some((start, end)),
+        };
+    }
+
+    ///把变化区间里的排名写回排行数据，有变化的批量持久化到redis
+    ///返回有变化的排行数据，用于给游戏服推送增量
+    pub fn flush(&mut self, need_save: bool) -> Vec<RankInfo> {
+        if let Some((start, end)) = self.dirty_range.take() {
+            let end = end.saturating_add(1).min(self.rank_index.len());
+            for (index, key) in self.rank_index.range(start, end).iter().enumerate() {
+                let rank = (start + index) as i32;
+                let ri = self.ranks.get_mut(&key.user_id);
+                if let Some(ri) = ri {
+                    if ri.rank != rank {
+                        ri.rank = rank;
+                        self.dirty_users.insert(key.user_id);
+                    }
                 }
             }
         }
+        let mut res = Vec::with_capacity(self.dirty_users.len());
+        for user_id in self.dirty_users.drain() {
+            let ri = self.ranks.get_mut(&user_id);
+            if ri.is_none() {
+                continue;
+            }
+            let ri = ri.unwrap();
+            //没有段位的不参与排名
+            if ri.league.id <= 0 {
+                ri.rank = -1;
+            }
+            res.push(ri.clone());
+        }
+        if !need_save || res.is_empty() {
+            return res;
+        }
+        let mut items = Vec::with_capacity(res.len());
+        for ri in res.iter() {
+            match serde_json::to_string(ri) {
+                Ok(json_value) => items.push((ri.user_id.to_string(), json_value)),
+                Err(e) => error!("{:?}", e),
+            }
+        }
+        //持久化到redis
+        let _: Option<u32> = crate::REDIS_POOL.hset_multiple(
+            REDIS_INDEX_RANK,
+            REDIS_KEY_CURRENT_RANK,
+            items.as_slice(),
+        );
+        res
     }
 }
@@ -24,7 +24,8 @@ pub fn init_timer(rm: Lock) {
 }
 
 fn sort_rank(rm: Lock) {
-    let mgr = crate::TEMPLATES.constant_temp_mgr();
+    let temps = crate::TEMPLATES.load();
+    let mgr = temps.constant_temp_mgr();
     let update_time = mgr.temps.get("rank_update_time");
     let time;
     match update_time {
@@ -44,20 +45,27 @@ fn sort_rank(rm: Lock) {
         loop {
             std::thread::sleep(Duration::from_millis(time));
             let mut lock = async_std::task::block_on(rm.lock());
-            if !lock.need_rank {
+            if !lock.is_dirty() && !lock.need_full_sync {
                 info!("执行排行定时器-排行榜没有任何变化,无需排序");
                 continue;
             }
             info!("执行排行定时器-开始执行排序");
             let take_time = std::time::SystemTime::now();
-            //排序
-            lock.sort(true);
-            //设置不需要排序
-            lock.need_rank = false;
+            //刷新有变化的排名
+            let delta = lock.flush(true);
             info!("执行排行定时器结束!耗时:{:?}", take_time.elapsed().unwrap());
 
-            //下发到游戏服务器
-            lock.push_2_server(GameCode::SyncRank.into_u32(), 0, vec![]);
+            //下发到游戏服务器，body为空则游戏服从redis全量加载，否则是有变化的排行数据
+            let mut bytes = vec![];
+            if lock.need_full_sync {
+                lock.need_full_sync = false;
+            } else {
+                match serde_json::to_vec(&delta) {
+                    Ok(res) => bytes = res,
+                    Err(e) => error!("{:?}", e),
+                }
+            }
+            lock.push_2_server(GameCode::SyncRank.into_u32(), 0, bytes);
             let take_time = std::time::SystemTime::now();
             let res = take_time.elapsed().unwrap();
             info!("更新rank并下发排行榜快照到游戏服结束!耗时{:?}", res);
@@ -2,23 +2,24 @@ use crate::battle::enums::{AttackState, BattleCterState, RobotState};
 use crate::battle::map_data::TileMap;
 use crossbeam::atomic::AtomicCell;
 use std::collections::HashMap;
+use std::sync::Arc;
 use tools::templates::buff_temp::BuffTemp;
 use tools::templates::skill_temp::SkillTemp;
 
 #[derive(Clone, Debug)]
 pub struct Skill {
     pub id: u32,
-    pub skill_temp: &'static SkillTemp,
+    pub skill_temp: Arc<SkillTemp>,
     pub cd_times: i8,    //剩余cd,如果是消耗能量则无视这个值
     pub is_active: bool, //是否激活
 }
 
-impl From<&'static SkillTemp> for Skill {
-    fn from(skill_temp: &'static SkillTemp) -> Self {
+impl From<&Arc<SkillTemp>> for Skill {
+    fn from(skill_temp: &Arc<SkillTemp>) -> Self {
         Skill {
             id: skill_temp.id,
             cd_times: 0,
-            skill_temp: skill_temp,
+            skill_temp: skill_temp.clone(),
             is_active: false,
         }
     }
@@ -26,8 +27,8 @@ impl From<&'static SkillTemp> for Skill {
 
 #[derive(Clone, Debug)]
 pub struct Item {
-    pub id: u32,                        //物品id
-    pub skill_temp: &'static SkillTemp, //物品带的技能
+    pub id: u32,                    //物品id
+    pub skill_temp: Arc<SkillTemp>, //物品带的技能
 }
 
 ///角色战斗buff
@@ -42,7 +43,7 @@ pub struct BattleBuff {
 #[derive(Clone, Debug)]
 pub struct Buff {
     pub id: u32,
-    pub buff_temp: &'static BuffTemp,
+    pub buff_temp: Arc<BuffTemp>,
     pub trigger_timesed: i8,       //已经触发过的次数
     pub keep_times: i8,            //剩余持续轮数
     pub scope: Vec<Direction>,     //buff的作用范围
@@ -54,7 +55,7 @@ pub struct Buff {
 
 #[derive(Debug, Clone)]
 pub struct Direction {
-    pub direction: &'static Vec<isize>,
+    pub direction: Vec<isize>,
 }
 
 ///角色战斗基础属性
@@ -0,0 +1,57 @@
+use crate::room::match_room::MatchConf;
+use serde::{Deserialize, Serialize};
+use tools::conf::{check_address, BaseConfig, Validate};
+use tools::game_key::GameKey;
+use tools::registry::RegistryConfig;
+
+///房间服配置，启动时从config/config.conf读取并校验
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RoomConfig {
+    #[serde(flatten)]
+    pub game: GameKey, //游戏和区服
+    #[serde(flatten)]
+    pub base: BaseConfig, //公共配置
+    #[serde(flatten)]
+    pub registry: RegistryConfig, //注册中心
+    #[serde(flatten)]
+    pub matching: MatchConf, //匹配参数
+    pub tcp_port: String,          //tcp监听地址
+    pub http_port: u16,            //http监听端口,0为不启动
+    pub capacity: u32,             //容量(房间数),0为不限制
+    pub match_stats_interval: u64, //匹配队列统计打印间隔(秒),0为不打印
+}
+
+impl Default for RoomConfig {
+    fn default() -> Self {
+        RoomConfig {
+            game: GameKey::default(),
+            base: BaseConfig::default(),
+            registry: RegistryConfig::default(),
+            matching: MatchConf::default(),
+            tcp_port: String::new(),
+            http_port: 0,
+            capacity: 0,
+            match_stats_interval: 60,
+        }
+    }
+}
+
+impl Validate for RoomConfig {
+    fn validate(&self) -> anyhow::Result<()> {
+        self.base.validate()?;
+        self.registry.validate()?;
+        check_address("tcp_port", self.tcp_port.as_str())?;
+        let matching = &self.matching;
+        if matching.score_spread < 0
+            || matching.spread_widen < 0
+            || matching.max_score_spread < matching.score_spread
+        {
+            anyhow::bail!("匹配积分差配置错误!{:?}", matching);
+        }
+        if matching.league_widen_time < 0 || matching.robot_timeout < 0 {
+            anyhow::bail!("match_league_widen_time和match_robot_timeout不能小于0!");
+        }
+        Ok(())
+    }
+}
@@ -1,21 +1,24 @@
 use crate::mgr::room_mgr::RoomMgr;
 use crate::mgr::RankInfo;
 use crate::room::character::Character;
+use crate::room::match_room::MatchConf;
 use crate::room::member::MemberState;
 use crate::room::member::{Member, PunishMatch};
 use crate::room::room::{MemberLeaveNoticeType, RoomSettingType, RoomState, MEMBER_MAX};
 use crate::room::room_model::{RoomModel, RoomSetting, RoomType, TeamId};
 use crate::task_timer::build_match_room_ready_task;
 use crate::{SEASON, WORLD_BOSS};
+use chrono::Local;
 use log::error;
 use log::info;
 use log::warn;
 use protobuf::Message;
+use rand::Rng;
 use std::borrow::BorrowMut;
 use std::convert::TryFrom;
 use std::str::FromStr;
 use std::sync::atomic::Ordering;
-use tools::cmd_code::{BattleCode, ClientCode, GameCode};
+use tools::cmd_code::{BattleCode, ClientCode, GameCode, ServerCommonCode};
 use tools::macros::GetMutRef;
 use tools::protos::room::{
     C_CHANGE_TEAM, C_CHOICE_AI, C_CHOOSE_CHARACTER, C_CHOOSE_SKILL, C_CONFIRM_INTO_ROOM, C_EMOJI,
@@ -31,25 +34,43 @@ use tools::templates::emoji_temp::EmojiTemp;
 use tools::util::packet::Packet;
 
 pub fn reload_temps(_: &mut RoomMgr, _: Packet) {
-    let path = std::env::current_dir();
-    if let Err(e) = path {
-        warn!("{:?}", e);
-        return;
-    }
-    let path = path.unwrap();
-    let str = path.as_os_str().to_str();
-    if let None = str {
-        warn!("reload_temps can not path to_str!");
-        return;
-    }
-    let str = str.unwrap();
-    let res = str.to_string() + "/template";
-    let res = crate::TEMPLATES.reload_temps(res.as_str());
-    if let Err(e) = res {
-        warn!("{:?}", e);
-        return;
+    let res = crate::TEMPLATES.reload();
+    match res {
+        Ok(report) => {
+            info!("reload_temps success!{}", report);
+        }
+        Err(e) => {
+            warn!("reload_temps fail,keep old templates!{:?}", e);
+        }
     }
-    info!("reload_temps success!");
+}
+
+///停服排空，之后不再创建、加入、匹配房间
+pub fn drain(rm: &mut RoomMgr, _: Packet) {
+    tools::shutdown::set_draining();
+    let value = serde_json::json!({
+        "server_type":"room",
+        "player_num":rm.player_room.len(),
+    });
+    rm.send_2_server(
+        ServerCommonCode::DrainAck.into_u32(),
+        0,
+        value.to_string().into_bytes(),
+    );
+    info!("房间服停服排空!{}", value);
+}
+
+///停服排空中，拒绝请求并通知客户端
+fn refuse_if_draining(rm: &mut RoomMgr, user_id: u32) -> bool {
+    if !tools::shutdown::is_draining() {
+        return false;
+    }
+    warn!("server is draining,refuse request!user_id:{}", user_id);
+    let mut sr = tools::protos::room::S_ROOM::new();
+    sr.is_succ = false;
+    sr.err_mess = String::from("server is stopping!");
+    rm.send_2_client(ClientCode::Room, user_id, sr.write_to_bytes().unwrap());
+    true
 }
 
 ///更新赛季
@@ -68,7 +89,8 @@ pub fn update_season(rm: &mut RoomMgr, packet: Packet) {
     }
 
     //处理更新内存
-    let mgr = crate::TEMPLATES.constant_temp_mgr();
+    let temps = crate::TEMPLATES.load();
+    let mgr = temps.constant_temp_mgr();
     let round_season_id = mgr.temps.get("round_season_id");
     if let None = round_season_id {
         warn!("the constant temp is None!key:round_season_id");
@@ -85,8 +107,6 @@ pub fn update_season(rm: &mut RoomMgr, packet: Packet) {
         return;
     }
 
-    let mut redis_lock = crate::REDIS_POOL.lock().unwrap();
-
     let cp: Vec<u32> = rm.player_room.keys().copied().collect();
 
     //更新所有内存数据
@@ -101,7 +121,7 @@ pub fn update_season(rm: &mut RoomMgr, packet: Packet) {
             continue;
         }
         let member = member.unwrap();
-        let rank: Option<String> = redis_lock.hget(
+        let rank: Option<String> = crate::REDIS_POOL.hget(
             crate::REDIS_INDEX_RANK,
             crate::REDIS_KEY_CURRENT_RANK,
             user_id.to_string().as_str(),
@@ -141,7 +161,8 @@ pub fn update_worldboss(rm: &mut RoomMgr, packet: Packet) {
         unsafe {
             //加入新worldboss
             let world_boss_id = crate::WORLD_BOSS.world_boss_id as u32;
-            let worldboss_temp = crate::TEMPLATES
+            let temps = crate::TEMPLATES.load();
+            let worldboss_temp = temps
                 .worldboss_temp_mgr()
                 .temps
                 .get(&world_boss_id)
@@ -156,6 +177,9 @@ pub fn update_worldboss(rm: &mut RoomMgr, packet: Packet) {
 
 ///创建房间
 pub fn create_room(rm: &mut RoomMgr, packet: Packet) {
+    if refuse_if_draining(rm, packet.get_user_id()) {
+        return;
+    }
     //解析gameserver过来的protobuf
     let mut grc = G_R_CREATE_ROOM::new();
     let res = grc.merge_from_bytes(packet.get_data());
@@ -187,7 +211,8 @@ pub fn create_room(rm: &mut RoomMgr, packet: Packet) {
             let room_setting_pt = grc.get_setting();
             let season_is_open = room_setting_pt.season_is_open;
             if season_is_open {
-                let season_temp = crate::TEMPLATES.season_temp_mgr().get_temp(&(1001 as u32));
+                let temps = crate::TEMPLATES.load();
+                let season_temp = temps.season_temp_mgr().get_temp(&1001);
                 if let Err(err) = season_temp {
                     warn!("{:?}", err);
                     return;
@@ -196,7 +221,8 @@ pub fn create_room(rm: &mut RoomMgr, packet: Packet) {
 
             let turn_limit_time_id = room_setting_pt.turn_limit_time as u8;
             let turn_limit_time;
-            let res = crate::TEMPLATES
+            let temps = crate::TEMPLATES.load();
+            let res = temps
                 .battle_limit_time_temp_mgr()
                 .get_temp(&turn_limit_time_id);
             match res {
@@ -212,13 +238,13 @@ pub fn create_room(rm: &mut RoomMgr, packet: Packet) {
             room_setting.turn_limit_time = turn_limit_time;
 
             let ai_level = room_setting_pt.ai_level as u8;
-            let mut res = crate::TEMPLATES
+            let mut res = temps
                 .constant_temp_mgr()
                 .temps
                 .get("ai_level_easy")
                 .unwrap();
             let re_id1 = u8::from_str(res.value.as_str()).unwrap();
-            res = crate::TEMPLATES
+            res = temps
                 .constant_temp_mgr()
                 .temps
                 .get("ai_level_hard")
@@ -233,7 +259,8 @@ pub fn create_room(rm: &mut RoomMgr, packet: Packet) {
         RoomType::WorldBossCustom => {
             let turn_limit_time;
             room_setting = RoomSetting::default();
-            let res = crate::TEMPLATES
+            let temps = crate::TEMPLATES.load();
+            let res = temps
                 .constant_temp_mgr()
                 .temps
                 .get("worldboss_turn_limit_time");
@@ -432,6 +459,9 @@ pub fn cancel_search_room(rm: &mut RoomMgr, packet: Packet) {
 
 ///寻找房间并加入房间
 pub fn search_room(rm: &mut RoomMgr, packet: Packet) {
+    if refuse_if_draining(rm, packet.get_user_id()) {
+        return;
+    }
     let mut grs = G_R_SEARCH_ROOM::new();
     let res = grs.merge_from_bytes(packet.get_data());
     if let Err(e) = res {
@@ -631,11 +661,8 @@ pub fn prepare_cancel(rm: &mut RoomMgr, packet: Packet) {
         return;
     }
 
-    let cter_temp = crate::TEMPLATES
-        .character_temp_mgr()
-        .temps
-        .get(&cter_id)
-        .unwrap();
+    let temps = crate::TEMPLATES.load();
+    let cter_temp = temps.character_temp_mgr().temps.get(&cter_id).unwrap();
 
     //校验玩家是否选了技能
     if prepare && member.chose_cter.skills.len() < cter_temp.usable_skill_count as usize {
@@ -689,9 +716,8 @@ pub fn choice_ai(rm: &mut RoomMgr, packet: Packet) {
     }
     let robot_temp_id = proto.get_robot_temp_id();
 
-    let robot_temp = crate::TEMPLATES
-        .robot_temp_mgr()
-        .get_temp_ref(&robot_temp_id);
+    let temps = crate::TEMPLATES.load();
+    let robot_temp = temps.robot_temp_mgr().get_temp_ref(&robot_temp_id);
     if let None = robot_temp {
         warn!(
             "this robot_temp is not find!robot_temp_id:{}",
@@ -722,7 +748,7 @@ pub fn choice_ai(rm: &mut RoomMgr, packet: Packet) {
     }
 
     //添加机器人
-    let robot = add_robot(rm, room_type, room_id, index, robot_temp_id);
+    let robot = add_robot(rm, room_type, room_id, index, robot_temp_id, true);
     if let Err(err) = robot {
         error!("{:?}", err);
         return;
@@ -796,11 +822,13 @@ pub fn add_robot(
     room_id: u32,
     index: usize,
     robot_temp_id: u32,
+    is_need_notice: bool,
 ) -> anyhow::Result<u32> {
     let room = rm.get_room_mut(room_type, room_id)?;
 
     //机器人模版管理器
-    let robot_temp_mgr = crate::TEMPLATES.robot_temp_mgr();
+    let temps = crate::TEMPLATES.load();
+    let robot_temp_mgr = temps.robot_temp_mgr();
     let robot_temp = robot_temp_mgr.get_temp_ref(&robot_temp_id).unwrap();
     let cter_id = robot_temp.cter_id;
     let robot_id;
@@ -837,8 +865,8 @@ pub fn add_robot(
     cter.skills.extend_from_slice(robot_temp.skills.as_slice());
     //将角色加入到成员里
     member.chose_cter = cter;
-    if member_new.is_some() {
-        room.add_member(member_new.unwrap(), Some(index), true)?;
+    if let Some(member_new) = member_new {
+        room.add_member(member_new, Some(index), is_need_notice)?;
         let room_id = room.get_room_id();
         let value = tools::binary::combine_int_2_long(room_type as u32, room_id);
         rm.player_room.insert(robot_id, value);
@@ -846,6 +874,57 @@ pub fn add_robot(
     Ok(robot_id)
 }
 
+///匹配等待超时的房间用机器人补满
+pub fn match_timeout_fill_robot(rm: &mut RoomMgr) {
+    let conf = MatchConf::from_conf();
+    let now = Local::now().timestamp_millis();
+    let room_ids = rm.match_room.get_robot_timeout_rooms(&conf, now);
+    for room_id in room_ids {
+        let res = fill_robot(rm, RoomType::OneVOneVOneVOneMatch, room_id);
+        if let Err(e) = res {
+            warn!("{:?}", e);
+            continue;
+        }
+        let task_sender = rm.get_task_sender_clone();
+        rm.match_room.on_robot_filled(room_id, task_sender);
+    }
+}
+
+///把房间的空位都补上机器人，机器人的角色不重复
+fn fill_robot(rm: &mut RoomMgr, room_type: RoomType, room_id: u32) -> anyhow::Result<()> {
+    let room = rm.get_room_mut(room_type, room_id)?;
+    let mut chose_cters: Vec<u32> = room
+        .members
+        .values()
+        .map(|member| member.chose_cter.cter_temp_id)
+        .collect();
+    let mut empty_index = vec![];
+    for (index, &user_id) in room.member_index.iter().enumerate() {
+        if user_id == 0 {
+            empty_index.push(index);
+        }
+    }
+    let temps = crate::TEMPLATES.load();
+    let robot_temp_mgr = temps.robot_temp_mgr();
+    let mut rand = rand::thread_rng();
+    for index in empty_index {
+        let robot_temps: Vec<_> = robot_temp_mgr
+            .temps
+            .values()
+            .filter(|temp| !chose_cters.contains(&temp.cter_id))
+            .collect();
+        if robot_temps.is_empty() {
+            anyhow::bail!("there is no robot_temp could choice!room_id:{}", room_id)
+        }
+        let robot_temp = robot_temps[rand.gen_range(0..robot_temps.len())];
+        let robot_id = add_robot(rm, room_type, room_id, index, robot_temp.get_id(), false)?;
+        chose_cters.push(robot_temp.cter_id);
+        let room = rm.get_room_mut(room_type, room_id)?;
+        room.robots.insert(robot_id);
+    }
+    Ok(())
+}
+
 ///换队伍
 pub fn change_team(rm: &mut RoomMgr, packet: Packet) {
     let user_id = &packet.get_user_id();
@@ -1026,7 +1105,8 @@ pub fn room_setting(rm: &mut RoomMgr, packet: Packet) {
             RoomSettingType::SeasonIsOpen => {
                 let season_is_open = proto_value == 1;
                 if season_is_open {
-                    let season_temp_mgr = crate::TEMPLATES.season_temp_mgr();
+                    let temps = crate::TEMPLATES.load();
+                    let season_temp_mgr = temps.season_temp_mgr();
                     let res = season_temp_mgr.get_temp(&(1001 as u32));
                     if let Err(err) = res {
                         warn!("{:?}", err);
@@ -1040,7 +1120,8 @@ pub fn room_setting(rm: &mut RoomMgr, packet: Packet) {
                 if id == 0 {
                     room.setting.turn_limit_time = 0;
                 } else {
-                    let limit_time_mgr = crate::TEMPLATES.battle_limit_time_temp_mgr();
+                    let temps = crate::TEMPLATES.load();
+                    let limit_time_mgr = temps.battle_limit_time_temp_mgr();
                     let res = limit_time_mgr.get_temp(&id);
                     match res {
                         Ok(temp) => {
@@ -1055,13 +1136,14 @@ pub fn room_setting(rm: &mut RoomMgr, packet: Packet) {
             }
             RoomSettingType::AILevel => {
                 let id = proto_value as u8;
-                let mut res = crate::TEMPLATES
+                let temps = crate::TEMPLATES.load();
+                let mut res = temps
                     .constant_temp_mgr()
                     .temps
                     .get("ai_level_easy")
                     .unwrap();
                 let re_id1 = u8::from_str(res.value.as_str()).unwrap();
-                res = crate::TEMPLATES
+                res = temps
                     .constant_temp_mgr()
                     .temps
                     .get("ai_level_hard")
@@ -1092,6 +1174,9 @@ pub fn room_setting(rm: &mut RoomMgr, packet: Packet) {
 ///加入房间
 pub fn join_room(rm: &mut RoomMgr, packet: Packet) {
     let user_id = packet.get_user_id();
+    if refuse_if_draining(rm, user_id) {
+        return;
+    }
     let mut grj = G_R_JOIN_ROOM::new();
     let res = grj.merge_from_bytes(packet.get_data());
     if let Err(e) = res {
@@ -1344,10 +1429,8 @@ pub fn choice_skills(rm: &mut RoomMgr, packet: Packet) {
         return;
     }
 
-    let cter_temp = crate::TEMPLATES
-        .character_temp_mgr()
-        .get_temp_ref(&cter_id)
-        .unwrap();
+    let temps = crate::TEMPLATES.load();
+    let cter_temp = temps.character_temp_mgr().get_temp_ref(&cter_id).unwrap();
     let usable_skill_count = cter_temp.usable_skill_count;
     //校验技能数量
     if skills.len() > usable_skill_count as usize {
@@ -1522,7 +1605,8 @@ pub fn summary(rm: &mut RoomMgr, packet: Packet) {
                         member.chose_cter = Character::default();
                         member.state = MemberState::NotReady;
                     });
-                let world_boss_temps = &crate::TEMPLATES.worldboss_temp_mgr().temps;
+                let temps = crate::TEMPLATES.load();
+                let world_boss_temps = &temps.worldboss_temp_mgr().temps;
                 let ai_v: Vec<Member> = room
                     .members
                     .values()
@@ -1577,7 +1661,8 @@ pub fn emoji(rm: &mut RoomMgr, packet: Packet) {
         return;
     }
     let emoji_id = ce.emoji_id;
-    let res: Option<&EmojiTemp> = crate::TEMPLATES.emoji_temp_mgr().temps.get(&emoji_id);
+    let temps = crate::TEMPLATES.load();
+    let res: Option<&EmojiTemp> = temps.emoji_temp_mgr().temps.get(&emoji_id);
     if res.is_none() {
         warn!("there is no temp for emoji_id:{}", emoji_id);
         return;
@@ -1,12 +1,15 @@
+mod config;
 mod handlers;
 mod mgr;
 mod net;
 mod room;
 mod task_timer;
 
+use crate::config::RoomConfig;
 use crate::mgr::room_mgr::RoomMgr;
+use crate::net::http::MatchStatsHttpHandler;
 use crate::net::tcp_server;
-use crate::task_timer::init_timer;
+use crate::task_timer::{init_match_timer, init_timer};
 use async_std::sync::{Arc, Mutex};
 use lazy_static::lazy_static;
 use log::{error, info, warn};
@@ -16,7 +19,8 @@ use std::env;
 use std::sync::atomic::AtomicU32;
 use tools::conf::Conf;
 use tools::redis_pool::RedisPoolTool;
-use tools::templates::template::{init_temps_mgr, TemplatesMgr};
+use tools::registry::{Registry, ServiceInfo, ROLE_ROOM};
+use tools::templates::template::{init_temps_mgr, Templates};
 
 //初始化全局线程池
 lazy_static! {
@@ -29,8 +33,17 @@ lazy_static! {
         let conf = Conf::init(res.as_str());
         conf
     };
+
+    ///校验过的配置
+    static ref CONFIG: RoomConfig = {
+        match CONF_MAP.parse() {
+            Ok(config) => config,
+            Err(e) => panic!("{:?}", e),
+        }
+    };
+
     ///静态配置文件
-    static ref TEMPLATES: TemplatesMgr = {
+    static ref TEMPLATES: Templates = {
         init_templates_mgr()
     };
 
@@ -47,11 +60,16 @@ lazy_static! {
     };
 
     ///reids客户端
-    static ref REDIS_POOL:Arc<std::sync::Mutex<RedisPoolTool>>={
-        let add: &str = CONF_MAP.get_str("redis_add");
-        let pass: &str = CONF_MAP.get_str("redis_pass");
-        let redis = RedisPoolTool::init(add,pass);
-        let redis:Arc<std::sync::Mutex<RedisPoolTool>> = Arc::new(std::sync::Mutex::new(redis));
+    static ref REDIS_POOL: RedisPoolTool = {
+        let mut redis = match RedisPoolTool::init(&CONFIG.base.redis_add, &CONFIG.base.redis_pass) {
+            Ok(redis) => redis,
+            Err(e) => {
+                error!("初始化redis失败!{:?}", e);
+                std::process::exit(1);
+            }
+        };
+        //按游戏和区服隔离redis数据
+        redis.set_namespace(CONFIG.game.namespace().as_str(), &[]);
         redis
     };
 }
@@ -109,7 +127,7 @@ impl WorldBoss {
 
 pub static mut ROOM_ID: Vec<u32> = Vec::new();
 
-fn init_templates_mgr() -> TemplatesMgr {
+fn init_templates_mgr() -> Templates {
     let path = env::current_dir().unwrap();
     let str = path.as_os_str().to_str().unwrap();
     let res = str.to_string() + "/template";
@@ -137,12 +155,24 @@ fn main() {
     //初始化日志模块
     init_log();
 
+    //打印生效的配置
+    info!("{}", CONF_MAP.dump(&*CONFIG));
+
+    //初始化数据包压缩
+    tools::compress::init(
+        CONFIG.base.compress_threshold,
+        CONFIG.base.compress_type.as_str(),
+    );
+
     //初始化配置
     init_temps();
 
     //初始化定时器任务
     init_timer(room_mgr.clone());
 
+    //初始化匹配定时器
+    init_match_timer(room_mgr.clone());
+
     //初始化赛季
     init_season();
 
@@ -152,21 +182,68 @@ fn main() {
     //初始化房间id
     init_room_id();
 
+    //注册到注册中心
+    init_registry(room_mgr.clone());
+
+    //初始化退出信号处理
+    init_shutdown(room_mgr.clone());
+
+    //初始化http服务
+    init_http_server(room_mgr.clone());
+
     //初始化tcp服务
     init_tcp_server(room_mgr.clone());
 }
 
+///初始化http服务端，查询匹配队列统计
+fn init_http_server(rm: Lock) {
+    let http_port = CONFIG.http_port;
+    if http_port == 0 {
+        return;
+    }
+    tools::http::Builder::new()
+        .route(Box::new(MatchStatsHttpHandler::new(rm)))
+        .bind(http_port);
+}
+
+///注册到注册中心，定时心跳上报房间内玩家数量
+fn init_registry(rm: Lock) {
+    let registry = Registry::new(&CONFIG.registry);
+    let address = CONFIG.registry.advertise_address(&CONFIG.tcp_port);
+    let capacity = CONFIG.capacity;
+    let heartbeat = registry.clone();
+    let m = move || {
+        let load = async_std::task::block_on(rm.lock()).player_room.len() as u32;
+        let info = ServiceInfo::new(ROLE_ROOM, address.clone(), capacity, load);
+        heartbeat.register(&REDIS_POOL, &info);
+    };
+    registry.spawn_heartbeat(m);
+}
+
+///收到退出信号后不再创建新房间，房间数据都在内存里，不需要保存
+fn init_shutdown(rm: Arc<Mutex<RoomMgr>>) {
+    let m = move || {
+        let lock = async_std::task::block_on(rm.lock());
+        info!("房间服停服排空!房间内玩家数量:{}", lock.player_room.len());
+    };
+    tools::shutdown::on_signal(m);
+}
+
 fn init_log() {
-    let info_log = CONF_MAP.get_str("info_log_path");
-    let error_log = CONF_MAP.get_str("error_log_path");
+    let info_log = &CONFIG.base.info_log_path;
+    let error_log = &CONFIG.base.error_log_path;
     tools::my_log::init_log(info_log, error_log);
 }
 
 ///初始化赛季信息
 fn init_season() {
-    let mut lock = REDIS_POOL.lock().unwrap();
+    let game_id = CONFIG.game.game_id.to_string();
     unsafe {
-        let res: Option<String> = lock.hget(REDIS_INDEX_GAME_SEASON, REDIS_KEY_GAME_SEASON, "101");
+        let res: Option<String> = REDIS_POOL.hget(
+            REDIS_INDEX_GAME_SEASON,
+            REDIS_KEY_GAME_SEASON,
+            game_id.as_str(),
+        );
         let str = res.unwrap();
         let value = serde_json::from_str(str.as_str());
         if let Err(e) = value {
@@ -212,13 +289,21 @@ fn init_season() {
 
 ///初始化worldboss信息
 fn init_world_boss() {
-    let mut lock = REDIS_POOL.lock().unwrap();
-    let res: Option<String> = lock.hget(REDIS_INDEX_GAME_SEASON, REDIS_KEY_WORLD_BOSS, "101");
+    let game_id = CONFIG.game.game_id.to_string();
+    let res: Option<String> = REDIS_POOL.hget(
+        REDIS_INDEX_GAME_SEASON,
+        REDIS_KEY_WORLD_BOSS,
+        game_id.as_str(),
+    );
     if let None = res {
-        error!("redis do not has season data about game:{}", 101);
+        error!("redis do not has season data about game:{}", game_id);
         return;
     }
-    let res: Option<String> = lock.hget(REDIS_INDEX_GAME_SEASON, REDIS_KEY_WORLD_BOSS, "101");
+    let res: Option<String> = REDIS_POOL.hget(
+        REDIS_INDEX_GAME_SEASON,
+        REDIS_KEY_WORLD_BOSS,
+        game_id.as_str(),
+    );
     let str = res.unwrap();
     let value = serde_json::from_str(str.as_str());
     if let Err(e) = value {
@@ -272,6 +357,6 @@ fn init_temps() {
 
 ///初始化tcp服务端
 fn init_tcp_server(rm: Lock) {
-    let tcp_port: &str = CONF_MAP.get_str("tcp_port");
+    let tcp_port: &str = &CONFIG.tcp_port;
     tcp_server::new(tcp_port, rm);
 }
@@ -1,7 +1,7 @@
 use crate::handlers::room_handler::{
     battle_kick_member, cancel_search_room, change_team, choice_ai, choice_skills,
-    choose_character, confirm_into_room, create_room, emoji, join_room, kick_member, leave_room,
-    off_line, prepare_cancel, reload_temps, room_setting, search_room, start, summary,
+    choose_character, confirm_into_room, create_room, drain, emoji, join_room, kick_member,
+    leave_room, off_line, prepare_cancel, reload_temps, room_setting, search_room, start, summary,
     update_season, update_worldboss,
 };
 use crate::room::custom_room::CustomRoom;
@@ -151,6 +151,10 @@ impl RoomMgr {
             if !need_rm_room && need_rm_cache {
                 self.remove_room_cache(room_type, room_id);
             }
+            //刷新匹配房的段位和积分
+            if !need_rm_room && room_type == RoomType::OneVOneVOneVOneMatch {
+                self.match_room.refresh_room_cache(&room_id);
+            }
             if !need_rm_room && need_cache_sort {
                 //重新排序
                 self.sort_for_match_room(room_type);
@@ -287,6 +291,9 @@ impl RoomMgr {
         //热更静态配置
         self.cmd_map
             .insert(ServerCommonCode::ReloadTemps.into_u32(), reload_temps);
+        //停服排空
+        self.cmd_map
+            .insert(ServerCommonCode::Drain.into_u32(), drain);
         //更新赛季信息
         self.cmd_map
             .insert(RoomCode::UpdateSeasonPush.into_u32(), update_season);
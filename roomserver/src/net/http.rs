@@ -0,0 +1,43 @@
+use crate::Lock;
+use async_std::task::block_on;
+use chrono::Local;
+use serde_json::json;
+use std::collections::HashMap;
+use tools::http::HttpServerHandler;
+
+///查询匹配队列统计
+pub struct MatchStatsHttpHandler {
+    rm: Lock,
+}
+
+impl MatchStatsHttpHandler {
+    pub fn new(rm: Lock) -> Self {
+        MatchStatsHttpHandler { rm }
+    }
+}
+
+impl HttpServerHandler for MatchStatsHttpHandler {
+    fn get_path(&self) -> &str {
+        "/match_stats"
+    }
+
+    fn do_get(
+        &mut self,
+        _uri: String,
+        _uri_params: HashMap<String, String>,
+    ) -> anyhow::Result<String> {
+        let now = Local::now().timestamp_millis();
+        let lock = block_on(self.rm.lock());
+        let stats = lock.match_room.get_match_stats(now);
+        let value = json!({
+            "status":"OK",
+            "queue_room_num":stats.queue_room_num,
+            "queue_player_num":stats.queue_player_num,
+            "max_wait_time":stats.max_wait_time,
+            "avg_wait_time":stats.avg_wait_time,
+            "matched_num":stats.matched_num,
+            "robot_fill_num":stats.robot_fill_num,
+        });
+        Ok(value.to_string())
+    }
+}
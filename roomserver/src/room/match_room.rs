@@ -3,9 +3,11 @@ use std::{
     collections::{hash_map::RandomState, HashMap},
 };
 
+use chrono::Local;
 use crossbeam::channel::Sender;
-use log::{error, info};
+use log::info;
 use rayon::slice::ParallelSliceMut;
+use serde::{Deserialize, Serialize};
 use tools::net_message_io::NetHandler;
 
 use crate::{
@@ -19,11 +21,72 @@ use super::{
     room_model::{RoomCache, RoomModel, RoomSetting, RoomType},
 };
 
+///匹配参数,配置文件里的key带match_前缀
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MatchConf {
+    #[serde(rename = "match_score_spread")]
+    pub score_spread: i32, //初始允许的积分差
+    #[serde(rename = "match_spread_widen")]
+    pub spread_widen: i32, //每等待一秒放宽的积分差
+    #[serde(rename = "match_max_score_spread")]
+    pub max_score_spread: i32, //积分差上限
+    #[serde(rename = "match_league_widen_time")]
+    pub league_widen_time: i64, //每等待多少秒允许多跨一个段位，为0则不限制段位
+    #[serde(rename = "match_robot_timeout")]
+    pub robot_timeout: i64, //等待多少秒后用机器人补满，为0则不补
+}
+
+impl Default for MatchConf {
+    fn default() -> Self {
+        MatchConf {
+            score_spread: 100,
+            spread_widen: 20,
+            max_score_spread: 1000,
+            league_widen_time: 10,
+            robot_timeout: 30,
+        }
+    }
+}
+
+impl MatchConf {
+    pub fn from_conf() -> Self {
+        crate::CONFIG.matching
+    }
+
+    ///等待wait_time秒后允许的积分差
+    pub fn get_score_spread(&self, wait_time: i64) -> i32 {
+        let spread = self.score_spread as i64 + wait_time * self.spread_widen as i64;
+        spread.min(self.max_score_spread as i64) as i32
+    }
+
+    ///等待wait_time秒后允许的段位差，None表示不限制
+    pub fn get_league_spread(&self, wait_time: i64) -> Option<i32> {
+        if self.league_widen_time <= 0 {
+            return None;
+        }
+        Some((wait_time / self.league_widen_time) as i32)
+    }
+}
+
+///匹配队列统计
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MatchStats {
+    pub queue_room_num: usize,   //排队中的房间数量
+    pub queue_player_num: usize, //排队中的真人数量
+    pub max_wait_time: i64,      //最长等待时间(秒)
+    pub avg_wait_time: i64,      //平均等待时间(秒)
+    pub matched_num: u64,        //累计匹配成功的房间数量
+    pub robot_fill_num: u64,     //累计用机器人补满的房间数量
+}
+
 ///匹配房结构体
 #[derive(Clone, Default)]
 pub struct MatchRoom {
     pub rooms: HashMap<u32, Room>,  //key:房间id    value:房间结构体
     pub room_cache: Vec<RoomCache>, //key:房间id    value:房间人数
+    matched_num: u64,               //累计匹配成功的房间数量
+    robot_fill_num: u64,            //累计用机器人补满的房间数量
 }
 
 impl RoomModel for MatchRoom {
@@ -62,8 +125,8 @@ impl RoomModel for MatchRoom {
         self.rooms.insert(room_id, room);
         let mut rc = RoomCache::default();
         rc.room_id = room_id;
-        rc.count = 1;
         self.room_cache.push(rc);
+        self.refresh_room_cache(&room_id);
         self.room_cache.par_sort_by(|a, b| b.count.cmp(&a.count));
         Ok(room_id)
     }
@@ -101,15 +164,15 @@ impl RoomModel for MatchRoom {
         }
 
         let room_cache = self.get_room_cache_mut(&room_id);
-        if let Some(room_cache) = room_cache {
-            room_cache.count -= 1;
+        if room_cache.is_some() {
+            self.refresh_room_cache(&room_id);
             //重新排序
             self.room_cache.par_sort_by(|a, b| b.count.cmp(&a.count));
         } else if room_cache.is_none() && need_add_cache {
             let mut rc = RoomCache::default();
             rc.room_id = room_id;
-            rc.count = now_count as u8;
             self.room_cache.push(rc);
+            self.refresh_room_cache(&room_id);
             //重新排序
             self.room_cache.par_sort_by(|a, b| b.count.cmp(&a.count));
             info!(
@@ -170,6 +233,91 @@ impl MatchRoom {
         self.room_cache.par_sort_by(|a, b| b.count.cmp(&a.count));
     }
 
+    ///根据房间内成员刷新匹配缓存的人数,段位,积分和开始匹配时间
+    pub fn refresh_room_cache(&mut self, room_id: &u32) {
+        let room = self.rooms.get(room_id);
+        if room.is_none() {
+            return;
+        }
+        let room = room.unwrap();
+        let mut real_count = 0;
+        let mut league_sum = 0;
+        let mut score_sum = 0;
+        let mut start_time = i64::MAX;
+        for member in room.members.values() {
+            if member.robot_temp_id > 0 {
+                continue;
+            }
+            real_count += 1;
+            league_sum += member.league.id as i64;
+            score_sum += member.league.league_score as i64;
+            start_time = start_time.min(member.join_time as i64);
+        }
+        let count = room.get_member_count() as u8;
+        let room_cache = self.room_cache.iter_mut().find(|x| x.room_id == *room_id);
+        if room_cache.is_none() {
+            return;
+        }
+        let room_cache = room_cache.unwrap();
+        room_cache.count = count;
+        room_cache.real_count = real_count as u8;
+        if real_count > 0 {
+            room_cache.league_id = (league_sum / real_count) as i8;
+            room_cache.score = (score_sum / real_count) as i32;
+            room_cache.start_time = start_time;
+        }
+    }
+
+    ///找一个段位和积分都在允许范围内的房间，人多的优先，人数一样积分接近的优先
+    ///允许的范围随房间等待时间放宽
+    fn find_match_room(&self, member: &Member, conf: &MatchConf, now: i64) -> Option<u32> {
+        let mut res: Option<(u8, i32, u32)> = None;
+        for room_cache in self.room_cache.iter() {
+            if room_cache.count as usize >= MEMBER_MAX {
+                continue;
+            }
+            let wait_time = (now - room_cache.start_time).max(0) / 1000;
+            let score_diff = (room_cache.score - member.league.league_score).abs();
+            if score_diff > conf.get_score_spread(wait_time) {
+                continue;
+            }
+            if let Some(league_spread) = conf.get_league_spread(wait_time) {
+                let league_diff = (room_cache.league_id as i32 - member.league.id as i32).abs();
+                if league_diff > league_spread {
+                    continue;
+                }
+            }
+            match res {
+                Some((count, diff, _))
+                    if count > room_cache.count
+                        || (count == room_cache.count && diff <= score_diff) => {}
+                _ => res = Some((room_cache.count, score_diff, room_cache.room_id)),
+            }
+        }
+        res.map(|(_, _, room_id)| room_id)
+    }
+
+    ///人满了就从匹配队列移除，推送匹配成功并创建确认进入房间的任务
+    fn check_match_success(&mut self, room_id: u32, task_sender: Sender<Task>) -> bool {
+        let room = self.rooms.get_mut(&room_id);
+        if room.is_none() {
+            return false;
+        }
+        let room = room.unwrap();
+        if room.get_member_count() < MEMBER_MAX {
+            return false;
+        }
+        //推送匹配成功通知
+        room.push_match_success();
+        //人满了就从队列里面弹出去
+        self.remove_room_cache(&room_id);
+        self.matched_num += 1;
+        info!("匹配房人满,将房间从匹配队列移除！room_id:{}", room_id);
+        //创建检测进入房间延迟任务
+        build_confirm_into_room_task(RoomType::OneVOneVOneVOneMatch, room_id, task_sender);
+        true
+    }
+
     ///快速加入
     pub fn quickly_start(
         &mut self,
@@ -177,58 +325,189 @@ impl MatchRoom {
         sender: NetHandler,
         task_sender: Sender<Task>,
     ) -> anyhow::Result<u32> {
-        let room_id: u32;
-        //如果房间缓存里没有，则创建新房间
-        if self.room_cache.is_empty() {
-            //创建房间
-            room_id = self.create_room(member, None, sender, task_sender)?;
-        } else {
-            //如果有，则往房间里塞
-            room_id = self.get_room_cache_last_room_id()?;
-            //将成员加进房间
-            let room_mut = self.get_mut_room_by_room_id(&room_id)?;
-            if room_mut.get_member_count() >= MEMBER_MAX {
-                anyhow::bail!("room is None,room_id:{}", room_id)
+        let conf = MatchConf::from_conf();
+        let now = Local::now().timestamp_millis();
+        let res = self.find_match_room(&member, &conf, now);
+        //没有合适的房间，则创建新房间
+        if res.is_none() {
+            let room_id = self.create_room(member, None, sender, task_sender)?;
+            return Ok(room_id);
+        }
+        let room_id = res.unwrap();
+        //将成员加进房间
+        let room_mut = self.get_mut_room_by_room_id(&room_id)?;
+        if room_mut.get_member_count() >= MEMBER_MAX {
+            anyhow::bail!("room is None,room_id:{}", room_id)
+        }
+        let user_id = member.user_id;
+        let score = member.league.league_score;
+        //将成员加入到房间中
+        room_mut.add_member(member, None, false)?;
+        let room_count = room_mut.get_member_count();
+        //刷新房间队列缓存
+        self.refresh_room_cache(&room_id);
+        //如果人满里，则从缓存房间列表中弹出
+        self.check_match_success(room_id, task_sender);
+        //重新排序
+        self.room_cache.par_sort_by(|a, b| b.count.cmp(&a.count));
+        let match_room_count = self.room_cache.len();
+        info!(
+            "玩家匹配到房间！当前房间人数：{},match_user_id:{},score:{},room_id:{}",
+            room_count, user_id, score, room_id
+        );
+        info!("当前匹配房数量:{}!", match_room_count);
+        Ok(room_id)
+    }
+
+    ///获得等待超时需要用机器人补满的房间
+    pub fn get_robot_timeout_rooms(&self, conf: &MatchConf, now: i64) -> Vec<u32> {
+        let mut res = vec![];
+        if conf.robot_timeout <= 0 {
+            return res;
+        }
+        for room_cache in self.room_cache.iter() {
+            if room_cache.real_count == 0 {
+                continue;
             }
-            let user_id = member.user_id;
-            //将成员加入到房间中
-            room_mut.add_member(member, None, false)?;
-            //解决房间队列缓存
-            let room_cache = self.room_cache.get_mut(0).unwrap();
-            //cache人数加1
-            room_cache.count += 1;
-            let room_cache_count = room_cache.count as usize;
-            //如果人满里，则从缓存房间列表中弹出
-            if room_cache_count >= MEMBER_MAX {
-                //人满了就从队列里面弹出去
-                self.remove_room_cache(&room_id);
-                info!("匹配房人满,将房间从匹配队列移除！room_id:{}", room_id);
-                //推送匹配成功通知
-                let room_mut = self.rooms.get_mut(&room_id).unwrap();
-                room_mut.push_match_success();
-                //创建检测进入房间延迟任务
-                build_confirm_into_room_task(RoomType::OneVOneVOneVOneMatch, room_id, task_sender);
+            let wait_time = (now - room_cache.start_time).max(0) / 1000;
+            if wait_time < conf.robot_timeout {
+                continue;
             }
-            //重新排序
-            self.room_cache.par_sort_by(|a, b| b.count.cmp(&a.count));
-            let match_room_count = self.room_cache.len();
-            info!(
-                "玩家匹配到房间！当前房间人数：{},match_user_id:{},room_id:{}",
-                room_cache_count, user_id, room_id
-            );
-            info!("当前匹配房数量:{}!", match_room_count);
+            res.push(room_cache.room_id);
         }
-        Ok(room_id)
+        res
     }
 
-    fn get_room_cache_last_room_id(&self) -> anyhow::Result<u32> {
-        let room_cache = self.room_cache.get(0);
-        if room_cache.is_none() {
-            let str = "room_cache is empty!".to_owned();
-            error!("{:?}", str.as_str());
-            anyhow::bail!("{:?}", str)
+    ///机器人补满之后的处理
+    pub fn on_robot_filled(&mut self, room_id: u32, task_sender: Sender<Task>) {
+        self.refresh_room_cache(&room_id);
+        if self.check_match_success(room_id, task_sender) {
+            self.robot_fill_num += 1;
+            info!("匹配等待超时,用机器人补满房间!room_id:{}", room_id);
         }
-        let room_id = room_cache.unwrap().room_id;
-        Ok(room_id)
+        //重新排序
+        self.room_cache.par_sort_by(|a, b| b.count.cmp(&a.count));
+    }
+
+    ///获得匹配队列统计
+    pub fn get_match_stats(&self, now: i64) -> MatchStats {
+        let mut stats = MatchStats {
+            matched_num: self.matched_num,
+            robot_fill_num: self.robot_fill_num,
+            ..Default::default()
+        };
+        let mut wait_time_sum = 0;
+        for room_cache in self.room_cache.iter() {
+            if room_cache.real_count == 0 {
+                continue;
+            }
+            let wait_time = (now - room_cache.start_time).max(0) / 1000;
+            stats.queue_room_num += 1;
+            stats.queue_player_num += room_cache.real_count as usize;
+            stats.max_wait_time = stats.max_wait_time.max(wait_time);
+            wait_time_sum += wait_time;
+        }
+        if stats.queue_room_num > 0 {
+            stats.avg_wait_time = wait_time_sum / stats.queue_room_num as i64;
+        }
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_conf() -> MatchConf {
+        MatchConf {
+            score_spread: 100,
+            spread_widen: 20,
+            max_score_spread: 300,
+            league_widen_time: 10,
+            robot_timeout: 30,
+        }
+    }
+
+    fn test_member(league_id: i8, score: i32) -> Member {
+        let mut member = Member::default();
+        member.league.id = league_id;
+        member.league.league_score = score;
+        member
+    }
+
+    ///只放缓存，不创建真的房间，find_match_room只看缓存
+    fn push_cache(mr: &mut MatchRoom, room_id: u32, count: u8, league_id: i8, score: i32) {
+        mr.room_cache.push(RoomCache {
+            room_id,
+            count,
+            real_count: count,
+            league_id,
+            score,
+            start_time: 0,
+        });
+    }
+
+    #[test]
+    fn spread_widen_with_wait_time() {
+        let conf = test_conf();
+        assert_eq!(conf.get_score_spread(0), 100);
+        assert_eq!(conf.get_score_spread(5), 200);
+        //不超过上限
+        assert_eq!(conf.get_score_spread(100), 300);
+
+        assert_eq!(conf.get_league_spread(0), Some(0));
+        assert_eq!(conf.get_league_spread(9), Some(0));
+        assert_eq!(conf.get_league_spread(25), Some(2));
+        let mut conf = conf;
+        conf.league_widen_time = 0;
+        assert_eq!(conf.get_league_spread(100), None);
+    }
+
+    #[test]
+    fn reject_room_out_of_league_spread() {
+        let conf = test_conf();
+        let mut mr = MatchRoom::default();
+        push_cache(&mut mr, 1, 1, 3, 1000);
+        let member = test_member(1, 1000);
+        //刚开始匹配，段位差2不允许
+        assert_eq!(mr.find_match_room(&member, &conf, 0), None);
+        //等了10秒只多允许一个段位
+        assert_eq!(mr.find_match_room(&member, &conf, 10_000), None);
+        //等了20秒可以
+        assert_eq!(mr.find_match_room(&member, &conf, 20_000), Some(1));
+        //积分差超过上限的等多久都不行
+        let member = test_member(3, 1400);
+        assert_eq!(mr.find_match_room(&member, &conf, 1_000_000), None);
+    }
+
+    #[test]
+    fn prefer_fuller_room_then_closer_score() {
+        let conf = test_conf();
+        let mut mr = MatchRoom::default();
+        push_cache(&mut mr, 1, 1, 1, 1000);
+        push_cache(&mut mr, 2, 2, 1, 1080);
+        push_cache(&mut mr, 3, 2, 1, 1030);
+        push_cache(&mut mr, 4, MEMBER_MAX as u8, 1, 1000);
+        let member = test_member(1, 1000);
+        //人满的跳过，人多的优先，人数一样积分接近的优先
+        assert_eq!(mr.find_match_room(&member, &conf, 0), Some(3));
+        //人数和积分差都一样的保留先找到的
+        push_cache(&mut mr, 5, 2, 1, 970);
+        assert_eq!(mr.find_match_room(&member, &conf, 0), Some(3));
+    }
+
+    #[test]
+    fn robot_fill_after_timeout() {
+        let conf = test_conf();
+        let mut mr = MatchRoom::default();
+        push_cache(&mut mr, 1, 1, 1, 1000);
+        //只有机器人的房间不补
+        push_cache(&mut mr, 2, 1, 1, 1000);
+        mr.room_cache[1].real_count = 0;
+        assert!(mr.get_robot_timeout_rooms(&conf, 29_999).is_empty());
+        assert_eq!(mr.get_robot_timeout_rooms(&conf, 30_000), vec![1]);
+        let mut conf = conf;
+        conf.robot_timeout = 0;
+        assert!(mr.get_robot_timeout_rooms(&conf, 1_000_000).is_empty());
     }
 }
@@ -64,17 +64,16 @@ impl Member {
         if self.robot_temp_id == 0 {
             return false;
         }
-        crate::TEMPLATES
+        let temps = crate::TEMPLATES.load();
+        temps
             .worldboss_temp_mgr()
             .temps
             .contains_key(&self.robot_temp_id)
     }
 
     pub fn new_for_robot(robot_temp_id: u32, team_id: u8, index: Option<u32>) -> Member {
-        let robot_temp = crate::TEMPLATES
-            .robot_temp_mgr()
-            .get_temp_ref(&robot_temp_id)
-            .unwrap();
+        let temps = crate::TEMPLATES.load();
+        let robot_temp = temps.robot_temp_mgr().get_temp_ref(&robot_temp_id).unwrap();
         let cter_id = robot_temp.cter_id;
         let mut member = Member::default();
 
@@ -112,7 +111,8 @@ impl Member {
         if id == 0 {
             return None;
         }
-        let punish_temp = crate::TEMPLATES.punish_temp_mgr().get_temp(&id);
+        let temps = crate::TEMPLATES.load();
+        let punish_temp = temps.punish_temp_mgr().get_temp(&id);
         if let Err(e) = punish_temp {
             warn!("{:?}", e);
             return None;
@@ -193,7 +193,8 @@ pub struct PunishMatch {
 impl PunishMatch {
     pub fn add_punish(&mut self) {
         self.start_time = chrono::Local::now().timestamp_millis();
-        let max_id = crate::TEMPLATES.punish_temp_mgr().max_id as u8;
+        let temps = crate::TEMPLATES.load();
+        let max_id = temps.punish_temp_mgr().max_id as u8;
         self.today_id += 1;
         if self.today_id >= max_id {
             self.today_id = max_id;
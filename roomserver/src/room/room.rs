@@ -484,7 +484,8 @@ impl Room {
                     return false;
                 }
 
-                let cter_temp = crate::TEMPLATES
+                let temps = crate::TEMPLATES.load();
+                let cter_temp = temps
                     .character_temp_mgr()
                     .temps
                     .get(&member.chose_cter.cter_temp_id)
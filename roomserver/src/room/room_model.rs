@@ -64,7 +64,8 @@ pub struct RoomSetting {
 
 impl Default for RoomSetting {
     fn default() -> Self {
-        let temp = TEMPLATES
+        let temps = TEMPLATES.load();
+        let temp = temps
             .constant_temp_mgr()
             .temps
             .get("battle_turn_limit_time");
@@ -126,6 +127,10 @@ impl From<&RoomSetting> for RoomSettingPt {
 pub struct RoomCache {
     pub room_id: u32,
     pub count: u8,
+    pub real_count: u8,  //真人数量
+    pub league_id: i8,   //真人平均段位
+    pub score: i32,      //真人平均积分
+    pub start_time: i64, //最早进入的真人开始匹配的时间(毫秒)
 }
 
 pub trait RoomModel {
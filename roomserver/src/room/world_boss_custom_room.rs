@@ -43,7 +43,8 @@ impl RoomModel for WorldBossCustomRoom {
         //加入worldboss
         unsafe {
             let world_boss_id = crate::WORLD_BOSS.world_boss_id as u32;
-            let worldboss_temp = crate::TEMPLATES
+            let temps = crate::TEMPLATES.load();
+            let worldboss_temp = temps
                 .worldboss_temp_mgr()
                 .temps
                 .get(&world_boss_id)
@@ -1,3 +1,4 @@
+use crate::handlers::room_handler::match_timeout_fill_robot;
 use crate::room::match_room::MatchRoom;
 use crate::room::member::MemberState;
 use crate::room::room::{MemberLeaveNoticeType, RoomState, MEMBER_MAX};
@@ -74,6 +75,33 @@ pub fn init_timer(rm: Lock) {
     info!("初始化定时器任务执行器成功!");
 }
 
+///初始化匹配定时器，每秒检查一次匹配超时的房间，定时打印匹配队列统计
+pub fn init_match_timer(rm: Lock) {
+    let m = move || {
+        let stats_interval = crate::CONFIG.match_stats_interval;
+        let mut count = 0_u64;
+        loop {
+            std::thread::sleep(Duration::from_secs(1));
+            count += 1;
+            let mut lock = block_on(rm.lock());
+            match_timeout_fill_robot(&mut lock);
+            if stats_interval == 0 || !count.is_multiple_of(stats_interval) {
+                continue;
+            }
+            let now = Local::now().timestamp_millis();
+            let stats = lock.match_room.get_match_stats(now);
+            info!("匹配队列统计:{:?}", stats);
+        }
+    };
+    let timer_thread = std::thread::Builder::new().name("MATCH_TIMER_THREAD".to_owned());
+    let res = timer_thread.spawn(m);
+    if let Err(e) = res {
+        error!("{:?}", e);
+        std::process::abort();
+    }
+    info!("初始化匹配定时器成功!");
+}
+
 ///执行匹配房间任务
 fn match_room_confirm_into(rm: Lock, task: Task) {
     let json_value = task.data;
@@ -268,10 +296,8 @@ fn match_room_ready(rm: Lock, task: Task) {
 pub fn build_match_room_ready_task(room_id: u32, task_sender: Sender<Task>) {
     //创建延迟任务，并发送给定时器接收方执行
     let mut task = Task::default();
-    let time_limit = crate::TEMPLATES
-        .constant_temp_mgr()
-        .temps
-        .get("kick_not_prepare_time");
+    let temps = crate::TEMPLATES.load();
+    let time_limit = temps.constant_temp_mgr().temps.get("kick_not_prepare_time");
     if let Some(time) = time_limit {
         let time = u64::from_str(time.value.as_str());
         match time {
@@ -298,7 +324,8 @@ pub fn build_match_room_ready_task(room_id: u32, task_sender: Sender<Task>) {
 pub fn build_confirm_into_room_task(room_type: RoomType, room_id: u32, task_sender: Sender<Task>) {
     //创建延迟任务，并发送给定时器接收方执行
     let mut task = Task::default();
-    let time_limit = crate::TEMPLATES
+    let temps = crate::TEMPLATES.load();
+    let time_limit = temps
         .constant_temp_mgr()
         .temps
         .get("match_room_be_sure_limit_time");
@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+use tools::conf::{check_address, BaseConfig, Validate};
+use tools::game_key::GameKey;
+use tools::registry::RegistryConfig;
+
+///游戏服配置，启动时从config/config.conf读取并校验
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServerConfig {
+    #[serde(flatten)]
+    pub game: GameKey, //游戏和区服
+    #[serde(flatten)]
+    pub base: BaseConfig, //公共配置
+    #[serde(flatten)]
+    pub registry: RegistryConfig, //注册中心
+    pub db_type: String,           //存储后端:"mysql","sqlite"或者"memory"
+    pub mysql: String,             //mysql地址
+    pub sqlite_path: String,       //sqlite文件路径
+    pub tcp_port: String,          //tcp监听地址
+    pub http_port: u16,            //http监听端口
+    pub user_center_state: String, //用户中心地址
+    pub save_journal_path: String, //存档日志路径
+    pub capacity: u32,             //容量(玩家数),0为不限制
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            game: GameKey::default(),
+            base: BaseConfig::default(),
+            registry: RegistryConfig::default(),
+            db_type: "mysql".to_owned(),
+            mysql: String::new(),
+            sqlite_path: String::new(),
+            tcp_port: String::new(),
+            http_port: 0,
+            user_center_state: String::new(),
+            save_journal_path: String::new(),
+            capacity: 0,
+        }
+    }
+}
+
+impl Validate for ServerConfig {
+    fn validate(&self) -> anyhow::Result<()> {
+        self.base.validate()?;
+        self.registry.validate()?;
+        check_address("tcp_port", self.tcp_port.as_str())?;
+        match self.db_type.as_str() {
+            "mysql" if self.mysql.is_empty() => anyhow::bail!("db_type为mysql时mysql不能为空!"),
+            "sqlite" if self.sqlite_path.is_empty() => {
+                anyhow::bail!("db_type为sqlite时sqlite_path不能为空!")
+            }
+            "mysql" | "sqlite" | "memory" => Ok(()),
+            _ => anyhow::bail!("db_type只能是mysql,sqlite或者memory!{:?}", self.db_type),
+        }
+    }
+}
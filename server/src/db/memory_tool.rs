@@ -0,0 +1,88 @@
+use crate::db::{SaveRow, Storage};
+use crate::JsonValue;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+///一张表,(user_id,tem_id)->content
+type MemoryTable = BTreeMap<(u32, u32), String>;
+
+///内存存储，进程退出数据就没了，用于测试和本地开发
+///表名->(user_id,tem_id)->content，没有tem_id的表tem_id存0
+#[derive(Default)]
+pub struct MemoryPool {
+    tables: Mutex<HashMap<String, MemoryTable>>,
+}
+
+impl Storage for MemoryPool {
+    fn insert(
+        &self,
+        table_name: &str,
+        user_id: u32,
+        tem_id: Option<u32>,
+        content: &str,
+    ) -> anyhow::Result<()> {
+        let mut tables = self.tables.lock().unwrap();
+        let table = tables.entry(table_name.to_owned()).or_default();
+        let key = (user_id, tem_id.unwrap_or(0));
+        if table.contains_key(&key) {
+            anyhow::bail!(
+                "duplicate entry!table:{},user_id:{},tem_id:{:?}",
+                table_name,
+                user_id,
+                tem_id
+            )
+        }
+        table.insert(key, content.to_owned());
+        Ok(())
+    }
+
+    fn update(
+        &self,
+        table_name: &str,
+        user_id: u32,
+        tem_id: Option<u32>,
+        content: &str,
+    ) -> anyhow::Result<()> {
+        let mut tables = self.tables.lock().unwrap();
+        let res = tables
+            .get_mut(table_name)
+            .and_then(|table| table.get_mut(&(user_id, tem_id.unwrap_or(0))));
+        //和mysql一样，没有这行的话什么都不做
+        if let Some(data) = res {
+            *data = content.to_owned();
+        }
+        Ok(())
+    }
+
+    fn query(
+        &self,
+        table_name: &str,
+        user_id: u32,
+        tem_id: Option<u32>,
+    ) -> anyhow::Result<Vec<JsonValue>> {
+        let tables = self.tables.lock().unwrap();
+        let mut res = Vec::new();
+        let table = tables.get(table_name);
+        if table.is_none() {
+            return Ok(res);
+        }
+        let table = table.unwrap();
+        for ((_, row_tem_id), content) in table.range((user_id, 0)..=(user_id, u32::MAX)) {
+            if tem_id.is_some() && tem_id.unwrap() != *row_tem_id {
+                continue;
+            }
+            let data: JsonValue = serde_json::from_str(content.as_str())?;
+            res.push(data);
+        }
+        Ok(res)
+    }
+
+    fn save_batch(&self, rows: &[SaveRow]) -> anyhow::Result<()> {
+        let mut tables = self.tables.lock().unwrap();
+        for row in rows.iter() {
+            let table = tables.entry(row.table_name.clone()).or_default();
+            table.insert((row.user_id, row.tem_id.unwrap_or(0)), row.content.clone());
+        }
+        Ok(())
+    }
+}
@@ -1,2 +1,152 @@
 pub mod dbtool;
+pub mod memory_tool;
+pub mod sqlite_tool;
 pub mod table_contants;
+
+use crate::config::ServerConfig;
+use crate::db::dbtool::DbPool;
+use crate::db::memory_tool::MemoryPool;
+use crate::db::sqlite_tool::SqlitePool;
+use crate::JsonValue;
+use log::info;
+use serde::{Deserialize, Serialize};
+
+///批量保存的一行数据
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SaveRow {
+    pub table_name: String,  //表名
+    pub user_id: u32,        //玩家id
+    pub tem_id: Option<u32>, //配置id,没有则为None
+    pub content: String,     //json数据
+}
+
+///存储后端trait，实体的增改查都通过它，不关心具体是mysql还是别的
+///每张表的行由(user_id,tem_id,content)组成，content为json字符串
+pub trait Storage: Send + Sync {
+    ///插入一行
+    fn insert(
+        &self,
+        table_name: &str,
+        user_id: u32,
+        tem_id: Option<u32>,
+        content: &str,
+    ) -> anyhow::Result<()>;
+
+    ///更新一行
+    fn update(
+        &self,
+        table_name: &str,
+        user_id: u32,
+        tem_id: Option<u32>,
+        content: &str,
+    ) -> anyhow::Result<()>;
+
+    ///查询玩家在表中的数据，tem_id为None则返回该玩家所有行
+    fn query(
+        &self,
+        table_name: &str,
+        user_id: u32,
+        tem_id: Option<u32>,
+    ) -> anyhow::Result<Vec<JsonValue>>;
+
+    ///在一个事务里批量保存，不存在则插入，存在则更新
+    ///要么全部成功，要么全部失败
+    fn save_batch(&self, rows: &[SaveRow]) -> anyhow::Result<()>;
+}
+
+///根据配置创建存储后端
+///db_type:mysql(默认),sqlite,memory
+pub fn new_storage(config: &ServerConfig) -> anyhow::Result<Box<dyn Storage>> {
+    let db_type = &config.db_type;
+    let storage: Box<dyn Storage> = match db_type.as_str() {
+        "sqlite" => {
+            let path = &config.sqlite_path;
+            match SqlitePool::new(path.as_str()) {
+                Ok(pool) => Box::new(pool),
+                Err(e) => anyhow::bail!("open sqlite fail!path:{},{:?}", path, e),
+            }
+        }
+        "memory" => Box::new(MemoryPool::default()),
+        _ => Box::new(DbPool::new()),
+    };
+    info!("初始化存储完成!db_type:{}", db_type);
+    Ok(storage)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::table_contants::{CHARACTER, USER};
+
+    fn row(table_name: &str, user_id: u32, tem_id: Option<u32>, content: &str) -> SaveRow {
+        SaveRow {
+            table_name: table_name.to_owned(),
+            user_id,
+            tem_id,
+            content: content.to_owned(),
+        }
+    }
+
+    ///登录插入，同步更新，定时保存批量upsert，每个存储后端行为要一样
+    fn login_sync_save(storage: &dyn Storage) {
+        //第一次登录插入，重复插入报错
+        storage.insert(USER, 1, None, r#"{"user_id":1}"#).unwrap();
+        assert!(storage.insert(USER, 1, None, "{}").is_err());
+        //同步更新
+        storage
+            .update(USER, 1, None, r#"{"user_id":1,"grade":2}"#)
+            .unwrap();
+        let res = storage.query(USER, 1, None).unwrap();
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0]["grade"], 2);
+
+        //定时保存，已存在的行覆盖，不存在的插入
+        let rows = vec![
+            row(CHARACTER, 1, Some(1001), r#"{"character_id":1001}"#),
+            row(CHARACTER, 1, Some(1002), r#"{"character_id":1002}"#),
+            row(CHARACTER, 2, Some(1001), r#"{"character_id":1001}"#),
+            row(USER, 1, None, r#"{"user_id":1,"grade":3}"#),
+        ];
+        storage.save_batch(rows.as_slice()).unwrap();
+        assert_eq!(storage.query(CHARACTER, 1, None).unwrap().len(), 2);
+        assert_eq!(storage.query(CHARACTER, 1, Some(1002)).unwrap().len(), 1);
+        assert!(storage.query(CHARACTER, 3, None).unwrap().is_empty());
+        assert_eq!(storage.query(USER, 1, None).unwrap()[0]["grade"], 3);
+    }
+
+    #[test]
+    fn sqlite_login_sync_save() {
+        login_sync_save(&SqlitePool::new("").unwrap());
+    }
+
+    #[test]
+    fn memory_login_sync_save() {
+        login_sync_save(&MemoryPool::default());
+    }
+
+    #[test]
+    fn new_storage_save_and_load() {
+        let path = std::env::temp_dir().join(format!("storage_test_{}.db", std::process::id()));
+        let path = path.to_str().unwrap().to_owned();
+        let _ = std::fs::remove_file(path.as_str());
+        let rows = [row(USER, 1, None, r#"{"user_id":1,"grade":3}"#)];
+        for db_type in ["sqlite", "memory"].iter() {
+            let config = ServerConfig {
+                db_type: db_type.to_string(),
+                sqlite_path: path.clone(),
+                ..Default::default()
+            };
+            let storage = new_storage(&config).unwrap();
+            storage.save_batch(&rows).unwrap();
+            //sqlite重新打开文件再读
+            let storage = match *db_type {
+                "sqlite" => new_storage(&config).unwrap(),
+                _ => storage,
+            };
+            let res = storage.query(USER, 1, None).unwrap();
+            assert_eq!(res.len(), 1);
+            assert_eq!(res[0]["grade"], 3);
+        }
+        let _ = std::fs::remove_file(path.as_str());
+    }
+}
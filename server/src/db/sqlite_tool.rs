@@ -0,0 +1,156 @@
+use crate::db::table_contants::TABLES;
+use crate::db::{SaveRow, Storage};
+use crate::JsonValue;
+use log::info;
+use rusqlite::{params, Connection};
+use std::sync::Mutex;
+
+///sqlite存储，用于本地开发，不需要起mysql
+///所有表结构一样:(user_id,tem_id,content)，没有tem_id的表tem_id存0
+pub struct SqlitePool {
+    conn: Mutex<Connection>,
+}
+
+impl SqlitePool {
+    ///打开sqlite文件，path为空则使用内存数据库
+    pub fn new(path: &str) -> anyhow::Result<SqlitePool> {
+        let conn = if path.is_empty() {
+            Connection::open_in_memory()?
+        } else {
+            Connection::open(path)?
+        };
+        for table_name in TABLES.iter() {
+            let sql = format!(
+                "create table if not exists {}(user_id integer not null,tem_id integer not null default 0,content text not null,primary key(user_id,tem_id))",
+                table_name
+            );
+            conn.execute(sql.as_str(), params![])?;
+        }
+        info!("初始化sqlite完成!path:{}", path);
+        Ok(SqlitePool {
+            conn: Mutex::new(conn),
+        })
+    }
+}
+
+impl Storage for SqlitePool {
+    fn insert(
+        &self,
+        table_name: &str,
+        user_id: u32,
+        tem_id: Option<u32>,
+        content: &str,
+    ) -> anyhow::Result<()> {
+        let sql = format!(
+            "insert into {}(user_id,tem_id,content) values(?1,?2,?3)",
+            table_name
+        );
+        let conn = self.conn.lock().unwrap();
+        conn.execute(sql.as_str(), params![user_id, tem_id.unwrap_or(0), content])?;
+        Ok(())
+    }
+
+    fn update(
+        &self,
+        table_name: &str,
+        user_id: u32,
+        tem_id: Option<u32>,
+        content: &str,
+    ) -> anyhow::Result<()> {
+        let sql = format!(
+            "update {} set content=?1 where user_id=?2 and tem_id=?3",
+            table_name
+        );
+        let conn = self.conn.lock().unwrap();
+        conn.execute(sql.as_str(), params![content, user_id, tem_id.unwrap_or(0)])?;
+        Ok(())
+    }
+
+    fn query(
+        &self,
+        table_name: &str,
+        user_id: u32,
+        tem_id: Option<u32>,
+    ) -> anyhow::Result<Vec<JsonValue>> {
+        let conn = self.conn.lock().unwrap();
+        let mut res = Vec::new();
+        let mut rows_str = Vec::new();
+        match tem_id {
+            Some(tem_id) => {
+                let sql = format!(
+                    "select content from {} where user_id=?1 and tem_id=?2",
+                    table_name
+                );
+                let mut stmt = conn.prepare(sql.as_str())?;
+                let rows = stmt.query_map(params![user_id, tem_id], |row| row.get(0))?;
+                for row in rows {
+                    let str: String = row?;
+                    rows_str.push(str);
+                }
+            }
+            None => {
+                let sql = format!(
+                    "select content from {} where user_id=?1 order by tem_id",
+                    table_name
+                );
+                let mut stmt = conn.prepare(sql.as_str())?;
+                let rows = stmt.query_map(params![user_id], |row| row.get(0))?;
+                for row in rows {
+                    let str: String = row?;
+                    rows_str.push(str);
+                }
+            }
+        }
+        for str in rows_str {
+            let data: JsonValue = serde_json::from_str(str.as_str())?;
+            res.push(data);
+        }
+        Ok(res)
+    }
+
+    fn save_batch(&self, rows: &[SaveRow]) -> anyhow::Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        for row in rows.iter() {
+            let sql = format!(
+                "insert or replace into {}(user_id,tem_id,content) values(?1,?2,?3)",
+                row.table_name
+            );
+            tx.execute(
+                sql.as_str(),
+                params![row.user_id, row.tem_id.unwrap_or(0), row.content],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::table_contants::CHARACTER;
+
+    ///事务里有一行失败则全部回滚，通用的增改查在db::tests里和其他存储后端一起测
+    #[test]
+    fn save_batch_rollback() {
+        let pool = SqlitePool::new("").unwrap();
+        let mut rows = vec![SaveRow {
+            table_name: CHARACTER.to_owned(),
+            user_id: 1,
+            tem_id: Some(1001),
+            content: r#"{"character_id":1001}"#.to_owned(),
+        }];
+        pool.save_batch(rows.as_slice()).unwrap();
+        rows[0].content = r#"{"character_id":1001,"lv":2}"#.to_owned();
+        rows.push(SaveRow {
+            table_name: "not_exist".to_owned(),
+            user_id: 1,
+            tem_id: None,
+            content: "{}".to_owned(),
+        });
+        assert!(pool.save_batch(rows.as_slice()).is_err());
+        let res = pool.query(CHARACTER, 1, Some(1001)).unwrap();
+        assert!(res[0].get("lv").is_none());
+    }
+}
@@ -10,3 +10,6 @@ pub const GRADE_FRAME: &str = "t_u_grade_frame";
 
 //灵魂头像
 pub const SOUL: &str = "t_u_soul";
+
+//所有玩家数据表
+pub const TABLES: [&str; 4] = [USER, CHARACTER, GRADE_FRAME, SOUL];
@@ -84,23 +84,14 @@ impl Characters {
     }
 
     pub fn query(table_name: &str, user_id: u32) -> Option<Self> {
-        let mut v: Vec<Value> = Vec::new();
-        v.push(Value::UInt(user_id as u64));
-
-        let mut sql = String::new();
-        sql.push_str("select * from ");
-        sql.push_str(table_name);
-        sql.push_str(" where user_id=:user_id");
-
-        let q: Result<QueryResult, Error> = DB_POOL.exe_sql(sql.as_str(), Some(v));
-        if q.is_err() {
-            error!("{:?}", q.err().unwrap());
+        let q = DB_POOL.query(table_name, user_id, None);
+        if let Err(e) = q {
+            error!("{:?}", e);
             return None;
         }
         let mut map = HashMap::new();
         let q = q.unwrap();
-        for _qr in q {
-            let (_, _, data): (u32, u32, serde_json::Value) = mysql::from_row(_qr.unwrap());
+        for data in q {
             let c = Character::init(data);
             map.insert(c.character_id, c);
         }
@@ -233,7 +224,8 @@ impl Dao for Character {
 
 fn get_init_characters(user_id: u32) -> Result<Vec<Character>, String> {
     let mut v: Vec<Character> = Vec::new();
-    let cter_temp: &CharacterTempMgr = TEMPLATES.character_temp_mgr();
+    let temps = TEMPLATES.load();
+    let cter_temp: &CharacterTempMgr = temps.character_temp_mgr();
     if cter_temp.is_empty() {
         error!("there are no Character templates!");
         return Err("there are no Character templates!".to_string());
@@ -83,10 +83,8 @@ impl Dao for GradeFrame {
 impl GradeFrame {
     pub fn new(user_id: u32) -> Self {
         let mut gf = GradeFrame::default();
-        let default_grade_frame = crate::TEMPLATES
-            .constant_temp_mgr()
-            .temps
-            .get("default_grade_frame");
+        let temps = crate::TEMPLATES.load();
+        let default_grade_frame = temps.constant_temp_mgr().temps.get("default_grade_frame");
         match default_grade_frame {
             Some(default_grade_frame) => {
                 let id = u32::from_str(default_grade_frame.value.as_str());
@@ -108,23 +106,14 @@ impl GradeFrame {
     }
 
     pub fn query(table_name: &str, user_id: u32) -> Option<Self> {
-        let mut v: Vec<Value> = Vec::new();
-        v.push(Value::UInt(user_id as u64));
-
-        let mut sql = String::new();
-        sql.push_str("select * from ");
-        sql.push_str(table_name);
-        sql.push_str(" where user_id=:user_id");
-
-        let q: Result<QueryResult, mysql::error::Error> = DB_POOL.exe_sql(sql.as_str(), Some(v));
-        if q.is_err() {
-            error!("{:?}", q.err().unwrap());
+        let q = DB_POOL.query(table_name, user_id, None);
+        if let Err(e) = q {
+            error!("{:?}", e);
             return None;
         }
         let q = q.unwrap();
         let mut res = None;
-        for _qr in q {
-            let (_, data): (u32, serde_json::Value) = mysql::from_row(_qr.unwrap());
+        for data in q {
             let c = GradeFrame::init(data);
             res = Some(c);
             break;
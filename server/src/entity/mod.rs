@@ -9,35 +9,10 @@ use crate::mgr::game_mgr::GameMgr;
 use crate::{Lock, DB_POOL};
 use async_std::task::block_on;
 use log::{error, info, warn};
-use mysql::prelude::ToValue;
-use mysql::{Error, QueryResult, Value};
 use std::any::Any;
 
 ///关于结构体转换的trait
 pub trait Entity: Send {
-    ///将自身转换成mysql到value，用于进行mysql的数据库操作
-    fn to_insert_vec_value(&self) -> Vec<Value> {
-        let mut v: Vec<Value> = Vec::new();
-        v.push(self.get_user_id().to_value());
-        let tem_id = self.get_tem_id();
-        if tem_id.is_some() {
-            v.push(Value::from(tem_id.unwrap()));
-        }
-        v.push(Value::from(self.get_data().to_string()));
-        v
-    }
-    ///将自身转换成mysql到value，用于进行mysql的数据库操作
-    fn to_update_vec_value(&self) -> Vec<Value> {
-        let mut v: Vec<Value> = Vec::new();
-        v.push(Value::from(self.get_data().to_string()));
-        v.push(self.get_user_id().to_value());
-        let tem_id = self.get_tem_id();
-        if tem_id.is_some() {
-            v.push(Value::from(tem_id.unwrap()));
-        }
-        v
-    }
-
     ///设置玩家id
     fn set_user_id(&mut self, user_id: u32);
 
@@ -79,20 +54,15 @@ pub trait Dao: Entity {
 
     ///更新函数（trait默认函数，不必重写）
     fn update(&self) -> Result<u32, String> {
-        let v: Vec<Value> = self.to_update_vec_value();
-        let mut sql = String::new();
-        sql.push_str("update ");
-        sql.push_str(self.get_table_name());
-        sql.push_str(" set content=:data where user_id=:user_id ");
-        let tem_id = self.get_tem_id();
-        if tem_id.is_some() {
-            sql.push_str("and tem_id=:tem_id");
-        }
-        let qr = DB_POOL.exe_sql(sql.as_str(), Some(v));
-        if qr.is_err() {
-            let err = qr.err().unwrap();
-            error!("{:?}", err);
-            return Err(err.to_string());
+        let res = DB_POOL.update(
+            self.get_table_name(),
+            self.get_user_id(),
+            self.get_tem_id(),
+            self.get_data().as_str(),
+        );
+        if let Err(e) = res {
+            error!("{:?}", e);
+            return Err(e.to_string());
         }
         self.clear_version();
         Ok(1)
@@ -100,28 +70,15 @@ pub trait Dao: Entity {
 
     ///insert函数（trait默认函数，不必重写）
     fn insert(&self) -> Result<u32, String> {
-        let v: Vec<Value> = self.to_insert_vec_value();
-        let mut sql = String::new();
-        sql.push_str("insert into ");
-        sql.push_str(self.get_table_name());
-        let tem_id = self.get_tem_id();
-
-        match tem_id {
-            Some(_) => {
-                sql.push_str(" values(:user_id,:tem_id,:content)");
-            }
-            None => {
-                sql.push_str(" values(:user_id,:content)");
-            }
-        }
-
-        let qr: Result<QueryResult, Error> = DB_POOL.exe_sql(sql.as_str(), Some(v));
-
-        if qr.is_err() {
-            let str = String::from_utf8(qr.unwrap().info());
-            let s = str.unwrap();
-            println!("{:?}", s);
-            return Err(s);
+        let res = DB_POOL.insert(
+            self.get_table_name(),
+            self.get_user_id(),
+            self.get_tem_id(),
+            self.get_data().as_str(),
+        );
+        if let Err(e) = res {
+            error!("{:?}", e);
+            return Err(e.to_string());
         }
         Ok(1)
     }
@@ -136,5 +93,15 @@ pub trait EntityData: Dao + Any {
 ///提供给http保存玩家数据的函数
 pub fn save_player_http(gm: Lock) {
     let mut gm = block_on(gm.lock());
-    gm.save_user_http();
+    if let Err(e) = gm.save_user_sync() {
+        error!("保存玩家数据失败!{:?}", e);
+    }
+}
+
+///收到退出信号时保存所有玩家数据，全部保存到db或者写入本地日志之后才退出进程
+pub fn save_player_on_shutdown(gm: Lock) {
+    let m = move || {
+        save_player_http(gm);
+    };
+    tools::shutdown::on_signal(m);
 }
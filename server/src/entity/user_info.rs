@@ -8,6 +8,7 @@ use protobuf::Message;
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::cell::Cell;
+use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::str::FromStr;
 use tools::cmd_code::{ClientCode, RoomCode};
@@ -177,7 +178,8 @@ impl User {
         let mut grade = res as usize;
         grade += 1;
         let mut max_grade = 2_u32;
-        let max_grade_temp = crate::TEMPLATES.constant_temp_mgr().temps.get("max_grade");
+        let temps = crate::TEMPLATES.load();
+        let max_grade_temp = temps.constant_temp_mgr().temps.get("max_grade");
         match max_grade_temp {
             Some(max_grade_temp) => {
                 let res = u32::from_str(max_grade_temp.value.as_str());
@@ -275,27 +277,15 @@ impl User {
     }
 
     pub fn query(table_name: &str, user_id: u32, tem_id: Option<u32>) -> Option<Self> {
-        let mut v: Vec<Value> = Vec::new();
-        v.push(Value::UInt(user_id as u64));
-
-        let mut sql = String::new();
-        sql.push_str("select * from ");
-        sql.push_str(table_name);
-        sql.push_str(" where user_id=:user_id");
-        if tem_id.is_some() {
-            sql.push_str(" and tem_id:tem_id");
-        }
-
-        let q: Result<QueryResult, Error> = DB_POOL.exe_sql(sql.as_str(), Some(v));
-        if q.is_err() {
-            error!("{:?}", q.err().unwrap());
+        let q = DB_POOL.query(table_name, user_id, tem_id);
+        if let Err(e) = q {
+            error!("{:?}", e);
             return None;
         }
         let q = q.unwrap();
 
         let mut data = None;
-        for _qr in q {
-            let (_, js): (u32, serde_json::Value) = mysql::from_row(_qr.unwrap());
+        for js in q {
             let u = User::init(js);
             data = Some(u);
         }
@@ -310,7 +300,8 @@ impl User {
         if id == 0 {
             return None;
         }
-        let punish_temp = crate::TEMPLATES.punish_temp_mgr().get_temp(&id);
+        let temps = crate::TEMPLATES.load();
+        let punish_temp = temps.punish_temp_mgr().get_temp(&id);
         if let Err(e) = punish_temp {
             warn!("{:?}", e);
             return None;
@@ -472,7 +463,8 @@ pub fn update_season(gm: &mut GameMgr, packet: Packet) {
 
     let season_id = proto.get_season_id();
     let next_update_time = proto.get_next_update_time();
-    let constant_tmp = TEMPLATES.constant_temp_mgr().temps.get("round_season_id");
+    let temps = TEMPLATES.load();
+    let constant_tmp = temps.constant_temp_mgr().temps.get("round_season_id");
     let default_session_id;
     match constant_tmp {
         Some(constant_temp) => {
@@ -587,19 +579,40 @@ pub fn show_rank(gm: &mut GameMgr, packet: Packet) {
 }
 
 ///同步排行榜快照
-pub fn sync_rank(gm: &mut GameMgr, _: Packet) {
+///body为空则从redis全量加载，否则是有变化的排行数据(json)
+pub fn sync_rank(gm: &mut GameMgr, packet: Packet) {
+    if !packet.get_data().is_empty() {
+        let res = serde_json::from_slice::<Vec<RankInfo>>(packet.get_data());
+        if let Err(e) = res {
+            error!("{:?}", e);
+            return;
+        }
+        let delta = res.unwrap();
+        let mut delta_map = HashMap::with_capacity(delta.len());
+        for ri in delta {
+            delta_map.insert(ri.user_id, ri);
+        }
+        //更新已经在榜上的
+        for rank_pt in gm.rank.iter_mut() {
+            if let Some(ri) = delta_map.remove(&rank_pt.user_id) {
+                *rank_pt = ri.into_rank_pt();
+            }
+        }
+        //新上榜的
+        for ri in delta_map.values() {
+            gm.rank.push(ri.into_rank_pt());
+        }
+        //排名小于0的是掉出排行榜的
+        gm.rank.retain(|x| x.rank >= 0);
+        gm.rank.par_sort_unstable_by(|a, b| a.rank.cmp(&b.rank));
+        return;
+    }
     //先清空排行榜
     gm.rank.clear();
 
-    let redis_lock = crate::REDIS_POOL.lock();
-    if let Err(err) = redis_lock {
-        error!("{:?}", err);
-        return;
-    }
-    let mut redis_lock = redis_lock.unwrap();
     //加载当前排行榜
     let ranks: Option<Vec<String>> =
-        redis_lock.hvals(crate::REDIS_INDEX_RANK, crate::REDIS_KEY_CURRENT_RANK);
+        crate::REDIS_POOL.hvals(crate::REDIS_INDEX_RANK, crate::REDIS_KEY_CURRENT_RANK);
     if let Some(ranks) = ranks {
         for rank_str in ranks {
             let ri: RankInfo = serde_json::from_str(rank_str.as_str()).unwrap();
@@ -5,8 +5,7 @@ use std::str::FromStr;
 
 ///从redis获得玩家数据
 pub fn get_user_from_redis(user_id: u32) -> Option<JsonValue> {
-    let mut redis_lock = REDIS_POOL.lock().unwrap();
-    let value: Option<String> = redis_lock.hget(
+    let value: Option<String> = REDIS_POOL.hget(
         REDIS_INDEX_USERS,
         REDIS_KEY_UID_2_PID,
         user_id.to_string().trim(),
@@ -16,7 +15,7 @@ pub fn get_user_from_redis(user_id: u32) -> Option<JsonValue> {
     }
     let pid = value.unwrap();
 
-    let res: Option<String> = redis_lock.hget(
+    let res: Option<String> = REDIS_POOL.hget(
         REDIS_INDEX_USERS,
         REDIS_KEY_USERS,
         &pid.to_string().as_str(),
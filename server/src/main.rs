@@ -1,11 +1,14 @@
+mod config;
 mod db;
 mod entity;
 mod helper;
 mod mgr;
 mod net;
-use crate::db::dbtool::DbPool;
+use crate::config::ServerConfig;
+use crate::db::Storage;
+use crate::entity::save_player_on_shutdown;
 use crate::mgr::game_mgr::GameMgr;
-use crate::net::http::{SavePlayerHttpHandler, StopServerHttpHandler};
+use crate::net::http::{SaveMetricsHttpHandler, SavePlayerHttpHandler, StopServerHttpHandler};
 use crate::net::tcp_server;
 use async_std::task::block_on;
 use tools::thread_pool::ThreadWorkPool;
@@ -19,7 +22,8 @@ use std::env;
 use tools::conf::Conf;
 use tools::http::HttpServerHandler;
 use tools::redis_pool::RedisPoolTool;
-use tools::templates::template::{init_temps_mgr, TemplatesMgr};
+use tools::registry::{Registry, ServiceInfo, ROLE_GAME};
+use tools::templates::template::{init_temps_mgr, Templates};
 
 #[macro_use]
 extern crate lazy_static;
@@ -36,10 +40,15 @@ lazy_static! {
         ThreadWorkPool::new("user_model", 9)
     };
 
-    ///数据库链接池
-    static ref DB_POOL: DbPool = {
-        let db_pool = DbPool::new();
-        db_pool
+    ///数据库链接池(存储后端由配置db_type决定)
+    static ref DB_POOL: Box<dyn Storage> = {
+        match db::new_storage(&CONFIG) {
+            Ok(db_pool) => db_pool,
+            Err(e) => {
+                error!("初始化存储失败!{:?}", e);
+                std::process::exit(1);
+            }
+        }
     };
 
     ///配置文件
@@ -51,8 +60,16 @@ lazy_static! {
         conf
     };
 
+    ///校验过的配置
+    static ref CONFIG: ServerConfig = {
+        match CONF_MAP.parse() {
+            Ok(config) => config,
+            Err(e) => panic!("{:?}", e),
+        }
+    };
+
     ///静态配置文件
-    static ref TEMPLATES: TemplatesMgr = {
+    static ref TEMPLATES: Templates = {
         let path = env::current_dir().unwrap();
         let str = path.as_os_str().to_str().unwrap();
         let res = str.to_string()+"/template";
@@ -61,11 +78,16 @@ lazy_static! {
     };
 
     ///reids客户端
-    static ref REDIS_POOL:Arc<std::sync::Mutex<RedisPoolTool>>={
-        let add: &str = &CONF_MAP.get_str("redis_add","");
-        let pass: &str = &CONF_MAP.get_str("redis_pass","");
-        let redis = RedisPoolTool::init(add,pass);
-        let redis:Arc<std::sync::Mutex<RedisPoolTool>> = Arc::new(std::sync::Mutex::new(redis));
+    static ref REDIS_POOL: RedisPoolTool = {
+        let mut redis = match RedisPoolTool::init(&CONFIG.base.redis_add, &CONFIG.base.redis_pass) {
+            Ok(redis) => redis,
+            Err(e) => {
+                error!("初始化redis失败!{:?}", e);
+                std::process::exit(1);
+            }
+        };
+        //按游戏和区服隔离redis数据
+        redis.set_namespace(CONFIG.game.namespace().as_str(), &[REDIS_KEY_USERS, REDIS_KEY_UID_2_PID]);
         redis
     };
 }
@@ -137,6 +159,15 @@ fn main() {
     //初始化日志模块
     init_log();
 
+    //打印生效的配置
+    info!("{}", CONF_MAP.dump(&*CONFIG));
+
+    //初始化数据包压缩
+    tools::compress::init(
+        CONFIG.base.compress_threshold,
+        CONFIG.base.compress_type.as_str(),
+    );
+
     //初始化配置
     init_temps();
 
@@ -155,13 +186,33 @@ fn main() {
     //初始化http服务端
     init_http_server(game_mgr.clone());
 
+    //注册到注册中心
+    init_registry(game_mgr.clone());
+
+    //初始化退出信号处理
+    save_player_on_shutdown(game_mgr.clone());
+
     //初始化tcp服务端
     init_tcp_server(game_mgr.clone());
 }
 
+///注册到注册中心，定时心跳上报在线玩家数量
+fn init_registry(gm: Lock) {
+    let registry = Registry::new(&CONFIG.registry);
+    let address = CONFIG.registry.advertise_address(&CONFIG.tcp_port);
+    let capacity = CONFIG.capacity;
+    let heartbeat = registry.clone();
+    let m = move || {
+        let load = block_on(gm.lock()).users.len() as u32;
+        let info = ServiceInfo::new(ROLE_GAME, address.clone(), capacity, load);
+        heartbeat.register(&REDIS_POOL, &info);
+    };
+    registry.spawn_heartbeat(m);
+}
+
 fn init_log() {
-    let info_log = &CONF_MAP.get_str("info_log_path", "");
-    let error_log = &CONF_MAP.get_str("error_log_path", "");
+    let info_log = &CONFIG.base.info_log_path;
+    let error_log = &CONFIG.base.error_log_path;
     tools::my_log::init_log(info_log, error_log);
 }
 
@@ -172,13 +223,21 @@ fn init_rank(gm: Lock) {
 
 ///初始化赛季信息
 fn init_season() {
-    let mut lock = REDIS_POOL.lock().unwrap();
-    let res: Option<String> = lock.hget(REDIS_INDEX_GAME_SEASON, REDIS_KEY_GAME_SEASON, "101");
+    let game_id = CONFIG.game.game_id.to_string();
+    let res: Option<String> = REDIS_POOL.hget(
+        REDIS_INDEX_GAME_SEASON,
+        REDIS_KEY_GAME_SEASON,
+        game_id.as_str(),
+    );
     if let None = res {
-        error!("redis do not has season data about game:{}", 101);
+        error!("redis do not has season data about game:{}", game_id);
         return;
     }
-    let res: Option<String> = lock.hget(REDIS_INDEX_GAME_SEASON, REDIS_KEY_GAME_SEASON, "101");
+    let res: Option<String> = REDIS_POOL.hget(
+        REDIS_INDEX_GAME_SEASON,
+        REDIS_KEY_GAME_SEASON,
+        game_id.as_str(),
+    );
     let str = res.unwrap();
     let value = serde_json::from_str(str.as_str());
     if let Err(e) = value {
@@ -225,13 +284,21 @@ fn init_season() {
 
 ///初始化worldboss信息
 fn init_world_boss() {
-    let mut lock = REDIS_POOL.lock().unwrap();
-    let res: Option<String> = lock.hget(REDIS_INDEX_GAME_SEASON, REDIS_KEY_WORLD_BOSS, "101");
+    let game_id = CONFIG.game.game_id.to_string();
+    let res: Option<String> = REDIS_POOL.hget(
+        REDIS_INDEX_GAME_SEASON,
+        REDIS_KEY_WORLD_BOSS,
+        game_id.as_str(),
+    );
     if let None = res {
-        error!("redis do not has season data about game:{}", 101);
+        error!("redis do not has season data about game:{}", game_id);
         return;
     }
-    let res: Option<String> = lock.hget(REDIS_INDEX_GAME_SEASON, REDIS_KEY_WORLD_BOSS, "101");
+    let res: Option<String> = REDIS_POOL.hget(
+        REDIS_INDEX_GAME_SEASON,
+        REDIS_KEY_WORLD_BOSS,
+        game_id.as_str(),
+    );
     let str = res.unwrap();
     let value = serde_json::from_str(str.as_str());
     if let Err(e) = value {
@@ -285,16 +352,17 @@ fn init_temps() {
 
 ///初始化http服务端
 fn init_http_server(gm: Lock) {
-    let http_port = CONF_MAP.get_usize("http_port", 0) as u16;
+    let http_port = CONFIG.http_port;
 
     tools::http::Builder::new()
         .route(Box::new(SavePlayerHttpHandler::new(gm.clone())))
         .route(Box::new(StopServerHttpHandler::new(gm.clone())))
+        .route(Box::new(SaveMetricsHttpHandler))
         .bind(http_port);
 }
 
 ///init tcp server
 fn init_tcp_server(gm: Lock) {
-    let tcp_port: &str = &CONF_MAP.get_str("tcp_port", "");
+    let tcp_port: &str = &CONFIG.tcp_port;
     tcp_server::new(tcp_port, gm);
 }
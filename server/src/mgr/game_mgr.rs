@@ -4,7 +4,10 @@ use crate::entity::user_info::{
     search_room, show_rank, sync_rank, update_season,
 };
 use crate::entity::{Entity, EntityData};
+use crate::mgr::save_mgr::SaveTask;
+use crate::net::tcp_server::login;
 use chrono::Local;
+use crossbeam::channel::Sender;
 use log::{error, info, warn};
 use protobuf::Message;
 use std::collections::hash_map::RandomState;
@@ -17,6 +20,7 @@ use tools::protos::base::{PlayerPt, PunishMatchPt, WorldBossPt};
 use tools::protos::base::{RankInfoPt, SeasonPt};
 use tools::protos::protocol::{C_SYNC_DATA, S_SYNC_DATA, S_USER_LOGIN};
 use tools::protos::server_protocol::{B_S_SUMMARY, G_S_MODIFY_NICK_NAME, UPDATE_WORLD_BOSS_PUSH};
+use tools::rpc::{self, RpcRouter};
 use tools::util::packet::Packet;
 use tools::{cmd_code::RankCode, protos::base::LeaguePt};
 
@@ -35,6 +39,8 @@ pub struct GameMgr {
     pub user_best_rank: HashMap<u32, RankInfoPt>, //玩家最佳排行
     net_handler: Option<NetHandler>,              //tcpchannel
     pub cmd_map: HashMap<u32, fn(&mut GameMgr, Packet), RandomState>, //命令管理
+    pub rpc_router: RpcRouter<GameMgr>,           //rpc命令管理
+    pub save_sender: Option<Sender<SaveTask>>,    //保存线程的sender
 }
 
 impl GameMgr {
@@ -48,9 +54,12 @@ impl GameMgr {
             last_season_rank: Vec::new(),
             user_best_rank: HashMap::new(),
             cmd_map: HashMap::new(),
+            rpc_router: RpcRouter::new(),
+            save_sender: None,
         };
         //初始化命令
         gm.cmd_init();
+        gm.rpc_init();
         gm
     }
 
@@ -88,10 +97,9 @@ impl GameMgr {
         self.last_season_rank.clear();
         self.user_best_rank.clear();
         self.rank.clear();
-        let mut redis_lock = crate::REDIS_POOL.lock().unwrap();
         //加载当前排行榜
         let ranks: Option<Vec<String>> =
-            redis_lock.hvals(crate::REDIS_INDEX_RANK, crate::REDIS_KEY_CURRENT_RANK);
+            crate::REDIS_POOL.hvals(crate::REDIS_INDEX_RANK, crate::REDIS_KEY_CURRENT_RANK);
         if let Some(ranks) = ranks {
             for rank_str in ranks {
                 let ri: RankInfo = serde_json::from_str(rank_str.as_str()).unwrap();
@@ -103,7 +111,7 @@ impl GameMgr {
 
         //加载上赛季排行榜
         let last_ranks: Option<Vec<String>> =
-            redis_lock.hvals(crate::REDIS_INDEX_RANK, crate::REDIS_KEY_LAST_RANK);
+            crate::REDIS_POOL.hvals(crate::REDIS_INDEX_RANK, crate::REDIS_KEY_LAST_RANK);
         if let Some(last_ranks) = last_ranks {
             for last_rank_str in last_ranks {
                 let ri: RankInfo = serde_json::from_str(last_rank_str.as_str()).unwrap();
@@ -115,7 +123,7 @@ impl GameMgr {
 
         //加载玩家最佳排名
         let best_ranks: Option<Vec<String>> =
-            redis_lock.hvals(crate::REDIS_INDEX_RANK, crate::REDIS_KEY_BEST_RANK);
+            crate::REDIS_POOL.hvals(crate::REDIS_INDEX_RANK, crate::REDIS_KEY_BEST_RANK);
         if let Some(best_ranks) = best_ranks {
             for best_rank_str in best_ranks {
                 let ri: RankInfo = serde_json::from_str(best_rank_str.as_str()).unwrap();
@@ -146,26 +154,57 @@ impl GameMgr {
         tcp.node_handler.network().send(endpoint, bytes.as_slice());
     }
 
-    pub fn save_user_http(&mut self) {
+    ///同步保存所有玩家数据，停服和http保存时调用
+    ///有改动的数据投递到保存线程，等待保存线程把队列里的数据全部保存到db或者写入本地日志
+    ///之前定时投递还没保存的数据也在队列里，一起保存
+    ///返回(投递的实体数量,没有保存到db的批次数量)
+    pub fn save_user_sync(&mut self) -> anyhow::Result<(usize, usize)> {
         let time = std::time::SystemTime::now();
-        let mut count: u32 = 0;
-        for (_, v) in self.users.iter_mut() {
-            if v.get_version() <= 0 {
-                continue;
-            }
-            v.update();
-            count += 1;
+        let sender = match self.save_sender.as_ref() {
+            Some(sender) => sender.clone(),
+            None => anyhow::bail!("save thread is not running!"),
+        };
+        let v = self.collect_need_save();
+        let count = v.len();
+        if count > 0 {
+            sender.send(SaveTask::Save(v))?;
         }
+        let (ack_sender, ack_rec) = crossbeam::channel::bounded(1);
+        sender.send(SaveTask::Flush(ack_sender))?;
+        let unsaved = ack_rec.recv()?;
         info!(
-            "玩家数据保存结束，保存个数:{},耗时：{}ms",
+            "玩家数据同步保存结束，投递数量:{},没有保存到db的批次:{},耗时：{}ms",
             count,
+            unsaved,
             time.elapsed().unwrap().as_millis()
         );
+        Ok((count, unsaved))
     }
 
     ///保存玩家数据
-    pub fn save_user(&mut self, sender: crossbeam::channel::Sender<Vec<Box<dyn EntityData>>>) {
+    pub fn save_user(&mut self) {
         let time = std::time::SystemTime::now();
+        let v = self.collect_need_save();
+        let count = v.len();
+        if count > 0 {
+            match self.save_sender.as_ref() {
+                Some(sender) => {
+                    if let Err(e) = sender.send(SaveTask::Save(v)) {
+                        error!("{:?}", e.to_string());
+                    }
+                }
+                None => error!("save thread is not running!"),
+            }
+        }
+        info!(
+            "开始执行定时保存玩家，发送数量:{},耗时:{}ms",
+            count,
+            time.elapsed().unwrap().as_millis()
+        );
+    }
+
+    ///深拷贝有改动的实体，并清空版本号
+    fn collect_need_save(&mut self) -> Vec<Box<dyn EntityData>> {
         let mut v: Vec<Box<dyn EntityData>> = Vec::new();
         for ud in self.users.values_mut() {
             if ud.get_version() <= 0 {
@@ -191,26 +230,20 @@ impl GameMgr {
             //由于这里是深拷贝，所以在这里提前清空版本号，不然在接收方那边执行update，清空的版本号也是clone的
             ud.clear_version();
         }
-        let count = v.len();
-        if count > 0 {
-            let res = sender.send(v);
-            match res {
-                Err(e) => {
-                    error!("{:?}", e.to_string());
-                }
-                _ => {}
-            }
-        }
-        info!(
-            "开始执行定时保存玩家，发送数量:{},耗时:{}ms",
-            count,
-            time.elapsed().unwrap().as_millis()
-        );
+        v
     }
 
     ///执行函数，通过packet拿到cmd，然后从cmdmap拿到函数指针调用
     pub fn invok(&mut self, packet: Packet) {
         let cmd = packet.get_cmd();
+        //rpc请求，处理完回复请求方
+        if let Some(handler) = self.rpc_router.get(&packet) {
+            let bytes = rpc::handle(self, handler, &packet);
+            let tcp = self.get_net_handler();
+            let endpoint = tcp.endpoint;
+            tcp.node_handler.network().send(endpoint, bytes.as_slice());
+            return;
+        }
         let f = self.cmd_map.get_mut(&cmd);
         match f {
             Some(func) => func(self, packet),
@@ -220,10 +253,17 @@ impl GameMgr {
         }
     }
 
+    ///rpc命令初始化
+    fn rpc_init(&mut self) {
+        self.rpc_router.register(GameCode::Login.into_u32(), login);
+    }
+
     ///命令初始化
     fn cmd_init(&mut self) {
         self.cmd_map
             .insert(ServerCommonCode::ReloadTemps.into_u32(), reload_temps);
+        self.cmd_map
+            .insert(ServerCommonCode::Drain.into_u32(), drain);
         self.cmd_map
             .insert(GameCode::UnloadUser.into_u32(), off_line);
         self.cmd_map
@@ -342,30 +382,41 @@ impl GameMgr {
 
 ///热更新配置文件
 pub fn reload_temps(_: &mut GameMgr, _: Packet) {
-    let path = std::env::current_dir();
-    if let Err(e) = path {
-        warn!("{:?}", e);
-        return;
-    }
-    let path = path.unwrap();
-    let str = path.as_os_str().to_str();
-    if let None = str {
-        warn!("reload_temps can not path to_str!");
-        return;
-    }
-    let str = str.unwrap();
-    let res = str.to_string() + "/template";
-    let res = crate::TEMPLATES.reload_temps(res.as_str());
+    let res = crate::TEMPLATES.reload();
     match res {
-        Ok(_) => {
-            info!("reload_temps success!");
+        Ok(report) => {
+            info!("reload_temps success!{}", report);
         }
         Err(e) => {
-            warn!("{:?}", e);
+            warn!("reload_temps fail,keep old templates!{:?}", e);
         }
     }
 }
 
+///停服排空，保存所有玩家数据后回复确认
+pub fn drain(gm: &mut GameMgr, _: Packet) {
+    tools::shutdown::set_draining();
+    //数据全部保存到db或者写入本地日志之后才能回复确认
+    let res = gm.save_user_sync();
+    if let Err(e) = res {
+        error!("停服排空保存玩家数据失败!{:?}", e);
+        return;
+    }
+    let (save_num, unsaved_num) = res.unwrap();
+    let value = serde_json::json!({
+        "server_type":"game",
+        "user_num":gm.users.len(),
+        "save_num":save_num,
+        "unsaved_num":unsaved_num,
+    });
+    gm.send_2_server(
+        ServerCommonCode::DrainAck.into_u32(),
+        0,
+        value.to_string().into_bytes(),
+    );
+    info!("游戏服停服排空完毕!{}", value);
+}
+
 ///同步数据
 fn sync(gm: &mut GameMgr, packet: Packet) {
     let user_id = packet.get_user_id();
@@ -1,4 +1,5 @@
 pub mod game_mgr;
+pub mod save_mgr;
 pub mod timer_mgr;
 
 use num_enum::IntoPrimitive;
@@ -0,0 +1,458 @@
+use crate::db::{SaveRow, Storage};
+use crate::entity::EntityData;
+use crate::DB_POOL;
+use chrono::Local;
+use crossbeam::channel::Sender;
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Write};
+use std::sync::RwLock;
+use std::time::Duration;
+
+///一批保存失败后最多重试次数，超过则写入本地日志文件
+const SAVE_MAX_RETRY_TIMES: u32 = 3;
+
+lazy_static! {
+    ///最近一次的保存统计数据，给http查询用，不用去锁保存线程
+    static ref SAVE_METRICS: RwLock<SaveMetrics> = RwLock::new(SaveMetrics::default());
+}
+
+///获得保存统计数据
+pub fn metrics() -> SaveMetrics {
+    match SAVE_METRICS.read() {
+        Ok(metrics) => *metrics,
+        Err(e) => *e.into_inner(),
+    }
+}
+
+///保存线程处理的任务，按投递顺序处理
+pub enum SaveTask {
+    Save(Vec<Box<dyn EntityData>>), //投递需要保存的实体
+    Flush(Sender<usize>),           //立刻保存队列里的所有数据,完成后回复没有保存到db的批次数量
+}
+
+///一个玩家待保存的数据，同一个事务里提交
+#[derive(Debug, Clone, Default)]
+pub struct SaveBatch {
+    pub user_id: u32,                           //玩家id
+    pub rows: BTreeMap<(String, u32), SaveRow>, //(表名,tem_id)->数据,新数据覆盖旧数据
+    pub retry_times: u32,                       //已重试次数
+    pub saved_at: i64,                          //最后一次投递数据的时间(毫秒)
+}
+
+impl SaveBatch {
+    fn new(user_id: u32) -> Self {
+        let mut sb = SaveBatch::default();
+        sb.user_id = user_id;
+        sb
+    }
+
+    fn push(&mut self, row: SaveRow) {
+        let key = (row.table_name.clone(), row.tem_id.unwrap_or(0));
+        self.rows.insert(key, row);
+    }
+
+    ///合并另一批数据，按时间新的覆盖旧的
+    fn merge(&mut self, other: SaveBatch) {
+        if other.saved_at < self.saved_at {
+            for (key, row) in other.rows {
+                self.rows.entry(key).or_insert(row);
+            }
+            return;
+        }
+        self.rows.extend(other.rows);
+        self.saved_at = other.saved_at;
+    }
+}
+
+///本地日志里的一行，记录数据产生的时间，重新加载时按时间先后覆盖
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct JournalLine {
+    user_id: u32,       //玩家id
+    saved_at: i64,      //数据产生的时间(毫秒)
+    rows: Vec<SaveRow>, //数据
+}
+
+///保存统计数据
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SaveMetrics {
+    pub flush_times: u64,     //flush次数
+    pub last_flush_ms: u128,  //上次flush耗时
+    pub max_flush_ms: u128,   //最大flush耗时
+    pub saved_batches: u64,   //成功保存的批次
+    pub failed_batches: u64,  //失败的批次(每次重试都算)
+    pub journal_batches: u64, //写入本地日志的批次
+    pub journal_pruned: u64,  //被新数据覆盖从本地日志里删掉的批次
+    pub backlog: usize,       //当前积压的批次(玩家数量)
+}
+
+///写后(write-behind)保存管理器
+///定时器把有改动的实体投递进来，按玩家合并成批次，每个玩家的数据在一个事务里批量upsert
+///失败的批次留在队列里等下次重试，重试次数用完写入本地日志文件，下次启动时重新加载
+///日志里的数据之后被更新的数据成功保存了，就从日志里删掉，防止重启时旧数据覆盖新数据
+pub struct SaveMgr {
+    pending: BTreeMap<u32, SaveBatch>,   //待保存的批次,user_id->批次
+    journaled: BTreeMap<u32, SaveBatch>, //已写入本地日志还没保存成功的数据,和日志文件内容一致
+    journal_path: String,                //本地日志文件路径,为空则不写
+    metrics: SaveMetrics,                //统计数据
+}
+
+impl SaveMgr {
+    pub fn new(journal_path: &str) -> Self {
+        let mut sm = SaveMgr {
+            pending: BTreeMap::new(),
+            journaled: BTreeMap::new(),
+            journal_path: journal_path.to_owned(),
+            metrics: SaveMetrics::default(),
+        };
+        sm.load_journal();
+        sm
+    }
+
+    ///投递需要保存的实体
+    pub fn push(&mut self, v: Vec<Box<dyn EntityData>>) {
+        let now = Local::now().timestamp_millis();
+        for entity in v {
+            let row = SaveRow {
+                table_name: entity.get_table_name().to_owned(),
+                user_id: entity.get_user_id(),
+                tem_id: entity.get_tem_id(),
+                content: entity.get_data(),
+            };
+            self.push_row(row, now);
+        }
+        self.metrics.backlog = self.pending.len();
+        self.publish_metrics();
+    }
+
+    fn push_row(&mut self, row: SaveRow, saved_at: i64) {
+        let user_id = row.user_id;
+        let batch = self
+            .pending
+            .entry(user_id)
+            .or_insert_with(|| SaveBatch::new(user_id));
+        batch.push(row);
+        batch.saved_at = saved_at;
+    }
+
+    ///是否有待保存的数据
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    ///把队列里的批次写到db
+    pub fn flush(&mut self) {
+        self.flush_to(&**DB_POOL);
+    }
+
+    ///停服时调用，同步保存队列里的所有数据，直到队列为空
+    ///失败的批次重试次数用完会写入本地日志，返回没有保存到db的批次数量
+    pub fn flush_all(&mut self) -> usize {
+        let count = self.pending.len();
+        let saved_batches = self.metrics.saved_batches;
+        let mut times = 0;
+        while !self.pending.is_empty() {
+            if times > 0 {
+                std::thread::sleep(Duration::from_secs(1));
+            }
+            self.flush();
+            times += 1;
+        }
+        count - (self.metrics.saved_batches - saved_batches) as usize
+    }
+
+    fn flush_to(&mut self, storage: &dyn Storage) {
+        if self.pending.is_empty() {
+            return;
+        }
+        let time = std::time::SystemTime::now();
+        let mut success_count = 0;
+        let mut fail_count = 0;
+        let mut journal_v = Vec::new();
+        let mut pruned = false;
+        let pending = std::mem::replace(&mut self.pending, BTreeMap::new());
+        for (user_id, mut batch) in pending {
+            let rows: Vec<SaveRow> = batch.rows.values().cloned().collect();
+            let res = storage.save_batch(rows.as_slice());
+            match res {
+                Ok(_) => {
+                    success_count += 1;
+                    pruned |= self.prune_journaled(&batch);
+                }
+                Err(e) => {
+                    fail_count += 1;
+                    batch.retry_times += 1;
+                    warn!(
+                        "保存玩家数据失败!user_id:{},retry_times:{},{:?}",
+                        user_id, batch.retry_times, e
+                    );
+                    if batch.retry_times >= SAVE_MAX_RETRY_TIMES {
+                        journal_v.push(batch);
+                    } else {
+                        self.pending.insert(user_id, batch);
+                    }
+                }
+            }
+        }
+        if pruned {
+            self.rewrite_journal();
+        }
+        if !journal_v.is_empty() {
+            self.write_journal(journal_v);
+        }
+        let spend_time = time.elapsed().unwrap().as_millis();
+        self.metrics.flush_times += 1;
+        self.metrics.last_flush_ms = spend_time;
+        if spend_time > self.metrics.max_flush_ms {
+            self.metrics.max_flush_ms = spend_time;
+        }
+        self.metrics.saved_batches += success_count;
+        self.metrics.failed_batches += fail_count;
+        self.metrics.backlog = self.pending.len();
+        self.publish_metrics();
+        info!(
+            "执行保存玩家数据结束!成功:{},失败:{},积压:{},耗时:{}ms,metrics:{:?}",
+            success_count, fail_count, self.metrics.backlog, spend_time, self.metrics
+        );
+    }
+
+    fn publish_metrics(&self) {
+        match SAVE_METRICS.write() {
+            Ok(mut metrics) => *metrics = self.metrics,
+            Err(e) => *e.into_inner() = self.metrics,
+        }
+    }
+
+    ///新数据保存成功了，日志里同一行(表名,tem_id)的旧数据就不需要了
+    ///返回是否有数据被删掉
+    fn prune_journaled(&mut self, batch: &SaveBatch) -> bool {
+        let journaled = match self.journaled.get_mut(&batch.user_id) {
+            Some(journaled) => journaled,
+            None => return false,
+        };
+        if journaled.saved_at > batch.saved_at {
+            return false;
+        }
+        let count = journaled.rows.len();
+        journaled
+            .rows
+            .retain(|key, _| !batch.rows.contains_key(key));
+        if journaled.rows.len() == count {
+            return false;
+        }
+        if journaled.rows.is_empty() {
+            self.journaled.remove(&batch.user_id);
+            self.metrics.journal_pruned += 1;
+        }
+        true
+    }
+
+    ///按内存里剩下的数据重写本地日志，先写临时文件再替换
+    fn rewrite_journal(&mut self) {
+        if self.journal_path.is_empty() {
+            return;
+        }
+        let tmp_path = format!("{}.tmp", self.journal_path);
+        let mut content = String::new();
+        for batch in self.journaled.values() {
+            let str = serde_json::to_string(&JournalLine::from(batch));
+            if let Err(e) = str {
+                error!("{:?}", e);
+                continue;
+            }
+            content.push_str(str.unwrap().as_str());
+            content.push('\n');
+        }
+        if let Err(e) = std::fs::write(tmp_path.as_str(), content) {
+            error!("重写本地日志失败!{:?}", e);
+            return;
+        }
+        if let Err(e) = std::fs::rename(tmp_path.as_str(), self.journal_path.as_str()) {
+            error!("重写本地日志失败!{:?}", e);
+        }
+    }
+
+    ///重试次数用完的批次追加写到本地日志文件，一行一个批次(SaveRow数组)
+    fn write_journal(&mut self, v: Vec<SaveBatch>) {
+        if self.journal_path.is_empty() {
+            error!("保存玩家数据失败，并且没有配置本地日志，数据丢失!{:?}", v);
+            return;
+        }
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.journal_path.as_str());
+        if let Err(e) = file {
+            error!("{:?},数据丢失!{:?}", e, v);
+            return;
+        }
+        let mut file = file.unwrap();
+        for batch in v {
+            let str = serde_json::to_string(&JournalLine::from(&batch));
+            if let Err(e) = str {
+                error!("{:?}", e);
+                continue;
+            }
+            let res = writeln!(file, "{}", str.unwrap());
+            if let Err(e) = res {
+                error!("{:?},数据丢失!{:?}", e, batch);
+                continue;
+            }
+            self.metrics.journal_batches += 1;
+            error!(
+                "保存玩家数据多次失败，已写入本地日志!user_id:{},path:{}",
+                batch.user_id, self.journal_path
+            );
+            let user_id = batch.user_id;
+            match self.journaled.get_mut(&user_id) {
+                Some(journaled) => journaled.merge(batch),
+                None => {
+                    self.journaled.insert(user_id, batch);
+                }
+            }
+        }
+    }
+
+    ///启动时加载本地日志里的批次重新保存，加载后清空日志
+    fn load_journal(&mut self) {
+        if self.journal_path.is_empty() {
+            return;
+        }
+        let file = std::fs::File::open(self.journal_path.as_str());
+        if let Err(_) = file {
+            return;
+        }
+        let reader = BufReader::new(file.unwrap());
+        let mut lines = Vec::new();
+        for line in reader.lines() {
+            if let Err(e) = line {
+                error!("{:?}", e);
+                break;
+            }
+            let line = line.unwrap();
+            if line.is_empty() {
+                continue;
+            }
+            let journal_line = serde_json::from_str::<JournalLine>(line.as_str());
+            if let Err(e) = journal_line {
+                error!("{:?}", e);
+                continue;
+            }
+            lines.push(journal_line.unwrap());
+        }
+        //按数据产生的时间先后放进队列，同一行数据新的覆盖旧的
+        lines.sort_by_key(|x| x.saved_at);
+        let count = lines.len();
+        for journal_line in lines {
+            for row in journal_line.rows {
+                self.push_row(row, journal_line.saved_at);
+            }
+        }
+        //加载进队列了，旧的日志备份一下再清空
+        let bak_path = format!("{}.bak", self.journal_path);
+        if let Err(e) = std::fs::rename(self.journal_path.as_str(), bak_path.as_str()) {
+            error!("{:?}", e);
+        }
+        self.metrics.backlog = self.pending.len();
+        self.publish_metrics();
+        info!("从本地日志加载待保存的玩家数据!数量:{}", count);
+    }
+}
+
+impl From<&SaveBatch> for JournalLine {
+    fn from(batch: &SaveBatch) -> Self {
+        JournalLine {
+            user_id: batch.user_id,
+            saved_at: batch.saved_at,
+            rows: batch.rows.values().cloned().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::memory_tool::MemoryPool;
+    use crate::db::table_contants::{CHARACTER, USER};
+    use crate::JsonValue;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    ///可以模拟db出错的存储
+    #[derive(Default)]
+    struct FailPool {
+        fail: AtomicBool,
+        inner: MemoryPool,
+    }
+
+    impl Storage for FailPool {
+        fn insert(&self, _: &str, _: u32, _: Option<u32>, _: &str) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        fn update(&self, _: &str, _: u32, _: Option<u32>, _: &str) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        fn query(
+            &self,
+            table_name: &str,
+            user_id: u32,
+            tem_id: Option<u32>,
+        ) -> anyhow::Result<Vec<JsonValue>> {
+            self.inner.query(table_name, user_id, tem_id)
+        }
+
+        fn save_batch(&self, rows: &[SaveRow]) -> anyhow::Result<()> {
+            if self.fail.load(Ordering::SeqCst) {
+                anyhow::bail!("db gone away")
+            }
+            self.inner.save_batch(rows)
+        }
+    }
+
+    fn row(table_name: &str, tem_id: Option<u32>, content: &str) -> SaveRow {
+        SaveRow {
+            table_name: table_name.to_owned(),
+            user_id: 1,
+            tem_id,
+            content: content.to_owned(),
+        }
+    }
+
+    #[test]
+    fn journal_is_pruned_by_newer_commit() {
+        let path = std::env::temp_dir().join(format!("save_journal_{}.log", std::process::id()));
+        let path = path.to_str().unwrap().to_owned();
+        let _ = std::fs::remove_file(path.as_str());
+        let pool = FailPool::default();
+        let mut sm = SaveMgr::new(path.as_str());
+
+        //连续失败，写入本地日志
+        pool.fail.store(true, Ordering::SeqCst);
+        sm.push_row(row(USER, None, r#"{"grade":1}"#), 1);
+        sm.push_row(row(CHARACTER, Some(1001), r#"{"lv":1}"#), 1);
+        for _ in 0..SAVE_MAX_RETRY_TIMES {
+            sm.flush_to(&pool);
+        }
+        assert!(sm.is_empty());
+        assert_eq!(sm.metrics.journal_batches, 1);
+
+        //同一个玩家更新的数据保存成功了，日志里只剩下没有被覆盖的行
+        pool.fail.store(false, Ordering::SeqCst);
+        sm.push_row(row(USER, None, r#"{"grade":2}"#), 2);
+        sm.flush_to(&pool);
+        let content = std::fs::read_to_string(path.as_str()).unwrap();
+        assert!(!content.contains("grade"));
+        assert!(content.contains("lv"));
+
+        //重启加载日志不会用旧数据覆盖新数据
+        let mut sm = SaveMgr::new(path.as_str());
+        sm.flush_to(&pool);
+        let res = pool.query(USER, 1, None).unwrap();
+        assert_eq!(res[0]["grade"], 2);
+        let res = pool.query(CHARACTER, 1, Some(1001)).unwrap();
+        assert_eq!(res[0]["lv"], 1);
+        let _ = std::fs::remove_file(format!("{}.bak", path));
+    }
+}
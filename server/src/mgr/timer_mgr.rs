@@ -1,10 +1,15 @@
 use async_std::task::block_on;
 use chrono::{Local, Timelike};
+use crossbeam::channel::RecvTimeoutError;
 use log::{error, info};
 use std::time::Duration;
 use std::time::SystemTime;
 
-use crate::Lock;
+use crate::mgr::save_mgr::{SaveMgr, SaveTask};
+use crate::{Lock, CONFIG};
+
+///保存失败后重试的间隔时间(秒)
+const SAVE_RETRY_INTERVAL: u64 = 30;
 
 ///初始化定时器任务函数
 pub fn init_timer(gm: Lock) {
@@ -58,41 +63,43 @@ fn zero_day(gm: Lock) {
 ///保存玩家数据的定时器任务函数
 fn save_timer(gm: Lock) {
     let (sender, rec) = crossbeam::channel::bounded(1024);
+    block_on(gm.lock()).save_sender = Some(sender);
 
     let m = move || loop {
         let gm = gm.clone();
-        block_on(gm.lock()).save_user(sender.clone());
+        block_on(gm.lock()).save_user();
         let d = Duration::from_secs(60 * 5);
         std::thread::sleep(d);
     };
 
-    let re = move || loop {
-        let res = rec.recv();
-        let mut count = 0;
-        match res {
-            Err(str) => {
-                error!("玩家数据保存出错,message:{:?}", str);
-            }
-            Ok(vec) => {
-                let time = std::time::SystemTime::now();
-                for v in vec {
-                    v.clear_version();
-                    let rs = v.update();
-                    match rs {
-                        Ok(_) => {
-                            count += 1;
-                        }
-                        Err(e) => {
-                            error!("{:?}", e);
-                        }
+    //写后保存，失败的批次在队列里等待重试
+    let journal_path = CONFIG.save_journal_path.clone();
+    let re = move || {
+        let mut save_mgr = SaveMgr::new(journal_path.as_str());
+        loop {
+            let res = rec.recv_timeout(Duration::from_secs(SAVE_RETRY_INTERVAL));
+            match res {
+                Ok(SaveTask::Save(vec)) => {
+                    save_mgr.push(vec);
+                }
+                Ok(SaveTask::Flush(ack)) => {
+                    let count = save_mgr.flush_all();
+                    if let Err(e) = ack.send(count) {
+                        error!("{:?}", e);
                     }
+                    continue;
                 }
-                info!(
-                    "执行定时保存玩家结束，执行数量:{},耗时:{}ms",
-                    count,
-                    time.elapsed().unwrap().as_millis()
-                );
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(e) => {
+                    error!("玩家数据保存出错,message:{:?}", e);
+                    save_mgr.flush_all();
+                    break;
+                }
+            }
+            if save_mgr.is_empty() {
+                continue;
             }
+            save_mgr.flush();
         }
     };
     std::thread::spawn(m);
@@ -1,4 +1,5 @@
 use crate::entity::save_player_http;
+use crate::mgr::save_mgr;
 use crate::Lock;
 use http_types::Error as HttpTypesError;
 use log::info;
@@ -35,6 +36,35 @@ impl HttpServerHandler for SavePlayerHttpHandler {
     }
 }
 
+///查询写后保存的统计数据(flush耗时,积压数量等)
+pub struct SaveMetricsHttpHandler;
+
+impl HttpServerHandler for SaveMetricsHttpHandler {
+    fn get_path(&self) -> &str {
+        "/save_metrics"
+    }
+
+    fn do_get(
+        &mut self,
+        _uri: String,
+        _uri_params: HashMap<String, String>,
+    ) -> anyhow::Result<String> {
+        let metrics = save_mgr::metrics();
+        let value = json!({
+            "status":"OK",
+            "flush_times":metrics.flush_times,
+            "last_flush_ms":metrics.last_flush_ms as u64,
+            "max_flush_ms":metrics.max_flush_ms as u64,
+            "saved_batches":metrics.saved_batches,
+            "failed_batches":metrics.failed_batches,
+            "journal_batches":metrics.journal_batches,
+            "journal_pruned":metrics.journal_pruned,
+            "backlog":metrics.backlog,
+        });
+        Ok(value.to_string())
+    }
+}
+
 pub struct StopServerHttpHandler {
     gm: Lock,
 }
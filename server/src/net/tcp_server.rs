@@ -14,8 +14,8 @@ use crate::Lock;
 use async_trait::async_trait;
 use log::{error, info, warn};
 use protobuf::Message;
-use tools::cmd_code::{ClientCode, GameCode, RankCode, ServerCommonCode};
-use tools::protos::protocol::C_USER_LOGIN;
+use tools::cmd_code::{GameCode, RankCode, ServerCommonCode};
+use tools::protos::protocol::{C_USER_LOGIN, S_USER_LOGIN};
 use tools::util::packet::Packet;
 
 #[derive(Clone)]
@@ -59,26 +59,8 @@ impl tools::net_message_io::MessageHandler for TcpServerHandler {
 }
 
 async fn unorder_message(gm: Lock, packet: Packet) {
-    let cmd = packet.get_cmd();
-    //判断是否执行登录
-    if cmd == GameCode::Login.into_u32() {
-        let mut c_login = C_USER_LOGIN::new();
-        let result = c_login.merge_from_bytes(packet.get_data());
-
-        if let Err(e) = result {
-            error!("{:?}", e);
-            return;
-        }
-        //执行登录
-        let result = login(gm, packet).await;
-        if let Err(e) = result {
-            error!("{:?}", e);
-            return;
-        }
-    } else {
-        //不登录就执行其他命令
-        gm.lock().await.invok(packet);
-    }
+    //登录是rpc请求，也在这里执行
+    gm.lock().await.invok(packet);
 }
 static ORDER_MESSAGE: std::sync::Once = std::sync::Once::new();
 async fn order_message(gm: Lock, packet: Packet) {
@@ -114,6 +96,7 @@ async fn handler_mess_s(gm: Lock, packet: Packet) {
         && cmd != GameCode::UnloadUser.into_u32()
         && cmd != GameCode::SyncRank.into_u32()
         && cmd != ServerCommonCode::ReloadTemps.into_u32()
+        && cmd != ServerCommonCode::Drain.into_u32()
         && packet.get_data().is_empty()
     {
         error!("packet bytes is null!cmd:{}", packet.get_cmd());
@@ -129,11 +112,12 @@ async fn handler_mess_s(gm: Lock, packet: Packet) {
     }
 }
 
-//登录函数，执行登录
-async fn login(gm: Lock, packet: Packet) -> anyhow::Result<()> {
-    //玩家id
-    let user_id = packet.get_user_id();
-    let mut gm_lock = gm.lock().await;
+///登录函数，执行登录，加载失败回复gate错误信息
+pub fn login(
+    gm_lock: &mut GameMgr,
+    user_id: u32,
+    _c_login: C_USER_LOGIN,
+) -> anyhow::Result<S_USER_LOGIN> {
     let user_data = gm_lock.users.contains_key(&user_id);
     //走登录流程
     //如果内存没有数据，则从数据库里面找
@@ -142,7 +126,7 @@ async fn login(gm: Lock, packet: Packet) -> anyhow::Result<()> {
         let user_data = init_user_data(user_id)?;
         gm_lock.users.insert(user_id, user_data);
     }
-    check_nick_name(&mut gm_lock, user_id);
+    check_nick_name(gm_lock, user_id);
     //封装会话
     let user_data = gm_lock.users.get_mut(&user_id);
     if user_data.is_none() {
@@ -157,14 +141,13 @@ async fn login(gm: Lock, packet: Packet) -> anyhow::Result<()> {
     //处理重制惩罚时间
     user.reset_punish_match();
 
-    //返回客户端
+    //返回gate，由gate回给客户端
     let lr = gm_lock.user2proto(user_id);
-    gm_lock.send_2_client(ClientCode::Login, user_id, lr.write_to_bytes()?);
     info!("用户完成登录！user_id:{}", &user_id);
-    Ok(())
+    Ok(lr)
 }
 
-fn check_nick_name(gm_lock: &mut async_std::sync::MutexGuard<GameMgr>, user_id: u32) {
+fn check_nick_name(gm_lock: &mut GameMgr, user_id: u32) {
     let user_data = gm_lock.users.get_mut(&user_id);
     if user_data.is_none() {
         return;
@@ -0,0 +1,27 @@
+use tools::templates::template::TemplatesMgr;
+
+///校验配置表目录，给策划改完表后、发布前检查用
+///用法:check_templates [配置表目录...],不传默认当前目录下的template
+///有错误时退出码为1
+fn main() {
+    let mut dirs: Vec<String> = std::env::args().skip(1).collect();
+    if dirs.is_empty() {
+        dirs.push("template".to_owned());
+    }
+    let mut ok = true;
+    for dir in dirs.iter() {
+        println!("校验配置表目录:{}", dir);
+        let temps_mgr = TemplatesMgr::load(dir.as_str());
+        if let Err(e) = temps_mgr {
+            println!("加载配置表失败!{}", e);
+            ok = false;
+            continue;
+        }
+        let report = temps_mgr.unwrap().check();
+        println!("{}", report);
+        ok &= report.is_ok();
+    }
+    if !ok {
+        std::process::exit(1);
+    }
+}
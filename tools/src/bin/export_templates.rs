@@ -0,0 +1,25 @@
+use tools::templates::template_export::export_dir;
+
+///把策划的xlsx目录导出成配置表json
+///用法:export_templates <xlsx目录> <json目录> [--dry-run]
+///--dry-run只打印差异和校验结果，不写文件；有错误时一个文件都不写，退出码为1
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let dry_run = args.iter().any(|x| x == "--dry-run");
+    let dirs: Vec<&String> = args.iter().filter(|x| !x.starts_with("--")).collect();
+    if dirs.len() != 2 {
+        println!("用法:export_templates <xlsx目录> <json目录> [--dry-run]");
+        std::process::exit(2);
+    }
+    let res = export_dir(dirs[0].as_str(), dirs[1].as_str(), dry_run);
+    if let Err(e) = res {
+        println!("导表失败!{}", e);
+        std::process::exit(1);
+    }
+    let report = res.unwrap();
+    print!("{}", report);
+    if !report.is_ok() {
+        println!("导表失败,没有写出任何文件!");
+        std::process::exit(1);
+    }
+}
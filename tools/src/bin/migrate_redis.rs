@@ -0,0 +1,84 @@
+use tools::game_key::GameKey;
+use tools::redis_pool::RedisClient;
+use tools::TOKIO_RT;
+
+///赛季和worldboss按game_id存在同一个hash里，只迁移这个游戏的field,(db index,hkey)
+const HASH_KEYS: [(u32, &str); 2] = [(1, "game_season"), (1, "world_boss")];
+
+///排行榜，评分和赛季轮次整个key迁移,(db index,key)
+const KEYS: [(u32, &str); 5] = [
+    (2, "current_rank"),
+    (2, "last_rank"),
+    (2, "best_rank"),
+    (2, "rating"),
+    (2, "season_round"),
+];
+
+///历史排行榜按赛季轮次分key,(db index,key模式)
+const HISTORY_RANK: (u32, &str) = (3, "history_rank-*");
+
+///把没有命名空间的旧redis数据迁移到游戏区服的命名空间下
+///升级到按游戏区服隔离redis数据的版本前，停服后对原来的游戏区服执行一次
+///用法:migrate_redis <redis地址> <redis密码> <game_id> <region_id>
+///已经迁移过或者新key已经有数据的会跳过，可以重复执行
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let game_key = match args.as_slice() {
+        [_, _, game_id, region_id] => match (game_id.parse(), region_id.parse()) {
+            (Ok(game_id), Ok(region_id)) => Some(GameKey::new(game_id, region_id)),
+            _ => None,
+        },
+        _ => None,
+    };
+    if game_key.is_none() {
+        println!("用法:migrate_redis <redis地址> <redis密码> <game_id> <region_id>");
+        std::process::exit(2);
+    }
+    let game_key = game_key.unwrap();
+    let mut client = match RedisClient::new(args[0].as_str(), args[1].as_str(), 1) {
+        Ok(client) => client,
+        Err(e) => {
+            println!("redis地址不对!{:?}", e);
+            std::process::exit(1);
+        }
+    };
+    client.set_namespace(game_key.namespace().as_str(), &[]);
+    let res = TOKIO_RT.block_on(migrate(&client, game_key));
+    if let Err(e) = res {
+        println!("迁移失败!{:?}", e);
+        std::process::exit(1);
+    }
+    println!("迁移完成!game:{}", game_key);
+}
+
+async fn migrate(client: &RedisClient, game_key: GameKey) -> anyhow::Result<()> {
+    let game_id = game_key.game_id.to_string();
+    for (index, hkey) in HASH_KEYS.iter() {
+        let res = client
+            .migrate_hash_field(*index, hkey, game_id.as_str())
+            .await?;
+        print_result(*index, format!("{}[{}]", hkey, game_id).as_str(), res);
+    }
+
+    let mut keys: Vec<(u32, String)> = KEYS.iter().map(|(x, y)| (*x, y.to_string())).collect();
+    let (index, pattern) = HISTORY_RANK;
+    for key in client.scan_keys(index, pattern).await? {
+        keys.push((index, key));
+    }
+    for (index, key) in keys {
+        let res = client.migrate_key(index, key.as_str()).await?;
+        print_result(index, key.as_str(), res);
+    }
+    Ok(())
+}
+
+fn print_result(index: u32, key: &str, res: bool) {
+    if res {
+        println!("已迁移!index:{},key:{}", index, key);
+    } else {
+        println!(
+            "跳过(没有旧数据或者新key已经有数据)!index:{},key:{}",
+            index, key
+        );
+    }
+}
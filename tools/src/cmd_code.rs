@@ -7,6 +7,14 @@ use num_enum::TryFromPrimitive;
 pub enum ServerCommonCode {
     //热更新静态配置
     ReloadTemps = 101,
+    //停服排空(停止接收新请求，保存数据)
+    Drain = 102,
+    //停服排空完成确认
+    DrainAck = 103,
+    //服务器注册(连上游戏中心时告诉它自己属于哪个游戏和区服),body:game_id(u32)|region_id(u32)
+    Register = 104,
+    //战斗服定时上报负载,body:room_num(u32)
+    LoadReport = 105,
 }
 
 impl ServerCommonCode {
@@ -24,6 +32,8 @@ pub enum GameCode {
     Min = 1000,
     //心跳
     HeartBeat = 1001,
+    //断线重连(恢复会话,只在gate处理),body:user_id(u32)|session_token(u64)
+    Resume = 1002,
     //登陆
     Login = 1003,
     //同步数据
@@ -134,6 +144,8 @@ pub enum BattleCode {
     Pos = 30005,              //架势请求
     Emoji = 30006,            //表情符号
     Buy = 30007,              //购买
+    Disconnect = 39995,       //暂时掉线(等待重连)
+    Reconnect = 39996,        //断线重连成功
     UpdateSeasonPush = 39997, //更新赛季通知
     OffLine = 39998,          //掉线
     LeaveRoom = 39999,        //离开房间
@@ -155,6 +167,9 @@ pub enum RankCode {
     UpdateRank = 40002,       //更新排行榜
     ModifyNickName = 40003,   //修改名字
     UpdateSeasonPush = 40004, //更新赛季通知
+    RankAroundMe = 40005,     //请求自己附近的排行(客户端请求指令)
+    RankPage = 40006,         //分页请求排行榜(客户端请求指令)
+    RankFriends = 40007,      //请求好友排行(客户端请求指令)
     Max = 50000,
 }
 
@@ -268,12 +283,22 @@ pub enum ClientCode {
     ModifyGradeFrameAndSoul = 10082,
     //获得上赛季排行榜返回
     GetLastSeasonRank = 10083,
+    //请求自己附近的排行返回
+    RankAroundMe = 10084,
+    //分页请求排行榜返回
+    RankPage = 10085,
+    //请求好友排行返回
+    RankFriends = 10086,
     //任务推送
     MissionNoice = 10101,
     //购买推送
     BuyNoice = 10102,
     //匹配惩罚推送
     PunishMatchPush = 10103,
+    //会话token推送(断线重连用),body:session_token(u64)|resume_window(u32)
+    SessionToken = 10104,
+    //断线重连返回,body:is_succ(u8)
+    Resume = 10105,
     //选择ai角色通知
     ChoiceAINotice = 10201,
     //最大命令号
@@ -0,0 +1,203 @@
+use log::warn;
+use std::io::Read;
+use std::sync::atomic::{AtomicU64, AtomicU8, AtomicUsize, Ordering};
+
+///压缩算法
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum CompressType {
+    Zstd = 0,
+    Lz4 = 1,
+}
+
+impl CompressType {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            1 => CompressType::Lz4,
+            _ => CompressType::Zstd,
+        }
+    }
+}
+
+///zstd压缩等级，取低等级换速度
+const ZSTD_LEVEL: i32 = 1;
+
+///客户端发来的包解压后最大字节数，超过则拒绝，防止一个小包解压出巨大的内存(解压炸弹)
+pub const MAX_CLIENT_DECOMPRESS_SIZE: usize = 1024 * 1024;
+///服务端之间的包解压后最大字节数
+pub const MAX_SERVER_DECOMPRESS_SIZE: usize = 64 * 1024 * 1024;
+
+///body超过这个字节数才压缩，为0则不压缩
+static COMPRESS_THRESHOLD: AtomicUsize = AtomicUsize::new(0);
+///发送时使用的压缩算法
+static COMPRESS_TYPE: AtomicU8 = AtomicU8::new(CompressType::Zstd as u8);
+
+static COMPRESS_TIMES: AtomicU64 = AtomicU64::new(0);
+static RAW_BYTES: AtomicU64 = AtomicU64::new(0);
+static COMPRESSED_BYTES: AtomicU64 = AtomicU64::new(0);
+static DECOMPRESS_TIMES: AtomicU64 = AtomicU64::new(0);
+
+///压缩统计数据
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CompressMetrics {
+    pub compress_times: u64,   //压缩次数
+    pub raw_bytes: u64,        //压缩前总字节数
+    pub compressed_bytes: u64, //压缩后总字节数
+    pub saved_bytes: u64,      //节省的字节数
+    pub decompress_times: u64, //解压次数
+}
+
+///初始化压缩配置
+///threshold:body超过多少字节才压缩，为0则不压缩
+///compress_type:"zstd"或者"lz4"
+pub fn init(threshold: usize, compress_type: &str) {
+    let compress_type = match compress_type {
+        "lz4" => CompressType::Lz4,
+        _ => CompressType::Zstd,
+    };
+    COMPRESS_THRESHOLD.store(threshold, Ordering::SeqCst);
+    COMPRESS_TYPE.store(compress_type as u8, Ordering::SeqCst);
+}
+
+///测试用:独占全局压缩配置，drop时恢复原来的配置，避免并行跑的测试互相改配置
+#[cfg(test)]
+pub(crate) struct TestConfig {
+    threshold: usize,
+    compress_type: u8,
+    _lock: std::sync::MutexGuard<'static, ()>,
+}
+
+#[cfg(test)]
+pub(crate) fn test_config(threshold: usize, compress_type: &str) -> TestConfig {
+    static LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+    let lock = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let config = TestConfig {
+        threshold: COMPRESS_THRESHOLD.load(Ordering::SeqCst),
+        compress_type: COMPRESS_TYPE.load(Ordering::SeqCst),
+        _lock: lock,
+    };
+    init(threshold, compress_type);
+    config
+}
+
+#[cfg(test)]
+impl Drop for TestConfig {
+    fn drop(&mut self) {
+        COMPRESS_THRESHOLD.store(self.threshold, Ordering::SeqCst);
+        COMPRESS_TYPE.store(self.compress_type, Ordering::SeqCst);
+    }
+}
+
+///获得压缩统计数据
+pub fn metrics() -> CompressMetrics {
+    let raw_bytes = RAW_BYTES.load(Ordering::Relaxed);
+    let compressed_bytes = COMPRESSED_BYTES.load(Ordering::Relaxed);
+    CompressMetrics {
+        compress_times: COMPRESS_TIMES.load(Ordering::Relaxed),
+        raw_bytes,
+        compressed_bytes,
+        saved_bytes: raw_bytes.saturating_sub(compressed_bytes),
+        decompress_times: DECOMPRESS_TIMES.load(Ordering::Relaxed),
+    }
+}
+
+///超过阈值就压缩，压缩后没有变小则返回None
+pub fn try_compress(bytes: &[u8]) -> Option<(CompressType, Vec<u8>)> {
+    let threshold = COMPRESS_THRESHOLD.load(Ordering::Relaxed);
+    let compress_type = CompressType::from_u8(COMPRESS_TYPE.load(Ordering::Relaxed));
+    let res = compress(bytes, threshold, compress_type)?;
+    COMPRESS_TIMES.fetch_add(1, Ordering::Relaxed);
+    RAW_BYTES.fetch_add(bytes.len() as u64, Ordering::Relaxed);
+    COMPRESSED_BYTES.fetch_add(res.len() as u64, Ordering::Relaxed);
+    Some((compress_type, res))
+}
+
+///超过阈值(为0则不压缩)就按指定算法压缩，压缩后没有变小则返回None
+pub fn compress(bytes: &[u8], threshold: usize, compress_type: CompressType) -> Option<Vec<u8>> {
+    if threshold == 0 || bytes.len() < threshold {
+        return None;
+    }
+    let res = match compress_type {
+        CompressType::Zstd => zstd::stream::encode_all(bytes, ZSTD_LEVEL),
+        CompressType::Lz4 => Ok(lz4_flex::compress_prepend_size(bytes)),
+    };
+    let res = match res {
+        Ok(res) => res,
+        Err(e) => {
+            warn!("{:?}", e);
+            return None;
+        }
+    };
+    if res.len() >= bytes.len() {
+        return None;
+    }
+    Some(res)
+}
+
+///解压，解压后超过max_size字节则返回错误
+pub fn decompress(
+    compress_type: CompressType,
+    bytes: &[u8],
+    max_size: usize,
+) -> anyhow::Result<Vec<u8>> {
+    let res = match compress_type {
+        CompressType::Zstd => {
+            //最多读max_size+1个字节，读满了说明超过上限
+            let decoder = zstd::stream::read::Decoder::new(bytes)?;
+            let mut res = Vec::new();
+            decoder
+                .take((max_size as u64).saturating_add(1))
+                .read_to_end(&mut res)?;
+            if res.len() > max_size {
+                anyhow::bail!("decompressed size is over limit!max_size:{}", max_size)
+            }
+            res
+        }
+        CompressType::Lz4 => {
+            //lz4的原始大小是发送方写在前4个字节的，不能信任，先校验再分配内存
+            if bytes.len() < 4 {
+                anyhow::bail!("lz4 body is too short!len:{}", bytes.len())
+            }
+            let size = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize;
+            if size > max_size {
+                anyhow::bail!(
+                    "decompressed size is over limit!size:{},max_size:{}",
+                    size,
+                    max_size
+                )
+            }
+            lz4_flex::decompress_size_prepended(bytes)?
+        }
+    };
+    DECOMPRESS_TIMES.fetch_add(1, Ordering::Relaxed);
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decompress_limit() {
+        let data = vec![7_u8; 4096];
+        for compress_type in [CompressType::Zstd, CompressType::Lz4].iter() {
+            let bytes = compress(data.as_slice(), 64, *compress_type).unwrap();
+            assert!(bytes.len() < data.len());
+            let res = decompress(*compress_type, bytes.as_slice(), data.len()).unwrap();
+            assert_eq!(res, data);
+            //解压后超过上限
+            assert!(decompress(*compress_type, bytes.as_slice(), data.len() - 1).is_err());
+        }
+        //没到阈值不压缩
+        assert!(compress(data.as_slice(), data.len() + 1, CompressType::Zstd).is_none());
+
+        //lz4伪造一个很大的原始大小
+        let mut bytes = compress(data.as_slice(), 64, CompressType::Lz4).unwrap();
+        bytes[..4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(decompress(
+            CompressType::Lz4,
+            bytes.as_slice(),
+            MAX_CLIENT_DECOMPRESS_SIZE
+        )
+        .is_err());
+    }
+}
@@ -1,25 +1,211 @@
 use crate::json::*;
+use log::warn;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::error::Error;
-use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::path::Path;
 use std::str::FromStr;
 
+///环境变量覆盖配置的前缀,CONF_REDIS_PASS覆盖redis_pass,嵌套的key用"__"分隔
+///对不上配置字段的环境变量只打警告，不影响启动
+pub const ENV_PREFIX: &str = "CONF_";
+
+///以这个结尾的key的值是文件路径，从文件读出真正的值，比如"redis_pass_file":"/run/secrets/redis_pass"
+pub const SECRET_FILE_SUFFIX: &str = "_file";
+
+///打印配置时要打码的key
+pub const SECRET_KEYS: [&str; 6] = [
+    "redis_pass",
+    "mysql",
+    "mysql_pass",
+    "web_api_key",
+    "app_secret",
+    "password",
+];
+
+///配置来源，后面的覆盖前面的
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ConfSource {
+    File,       //配置文件
+    Env,        //环境变量
+    Cli,        //命令行参数
+    SecretFile, //密钥文件
+}
+
+///配置结构体的校验，类型之外的检查(取值范围，地址格式等)
+pub trait Validate {
+    fn validate(&self) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
 ///conf of struct
 #[derive(Default)]
 pub struct Conf {
     pub conf: HashMap<String, JsonValue>,
+    pub path: String,                         //配置文件路径
+    pub sources: HashMap<String, ConfSource>, //不是来自配置文件的key
 }
 
 impl Conf {
-    ///初始化配置文件
+    ///初始化配置文件，读不到或者格式不对直接panic
     pub fn init(path: &str) -> Conf {
-        let conf = read_conf_from_file(path);
-        let conf = Conf {
-            conf: conf.unwrap(),
+        match Conf::load(path) {
+            Ok(conf) => conf,
+            Err(e) => panic!("加载配置失败!{:?}", e),
+        }
+    }
+
+    ///读取配置文件(json或者key=value格式)，再依次叠加环境变量，命令行参数，最后读取密钥文件
+    pub fn load(path: &str) -> anyhow::Result<Conf> {
+        let map = match read_conf_from_file(path) {
+            Ok(map) => map,
+            Err(e) => anyhow::bail!("读取配置文件失败!path:{},{}", path, e),
+        };
+        let mut conf = Conf::new(map);
+        conf.path = path.to_owned();
+        let env = std::env::vars().filter_map(|(key, value)| {
+            let key = key.strip_prefix(ENV_PREFIX)?;
+            Some((key.to_lowercase().replace("__", "."), value))
+        });
+        conf.apply_overrides(env, ConfSource::Env)?;
+        conf.apply_overrides(parse_args(std::env::args().skip(1)), ConfSource::Cli)?;
+        conf.load_secret_files()?;
+        Ok(conf)
+    }
+
+    ///覆盖配置，key可以是"a.b.0.c"这样的路径
+    ///原来是字符串的保持字符串，其他的按json解析，解析不了的当字符串
+    ///环境变量里可能有其他程序用的CONF_开头的变量，路径不存在的跳过，命令行参数的直接返回错误
+    pub fn apply_overrides<I>(&mut self, overrides: I, source: ConfSource) -> anyhow::Result<()>
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        for (path, value) in overrides {
+            match self.set_path(path.as_str(), value) {
+                Ok(top) => {
+                    self.sources.insert(top, source);
+                }
+                Err(e) if source == ConfSource::Env => {
+                    warn!("忽略环境变量{}!{}", env_name(path.as_str()), e)
+                }
+                Err(e) => anyhow::bail!("{},source:{:?}", e, source),
+            }
+        }
+        Ok(())
+    }
+
+    ///按路径写入值，返回顶层的key
+    fn set_path(&mut self, path: &str, value: String) -> anyhow::Result<String> {
+        let mut keys = path.split('.');
+        let top = keys.next().unwrap_or_default().to_owned();
+        if top.is_empty() {
+            anyhow::bail!("配置覆盖的key为空!{:?}", path);
+        }
+        let keys: Vec<&str> = keys.collect();
+        if !keys.is_empty() && !self.conf.contains_key(&top) {
+            anyhow::bail!("配置覆盖的路径不存在!{:?}", path);
+        }
+        let mut target = self.conf.entry(top.clone()).or_insert(JsonValue::Null);
+        for key in keys {
+            target = match child_mut(target, key) {
+                Some(v) => v,
+                None => anyhow::bail!("配置覆盖的路径不存在!{:?}", path),
+            };
+        }
+        *target = if target.is_string() {
+            JsonValue::from(value)
+        } else {
+            JsonValue::from_str(value.as_str()).unwrap_or_else(|_| JsonValue::from(value))
         };
-        conf
+        Ok(top)
+    }
+
+    ///把xxx_file的key替换成xxx，值是文件内容(去掉首尾空白)
+    pub fn load_secret_files(&mut self) -> anyhow::Result<()> {
+        let keys: Vec<String> = self.conf.keys().cloned().collect();
+        for key in keys {
+            let mut value = self.conf.remove(&key).unwrap();
+            let changed = resolve_secret_files(&mut value)?;
+            match key.strip_suffix(SECRET_FILE_SUFFIX) {
+                Some(real_key) if value.is_string() => {
+                    let res = read_secret_file(value.as_str().unwrap())?;
+                    self.conf.insert(real_key.to_owned(), res);
+                    self.sources
+                        .insert(real_key.to_owned(), ConfSource::SecretFile);
+                }
+                _ => {
+                    if changed {
+                        self.sources.insert(key.clone(), ConfSource::SecretFile);
+                    }
+                    self.conf.insert(key.clone(), value);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    ///按配置结构体解析并校验
+    ///类型不对，有拼错或者不认识的key，validate不通过都返回错误
+    ///环境变量带进来的不认识的key只打警告
+    pub fn parse<T>(&self) -> anyhow::Result<T>
+    where
+        T: DeserializeOwned + Serialize + Validate,
+    {
+        let value = JsonValue::Object(self.conf.clone().into_iter().collect());
+        let res: T = match serde_json::from_value(value) {
+            Ok(res) => res,
+            Err(e) => anyhow::bail!("配置错误!path:{},{}", self.path, e),
+        };
+        let known = match serde_json::to_value(&res)? {
+            JsonValue::Object(map) => map,
+            _ => anyhow::bail!("配置结构体必须是struct!path:{}", self.path),
+        };
+        let mut unknown = Vec::new();
+        for key in self.conf.keys() {
+            if known.contains_key(key.as_str()) {
+                continue;
+            }
+            if self.sources.get(key) == Some(&ConfSource::Env) {
+                warn!("忽略环境变量{},没有对应的配置!", env_name(key));
+                continue;
+            }
+            let res = match similar_key(key, known.keys()) {
+                Some(similar) => format!("{}(是不是{}?)", key, similar),
+                None => key.clone(),
+            };
+            unknown.push(res);
+        }
+        if !unknown.is_empty() {
+            unknown.sort();
+            anyhow::bail!(
+                "配置有不认识的key!path:{},keys:{}",
+                self.path,
+                unknown.join(",")
+            );
+        }
+        if let Err(e) = res.validate() {
+            anyhow::bail!("配置错误!path:{},{}", self.path, e);
+        }
+        Ok(res)
+    }
+
+    ///生效的配置，每行一个key，敏感信息打码，标出不是来自配置文件的key
+    pub fn dump<T: Serialize>(&self, config: &T) -> String {
+        let mut value = serde_json::to_value(config).unwrap_or_default();
+        mask_secrets(&mut value);
+        let mut res = format!("生效的配置:{}", self.path);
+        if let JsonValue::Object(map) = value {
+            for (key, value) in map {
+                res.push_str(format!("\n    {} = {}", key, value).as_str());
+                if let Some(source) = self.sources.get(&key) {
+                    res.push_str(format!(" ({:?})", source).as_str());
+                }
+            }
+        }
+        res
     }
 
     pub fn get_f64(&self, key: &str, default: f64) -> f64 {
@@ -27,7 +213,8 @@ impl Conf {
         if value.is_none() {
             return default;
         }
-        value.unwrap().as_f64().unwrap()
+        let value = value.unwrap().as_f64();
+        value.unwrap_or_else(|| panic!("配置类型错误,{}不是数字!", key))
     }
 
     ///拿整数
@@ -36,7 +223,8 @@ impl Conf {
         if value.is_none() {
             return default;
         }
-        value.unwrap().as_i64().unwrap() as usize
+        let value = value.unwrap().as_i64();
+        value.unwrap_or_else(|| panic!("配置类型错误,{}不是整数!", key)) as usize
     }
 
     pub fn get_isize(&self, key: &str, default: isize) -> isize {
@@ -44,7 +232,8 @@ impl Conf {
         if value.is_none() {
             return default;
         }
-        value.unwrap().as_i64().unwrap() as isize
+        let value = value.unwrap().as_i64();
+        value.unwrap_or_else(|| panic!("配置类型错误,{}不是整数!", key)) as isize
     }
 
     ///拿bool
@@ -53,7 +242,8 @@ impl Conf {
         if value.is_none() {
             return default;
         }
-        value.unwrap().as_bool().unwrap()
+        let value = value.unwrap().as_bool();
+        value.unwrap_or_else(|| panic!("配置类型错误,{}不是bool!", key))
     }
 
     ///拿字符切片
@@ -75,52 +265,339 @@ impl Conf {
     }
 
     pub fn new(map: HashMap<String, JsonValue>) -> Self {
-        let mut conf = Conf::default();
-        conf.conf = map;
-        conf
+        Conf {
+            conf: map,
+            ..Default::default()
+        }
+    }
+}
+
+///各个服务器共用的配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BaseConfig {
+    pub redis_add: String, //redis地址
+    #[serde(deserialize_with = "de_string")]
+    pub redis_pass: String, //redis密码
+    pub info_log_path: String, //info日志路径
+    pub error_log_path: String, //error日志路径
+    pub compress_threshold: usize, //超过这个大小的消息压缩，为0不压缩
+    pub compress_type: String, //压缩算法:"zstd"或者"lz4"
+    pub debug: bool,       //调试模式
+}
+
+impl Default for BaseConfig {
+    fn default() -> Self {
+        BaseConfig {
+            redis_add: String::new(),
+            redis_pass: String::new(),
+            info_log_path: String::new(),
+            error_log_path: String::new(),
+            compress_threshold: 0,
+            compress_type: "zstd".to_owned(),
+            debug: false,
+        }
+    }
+}
+
+impl Validate for BaseConfig {
+    fn validate(&self) -> anyhow::Result<()> {
+        if !self.redis_add.starts_with("redis://") {
+            anyhow::bail!("redis_add必须以redis://开头!{:?}", self.redis_add);
+        }
+        if self.info_log_path.is_empty() || self.error_log_path.is_empty() {
+            anyhow::bail!("info_log_path和error_log_path不能为空!");
+        }
+        match self.compress_type.as_str() {
+            "zstd" | "lz4" => Ok(()),
+            _ => anyhow::bail!("compress_type只能是zstd或者lz4!{:?}", self.compress_type),
+        }
+    }
+}
+
+///字符串字段也接受数字，key=value格式和环境变量里的纯数字密码会被解析成数字
+pub fn de_string<'de, D: serde::Deserializer<'de>>(d: D) -> Result<String, D::Error> {
+    match JsonValue::deserialize(d)? {
+        JsonValue::String(s) => Ok(s),
+        JsonValue::Number(n) => Ok(n.to_string()),
+        v => Err(serde::de::Error::custom(format!("需要字符串,实际是{}", v))),
+    }
+}
+
+///检查监听地址格式，比如"0.0.0.0:9091"
+pub fn check_address(key: &str, address: &str) -> anyhow::Result<()> {
+    if let Err(e) = address.parse::<std::net::SocketAddr>() {
+        anyhow::bail!("{}不是合法的地址!{:?},{}", key, address, e);
+    }
+    Ok(())
+}
+
+///命令行参数里的--key=value
+fn parse_args<I: Iterator<Item = String>>(args: I) -> Vec<(String, String)> {
+    args.filter_map(|arg| {
+        let arg = arg.strip_prefix("--")?;
+        let index = arg.find('=')?;
+        Some((arg[..index].to_owned(), arg[index + 1..].to_owned()))
+    })
+    .collect()
+}
+
+///配置路径对应的环境变量名,"a.b"对应CONF_A__B
+fn env_name(path: &str) -> String {
+    format!("{}{}", ENV_PREFIX, path.replace('.', "__").to_uppercase())
+}
+
+fn child_mut<'a>(value: &'a mut JsonValue, key: &str) -> Option<&'a mut JsonValue> {
+    match value {
+        JsonValue::Array(array) => array.get_mut(key.parse::<usize>().ok()?),
+        JsonValue::Object(map) => map.get_mut(key),
+        _ => None,
+    }
+}
+
+///嵌套的json里的xxx_file也替换掉，返回是否有替换
+fn resolve_secret_files(value: &mut JsonValue) -> anyhow::Result<bool> {
+    let mut changed = false;
+    match value {
+        JsonValue::Array(array) => {
+            for v in array.iter_mut() {
+                changed |= resolve_secret_files(v)?;
+            }
+        }
+        JsonValue::Object(map) => {
+            let keys: Vec<String> = map.keys().cloned().collect();
+            for key in keys {
+                let real_key = key.strip_suffix(SECRET_FILE_SUFFIX);
+                let path = map.get(&key).and_then(|v| v.as_str()).map(|v| v.to_owned());
+                match (real_key, path) {
+                    (Some(real_key), Some(path)) => {
+                        let res = read_secret_file(path.as_str())?;
+                        map.insert(real_key.to_owned(), res);
+                        map.remove(&key);
+                        changed = true;
+                    }
+                    _ => changed |= resolve_secret_files(map.get_mut(&key).unwrap())?,
+                }
+            }
+        }
+        _ => {}
+    }
+    Ok(changed)
+}
+
+fn read_secret_file(path: &str) -> anyhow::Result<JsonValue> {
+    match std::fs::read_to_string(path) {
+        Ok(res) => Ok(JsonValue::from(res.trim())),
+        Err(e) => anyhow::bail!("读取密钥文件失败!path:{},{}", path, e),
     }
 }
 
-///读取配置文件
+fn mask_secrets(value: &mut JsonValue) {
+    match value {
+        JsonValue::Array(array) => array.iter_mut().for_each(mask_secrets),
+        JsonValue::Object(map) => {
+            for (key, v) in map.iter_mut() {
+                if SECRET_KEYS.contains(&key.as_str()) && !v.is_null() {
+                    *v = JsonValue::from("******");
+                } else {
+                    mask_secrets(v);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+///找编辑距离最近的已知key,用来提示拼写错误
+fn similar_key<'a, I: Iterator<Item = &'a String>>(key: &str, known: I) -> Option<&'a String> {
+    known
+        .map(|k| (edit_distance(key, k), k))
+        .filter(|(distance, _)| *distance <= 2)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, k)| k)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut last = row[0];
+        row[0] = i + 1;
+        for j in 0..b.len() {
+            let old = row[j + 1];
+            let cost = if ca == b[j] { last } else { last + 1 };
+            row[j + 1] = cost.min(row[j] + 1).min(old + 1);
+            last = old;
+        }
+    }
+    row[b.len()]
+}
+
+///读取配置文件，第一个非空字符是"{"的按json解析，否则按key=value解析
 fn read_conf_from_file<P: AsRef<Path>>(
     path: P,
 ) -> Result<HashMap<String, JsonValue>, Box<dyn Error>> {
-    // Open the file in read-only mode with buffer.
-    let file = File::open(path)?;
-    let reader = BufReader::new(file);
-
-    // Read the JSON contents of the file as an instance of `map`.
-    let map = serde_json::from_reader(reader)?;
-
-    // Return the `map`.
-    Ok(map)
+    let content = std::fs::read_to_string(path)?;
+    if content.trim_start().starts_with('{') {
+        // Read the JSON contents of the file as an instance of `map`.
+        let map = serde_json::from_str(content.as_str())?;
+        return Ok(map);
+    }
+    parse_key_value(BufReader::new(content.as_bytes()))
 }
 
-pub fn read(path: &str) -> anyhow::Result<Conf> {
-    let file = File::open(path).unwrap();
-    let buf_reader = BufReader::new(file);
+///解析key=value格式，#开头的是注释
+fn parse_key_value<R: BufRead>(reader: R) -> Result<HashMap<String, JsonValue>, Box<dyn Error>> {
     let mut map = HashMap::new();
-    for line in buf_reader.lines() {
+    for (index, line) in reader.lines().enumerate() {
         let a = line?;
-
-        if a.starts_with("#") {
+        let a = a.trim();
+        if a.starts_with('#') {
             continue;
         }
         if a.is_empty() {
             continue;
         }
-        let v: Vec<&str> = a.split("=").into_iter().map(|x| x.trim()).collect();
-        let key = v.get(0).unwrap().to_owned().to_owned();
-        let value = serde_json::Value::from_str(v.get(1).unwrap().to_owned());
+        let v: Vec<&str> = a.splitn(2, '=').map(|x| x.trim()).collect();
+        if v.len() != 2 || v[0].is_empty() {
+            return Err(format!("第{}行不是key=value格式!{:?}", index + 1, a).into());
+        }
+        let value = serde_json::Value::from_str(v[1]);
         match value {
-            Ok(v) => {
-                map.insert(key, v);
+            Ok(value) => {
+                map.insert(v[0].to_owned(), value);
             }
             Err(_) => {
-                let v = serde_json::Value::from(v.get(1).unwrap().to_owned());
-                map.insert(key, v);
+                map.insert(v[0].to_owned(), serde_json::Value::from(v[1]));
             }
         }
     }
-    Ok(Conf::new(map))
+    Ok(map)
+}
+
+///读取配置文件，格式自动识别，同Conf::load
+pub fn read(path: &str) -> anyhow::Result<Conf> {
+    Conf::load(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Default, Serialize, Deserialize)]
+    #[serde(default)]
+    struct TestConfig {
+        #[serde(flatten)]
+        base: BaseConfig,
+        tcp_port: String,
+        capacity: u32,
+    }
+
+    impl Validate for TestConfig {
+        fn validate(&self) -> anyhow::Result<()> {
+            self.base.validate()?;
+            check_address("tcp_port", self.tcp_port.as_str())
+        }
+    }
+
+    fn conf(text: &str) -> Conf {
+        Conf::new(parse_key_value(BufReader::new(text.as_bytes())).unwrap())
+    }
+
+    const TEXT: &str = "redis_add = redis://localhost/\nredis_pass = 123\ninfo_log_path = info.log\nerror_log_path = error.log\ntcp_port = 0.0.0.0:9091\ncapacity = 10";
+
+    #[test]
+    fn parse_and_override() {
+        let text = TEXT;
+        let mut conf = conf(text);
+        let overrides = vec![
+            ("capacity".to_owned(), "20".to_owned()),
+            ("redis_pass".to_owned(), "456".to_owned()),
+        ];
+        conf.apply_overrides(overrides, ConfSource::Env).unwrap();
+        let config: TestConfig = conf.parse().unwrap();
+        assert_eq!(config.capacity, 20);
+        assert_eq!(config.base.compress_type, "zstd");
+        let dump = conf.dump(&config);
+        assert!(dump.contains("redis_pass = \"******\" (Env)"));
+
+        //类型不对
+        let mut conf = conf_with(text, "capacity = abc");
+        assert!(conf.parse::<TestConfig>().is_err());
+        //拼错的key
+        conf = conf_with(text, "capacty = 10");
+        let err = conf.parse::<TestConfig>().unwrap_err().to_string();
+        assert!(err.contains("capacty(是不是capacity?)"));
+        //校验不通过
+        conf = conf_with(text, "tcp_port = 9091");
+        assert!(conf.parse::<TestConfig>().is_err());
+    }
+
+    #[test]
+    fn env_and_cli_override() {
+        let path = std::env::temp_dir().join(format!("conf_test_{}.conf", std::process::id()));
+        std::fs::write(&path, TEXT).unwrap();
+        std::env::set_var("CONF_CAPACITY", "40");
+        //其他程序用的变量不影响启动
+        std::env::set_var("CONF_UNRELATED_TOOL_HOME", "/opt/tool");
+        std::env::set_var("CONF_UNRELATED__NESTED", "1");
+        let conf = Conf::load(path.to_str().unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        std::env::remove_var("CONF_CAPACITY");
+        std::env::remove_var("CONF_UNRELATED_TOOL_HOME");
+        std::env::remove_var("CONF_UNRELATED__NESTED");
+        let config: TestConfig = conf.parse().unwrap();
+        assert_eq!(config.capacity, 40);
+        assert!(conf.dump(&config).contains("capacity = 40 (Env)"));
+
+        //命令行参数覆盖环境变量，拼错的key和不存在的路径报错
+        let mut conf = conf;
+        let args = vec!["--capacity=50".to_owned(), "-v".to_owned()];
+        conf.apply_overrides(parse_args(args.into_iter()), ConfSource::Cli)
+            .unwrap();
+        assert_eq!(conf.parse::<TestConfig>().unwrap().capacity, 50);
+        let overrides = vec![("tcp_port.0".to_owned(), "1".to_owned())];
+        assert!(conf.apply_overrides(overrides, ConfSource::Cli).is_err());
+        let overrides = vec![("capcity".to_owned(), "1".to_owned())];
+        conf.apply_overrides(overrides, ConfSource::Cli).unwrap();
+        assert!(conf.parse::<TestConfig>().is_err());
+    }
+
+    #[test]
+    fn load_secret_files() {
+        let path = std::env::temp_dir().join(format!("conf_secret_{}", std::process::id()));
+        std::fs::write(&path, "secret\n").unwrap();
+        let path_str = path.to_str().unwrap();
+        let mut conf = conf_with(TEXT, format!("redis_pass_file = {}", path_str).as_str());
+        let steam = format!("[{{\"web_api_key_file\":{:?}}}]", path_str);
+        conf.conf.insert(
+            "steam".to_owned(),
+            JsonValue::from_str(steam.as_str()).unwrap(),
+        );
+        conf.load_secret_files().unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(!conf.conf.contains_key("redis_pass_file"));
+        assert_eq!(
+            conf.sources.get("redis_pass"),
+            Some(&ConfSource::SecretFile)
+        );
+        assert_eq!(conf.conf["steam"][0]["web_api_key"], "secret");
+        assert_eq!(conf.sources.get("steam"), Some(&ConfSource::SecretFile));
+        conf.conf.remove("steam");
+        let config: TestConfig = conf.parse().unwrap();
+        assert_eq!(config.base.redis_pass, "secret");
+        assert!(conf
+            .dump(&config)
+            .contains("redis_pass = \"******\" (SecretFile)"));
+
+        //密钥文件不存在
+        let mut conf = conf_with(TEXT, "redis_pass_file = /not/exist/redis_pass");
+        assert!(conf.load_secret_files().is_err());
+    }
+
+    ///后面的行覆盖前面的
+    fn conf_with(text: &str, line: &str) -> Conf {
+        conf(format!("{}\n{}", text, line).as_str())
+    }
 }
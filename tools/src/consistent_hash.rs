@@ -0,0 +1,120 @@
+use std::collections::BTreeMap;
+
+///每个节点默认的虚拟节点数量
+pub const DEFAULT_REPLICAS: usize = 160;
+
+///一致性哈希环
+///用虚拟节点把每个服务器打散到环上，增减服务器时只有相邻区间的key需要迁移
+#[derive(Debug, Clone)]
+pub struct HashRing {
+    replicas: usize,             //每个节点的虚拟节点数量
+    ring: BTreeMap<u64, String>, //环,key:虚拟节点hash,value:节点
+    nodes: Vec<String>,          //所有节点
+}
+
+impl Default for HashRing {
+    fn default() -> Self {
+        HashRing::new(DEFAULT_REPLICAS)
+    }
+}
+
+impl HashRing {
+    pub fn new(replicas: usize) -> Self {
+        HashRing {
+            replicas: replicas.max(1),
+            ring: BTreeMap::new(),
+            nodes: Vec::new(),
+        }
+    }
+
+    ///添加节点，已经存在则忽略
+    pub fn add(&mut self, node: &str) {
+        if self.contains(node) {
+            return;
+        }
+        for i in 0..self.replicas {
+            let hash = fnv1a(format!("{}#{}", node, i).as_bytes());
+            self.ring.insert(hash, node.to_owned());
+        }
+        self.nodes.push(node.to_owned());
+    }
+
+    ///删除节点
+    pub fn remove(&mut self, node: &str) {
+        if !self.contains(node) {
+            return;
+        }
+        self.ring.retain(|_, v| v != node);
+        self.nodes.retain(|x| x != node);
+    }
+
+    pub fn contains(&self, node: &str) -> bool {
+        self.nodes.iter().any(|x| x == node)
+    }
+
+    pub fn nodes(&self) -> &[String] {
+        self.nodes.as_slice()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    ///找到key落在环上的节点(顺时针第一个虚拟节点)
+    pub fn get(&self, key: u32) -> Option<&String> {
+        if self.ring.is_empty() {
+            return None;
+        }
+        let hash = fnv1a(&key.to_le_bytes());
+        self.ring
+            .range(hash..)
+            .next()
+            .or_else(|| self.ring.iter().next())
+            .map(|(_, node)| node)
+    }
+}
+
+///FNV-1a哈希，不同进程，不同版本算出来的结果一样
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for b in bytes {
+        hash ^= *b as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn remove_node_only_moves_its_keys() {
+        let mut ring = HashRing::default();
+        for node in &["game1", "game2", "game3"] {
+            ring.add(node);
+        }
+        let mut placed = HashMap::new();
+        let mut count: HashMap<String, usize> = HashMap::new();
+        for user_id in 1..=3000 {
+            let node = ring.get(user_id).unwrap().clone();
+            *count.entry(node.clone()).or_default() += 1;
+            placed.insert(user_id, node);
+        }
+        //分布不能太偏
+        for node in ring.nodes() {
+            assert!(count[node] > 600, "{}:{}", node, count[node]);
+        }
+
+        ring.remove("game2");
+        for (user_id, node) in placed {
+            let now = ring.get(user_id).unwrap();
+            if node != "game2" {
+                assert_eq!(*now, node);
+            } else {
+                assert_ne!(now, "game2");
+            }
+        }
+    }
+}
@@ -64,3 +64,48 @@ pub fn read_disc(path: &str) -> Result<Vec<HashMap<String, DataType>>, Error> {
     }
     Ok(res)
 }
+
+///excel里的一行数据
+#[derive(Debug, Clone, Default)]
+pub struct SheetRow {
+    pub row: usize,           //excel里的行号(从1开始)
+    pub cells: Vec<DataType>, //这一行所有格子
+}
+
+///第一个sheet,第2行是字段名(遇到空格子结束),第5行开始是数据
+#[derive(Debug, Clone, Default)]
+pub struct Sheet {
+    pub fields: Vec<String>, //字段名
+    pub rows: Vec<SheetRow>, //数据行
+}
+
+///读取第一个sheet,和read的格式一样，但是保留行号和格子原始类型，导表用
+pub fn read_sheet(path: &str) -> anyhow::Result<Sheet> {
+    let workbook: Result<Xlsx<_>, _> = open_workbook(path);
+    let mut workbook = match workbook {
+        Ok(workbook) => workbook,
+        Err(e) => anyhow::bail!("打开excel失败!path:{},{:?}", path, e),
+    };
+    let range = match workbook.worksheet_range_at(0) {
+        Some(Ok(range)) => range,
+        Some(Err(e)) => anyhow::bail!("读取sheet失败!path:{},{:?}", path, e),
+        None => anyhow::bail!("excel里没有sheet!path:{}", path),
+    };
+    let mut sheet = Sheet::default();
+    for (index, data_type) in range.rows().enumerate() {
+        let row = index + 1;
+        if row == 2 {
+            for cell in data_type.iter() {
+                if cell.is_empty() {
+                    break;
+                }
+                sheet.fields.push(cell.to_string().trim().to_owned());
+            }
+        }
+        if row > 4 {
+            let cells = data_type.to_vec();
+            sheet.rows.push(SheetRow { row, cells });
+        }
+    }
+    Ok(sheet)
+}
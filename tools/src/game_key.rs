@@ -0,0 +1,69 @@
+use crate::cmd_code::ServerCommonCode;
+use crate::util::bytebuf::ByteBuf;
+use crate::util::packet::Packet;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+///游戏和区服标识
+///多个游戏或者区服共用一个redis和游戏中心时，用来隔离赛季，排行榜，worldboss等数据
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct GameKey {
+    pub game_id: u32,   //游戏id
+    pub region_id: u32, //区服id
+}
+
+impl GameKey {
+    pub fn new(game_id: u32, region_id: u32) -> Self {
+        GameKey { game_id, region_id }
+    }
+
+    ///redis key的命名空间
+    pub fn namespace(&self) -> String {
+        format!("{}:{}", self.game_id, self.region_id)
+    }
+
+    ///服务器连上游戏中心时发送的注册包,body:game_id(u32)|region_id(u32)
+    pub fn build_register_bytes(&self) -> Vec<u8> {
+        let mut bb = ByteBuf::new();
+        bb.push_u32(self.game_id);
+        bb.push_u32(self.region_id);
+        Packet::build_packet_bytes(
+            ServerCommonCode::Register.into_u32(),
+            0,
+            bb.into_bytes(),
+            true,
+            false,
+        )
+    }
+
+    ///解析注册包body
+    pub fn from_register_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        let mut bb = ByteBuf::from(bytes);
+        let game_id = bb.read_u32()?;
+        let region_id = bb.read_u32()?;
+        Ok(GameKey::new(game_id, region_id))
+    }
+}
+
+impl Display for GameKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.game_id, self.region_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn register_bytes_round_trip() {
+        let game_key = GameKey::new(102, 3);
+        let bytes = game_key.build_register_bytes();
+        let mut packets = Packet::build_array_from_server(bytes).unwrap();
+        let packet = packets.pop_front().unwrap();
+        assert_eq!(packet.get_cmd(), ServerCommonCode::Register.into_u32());
+        let res = GameKey::from_register_bytes(packet.get_data()).unwrap();
+        assert_eq!(res, game_key);
+        assert_eq!(res.namespace(), "102:3");
+    }
+}
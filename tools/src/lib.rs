@@ -6,7 +6,6 @@ mod tests {
 
     #[test]
     fn it_works() {
-        //crate::redis_pool::test_api("redis://localhost/","reison");
         // crate::protos::proto();
         // let m = || {
         //     crate::rpc_server::test_rpc_server();
@@ -31,17 +30,25 @@ mod tests {
 }
 pub mod binary;
 pub mod cmd_code;
+pub mod compress;
 pub mod conf;
+pub mod consistent_hash;
 pub mod excel;
+pub mod game_key;
 pub mod http;
 pub mod json;
 pub mod macros;
 pub mod my_log;
 pub mod net_message_io;
 pub mod protos;
+pub mod rating;
+pub mod reconnect;
 pub mod redis_pool;
+pub mod registry;
+pub mod rpc;
 pub mod rpc_client;
 pub mod rpc_server;
+pub mod shutdown;
 pub mod tcp;
 pub mod tcp_tokio;
 pub mod templates;
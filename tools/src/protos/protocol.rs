@@ -2770,6 +2770,1163 @@ impl ::protobuf::reflect::ProtobufValue for S_GET_LAST_SEASON_RANK {
     }
 }
 
+#[derive(PartialEq,Clone,Default)]
+pub struct C_RANK_AROUND_ME {
+    // message fields
+    pub range: u32,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a C_RANK_AROUND_ME {
+    fn default() -> &'a C_RANK_AROUND_ME {
+        <C_RANK_AROUND_ME as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl C_RANK_AROUND_ME {
+    pub fn new() -> C_RANK_AROUND_ME {
+        ::std::default::Default::default()
+    }
+
+    // uint32 range = 1;
+
+
+    pub fn get_range(&self) -> u32 {
+        self.range
+    }
+    pub fn clear_range(&mut self) {
+        self.range = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_range(&mut self, v: u32) {
+        self.range = v;
+    }
+}
+
+impl ::protobuf::Message for C_RANK_AROUND_ME {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.range = tmp;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if self.range != 0 {
+            my_size += ::protobuf::rt::value_size(1, self.range, ::protobuf::wire_format::WireTypeVarint);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if self.range != 0 {
+            os.write_uint32(1, self.range)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> C_RANK_AROUND_ME {
+        C_RANK_AROUND_ME::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::LazyV2<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::LazyV2::INIT;
+        descriptor.get(|| {
+            let mut fields = ::std::vec::Vec::new();
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "range",
+                |m: &C_RANK_AROUND_ME| { &m.range },
+                |m: &mut C_RANK_AROUND_ME| { &mut m.range },
+            ));
+            ::protobuf::reflect::MessageDescriptor::new_pb_name::<C_RANK_AROUND_ME>(
+                "C_RANK_AROUND_ME",
+                fields,
+                file_descriptor_proto()
+            )
+        })
+    }
+
+    fn default_instance() -> &'static C_RANK_AROUND_ME {
+        static instance: ::protobuf::rt::LazyV2<C_RANK_AROUND_ME> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(C_RANK_AROUND_ME::new)
+    }
+}
+
+impl ::protobuf::Clear for C_RANK_AROUND_ME {
+    fn clear(&mut self) {
+        self.range = 0;
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for C_RANK_AROUND_ME {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for C_RANK_AROUND_ME {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default)]
+pub struct S_RANK_AROUND_ME {
+    // message fields
+    pub self_rank: ::protobuf::SingularPtrField<super::base::RankInfoPt>,
+    pub ranks: ::protobuf::RepeatedField<super::base::RankInfoPt>,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a S_RANK_AROUND_ME {
+    fn default() -> &'a S_RANK_AROUND_ME {
+        <S_RANK_AROUND_ME as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl S_RANK_AROUND_ME {
+    pub fn new() -> S_RANK_AROUND_ME {
+        ::std::default::Default::default()
+    }
+
+    // .protos.RankInfoPt self_rank = 1;
+
+
+    pub fn get_self_rank(&self) -> &super::base::RankInfoPt {
+        self.self_rank.as_ref().unwrap_or_else(|| <super::base::RankInfoPt as ::protobuf::Message>::default_instance())
+    }
+    pub fn clear_self_rank(&mut self) {
+        self.self_rank.clear();
+    }
+
+    pub fn has_self_rank(&self) -> bool {
+        self.self_rank.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_self_rank(&mut self, v: super::base::RankInfoPt) {
+        self.self_rank = ::protobuf::SingularPtrField::some(v);
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_self_rank(&mut self) -> &mut super::base::RankInfoPt {
+        if self.self_rank.is_none() {
+            self.self_rank.set_default();
+        }
+        self.self_rank.as_mut().unwrap()
+    }
+
+    // Take field
+    pub fn take_self_rank(&mut self) -> super::base::RankInfoPt {
+        self.self_rank.take().unwrap_or_else(|| super::base::RankInfoPt::new())
+    }
+
+    // repeated .protos.RankInfoPt ranks = 2;
+
+
+    pub fn get_ranks(&self) -> &[super::base::RankInfoPt] {
+        &self.ranks
+    }
+    pub fn clear_ranks(&mut self) {
+        self.ranks.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_ranks(&mut self, v: ::protobuf::RepeatedField<super::base::RankInfoPt>) {
+        self.ranks = v;
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_ranks(&mut self) -> &mut ::protobuf::RepeatedField<super::base::RankInfoPt> {
+        &mut self.ranks
+    }
+
+    // Take field
+    pub fn take_ranks(&mut self) -> ::protobuf::RepeatedField<super::base::RankInfoPt> {
+        ::std::mem::replace(&mut self.ranks, ::protobuf::RepeatedField::new())
+    }
+}
+
+impl ::protobuf::Message for S_RANK_AROUND_ME {
+    fn is_initialized(&self) -> bool {
+        for v in &self.self_rank {
+            if !v.is_initialized() {
+                return false;
+            }
+        };
+        for v in &self.ranks {
+            if !v.is_initialized() {
+                return false;
+            }
+        };
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_singular_message_into(wire_type, is, &mut self.self_rank)?;
+                },
+                2 => {
+                    ::protobuf::rt::read_repeated_message_into(wire_type, is, &mut self.ranks)?;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if let Some(ref v) = self.self_rank.as_ref() {
+            let len = v.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
+        }
+        for value in &self.ranks {
+            let len = value.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
+        };
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if let Some(ref v) = self.self_rank.as_ref() {
+            os.write_tag(1, ::protobuf::wire_format::WireTypeLengthDelimited)?;
+            os.write_raw_varint32(v.get_cached_size())?;
+            v.write_to_with_cached_sizes(os)?;
+        }
+        for v in &self.ranks {
+            os.write_tag(2, ::protobuf::wire_format::WireTypeLengthDelimited)?;
+            os.write_raw_varint32(v.get_cached_size())?;
+            v.write_to_with_cached_sizes(os)?;
+        };
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> S_RANK_AROUND_ME {
+        S_RANK_AROUND_ME::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::LazyV2<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::LazyV2::INIT;
+        descriptor.get(|| {
+            let mut fields = ::std::vec::Vec::new();
+            fields.push(::protobuf::reflect::accessor::make_singular_ptr_field_accessor::<_, ::protobuf::types::ProtobufTypeMessage<super::base::RankInfoPt>>(
+                "self_rank",
+                |m: &S_RANK_AROUND_ME| { &m.self_rank },
+                |m: &mut S_RANK_AROUND_ME| { &mut m.self_rank },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_repeated_field_accessor::<_, ::protobuf::types::ProtobufTypeMessage<super::base::RankInfoPt>>(
+                "ranks",
+                |m: &S_RANK_AROUND_ME| { &m.ranks },
+                |m: &mut S_RANK_AROUND_ME| { &mut m.ranks },
+            ));
+            ::protobuf::reflect::MessageDescriptor::new_pb_name::<S_RANK_AROUND_ME>(
+                "S_RANK_AROUND_ME",
+                fields,
+                file_descriptor_proto()
+            )
+        })
+    }
+
+    fn default_instance() -> &'static S_RANK_AROUND_ME {
+        static instance: ::protobuf::rt::LazyV2<S_RANK_AROUND_ME> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(S_RANK_AROUND_ME::new)
+    }
+}
+
+impl ::protobuf::Clear for S_RANK_AROUND_ME {
+    fn clear(&mut self) {
+        self.self_rank.clear();
+        self.ranks.clear();
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for S_RANK_AROUND_ME {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for S_RANK_AROUND_ME {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default)]
+pub struct C_RANK_PAGE {
+    // message fields
+    pub page: u32,
+    pub page_size: u32,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a C_RANK_PAGE {
+    fn default() -> &'a C_RANK_PAGE {
+        <C_RANK_PAGE as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl C_RANK_PAGE {
+    pub fn new() -> C_RANK_PAGE {
+        ::std::default::Default::default()
+    }
+
+    // uint32 page = 1;
+
+
+    pub fn get_page(&self) -> u32 {
+        self.page
+    }
+    pub fn clear_page(&mut self) {
+        self.page = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_page(&mut self, v: u32) {
+        self.page = v;
+    }
+
+    // uint32 page_size = 2;
+
+
+    pub fn get_page_size(&self) -> u32 {
+        self.page_size
+    }
+    pub fn clear_page_size(&mut self) {
+        self.page_size = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_page_size(&mut self, v: u32) {
+        self.page_size = v;
+    }
+}
+
+impl ::protobuf::Message for C_RANK_PAGE {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.page = tmp;
+                },
+                2 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.page_size = tmp;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if self.page != 0 {
+            my_size += ::protobuf::rt::value_size(1, self.page, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if self.page_size != 0 {
+            my_size += ::protobuf::rt::value_size(2, self.page_size, ::protobuf::wire_format::WireTypeVarint);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if self.page != 0 {
+            os.write_uint32(1, self.page)?;
+        }
+        if self.page_size != 0 {
+            os.write_uint32(2, self.page_size)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> C_RANK_PAGE {
+        C_RANK_PAGE::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::LazyV2<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::LazyV2::INIT;
+        descriptor.get(|| {
+            let mut fields = ::std::vec::Vec::new();
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "page",
+                |m: &C_RANK_PAGE| { &m.page },
+                |m: &mut C_RANK_PAGE| { &mut m.page },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "page_size",
+                |m: &C_RANK_PAGE| { &m.page_size },
+                |m: &mut C_RANK_PAGE| { &mut m.page_size },
+            ));
+            ::protobuf::reflect::MessageDescriptor::new_pb_name::<C_RANK_PAGE>(
+                "C_RANK_PAGE",
+                fields,
+                file_descriptor_proto()
+            )
+        })
+    }
+
+    fn default_instance() -> &'static C_RANK_PAGE {
+        static instance: ::protobuf::rt::LazyV2<C_RANK_PAGE> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(C_RANK_PAGE::new)
+    }
+}
+
+impl ::protobuf::Clear for C_RANK_PAGE {
+    fn clear(&mut self) {
+        self.page = 0;
+        self.page_size = 0;
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for C_RANK_PAGE {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for C_RANK_PAGE {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default)]
+pub struct S_RANK_PAGE {
+    // message fields
+    pub page: u32,
+    pub page_size: u32,
+    pub total: u32,
+    pub ranks: ::protobuf::RepeatedField<super::base::RankInfoPt>,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a S_RANK_PAGE {
+    fn default() -> &'a S_RANK_PAGE {
+        <S_RANK_PAGE as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl S_RANK_PAGE {
+    pub fn new() -> S_RANK_PAGE {
+        ::std::default::Default::default()
+    }
+
+    // uint32 page = 1;
+
+
+    pub fn get_page(&self) -> u32 {
+        self.page
+    }
+    pub fn clear_page(&mut self) {
+        self.page = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_page(&mut self, v: u32) {
+        self.page = v;
+    }
+
+    // uint32 page_size = 2;
+
+
+    pub fn get_page_size(&self) -> u32 {
+        self.page_size
+    }
+    pub fn clear_page_size(&mut self) {
+        self.page_size = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_page_size(&mut self, v: u32) {
+        self.page_size = v;
+    }
+
+    // uint32 total = 3;
+
+
+    pub fn get_total(&self) -> u32 {
+        self.total
+    }
+    pub fn clear_total(&mut self) {
+        self.total = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_total(&mut self, v: u32) {
+        self.total = v;
+    }
+
+    // repeated .protos.RankInfoPt ranks = 4;
+
+
+    pub fn get_ranks(&self) -> &[super::base::RankInfoPt] {
+        &self.ranks
+    }
+    pub fn clear_ranks(&mut self) {
+        self.ranks.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_ranks(&mut self, v: ::protobuf::RepeatedField<super::base::RankInfoPt>) {
+        self.ranks = v;
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_ranks(&mut self) -> &mut ::protobuf::RepeatedField<super::base::RankInfoPt> {
+        &mut self.ranks
+    }
+
+    // Take field
+    pub fn take_ranks(&mut self) -> ::protobuf::RepeatedField<super::base::RankInfoPt> {
+        ::std::mem::replace(&mut self.ranks, ::protobuf::RepeatedField::new())
+    }
+}
+
+impl ::protobuf::Message for S_RANK_PAGE {
+    fn is_initialized(&self) -> bool {
+        for v in &self.ranks {
+            if !v.is_initialized() {
+                return false;
+            }
+        };
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.page = tmp;
+                },
+                2 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.page_size = tmp;
+                },
+                3 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.total = tmp;
+                },
+                4 => {
+                    ::protobuf::rt::read_repeated_message_into(wire_type, is, &mut self.ranks)?;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if self.page != 0 {
+            my_size += ::protobuf::rt::value_size(1, self.page, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if self.page_size != 0 {
+            my_size += ::protobuf::rt::value_size(2, self.page_size, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if self.total != 0 {
+            my_size += ::protobuf::rt::value_size(3, self.total, ::protobuf::wire_format::WireTypeVarint);
+        }
+        for value in &self.ranks {
+            let len = value.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
+        };
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if self.page != 0 {
+            os.write_uint32(1, self.page)?;
+        }
+        if self.page_size != 0 {
+            os.write_uint32(2, self.page_size)?;
+        }
+        if self.total != 0 {
+            os.write_uint32(3, self.total)?;
+        }
+        for v in &self.ranks {
+            os.write_tag(4, ::protobuf::wire_format::WireTypeLengthDelimited)?;
+            os.write_raw_varint32(v.get_cached_size())?;
+            v.write_to_with_cached_sizes(os)?;
+        };
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> S_RANK_PAGE {
+        S_RANK_PAGE::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::LazyV2<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::LazyV2::INIT;
+        descriptor.get(|| {
+            let mut fields = ::std::vec::Vec::new();
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "page",
+                |m: &S_RANK_PAGE| { &m.page },
+                |m: &mut S_RANK_PAGE| { &mut m.page },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "page_size",
+                |m: &S_RANK_PAGE| { &m.page_size },
+                |m: &mut S_RANK_PAGE| { &mut m.page_size },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "total",
+                |m: &S_RANK_PAGE| { &m.total },
+                |m: &mut S_RANK_PAGE| { &mut m.total },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_repeated_field_accessor::<_, ::protobuf::types::ProtobufTypeMessage<super::base::RankInfoPt>>(
+                "ranks",
+                |m: &S_RANK_PAGE| { &m.ranks },
+                |m: &mut S_RANK_PAGE| { &mut m.ranks },
+            ));
+            ::protobuf::reflect::MessageDescriptor::new_pb_name::<S_RANK_PAGE>(
+                "S_RANK_PAGE",
+                fields,
+                file_descriptor_proto()
+            )
+        })
+    }
+
+    fn default_instance() -> &'static S_RANK_PAGE {
+        static instance: ::protobuf::rt::LazyV2<S_RANK_PAGE> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(S_RANK_PAGE::new)
+    }
+}
+
+impl ::protobuf::Clear for S_RANK_PAGE {
+    fn clear(&mut self) {
+        self.page = 0;
+        self.page_size = 0;
+        self.total = 0;
+        self.ranks.clear();
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for S_RANK_PAGE {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for S_RANK_PAGE {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default)]
+pub struct C_RANK_FRIENDS {
+    // message fields
+    pub user_ids: ::std::vec::Vec<u32>,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a C_RANK_FRIENDS {
+    fn default() -> &'a C_RANK_FRIENDS {
+        <C_RANK_FRIENDS as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl C_RANK_FRIENDS {
+    pub fn new() -> C_RANK_FRIENDS {
+        ::std::default::Default::default()
+    }
+
+    // repeated uint32 user_ids = 1;
+
+
+    pub fn get_user_ids(&self) -> &[u32] {
+        &self.user_ids
+    }
+    pub fn clear_user_ids(&mut self) {
+        self.user_ids.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_user_ids(&mut self, v: ::std::vec::Vec<u32>) {
+        self.user_ids = v;
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_user_ids(&mut self) -> &mut ::std::vec::Vec<u32> {
+        &mut self.user_ids
+    }
+
+    // Take field
+    pub fn take_user_ids(&mut self) -> ::std::vec::Vec<u32> {
+        ::std::mem::replace(&mut self.user_ids, ::std::vec::Vec::new())
+    }
+}
+
+impl ::protobuf::Message for C_RANK_FRIENDS {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_repeated_uint32_into(wire_type, is, &mut self.user_ids)?;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        for value in &self.user_ids {
+            my_size += ::protobuf::rt::value_size(1, *value, ::protobuf::wire_format::WireTypeVarint);
+        };
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        for v in &self.user_ids {
+            os.write_uint32(1, *v)?;
+        };
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> C_RANK_FRIENDS {
+        C_RANK_FRIENDS::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::LazyV2<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::LazyV2::INIT;
+        descriptor.get(|| {
+            let mut fields = ::std::vec::Vec::new();
+            fields.push(::protobuf::reflect::accessor::make_vec_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "user_ids",
+                |m: &C_RANK_FRIENDS| { &m.user_ids },
+                |m: &mut C_RANK_FRIENDS| { &mut m.user_ids },
+            ));
+            ::protobuf::reflect::MessageDescriptor::new_pb_name::<C_RANK_FRIENDS>(
+                "C_RANK_FRIENDS",
+                fields,
+                file_descriptor_proto()
+            )
+        })
+    }
+
+    fn default_instance() -> &'static C_RANK_FRIENDS {
+        static instance: ::protobuf::rt::LazyV2<C_RANK_FRIENDS> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(C_RANK_FRIENDS::new)
+    }
+}
+
+impl ::protobuf::Clear for C_RANK_FRIENDS {
+    fn clear(&mut self) {
+        self.user_ids.clear();
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for C_RANK_FRIENDS {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for C_RANK_FRIENDS {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default)]
+pub struct S_RANK_FRIENDS {
+    // message fields
+    pub ranks: ::protobuf::RepeatedField<super::base::RankInfoPt>,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a S_RANK_FRIENDS {
+    fn default() -> &'a S_RANK_FRIENDS {
+        <S_RANK_FRIENDS as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl S_RANK_FRIENDS {
+    pub fn new() -> S_RANK_FRIENDS {
+        ::std::default::Default::default()
+    }
+
+    // repeated .protos.RankInfoPt ranks = 1;
+
+
+    pub fn get_ranks(&self) -> &[super::base::RankInfoPt] {
+        &self.ranks
+    }
+    pub fn clear_ranks(&mut self) {
+        self.ranks.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_ranks(&mut self, v: ::protobuf::RepeatedField<super::base::RankInfoPt>) {
+        self.ranks = v;
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_ranks(&mut self) -> &mut ::protobuf::RepeatedField<super::base::RankInfoPt> {
+        &mut self.ranks
+    }
+
+    // Take field
+    pub fn take_ranks(&mut self) -> ::protobuf::RepeatedField<super::base::RankInfoPt> {
+        ::std::mem::replace(&mut self.ranks, ::protobuf::RepeatedField::new())
+    }
+}
+
+impl ::protobuf::Message for S_RANK_FRIENDS {
+    fn is_initialized(&self) -> bool {
+        for v in &self.ranks {
+            if !v.is_initialized() {
+                return false;
+            }
+        };
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_repeated_message_into(wire_type, is, &mut self.ranks)?;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        for value in &self.ranks {
+            let len = value.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
+        };
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        for v in &self.ranks {
+            os.write_tag(1, ::protobuf::wire_format::WireTypeLengthDelimited)?;
+            os.write_raw_varint32(v.get_cached_size())?;
+            v.write_to_with_cached_sizes(os)?;
+        };
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> S_RANK_FRIENDS {
+        S_RANK_FRIENDS::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::LazyV2<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::LazyV2::INIT;
+        descriptor.get(|| {
+            let mut fields = ::std::vec::Vec::new();
+            fields.push(::protobuf::reflect::accessor::make_repeated_field_accessor::<_, ::protobuf::types::ProtobufTypeMessage<super::base::RankInfoPt>>(
+                "ranks",
+                |m: &S_RANK_FRIENDS| { &m.ranks },
+                |m: &mut S_RANK_FRIENDS| { &mut m.ranks },
+            ));
+            ::protobuf::reflect::MessageDescriptor::new_pb_name::<S_RANK_FRIENDS>(
+                "S_RANK_FRIENDS",
+                fields,
+                file_descriptor_proto()
+            )
+        })
+    }
+
+    fn default_instance() -> &'static S_RANK_FRIENDS {
+        static instance: ::protobuf::rt::LazyV2<S_RANK_FRIENDS> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(S_RANK_FRIENDS::new)
+    }
+}
+
+impl ::protobuf::Clear for S_RANK_FRIENDS {
+    fn clear(&mut self) {
+        self.ranks.clear();
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for S_RANK_FRIENDS {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for S_RANK_FRIENDS {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
 static file_descriptor_proto_data: &'static [u8] = b"\
     \n\x0eprotocol.proto\x12\x06protos\x1a\nbase.proto\"{\n\x0cC_USER_LOGIN\
     \x12+\n\x11register_platform\x18\x01\x20\x01(\tR\x10registerPlatform\x12\
@@ -2800,8 +3957,18 @@ static file_descriptor_proto_data: &'static [u8] = b"\
     \n\x1dS_MODIFY_GRADE_FRAME_AND_SOUL\x12\x17\n\x07is_succ\x18\x01\x20\x01\
     (\x08R\x06isSucc\x12\x19\n\x08err_mess\x18\x02\x20\x01(\tR\x07errMess\"\
     \x18\n\x16C_GET_LAST_SEASON_RANK\"B\n\x16S_GET_LAST_SEASON_RANK\x12(\n\
-    \x05ranks\x18\x01\x20\x03(\x0b2\x12.protos.RankInfoPtR\x05ranksb\x06prot\
-    o3\
+    \x05ranks\x18\x01\x20\x03(\x0b2\x12.protos.RankInfoPtR\x05ranks\"(\n\x10\
+    C_RANK_AROUND_ME\x12\x14\n\x05range\x18\x01\x20\x01(\rR\x05range\"m\n\
+    \x10S_RANK_AROUND_ME\x12/\n\tself_rank\x18\x01\x20\x01(\x0b2\x12.protos.\
+    RankInfoPtR\x08selfRank\x12(\n\x05ranks\x18\x02\x20\x03(\x0b2\x12.protos\
+    .RankInfoPtR\x05ranks\">\n\x0bC_RANK_PAGE\x12\x12\n\x04page\x18\x01\x20\
+    \x01(\rR\x04page\x12\x1b\n\tpage_size\x18\x02\x20\x01(\rR\x08pageSize\"~\
+    \n\x0bS_RANK_PAGE\x12\x12\n\x04page\x18\x01\x20\x01(\rR\x04page\x12\x1b\
+    \n\tpage_size\x18\x02\x20\x01(\rR\x08pageSize\x12\x14\n\x05total\x18\x03\
+    \x20\x01(\rR\x05total\x12(\n\x05ranks\x18\x04\x20\x03(\x0b2\x12.protos.R\
+    ankInfoPtR\x05ranks\"+\n\x0eC_RANK_FRIENDS\x12\x19\n\x08user_ids\x18\x01\
+    \x20\x03(\rR\x07userIds\":\n\x0eS_RANK_FRIENDS\x12(\n\x05ranks\x18\x01\
+    \x20\x03(\x0b2\x12.protos.RankInfoPtR\x05ranksb\x06proto3\
 ";
 
 static file_descriptor_proto_lazy: ::protobuf::rt::LazyV2<::protobuf::descriptor::FileDescriptorProto> = ::protobuf::rt::LazyV2::INIT;
@@ -0,0 +1,212 @@
+use crate::conf::Validate;
+use crate::templates::league_temp::LeagueTempMgr;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::{LN_10, PI};
+
+///评分参数(Glicko)
+///配置文件里的key带rating_前缀
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RatingConf {
+    #[serde(rename = "rating_scale")]
+    pub scale: f64, //分差缩放，分差为scale时强者期望胜率约为91%
+    #[serde(rename = "rating_init_deviation")]
+    pub init_deviation: f64, //新玩家和新赛季的评分偏差
+    #[serde(rename = "rating_min_deviation")]
+    pub min_deviation: f64, //评分偏差下限，防止老玩家积分变化太小
+    #[serde(rename = "rating_max_deviation")]
+    pub max_deviation: f64, //评分偏差上限
+    #[serde(rename = "rating_decay")]
+    pub decay: f64, //每个不活跃周期评分偏差增长的系数
+    #[serde(rename = "rating_period")]
+    pub period: i64, //不活跃周期(毫秒)
+}
+
+impl Default for RatingConf {
+    fn default() -> Self {
+        RatingConf {
+            scale: 400_f64,
+            init_deviation: 120_f64,
+            min_deviation: 40_f64,
+            max_deviation: 120_f64,
+            decay: 10_f64,
+            period: 24 * 3600 * 1000,
+        }
+    }
+}
+
+impl Validate for RatingConf {
+    fn validate(&self) -> anyhow::Result<()> {
+        if self.scale <= 0_f64 || self.period <= 0 {
+            anyhow::bail!("rating_scale和rating_period必须大于0!");
+        }
+        if self.min_deviation <= 0_f64 || self.min_deviation > self.max_deviation {
+            anyhow::bail!(
+                "rating_min_deviation必须大于0并且不超过rating_max_deviation!{},{}",
+                self.min_deviation,
+                self.max_deviation
+            );
+        }
+        Ok(())
+    }
+}
+
+impl RatingConf {
+    fn q(&self) -> f64 {
+        LN_10 / self.scale
+    }
+
+    ///偏差越大，这个对手的结果权重越小
+    fn g(&self, deviation: f64) -> f64 {
+        let q = self.q();
+        1_f64 / (1_f64 + 3_f64 * q * q * deviation * deviation / (PI * PI)).sqrt()
+    }
+
+    ///期望得分
+    fn expect(&self, rating: f64, other: f64, other_g: f64) -> f64 {
+        1_f64 / (1_f64 + 10_f64.powf(-other_g * (rating - other) / self.scale))
+    }
+}
+
+///玩家评分，积分就是评分取整
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct Rating {
+    pub rating: f64,    //评分
+    pub deviation: f64, //评分偏差
+    pub last_time: i64, //最近一次参与结算的时间(毫秒)，为0则没有参与过
+}
+
+impl Rating {
+    pub fn new(score: i32, conf: &RatingConf) -> Self {
+        Rating {
+            rating: score as f64,
+            deviation: conf.init_deviation,
+            last_time: 0,
+        }
+    }
+
+    ///积分
+    pub fn get_score(&self) -> i32 {
+        self.rating.round() as i32
+    }
+
+    ///积分被别的逻辑改过(惩罚,赛季重置)，以积分为准
+    pub fn sync_score(&mut self, score: i32) {
+        if self.get_score() != score {
+            self.rating = score as f64;
+        }
+    }
+
+    ///新赛季重置积分，偏差回到初始值
+    pub fn reset_season(&mut self, score: i32, conf: &RatingConf) {
+        self.rating = score as f64;
+        self.deviation = self.deviation.max(conf.init_deviation);
+    }
+
+    ///算上不活跃衰减之后的评分偏差
+    pub fn get_deviation(&self, conf: &RatingConf, now: i64) -> f64 {
+        let mut deviation = self.deviation;
+        if self.last_time > 0 && conf.period > 0 && now > self.last_time {
+            let periods = ((now - self.last_time) / conf.period) as f64;
+            deviation = (deviation * deviation + conf.decay * conf.decay * periods).sqrt();
+        }
+        deviation.max(conf.min_deviation).min(conf.max_deviation)
+    }
+
+    ///段位id，按积分映射到段位配置
+    pub fn get_league_id(&self, mgr: &LeagueTempMgr) -> i8 {
+        match mgr.get_league_by_score(self.get_score()) {
+            Ok(temp) => temp.id,
+            Err(_) => 0,
+        }
+    }
+
+    ///根据一局的对局结果计算新的评分
+    ///opponents:(对手评分,结果)，结果1为赢，0.5为平，0为输
+    pub fn update(&self, conf: &RatingConf, opponents: &[(Rating, f64)], now: i64) -> Rating {
+        let deviation = self.get_deviation(conf, now);
+        let mut res = *self;
+        res.last_time = now;
+        if opponents.is_empty() {
+            res.deviation = deviation;
+            return res;
+        }
+        let q = conf.q();
+        let mut d_sum = 0_f64;
+        let mut delta_sum = 0_f64;
+        for (other, outcome) in opponents.iter() {
+            let other_g = conf.g(other.get_deviation(conf, now));
+            let e = conf.expect(self.rating, other.rating, other_g);
+            d_sum += other_g * other_g * e * (1_f64 - e);
+            delta_sum += other_g * (outcome - e);
+        }
+        let d2 = 1_f64 / (q * q * d_sum);
+        let denominator = 1_f64 / (deviation * deviation) + 1_f64 / d2;
+        res.rating = self.rating + q / denominator * delta_sum;
+        res.deviation = (1_f64 / denominator)
+            .sqrt()
+            .max(conf.min_deviation)
+            .min(conf.max_deviation);
+        res
+    }
+}
+
+///多人混战两两比较的结果，名次越小越好
+pub fn ffa_outcome(rank: u8, other_rank: u8) -> f64 {
+    if rank < other_rank {
+        1_f64
+    } else if rank == other_rank {
+        0.5_f64
+    } else {
+        0_f64
+    }
+}
+
+///多人混战结算，返回每个人的新评分，顺序和传入的一致
+///players:(评分,名次)
+pub fn calc_ffa(conf: &RatingConf, players: &[(Rating, u8)], now: i64) -> Vec<Rating> {
+    let mut res = Vec::with_capacity(players.len());
+    for (index, (rating, rank)) in players.iter().enumerate() {
+        let mut opponents = vec![];
+        for (other_index, (other, other_rank)) in players.iter().enumerate() {
+            if other_index == index {
+                continue;
+            }
+            opponents.push((*other, ffa_outcome(*rank, *other_rank)));
+        }
+        res.push(rating.update(conf, opponents.as_slice(), now));
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ffa_winner_gains_and_loser_drops() {
+        let conf = RatingConf::default();
+        let players: Vec<(Rating, u8)> = (0..4)
+            .map(|rank| (Rating::new(500, &conf), rank as u8))
+            .collect();
+        let res = calc_ffa(&conf, players.as_slice(), 1000);
+        assert!(res[0].get_score() > 500);
+        assert!(res[3].get_score() < 500);
+        assert!(res[0].get_score() > res[1].get_score());
+        //等分对局，总分变化接近0
+        let total: f64 = res.iter().map(|x| x.rating - 500_f64).sum();
+        assert!(total.abs() < 1_f64);
+        assert!(res[0].deviation < conf.init_deviation);
+    }
+
+    #[test]
+    fn deviation_grows_when_inactive() {
+        let conf = RatingConf::default();
+        let mut rating = Rating::new(500, &conf);
+        rating.deviation = conf.min_deviation;
+        rating.last_time = 1;
+        let deviation = rating.get_deviation(&conf, 1 + conf.period * 30);
+        assert!(deviation > conf.min_deviation);
+        assert!(deviation <= conf.max_deviation);
+    }
+}
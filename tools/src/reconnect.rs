@@ -0,0 +1,347 @@
+use crate::conf::Validate;
+use crate::tcp::{
+    aborted, broken_pipe, connect, connection_refused, interrupted, not_connected, reset,
+    set_tream_param, time_out, would_block,
+};
+use crossbeam::channel::{Receiver, Sender};
+use log::{error, info, warn};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io::{Read, Write};
+use std::net::{Shutdown, TcpStream};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+///断线重连配置
+#[derive(Debug, Clone)]
+pub struct ReconnectConf {
+    pub base_delay: Duration, //第一次重连等待时间
+    pub max_delay: Duration,  //重连等待时间上限
+    pub buffer_size: usize,   //断线期间最多缓存的消息数量
+}
+
+impl ReconnectConf {
+    ///从校验过的配置创建
+    pub fn new(config: &ReconnectConfig) -> Self {
+        ReconnectConf {
+            base_delay: Duration::from_millis(config.reconnect_base_delay),
+            max_delay: Duration::from_millis(config.reconnect_max_delay),
+            buffer_size: config.reconnect_buffer_size,
+        }
+    }
+}
+
+///断线重连配置项，和其他配置一起校验
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReconnectConfig {
+    pub reconnect_base_delay: u64,    //第一次重连等待时间(毫秒)
+    pub reconnect_max_delay: u64,     //重连等待时间上限(毫秒)
+    pub reconnect_buffer_size: usize, //断线期间最多缓存的消息数量
+}
+
+impl Default for ReconnectConfig {
+    fn default() -> Self {
+        ReconnectConfig {
+            reconnect_base_delay: 500,
+            reconnect_max_delay: 30000,
+            reconnect_buffer_size: 10000,
+        }
+    }
+}
+
+impl Validate for ReconnectConfig {
+    fn validate(&self) -> anyhow::Result<()> {
+        if self.reconnect_base_delay == 0 || self.reconnect_base_delay > self.reconnect_max_delay {
+            anyhow::bail!(
+                "reconnect_base_delay必须大于0并且不超过reconnect_max_delay!{},{}",
+                self.reconnect_base_delay,
+                self.reconnect_max_delay
+            );
+        }
+        if self.reconnect_buffer_size == 0 {
+            anyhow::bail!("reconnect_buffer_size不能为0!");
+        }
+        Ok(())
+    }
+}
+
+///指数退避，每失败一次等待时间翻倍，直到上限，连上以后重置
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    base: Duration,    //初始等待时间
+    max: Duration,     //等待时间上限
+    current: Duration, //下一次的等待时间
+}
+
+impl Backoff {
+    pub fn new(base: Duration, max: Duration) -> Self {
+        let base = base.max(Duration::from_millis(1));
+        let max = max.max(base);
+        Backoff {
+            base,
+            max,
+            current: base,
+        }
+    }
+
+    ///下一次等待多久，加上一半以内的随机抖动，避免一起重启的服务器同时重连
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = self.current;
+        self.current = (self.current * 2).min(self.max);
+        let half = delay.as_millis() as u64 / 2;
+        let jitter = rand::thread_rng().gen_range(0..=half);
+        Duration::from_millis(delay.as_millis() as u64 - half + jitter)
+    }
+
+    pub fn reset(&mut self) {
+        self.current = self.base;
+    }
+}
+
+///断线期间的发送缓存，满了丢掉最老的消息
+#[derive(Debug, Default)]
+pub struct OutBuffer {
+    queue: VecDeque<Vec<u8>>, //缓存的消息
+    max: usize,               //最多缓存数量
+    dropped: usize,           //因为满了丢掉的消息数量
+}
+
+impl OutBuffer {
+    pub fn new(max: usize) -> Self {
+        OutBuffer {
+            queue: VecDeque::new(),
+            max: max.max(1),
+            dropped: 0,
+        }
+    }
+
+    pub fn push(&mut self, bytes: Vec<u8>) {
+        if self.queue.len() >= self.max {
+            self.queue.pop_front();
+            self.dropped += 1;
+            if self.dropped % 1000 == 1 {
+                warn!("断线缓存已满，丢弃最老的消息!已丢弃:{}", self.dropped);
+            }
+        }
+        self.queue.push_back(bytes);
+    }
+
+    ///发送失败的消息放回队头，保证顺序
+    pub fn push_front(&mut self, bytes: Vec<u8>) {
+        self.queue.push_front(bytes);
+    }
+
+    pub fn pop(&mut self) -> Option<Vec<u8>> {
+        self.queue.pop_front()
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+}
+
+///断线自动重连的tcp客户端事件
+pub trait ReconnectHandler: Send + 'static {
+    ///每次连接前调用，返回要连接的地址(可以重新从注册中心发现),返回None不再连接
+    fn address(&mut self) -> Option<String>;
+
+    ///重新握手:连上后最先发出的消息(注册自己等)，在断线期间缓存的消息之前发送
+    fn handshake(&mut self) -> Vec<Vec<u8>> {
+        Vec::new()
+    }
+
+    ///连接成功,ts在客户端整个生命周期内不变，断线期间往里发的消息会缓存起来，重连后补发
+    ///reconnect:是否是断线重连
+    fn on_connect(&mut self, ts: Sender<Vec<u8>>, reconnect: bool);
+
+    ///连接断开
+    fn on_disconnect(&mut self) {}
+
+    ///收到服务器消息
+    fn on_message(&mut self, mess: Vec<u8>);
+}
+
+///写线程的连接状态变化
+enum Link {
+    Connected(TcpStream, Vec<Vec<u8>>), //连上了，带着握手消息
+    Disconnected,                       //断开了
+}
+
+///启动断线自动重连的tcp客户端，返回读线程，address返回None时结束
+pub fn spawn<H: ReconnectHandler>(name: &str, handler: H, conf: ReconnectConf) -> JoinHandle<()> {
+    let (sender, rec) = crossbeam::channel::unbounded();
+    let (link_sender, link_rec) = crossbeam::channel::unbounded();
+    let buffer_size = conf.buffer_size;
+    let res = std::thread::Builder::new()
+        .name(format!("{}_WRITE", name))
+        .spawn(move || write_loop(rec, link_rec, buffer_size));
+    if let Err(e) = res {
+        error!("{:?}", e);
+    }
+    let res = std::thread::Builder::new()
+        .name(format!("{}_READ", name))
+        .spawn(move || read_loop(handler, conf, sender, link_sender));
+    res.unwrap()
+}
+
+///连接，读取，断开后按退避时间重连
+fn read_loop<H: ReconnectHandler>(
+    mut handler: H,
+    conf: ReconnectConf,
+    sender: Sender<Vec<u8>>,
+    link_sender: Sender<Link>,
+) {
+    let mut backoff = Backoff::new(conf.base_delay, conf.max_delay);
+    let mut reconnect = false;
+    loop {
+        let address = match handler.address() {
+            Some(address) => address,
+            None => {
+                info!("tcp客户端不再重连!");
+                return;
+            }
+        };
+        let stream = connect(address.as_str()).map_err(anyhow::Error::from);
+        let stream = stream.and_then(|ts| set_tream_param(&ts).map(|_| ts));
+        let stream = stream.and_then(|ts| Ok((ts.try_clone()?, ts)));
+        let (mut read, write) = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                let delay = backoff.next_delay();
+                error!("连接服务器失败！{:?},{},{:?}后重试", address, e, delay);
+                std::thread::sleep(delay);
+                continue;
+            }
+        };
+        backoff.reset();
+        info!("连接服务器成功！{:?},reconnect:{}", address, reconnect);
+        let handshake = handler.handshake();
+        if link_sender.send(Link::Connected(write, handshake)).is_err() {
+            error!("tcp客户端写线程已经退出!");
+            return;
+        }
+        handler.on_connect(sender.clone(), reconnect);
+        reconnect = true;
+
+        let mut read_bytes: [u8; 51200] = [0; 51200];
+        loop {
+            match read.read(&mut read_bytes) {
+                Ok(0) => break,
+                Ok(size) => handler.on_message(read_bytes[..size].to_vec()),
+                Err(ref err) if would_block(err) | interrupted(err) | time_out(err) => continue,
+                Err(ref err)
+                    if reset(err)
+                        | connection_refused(err)
+                        | aborted(err)
+                        | not_connected(err)
+                        | broken_pipe(err) =>
+                {
+                    break
+                }
+                Err(err) => {
+                    error!("TCP-CLIENT:{:?}", err);
+                    break;
+                }
+            }
+        }
+        let _ = read.shutdown(Shutdown::Both);
+        let _ = link_sender.send(Link::Disconnected);
+        warn!("tcp客户端断开链接！准备重连:{:?}", address);
+        handler.on_disconnect();
+    }
+}
+
+///往当前连接写消息，断开期间写进缓存，连上后先发握手消息再补发缓存
+fn write_loop(rec: Receiver<Vec<u8>>, link_rec: Receiver<Link>, buffer_size: usize) {
+    let mut stream: Option<TcpStream> = None;
+    let mut buffer = OutBuffer::new(buffer_size);
+    loop {
+        crossbeam::channel::select! {
+            recv(link_rec) -> link => match link {
+                Ok(Link::Connected(mut ts, handshake)) => {
+                    let mut ok = handshake.iter().all(|bytes| write_bytes(&mut ts, bytes));
+                    let num = buffer.len();
+                    while ok {
+                        let bytes = match buffer.pop() {
+                            Some(bytes) => bytes,
+                            None => break,
+                        };
+                        if !write_bytes(&mut ts, &bytes) {
+                            buffer.push_front(bytes);
+                            ok = false;
+                        }
+                    }
+                    if ok {
+                        info!("重连后补发缓存消息:{}", num);
+                        stream = Some(ts);
+                    } else {
+                        //读线程会发现断开并重连
+                        let _ = ts.shutdown(Shutdown::Both);
+                    }
+                }
+                Ok(Link::Disconnected) => stream = None,
+                //读线程结束了，不再重连
+                Err(_) => return,
+            },
+            recv(rec) -> data => match data {
+                Ok(bytes) => match stream.as_mut() {
+                    Some(ts) => {
+                        if !write_bytes(ts, &bytes) {
+                            let _ = ts.shutdown(Shutdown::Both);
+                            stream = None;
+                            buffer.push_front(bytes);
+                        }
+                    }
+                    None => buffer.push(bytes),
+                },
+                Err(_) => return,
+            },
+        }
+    }
+}
+
+fn write_bytes(ts: &mut TcpStream, bytes: &[u8]) -> bool {
+    let res = ts.write_all(bytes).and_then(|_| ts.flush());
+    if let Err(e) = res {
+        warn!("{:?}", e);
+        return false;
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_and_buffer() {
+        let mut backoff = Backoff::new(Duration::from_millis(100), Duration::from_millis(1000));
+        let mut last = Duration::from_millis(0);
+        for _ in 0..10 {
+            last = backoff.next_delay();
+            assert!(last <= Duration::from_millis(1000));
+        }
+        assert!(last >= Duration::from_millis(500));
+        backoff.reset();
+        assert!(backoff.next_delay() <= Duration::from_millis(100));
+
+        let mut buffer = OutBuffer::new(2);
+        for i in 0..3u8 {
+            buffer.push(vec![i]);
+        }
+        assert_eq!(buffer.dropped(), 1);
+        assert_eq!(buffer.pop(), Some(vec![1]));
+        assert_eq!(buffer.pop(), Some(vec![2]));
+        assert!(buffer.is_empty());
+    }
+}
@@ -1,160 +1,522 @@
 use super::*;
-use redis::{Commands, Connection, FromRedisValue, Pipeline};
+use redis::aio::ConnectionManager;
+use redis::{
+    from_redis_value, Cmd, ConnectionInfo, ErrorKind, FromRedisValue, IntoConnectionInfo, Pipeline,
+    RedisError, RedisResult, Value,
+};
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
 
-///redis客户端封装结构体
+///每个db index默认的连接数量
+pub const DEFAULT_POOL_SIZE: usize = 4;
+
+///一个db index的一组连接，轮流使用，每个连接断线后自动重连
+struct IndexPool {
+    conns: Vec<ConnectionManager>, //连接
+    next: AtomicUsize,             //下一个使用的连接
+}
+
+impl IndexPool {
+    async fn connect(info: ConnectionInfo, size: usize) -> RedisResult<Self> {
+        let client = redis::Client::open(info)?;
+        let mut conns = Vec::with_capacity(size);
+        for _ in 0..size.max(1) {
+            conns.push(ConnectionManager::new(client.clone()).await?);
+        }
+        Ok(IndexPool {
+            conns,
+            next: AtomicUsize::new(0),
+        })
+    }
+
+    fn get(&self) -> ConnectionManager {
+        let index = self.next.fetch_add(1, Ordering::Relaxed) % self.conns.len();
+        self.conns[index].clone()
+    }
+}
+
+///异步redis客户端
+///每个db index单独一组连接，不需要select切换，可以clone到多个线程同时使用
+///命令在TOKIO_RT上执行，所以在async_std的task里也可以直接await
+#[derive(Clone)]
+pub struct RedisClient {
+    info: ConnectionInfo,                            //连接信息，db按index替换
+    pool_size: usize,                                //每个db index的连接数量
+    pools: Arc<Mutex<HashMap<u32, Arc<IndexPool>>>>, //key:db index
+    namespace: String,                               //key的命名空间，为空则不加前缀
+    global_keys: Arc<HashSet<String>>,               //不加命名空间的key(比如用户中心写的账号数据)
+}
+
+impl RedisClient {
+    ///创建客户端，连接在第一次使用某个db index时建立
+    pub fn new(add: &str, password: &str, pool_size: usize) -> RedisResult<Self> {
+        let mut info = add.into_connection_info()?;
+        if !password.is_empty() {
+            info.passwd = Some(password.to_owned());
+        }
+        Ok(RedisClient {
+            info,
+            pool_size,
+            pools: Arc::new(Mutex::new(HashMap::new())),
+            namespace: String::new(),
+            global_keys: Arc::new(HashSet::new()),
+        })
+    }
+
+    ///设置key的命名空间，之后所有key都会加上"namespace:"前缀
+    ///global_keys里的key多个游戏共用，不加前缀
+    pub fn set_namespace(&mut self, namespace: &str, global_keys: &[&str]) {
+        self.namespace = namespace.to_owned();
+        self.global_keys = Arc::new(global_keys.iter().map(|x| x.to_string()).collect());
+    }
+
+    ///加上命名空间的key
+    fn ns_key(&self, key: &str) -> String {
+        if self.namespace.is_empty() || self.global_keys.contains(key) {
+            return key.to_owned();
+        }
+        let mut res = String::with_capacity(self.namespace.len() + key.len() + 1);
+        res.push_str(self.namespace.as_str());
+        res.push(':');
+        res.push_str(key);
+        res
+    }
+
+    ///连接池的锁里不会panic，被其他地方poison了也继续用
+    fn lock_pools(&self) -> MutexGuard<'_, HashMap<u32, Arc<IndexPool>>> {
+        self.pools.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    ///获得db index的连接，还没有连接或者上次连接失败的重新连接
+    async fn get_conn(&self, index: u32) -> RedisResult<ConnectionManager> {
+        let pool = self.lock_pools().get(&index).cloned();
+        if let Some(pool) = pool {
+            return Ok(pool.get());
+        }
+        let mut info = self.info.clone();
+        info.db = index as i64;
+        let pool = Arc::new(IndexPool::connect(info, self.pool_size).await?);
+        let pool = self.lock_pools().entry(index).or_insert(pool).clone();
+        info!(
+            "redis连接成功!index:{},连接数量:{}",
+            index,
+            pool.conns.len()
+        );
+        Ok(pool.get())
+    }
+
+    ///在TOKIO_RT上执行，断线重连需要tokio的上下文
+    async fn run<F>(&self, f: F) -> RedisResult<Value>
+    where
+        F: Future<Output = RedisResult<Value>> + Send + 'static,
+    {
+        match TOKIO_RT.spawn(f).await {
+            Ok(res) => res,
+            Err(e) => Err(RedisError::from((
+                ErrorKind::IoError,
+                "redis task fail!",
+                e.to_string(),
+            ))),
+        }
+    }
+
+    ///执行单个命令
+    pub async fn query<T: FromRedisValue>(&self, index: u32, cmd: Cmd) -> RedisResult<T> {
+        let client = self.clone();
+        let f = async move {
+            let mut conn = client.get_conn(index).await?;
+            cmd.query_async(&mut conn).await
+        };
+        from_redis_value(&self.run(f).await?)
+    }
+
+    ///执行pipeline,一次往返发送所有命令
+    pub async fn query_pipe<T: FromRedisValue>(
+        &self,
+        index: u32,
+        pipe: Pipeline,
+    ) -> RedisResult<T> {
+        let client = self.clone();
+        let f = async move {
+            let mut conn = client.get_conn(index).await?;
+            pipe.query_async(&mut conn).await
+        };
+        from_redis_value(&self.run(f).await?)
+    }
+
+    ///删掉hash里的旧key，写入新key，在一个事务里执行
+    pub async fn replace_hash<T: FromRedisValue>(
+        &self,
+        index: u32,
+        hkey: &str,
+        old_key: &str,
+        new_key: &str,
+        value: &str,
+    ) -> RedisResult<T> {
+        let hkey = self.ns_key(hkey);
+        let mut pipe = redis::pipe();
+        pipe.atomic()
+            .hdel(hkey.as_str(), old_key)
+            .ignore()
+            .hset(hkey.as_str(), new_key, value);
+        let (res,): (T,) = self.query_pipe(index, pipe).await?;
+        Ok(res)
+    }
+
+    ///删除key
+    pub async fn del<T: FromRedisValue>(&self, index: u32, hkey: &str) -> RedisResult<T> {
+        self.query(index, Cmd::del(self.ns_key(hkey))).await
+    }
+
+    ///删除hash里的key
+    pub async fn hdel<T: FromRedisValue>(
+        &self,
+        index: u32,
+        hkey: &str,
+        key: &str,
+    ) -> RedisResult<T> {
+        self.query(index, Cmd::hdel(self.ns_key(hkey), key)).await
+    }
+
+    ///获得hash所有values
+    pub async fn hvals<T: FromRedisValue>(&self, index: u32, hkey: &str) -> RedisResult<T> {
+        self.query(index, Cmd::hvals(self.ns_key(hkey))).await
+    }
+
+    ///获得hash所有key和value
+    pub async fn hgetall<T: FromRedisValue>(&self, index: u32, hkey: &str) -> RedisResult<T> {
+        self.query(index, Cmd::hgetall(self.ns_key(hkey))).await
+    }
+
+    ///写hash
+    pub async fn hset<T: FromRedisValue>(
+        &self,
+        index: u32,
+        hkey: &str,
+        key: &str,
+        value: &str,
+    ) -> RedisResult<T> {
+        self.query(index, Cmd::hset(self.ns_key(hkey), key, value))
+            .await
+    }
+
+    ///批量写同一个hash
+    pub async fn hset_multiple<T: FromRedisValue>(
+        &self,
+        index: u32,
+        hkey: &str,
+        items: &[(String, String)],
+    ) -> RedisResult<T> {
+        self.query(index, Cmd::hset_multiple(self.ns_key(hkey), items))
+            .await
+    }
+
+    ///读hash
+    pub async fn hget<T: FromRedisValue>(
+        &self,
+        index: u32,
+        hkey: &str,
+        key: &str,
+    ) -> RedisResult<T> {
+        self.query(index, Cmd::hget(self.ns_key(hkey), key)).await
+    }
+
+    ///批量读同一个hash,一次往返,结果和keys顺序一致,不存在的为None
+    pub async fn hmget<T: FromRedisValue>(
+        &self,
+        index: u32,
+        hkey: &str,
+        keys: &[&str],
+    ) -> RedisResult<Vec<Option<T>>> {
+        if keys.is_empty() {
+            return Ok(vec![]);
+        }
+        let mut cmd = redis::cmd("HMGET");
+        cmd.arg(self.ns_key(hkey)).arg(keys);
+        self.query(index, cmd).await
+    }
+
+    ///写入带过期时间的key,ttl:毫秒
+    pub async fn pset_ex<T: FromRedisValue>(
+        &self,
+        index: u32,
+        key: &str,
+        value: &str,
+        ttl: usize,
+    ) -> RedisResult<T> {
+        self.query(index, Cmd::pset_ex(self.ns_key(key), value, ttl))
+            .await
+    }
+
+    ///读取命名空间下所有匹配pattern的key的值，查找和读取之间过期的key跳过
+    pub async fn pattern_values(&self, index: u32, pattern: &str) -> RedisResult<Vec<String>> {
+        let keys = self.scan_keys(index, self.ns_key(pattern).as_str()).await?;
+        if keys.is_empty() {
+            return Ok(vec![]);
+        }
+        let mut cmd = redis::cmd("MGET");
+        cmd.arg(keys);
+        let res: Vec<Option<String>> = self.query(index, cmd).await?;
+        Ok(res.into_iter().flatten().collect())
+    }
+
+    ///按模式查找key,用SCAN分批查，不会像KEYS那样卡住redis
+    ///pattern不加命名空间，返回完整的key
+    pub async fn scan_keys(&self, index: u32, pattern: &str) -> RedisResult<Vec<String>> {
+        let mut res = vec![];
+        let mut cursor = 0_u64;
+        loop {
+            let mut cmd = redis::cmd("SCAN");
+            cmd.arg(cursor)
+                .arg("MATCH")
+                .arg(pattern)
+                .arg("COUNT")
+                .arg(1000);
+            let (next, keys): (u64, Vec<String>) = self.query(index, cmd).await?;
+            res.extend(keys);
+            if next == 0 {
+                break;
+            }
+            cursor = next;
+        }
+        Ok(res)
+    }
+
+    ///把没有命名空间的旧key改名成带命名空间的key
+    ///旧key不存在或者新key已经有数据的不处理，返回是否迁移了
+    pub async fn migrate_key(&self, index: u32, key: &str) -> RedisResult<bool> {
+        let new_key = self.ns_key(key);
+        if new_key == key {
+            return Ok(false);
+        }
+        let exists: bool = self.query(index, Cmd::exists(key)).await?;
+        if !exists {
+            return Ok(false);
+        }
+        self.query(index, Cmd::rename_nx(key, new_key.as_str()))
+            .await
+    }
+
+    ///把旧hash里的一个field迁移到带命名空间的hash里
+    ///旧hash里没有或者新hash里已经有这个field的不处理，返回是否迁移了
+    pub async fn migrate_hash_field(
+        &self,
+        index: u32,
+        hkey: &str,
+        field: &str,
+    ) -> RedisResult<bool> {
+        let new_key = self.ns_key(hkey);
+        if new_key == hkey {
+            return Ok(false);
+        }
+        let value: Option<String> = self.query(index, Cmd::hget(hkey, field)).await?;
+        if value.is_none() {
+            return Ok(false);
+        }
+        let res: bool = self
+            .query(index, Cmd::hset_nx(new_key, field, value.unwrap()))
+            .await?;
+        if res {
+            self.query::<()>(index, Cmd::hdel(hkey, field)).await?;
+        }
+        Ok(res)
+    }
+
+    ///有序集合添加成员
+    pub async fn zadd<T: FromRedisValue>(
+        &self,
+        index: u32,
+        zkey: &str,
+        key: &str,
+        value: isize,
+    ) -> RedisResult<T> {
+        self.query(index, Cmd::zadd(self.ns_key(zkey), key, value))
+            .await
+    }
+
+    ///得到整个有序集合
+    pub async fn zrange<T: FromRedisValue>(&self, index: u32, zkey: &str) -> RedisResult<T> {
+        self.query(index, Cmd::zrange(self.ns_key(zkey), 0, -1))
+            .await
+    }
+
+    ///有序集合成员分数加1
+    pub async fn zincrby<T: FromRedisValue>(
+        &self,
+        index: u32,
+        zkey: &str,
+        key: &str,
+    ) -> RedisResult<T> {
+        self.query(index, Cmd::zincr(self.ns_key(zkey), key, 1))
+            .await
+    }
+}
+
+///在同步代码里等待异步结果，在tokio的线程里(比如http处理函数)用block_in_place
+fn block_on<F: Future>(f: F) -> F::Output {
+    match tokio::runtime::Handle::try_current() {
+        Ok(handle) => tokio::task::block_in_place(move || handle.block_on(f)),
+        Err(_) => TOKIO_RT.block_on(f),
+    }
+}
+
+///redis客户端同步封装，给原来的同步调用使用，出错打印日志返回None
+///新代码直接用client()拿到RedisClient异步调用
+///内部的RedisClient可以多线程共用，不需要再套Mutex，clone出来的共用同一组连接
+#[derive(Clone)]
 pub struct RedisPoolTool {
-    conn: Connection,
+    client: RedisClient,
 }
 
 ///封装redis基本操作命令
 impl RedisPoolTool {
     ///初始化结构体
-    pub fn init(add: &str, password: &str) -> RedisPoolTool {
-        let client = redis::Client::open(add).unwrap();
-        info!("初始化redis客户端完成!");
-        let mut redis_pool = RedisPoolTool {
-            conn: client.get_connection().unwrap(),
+    ///先连上0号db，地址或者密码不对在启动时就返回错误
+    pub fn init(add: &str, password: &str) -> anyhow::Result<RedisPoolTool> {
+        let client = match RedisClient::new(add, password, DEFAULT_POOL_SIZE) {
+            Ok(client) => client,
+            Err(e) => anyhow::bail!("redis地址不对!add:{:?},{:?}", add, e),
         };
-        redis::pipe()
-            .cmd("AUTH")
-            .arg(password)
-            .execute(&mut redis_pool.conn);
-        redis_pool
+        if let Err(e) = block_on(client.get_conn(0)) {
+            anyhow::bail!("redis连接失败!add:{:?},{:?}", add, e);
+        }
+        info!("初始化redis客户端完成!");
+        Ok(RedisPoolTool { client })
+    }
+
+    ///异步客户端，带着当前的命名空间
+    pub fn client(&self) -> RedisClient {
+        self.client.clone()
+    }
+
+    ///设置key的命名空间，之后所有key都会加上"namespace:"前缀
+    ///global_keys里的key多个游戏共用，不加前缀
+    pub fn set_namespace(&mut self, namespace: &str, global_keys: &[&str]) {
+        self.client.set_namespace(namespace, global_keys);
+        info!("redis命名空间:{}", namespace);
+    }
+
+    ///同一组连接，换一个命名空间，给一个进程管多个游戏区服的时候用
+    pub fn with_namespace(&self, namespace: &str, global_keys: &[&str]) -> RedisPoolTool {
+        let mut client = self.client.clone();
+        client.set_namespace(namespace, global_keys);
+        RedisPoolTool { client }
     }
 
     pub fn replace_hash<T: FromRedisValue>(
-        &mut self,
+        &self,
         index: u32,
         hkey: &str,
         old_key: &str,
         new_key: &str,
         value: &str,
     ) -> Option<T> {
-        let mut pip = get_pip();
-        let conn_mut = &mut self.conn;
-        pip.cmd("select").arg(index).execute(conn_mut);
-        let mut res = conn_mut.hdel(hkey, old_key);
-        if let Err(e) = res {
-            error!("{:?}", e);
-        }
-        res = conn_mut.hset(hkey, new_key, value);
-        pip.cmd("select").arg(0).execute(conn_mut);
-        match res {
-            Ok(v) => Some(v),
-            Err(e) => {
-                error!("{:?}", e);
-                None
-            }
-        }
+        let res = block_on(
+            self.client
+                .replace_hash(index, hkey, old_key, new_key, value),
+        );
+        log_err(res, || format!("index:{},hkey:{:?}", index, hkey))
     }
 
     ///Delete one or more keys
-    pub fn del<T: FromRedisValue>(&mut self, index: u32, hkey: &str) -> Option<T> {
-        let mut pip = get_pip();
-        let conn_mut = &mut self.conn;
-        pip.cmd("select").arg(index).execute(conn_mut);
-        let res = conn_mut.del(hkey);
-        pip.cmd("select").arg(0).execute(conn_mut);
-        match res {
-            Ok(v) => Some(v),
-            Err(e) => {
-                error!("{:?}", e);
-                None
-            }
-        }
+    pub fn del<T: FromRedisValue>(&self, index: u32, hkey: &str) -> Option<T> {
+        let res = block_on(self.client.del(index, hkey));
+        log_err(res, || format!("index:{},hkey:{:?}", index, hkey))
     }
 
     ///Deletes a single (or multiple) fields from a hash
-    pub fn hdel<T: FromRedisValue>(&mut self, index: u32, hkey: &str, key: &str) -> Option<T> {
-        let mut pip = get_pip();
-        let conn_mut = &mut self.conn;
-        pip.cmd("select").arg(index).execute(conn_mut);
-        let res = conn_mut.hdel(hkey, key);
-        pip.cmd("select").arg(0).execute(conn_mut);
-        match res {
-            Ok(v) => Some(v),
-            Err(e) => {
-                error!("{:?}", e);
-                None
-            }
-        }
+    pub fn hdel<T: FromRedisValue>(&self, index: u32, hkey: &str, key: &str) -> Option<T> {
+        let res = block_on(self.client.hdel(index, hkey, key));
+        log_err(res, || {
+            format!("index:{},hkey:{:?},key:{:?}", index, hkey, key)
+        })
     }
 
     ///获得redis所有values
-    pub fn hvals<T: FromRedisValue>(&mut self, index: u32, hkey: &str) -> Option<T> {
-        let mut pip = get_pip();
-        let conn_mut = &mut self.conn;
-        pip.cmd("select").arg(index).execute(conn_mut);
-        let res = conn_mut.hvals(hkey);
-        pip.cmd("select").arg(0).execute(conn_mut);
-        match res {
-            Ok(v) => Some(v),
-            Err(e) => {
-                error!("{:?}", e);
-                None
-            }
-        }
+    pub fn hvals<T: FromRedisValue>(&self, index: u32, hkey: &str) -> Option<T> {
+        let res = block_on(self.client.hvals(index, hkey));
+        log_err(res, || format!("index:{},hkey:{:?}", index, hkey))
     }
 
     ///Gets all the fields and values in a hash.
-    pub fn hgetall<T: FromRedisValue>(&mut self, index: u32, hkey: &str) -> Option<T> {
-        let mut pip = get_pip();
-        let conn_mut = &mut self.conn;
-        pip.cmd("select").arg(index).execute(conn_mut);
-        let res = conn_mut.hgetall(hkey);
-        pip.cmd("select").arg(0).execute(conn_mut);
-        match res {
-            Ok(v) => Some(v),
-            Err(e) => {
-                error!("{:?}", e);
-                None
-            }
-        }
+    pub fn hgetall<T: FromRedisValue>(&self, index: u32, hkey: &str) -> Option<T> {
+        let res = block_on(self.client.hgetall(index, hkey));
+        log_err(res, || format!("index:{},hkey:{:?}", index, hkey))
     }
 
     ///操作hash数据结构
     pub fn hset<T: FromRedisValue>(
-        &mut self,
+        &self,
         index: u32,
         hkey: &str,
         key: &str,
         value: &str,
     ) -> Option<T> {
-        let mut pip = get_pip();
-        let conn_mut = &mut self.conn;
-        pip.cmd("select").arg(index).execute(conn_mut);
-        let res = conn_mut.hset(hkey, key, value);
-        pip.cmd("select").arg(0).execute(conn_mut);
-        match res {
-            Ok(v) => Some(v),
-            Err(e) => {
-                error!(
-                    "{:?},index:{},hkey:{:?},key:{:?},value:{:?}",
-                    e, index, hkey, key, value
-                );
-                None
-            }
-        }
+        let res = block_on(self.client.hset(index, hkey, key, value));
+        log_err(res, || {
+            format!(
+                "index:{},hkey:{:?},key:{:?},value:{:?}",
+                index, hkey, key, value
+            )
+        })
+    }
+
+    ///批量操作hash数据结构
+    pub fn hset_multiple<T: FromRedisValue>(
+        &self,
+        index: u32,
+        hkey: &str,
+        items: &[(String, String)],
+    ) -> Option<T> {
+        let res = block_on(self.client.hset_multiple(index, hkey, items));
+        log_err(res, || {
+            format!("index:{},hkey:{:?},len:{}", index, hkey, items.len())
+        })
+    }
+
+    ///批量读hash数据结构,结果和keys顺序一致,不存在的为None
+    pub fn hmget<T: FromRedisValue>(
+        &self,
+        index: u32,
+        hkey: &str,
+        keys: &[&str],
+    ) -> Option<Vec<Option<T>>> {
+        let res = block_on(self.client.hmget(index, hkey, keys));
+        log_err(res, || {
+            format!("index:{},hkey:{:?},len:{}", index, hkey, keys.len())
+        })
+    }
+
+    ///写入带过期时间的key,ttl:毫秒
+    pub fn pset_ex<T: FromRedisValue>(
+        &self,
+        index: u32,
+        key: &str,
+        value: &str,
+        ttl: usize,
+    ) -> Option<T> {
+        let res = block_on(self.client.pset_ex(index, key, value, ttl));
+        log_err(res, || format!("index:{},key:{:?}", index, key))
+    }
+
+    ///读取所有匹配pattern的key的值
+    pub fn pattern_values(&self, index: u32, pattern: &str) -> Option<Vec<String>> {
+        let res = block_on(self.client.pattern_values(index, pattern));
+        log_err(res, || format!("index:{},pattern:{:?}", index, pattern))
     }
 
     ///读hash数据结构
-    pub fn hget<T: FromRedisValue>(&mut self, index: u32, hkey: &str, key: &str) -> Option<T> {
-        let mut pip = get_pip();
-        let conn_mut = &mut self.conn;
-        pip.cmd("select").arg(index).execute(conn_mut);
-        let res = conn_mut.hget(hkey, key);
-        pip.cmd("select").arg(0).execute(conn_mut);
-        if res.is_err() {
+    pub fn hget<T: FromRedisValue>(&self, index: u32, hkey: &str, key: &str) -> Option<T> {
+        let res = block_on(self.client.hget(index, hkey, key));
+        if let Err(e) = res.as_ref() {
             warn!(
                 "hget has error:{:?},index:{},hkey:{:?},key:{:?}",
-                res.err().unwrap(),
-                index,
-                hkey,
-                key
+                e, index, hkey, key
             );
-            return None;
         }
-        Some(res.unwrap())
+        res.ok()
     }
 
     ///操作有序集合，单个添加
@@ -162,73 +524,52 @@ impl RedisPoolTool {
     /// key：有序集合成员的key
     /// value：有序集合成员value
     pub fn zadd<T: FromRedisValue>(
-        &mut self,
+        &self,
         index: u32,
         zkey: &str,
         key: &str,
         value: isize,
     ) -> Option<T> {
-        let mut pip = get_pip();
-        let conn_mut = &mut self.conn;
-        pip.cmd("select").arg(index).execute(conn_mut);
-        let res = conn_mut.zadd(zkey, key, value);
-        pip.cmd("select").arg(0).execute(conn_mut);
-        match res {
-            Ok(v) => Some(v),
-            Err(e) => {
-                error!("{:?}", e);
-                None
-            }
-        }
+        let res = block_on(self.client.zadd(index, zkey, key, value));
+        log_err(res, || {
+            format!("index:{},zkey:{:?},key:{:?}", index, zkey, key)
+        })
     }
 
     ///得到整个有序集合
     /// zkey:有序集合的key
-    pub fn zrange<T: FromRedisValue>(&mut self, index: u32, zkey: &str) -> Option<T> {
-        let mut pip = get_pip();
-        let conn_mut = &mut self.conn;
-        pip.cmd("select").arg(index).execute(conn_mut);
-        let res = conn_mut.zrange(zkey, 0, -1);
-        pip.cmd("select").arg(0).execute(conn_mut);
-        match res {
-            Ok(v) => Some(v),
-            Err(e) => {
-                error!("{:?}", e);
-                None
-            }
-        }
+    pub fn zrange<T: FromRedisValue>(&self, index: u32, zkey: &str) -> Option<T> {
+        let res = block_on(self.client.zrange(index, zkey));
+        log_err(res, || format!("index:{},zkey:{:?}", index, zkey))
     }
 
     ///有序集合自增操作
     /// zkey:有序集合的key
     /// key：有序集合成员的key
-    pub fn zincrby<T: FromRedisValue>(&mut self, index: u32, zkey: &str, key: &str) -> Option<T> {
-        let mut pip = get_pip();
-        let conn_mut = &mut self.conn;
-        pip.cmd("select").arg(index).execute(conn_mut);
-        let res = conn_mut.zincr(zkey, key, "increment");
-        pip.cmd("select").arg(0).execute(conn_mut);
-        match res {
-            Ok(v) => Some(v),
-            Err(e) => {
-                error!("{:?}", e);
-                None
-            }
-        }
+    pub fn zincrby<T: FromRedisValue>(&self, index: u32, zkey: &str, key: &str) -> Option<T> {
+        let res = block_on(self.client.zincrby(index, zkey, key));
+        log_err(res, || {
+            format!("index:{},zkey:{:?},key:{:?}", index, zkey, key)
+        })
     }
 
     ///读hash数据结构
-    pub fn test<T: FromRedisValue>(&mut self, index: u32, hkey: &str, key: &str) -> Option<T> {
-        let mut pip = get_pip();
-        let conn_mut = &mut self.conn;
-        pip.cmd("select").arg(index).execute(conn_mut);
-        let res = conn_mut.hget(hkey, key);
-        pip.cmd("select").arg(0).execute(conn_mut);
-        if res.is_err() {
-            error!("{:?}", res.err().unwrap());
-            return None;
-        }
-        Some(res.unwrap())
+    pub fn test<T: FromRedisValue>(&self, index: u32, hkey: &str, key: &str) -> Option<T> {
+        let res = block_on(self.client.hget(index, hkey, key));
+        log_err(res, || {
+            format!("index:{},hkey:{:?},key:{:?}", index, hkey, key)
+        })
+    }
+}
+
+///出错打印日志，返回None
+fn log_err<T, F: FnOnce() -> String>(res: RedisResult<T>, f: F) -> Option<T> {
+    match res {
+        Ok(v) => Some(v),
+        Err(e) => {
+            error!("{:?},{}", e, f());
+            None
+        }
     }
 }
 
@@ -236,16 +577,63 @@ pub fn get_pip() -> Pipeline {
     redis::pipe()
 }
 
-pub fn test_api(add: &str, pass: &str) {
-    let mut rpt = RedisPoolTool::init(add, pass);
-    let value: Option<String> = rpt.replace_hash(0, "name_2_uid", "test1", "test2", "123");
-    if value.is_some() {
-        let value = value.unwrap();
-        println!("sdfsfd:{:?}", value);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn namespace_prefixes_keys_except_global() {
+        let mut client = RedisClient::new("redis://localhost:6379/", "pass", 1).unwrap();
+        assert_eq!(client.info.passwd.as_deref(), Some("pass"));
+        assert_eq!(client.ns_key("current_rank"), "current_rank");
+
+        client.set_namespace("101:0", &["users"]);
+        assert_eq!(client.ns_key("current_rank"), "101:0:current_rank");
+        assert_eq!(client.ns_key("users"), "users");
     }
-}
 
-#[test]
-pub fn test() {
-    test_api("redis://localhost:6379/", "reison");
+    #[test]
+    fn init_returns_error() {
+        //地址不对
+        assert!(RedisPoolTool::init("localhost:6379", "").is_err());
+
+        //连不上，先占一个端口再释放掉，保证没有redis在监听
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        drop(listener);
+        let add = format!("redis://127.0.0.1:{}/", port);
+        assert!(RedisPoolTool::init(add.as_str(), "").is_err());
+    }
+
+    ///需要本地redis,地址用REDIS_TEST_ADD环境变量配置，连不上就跳过
+    #[test]
+    fn hmget_in_one_round_trip() {
+        let add = std::env::var("REDIS_TEST_ADD").unwrap_or("redis://127.0.0.1:6379/".to_owned());
+        let redis = match RedisPoolTool::init(add.as_str(), "") {
+            Ok(redis) => redis,
+            Err(e) => {
+                println!("没有可用的redis,跳过!{:?}", e);
+                return;
+            }
+        };
+        let namespace = format!("hmget_test_{}", std::process::id());
+        let redis = redis.with_namespace(namespace.as_str(), &[]);
+        let items = vec![
+            ("1".to_owned(), "a".to_owned()),
+            ("2".to_owned(), "b".to_owned()),
+        ];
+        let _: Option<String> = redis.hset_multiple(0, "users", items.as_slice());
+
+        let res: Option<Vec<Option<String>>> = redis.hmget(0, "users", &["2", "3", "1"]);
+        let expect = vec![Some("b".to_owned()), None, Some("a".to_owned())];
+        assert_eq!(res, Some(expect));
+        let res: Option<Vec<Option<String>>> = redis.hmget(0, "users", &[]);
+        assert_eq!(res, Some(vec![]));
+
+        //其他命名空间读不到
+        let other = redis.with_namespace("hmget_test_other", &[]);
+        let res: Option<Vec<Option<String>>> = other.hmget(0, "users", &["1"]);
+        assert_eq!(res, Some(vec![None]));
+        let _: Option<u32> = redis.del(0, "users");
+    }
 }
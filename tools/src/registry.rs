@@ -0,0 +1,295 @@
+use crate::conf::Validate;
+use crate::redis_pool::RedisPoolTool;
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+///注册中心redis index
+pub const REDIS_INDEX_REGISTRY: u32 = 4;
+
+///游戏服
+pub const ROLE_GAME: &str = "game";
+///gate路由服
+pub const ROLE_GATE: &str = "gate";
+///游戏中心(给gate连的地址)
+pub const ROLE_GAME_CENTER_GATE: &str = "game_center_gate";
+///游戏中心(给战斗服连的地址)
+pub const ROLE_GAME_CENTER_BATTLE: &str = "game_center_battle";
+///房间服
+pub const ROLE_ROOM: &str = "room";
+///排行榜服
+pub const ROLE_RANK: &str = "rank";
+
+///注册中心模式
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum RegistryMode {
+    File,  //从本地配置文件读取地址(本地开发用)
+    Redis, //注册到redis，通过心跳续期
+}
+
+///服务器注册信息
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ServiceInfo {
+    pub role: String,     //服务器类型
+    pub address: String,  //对外地址
+    pub capacity: u32,    //容量(玩家数或者房间数),0为不限制
+    pub load: u32,        //当前负载
+    pub healthy: bool,    //是否健康(停服排空中为false)
+    pub update_time: i64, //最后一次心跳时间(毫秒)，只用来排查问题，过期由redis处理
+}
+
+impl ServiceInfo {
+    pub fn new(role: &str, address: String, capacity: u32, load: u32) -> Self {
+        ServiceInfo {
+            role: role.to_owned(),
+            address,
+            capacity,
+            load,
+            healthy: !crate::shutdown::is_draining(),
+            update_time: chrono::Local::now().timestamp_millis(),
+        }
+    }
+}
+
+///注册中心配置，和其他配置一起校验
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RegistryConfig {
+    pub registry_mode: String,         //"file"或者"redis"
+    pub registry_ttl: usize,           //注册信息有效期(秒)
+    pub registry_host: Option<String>, //对外地址的ip,没配置的用监听地址
+}
+
+impl Default for RegistryConfig {
+    fn default() -> Self {
+        RegistryConfig {
+            registry_mode: "file".to_owned(),
+            registry_ttl: 15,
+            registry_host: None,
+        }
+    }
+}
+
+impl Validate for RegistryConfig {
+    fn validate(&self) -> anyhow::Result<()> {
+        match self.registry_mode.as_str() {
+            "file" | "redis" => {}
+            _ => anyhow::bail!("registry_mode只能是file或者redis!{:?}", self.registry_mode),
+        }
+        if self.registry_ttl == 0 {
+            anyhow::bail!("registry_ttl不能为0!");
+        }
+        Ok(())
+    }
+}
+
+///服务注册与发现
+///redis模式下每个进程一个key,启动时注册自己，然后定时心跳续期，超过ttl没有心跳的key由redis删掉
+///key跟着redis客户端的命名空间走，同一个游戏区服的服务器才能互相发现
+///file模式下不注册，地址从配置文件读取
+#[derive(Debug, Clone)]
+pub struct Registry {
+    pub mode: RegistryMode, //模式
+    pub ttl: i64,           //注册信息有效期(毫秒)
+}
+
+impl Registry {
+    ///从校验过的配置创建
+    pub fn new(config: &RegistryConfig) -> Self {
+        let mode = match config.registry_mode.as_str() {
+            "redis" => RegistryMode::Redis,
+            _ => RegistryMode::File,
+        };
+        let ttl = config.registry_ttl as i64 * 1000;
+        Registry { mode, ttl }
+    }
+
+    pub fn is_file_mode(&self) -> bool {
+        self.mode == RegistryMode::File
+    }
+
+    ///心跳间隔，有效期的三分之一
+    pub fn heartbeat_interval(&self) -> Duration {
+        Duration::from_millis((self.ttl / 3).max(1000) as u64)
+    }
+
+    ///启动心跳线程，立刻注册一次，之后每个心跳间隔调用一次f续期
+    ///file模式不需要注册，不启动
+    pub fn spawn_heartbeat<F>(&self, f: F)
+    where
+        F: Fn() + Send + 'static,
+    {
+        if self.is_file_mode() {
+            return;
+        }
+        let interval = self.heartbeat_interval();
+        let m = move || loop {
+            f();
+            std::thread::sleep(interval);
+        };
+        let res = std::thread::Builder::new()
+            .name("REGISTRY_HEARTBEAT".to_owned())
+            .spawn(m);
+        if let Err(e) = res {
+            error!("{:?}", e);
+        }
+    }
+
+    fn redis_key(role: &str, address: &str) -> String {
+        format!("registry:{}:{}", role, address)
+    }
+
+    fn redis_pattern(role: &str) -> String {
+        format!("registry:{}:*", role)
+    }
+
+    ///注册或者心跳续期
+    pub fn register(&self, redis: &RedisPoolTool, info: &ServiceInfo) {
+        if self.is_file_mode() {
+            return;
+        }
+        let res = serde_json::to_string(info);
+        if let Err(e) = res {
+            error!("{:?}", e);
+            return;
+        }
+        let _: Option<()> = redis.pset_ex(
+            REDIS_INDEX_REGISTRY,
+            Registry::redis_key(info.role.as_str(), info.address.as_str()).as_str(),
+            res.unwrap().as_str(),
+            self.ttl as usize,
+        );
+    }
+
+    ///注销
+    pub fn unregister(&self, redis: &RedisPoolTool, role: &str, address: &str) {
+        if self.is_file_mode() {
+            return;
+        }
+        let _: Option<u32> = redis.del(
+            REDIS_INDEX_REGISTRY,
+            Registry::redis_key(role, address).as_str(),
+        );
+        info!("从注册中心注销!role:{},address:{}", role, address);
+    }
+
+    ///发现某类型所有健康的服务器，按地址排序
+    ///file模式直接返回static_addresses
+    pub fn discover(
+        &self,
+        redis: &RedisPoolTool,
+        role: &str,
+        static_addresses: &[String],
+    ) -> Vec<ServiceInfo> {
+        if self.is_file_mode() {
+            return static_addresses
+                .iter()
+                .filter(|x| !x.is_empty())
+                .map(|x| {
+                    let mut info = ServiceInfo::new(role, x.clone(), 0, 0);
+                    info.healthy = true;
+                    info
+                })
+                .collect();
+        }
+        let pattern = Registry::redis_pattern(role);
+        let res = redis.pattern_values(REDIS_INDEX_REGISTRY, pattern.as_str());
+        let mut v = Vec::new();
+        for value in res.unwrap_or_default() {
+            let info = serde_json::from_str::<ServiceInfo>(value.as_str());
+            match info {
+                Ok(info) if info.healthy => v.push(info),
+                Ok(_) => {}
+                Err(e) => error!("{:?}", e),
+            }
+        }
+        v.sort_by(|a, b| a.address.cmp(&b.address));
+        v
+    }
+}
+
+impl RegistryConfig {
+    ///对外地址:配置了registry_host就用它替换监听地址里的ip,没配置的0.0.0.0换成127.0.0.1
+    pub fn advertise_address(&self, listen: &str) -> String {
+        let index = listen.rfind(':');
+        if index.is_none() {
+            return listen.to_owned();
+        }
+        let (ip, port) = listen.split_at(index.unwrap());
+        let host = match self.registry_host.as_deref() {
+            Some(host) => host,
+            None if ip == "0.0.0.0" => "127.0.0.1",
+            None => ip,
+        };
+        format!("{}{}", host, port)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn role_pattern_not_match_other_role() {
+        let pattern = Registry::redis_pattern(ROLE_GAME);
+        let prefix = pattern.trim_end_matches('*');
+        let key = Registry::redis_key(ROLE_GAME, "127.0.0.1:16801");
+        assert!(key.starts_with(prefix));
+        let key = Registry::redis_key(ROLE_GAME_CENTER_GATE, "127.0.0.1:16801");
+        assert!(!key.starts_with(prefix));
+    }
+
+    #[test]
+    fn advertise_address_replace_host() {
+        let mut config = RegistryConfig::default();
+        assert_eq!(config.advertise_address("0.0.0.0:16801"), "127.0.0.1:16801");
+        assert_eq!(config.advertise_address("10.0.0.2:16801"), "10.0.0.2:16801");
+        config.registry_host = Some("192.168.1.5".to_owned());
+        assert_eq!(config.advertise_address("0.0.0.0:16801"), "192.168.1.5:16801");
+    }
+
+    ///需要本地redis,地址用REDIS_TEST_ADD环境变量配置，连不上就跳过
+    #[test]
+    fn register_discover_and_expire() {
+        let add = std::env::var("REDIS_TEST_ADD").unwrap_or("redis://127.0.0.1:6379/".to_owned());
+        let redis = match RedisPoolTool::init(add.as_str(), "") {
+            Ok(redis) => redis,
+            Err(e) => {
+                println!("没有可用的redis,跳过!{:?}", e);
+                return;
+            }
+        };
+        let namespace = format!("registry_test_{}", std::process::id());
+        let redis = redis.with_namespace(namespace.as_str(), &[]);
+        let registry = Registry {
+            mode: RegistryMode::Redis,
+            ttl: 500,
+        };
+        let game = ServiceInfo::new(ROLE_GAME, "127.0.0.1:16801".to_owned(), 0, 1);
+        let center = ServiceInfo::new(ROLE_GAME_CENTER_GATE, "127.0.0.1:16802".to_owned(), 0, 1);
+        registry.register(&redis, &game);
+        registry.register(&redis, &center);
+
+        let res = registry.discover(&redis, ROLE_GAME, &[]);
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].address, game.address);
+
+        //其他游戏区服的命名空间看不到
+        let other = redis.with_namespace("registry_test_other", &[]);
+        assert!(registry.discover(&other, ROLE_GAME, &[]).is_empty());
+
+        //注销
+        registry.unregister(&redis, ROLE_GAME, game.address.as_str());
+        assert!(registry.discover(&redis, ROLE_GAME, &[]).is_empty());
+
+        //没有心跳续期，过了ttl由redis删掉
+        assert_eq!(
+            registry.discover(&redis, ROLE_GAME_CENTER_GATE, &[]).len(),
+            1
+        );
+        std::thread::sleep(Duration::from_millis(700));
+        assert!(registry
+            .discover(&redis, ROLE_GAME_CENTER_GATE, &[])
+            .is_empty());
+    }
+}
@@ -0,0 +1,273 @@
+use crate::util::packet::{Packet, FLAG_RPC_ERROR, FLAG_RPC_REPLY};
+use futures::channel::oneshot;
+use log::warn;
+use protobuf::Message;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+type RpcResult = anyhow::Result<Packet>;
+
+///rpc请求方，给请求分配id，收到回复时唤醒等待的请求
+#[derive(Clone, Default)]
+pub struct RpcClient {
+    next_id: Arc<AtomicU32>,                                       //下一个请求id
+    pending: Arc<Mutex<HashMap<u32, oneshot::Sender<RpcResult>>>>, //等待回复的请求
+}
+
+impl RpcClient {
+    pub fn new() -> Self {
+        RpcClient::default()
+    }
+
+    ///给packet分配请求id，packet由调用方按原来的路由发出去，通过返回的RpcReply等待回复
+    pub fn request(&self, packet: &mut Packet, timeout: Duration) -> RpcReply {
+        let mut rpc_id = 0;
+        //0表示不是rpc，跳过
+        while rpc_id == 0 {
+            rpc_id = self.next_id.fetch_add(1, Ordering::Relaxed).wrapping_add(1);
+        }
+        packet.set_rpc_id(rpc_id);
+        let (sender, rec) = oneshot::channel();
+        self.pending.lock().unwrap().insert(rpc_id, sender);
+        RpcReply {
+            rpc_id,
+            cmd: packet.get_cmd(),
+            timeout,
+            rec,
+            pending: self.pending.clone(),
+        }
+    }
+
+    ///收到rpc回复，唤醒等待的请求，不是rpc回复返回false
+    pub fn on_reply(&self, packet: &Packet) -> bool {
+        if !packet.is_rpc_reply() {
+            return false;
+        }
+        let rpc_id = packet.get_rpc_id();
+        let sender = self.pending.lock().unwrap().remove(&rpc_id);
+        let sender = match sender {
+            Some(sender) => sender,
+            None => {
+                warn!(
+                    "rpc request is timeout or not exist!cmd:{},rpc_id:{}",
+                    packet.get_cmd(),
+                    rpc_id
+                );
+                return true;
+            }
+        };
+        let res = if packet.get_flags() & FLAG_RPC_ERROR != 0 {
+            let err = String::from_utf8_lossy(packet.get_data());
+            Err(anyhow::anyhow!(
+                "rpc error!cmd:{},{}",
+                packet.get_cmd(),
+                err
+            ))
+        } else {
+            Ok(packet.clone())
+        };
+        //等待方已经超时放弃了
+        let _ = sender.send(res);
+        true
+    }
+
+    ///等待回复中的请求数量
+    pub fn pending_num(&self) -> usize {
+        self.pending.lock().unwrap().len()
+    }
+}
+
+///等待中的rpc回复
+pub struct RpcReply {
+    rpc_id: u32,                       //请求id
+    cmd: u32,                          //请求命令
+    timeout: Duration,                 //超时时间
+    rec: oneshot::Receiver<RpcResult>, //回复
+    pending: Arc<Mutex<HashMap<u32, oneshot::Sender<RpcResult>>>>,
+}
+
+impl RpcReply {
+    ///等待回复，超时或者对方处理失败返回Err
+    pub async fn wait(self) -> RpcResult {
+        let RpcReply {
+            rpc_id,
+            cmd,
+            timeout,
+            rec,
+            pending,
+        } = self;
+        match async_std::future::timeout(timeout, rec).await {
+            Ok(Ok(res)) => res,
+            Ok(Err(_)) => anyhow::bail!("rpc is canceled!cmd:{},rpc_id:{}", cmd, rpc_id),
+            Err(_) => {
+                pending.lock().unwrap().remove(&rpc_id);
+                anyhow::bail!("rpc timeout!cmd:{},rpc_id:{}", cmd, rpc_id)
+            }
+        }
+    }
+
+    ///等待回复并解析成protobuf消息
+    pub async fn wait_message<Resp: Message>(self) -> anyhow::Result<Resp> {
+        let packet = self.wait().await?;
+        let resp = Resp::parse_from_bytes(packet.get_data())?;
+        Ok(resp)
+    }
+}
+
+///rpc处理函数，执行请求返回回复的body
+pub type RpcHandler<T> = Arc<dyn Fn(&mut T, &Packet) -> anyhow::Result<Vec<u8>> + Send + Sync>;
+
+///rpc服务方，按命令注册处理函数，请求和回复都是protobuf消息
+pub struct RpcRouter<T> {
+    handlers: HashMap<u32, RpcHandler<T>>,
+}
+
+impl<T> Default for RpcRouter<T> {
+    fn default() -> Self {
+        RpcRouter {
+            handlers: HashMap::new(),
+        }
+    }
+}
+
+impl<T: 'static> RpcRouter<T> {
+    pub fn new() -> Self {
+        RpcRouter::default()
+    }
+
+    ///注册处理函数,f:(管理器,user_id,请求)->回复
+    pub fn register<Req, Resp>(&mut self, cmd: u32, f: fn(&mut T, u32, Req) -> anyhow::Result<Resp>)
+    where
+        Req: Message,
+        Resp: Message,
+    {
+        let handler = move |t: &mut T, packet: &Packet| -> anyhow::Result<Vec<u8>> {
+            let req = Req::parse_from_bytes(packet.get_data())?;
+            let resp = f(t, packet.get_user_id(), req)?;
+            Ok(resp.write_to_bytes()?)
+        };
+        self.handlers.insert(cmd, Arc::new(handler));
+    }
+
+    ///获得rpc请求的处理函数，不是rpc请求或者没有注册返回None
+    pub fn get(&self, packet: &Packet) -> Option<RpcHandler<T>> {
+        if packet.get_rpc_id() == 0 || packet.is_rpc_reply() {
+            return None;
+        }
+        self.handlers.get(&packet.get_cmd()).cloned()
+    }
+}
+
+///执行rpc请求，返回回复的bytes,处理失败回复错误信息
+pub fn handle<T>(t: &mut T, handler: RpcHandler<T>, request: &Packet) -> Vec<u8> {
+    let res = handler(t, request);
+    if let Err(e) = res.as_ref() {
+        warn!(
+            "rpc handle fail!cmd:{},user_id:{},{:?}",
+            request.get_cmd(),
+            request.get_user_id(),
+            e
+        );
+    }
+    build_reply_bytes(request, res)
+}
+
+///构建rpc回复，带上请求的命令，user_id和请求id
+pub fn build_reply_bytes(request: &Packet, res: anyhow::Result<Vec<u8>>) -> Vec<u8> {
+    let mut packet = Packet::new(request.get_cmd(), 0, request.get_user_id());
+    packet.set_is_client(false);
+    packet.set_rpc_id(request.get_rpc_id());
+    match res {
+        Ok(bytes) => {
+            packet.set_flags(FLAG_RPC_REPLY);
+            packet.set_data_from_vec(bytes);
+        }
+        Err(e) => {
+            packet.set_flags(FLAG_RPC_REPLY | FLAG_RPC_ERROR);
+            packet.set_data_from_vec(e.to_string().into_bytes());
+        }
+    }
+    packet.build_server_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protos::protocol::{C_USER_LOGIN, S_USER_LOGIN};
+
+    struct Mgr {
+        login_num: u32,
+    }
+
+    fn login(mgr: &mut Mgr, user_id: u32, req: C_USER_LOGIN) -> anyhow::Result<S_USER_LOGIN> {
+        if req.get_user_id() != user_id {
+            anyhow::bail!("user_id is wrong!")
+        }
+        mgr.login_num += 1;
+        let mut res = S_USER_LOGIN::new();
+        res.set_is_succ(true);
+        Ok(res)
+    }
+
+    fn call(client: &RpcClient, router: &RpcRouter<Mgr>, mgr: &mut Mgr, user_id: u32) -> RpcReply {
+        let mut req = C_USER_LOGIN::new();
+        req.set_user_id(1);
+        let mut packet = Packet::new(1, 0, user_id);
+        packet.set_data_from_vec(req.write_to_bytes().unwrap());
+        let reply = client.request(&mut packet, Duration::from_millis(100));
+        let packet = Packet::build_array_from_server(packet.build_server_bytes()).unwrap();
+        let handler = router.get(&packet[0]).unwrap();
+        let bytes = handle(mgr, handler, &packet[0]);
+        let packet = Packet::build_array_from_server(bytes).unwrap();
+        assert!(client.on_reply(&packet[0]));
+        reply
+    }
+
+    #[test]
+    fn request_reply() {
+        let client = RpcClient::new();
+        let mut router = RpcRouter::new();
+        router.register(1, login);
+        let mut mgr = Mgr { login_num: 0 };
+
+        let reply = call(&client, &router, &mut mgr, 1);
+        let res = async_std::task::block_on(reply.wait_message::<S_USER_LOGIN>());
+        assert!(res.unwrap().is_succ);
+        assert_eq!(mgr.login_num, 1);
+
+        //处理失败回复错误
+        let reply = call(&client, &router, &mut mgr, 2);
+        let res = async_std::task::block_on(reply.wait());
+        assert!(res.unwrap_err().to_string().contains("user_id is wrong!"));
+
+        //超时
+        let mut packet = Packet::new(1, 0, 1);
+        let reply = client.request(&mut packet, Duration::from_millis(10));
+        assert!(async_std::task::block_on(reply.wait()).is_err());
+        assert_eq!(client.pending_num(), 0);
+    }
+
+    #[test]
+    fn reply_after_timeout() {
+        let client = RpcClient::new();
+        let mut router = RpcRouter::new();
+        router.register(1, login);
+        let mut mgr = Mgr { login_num: 0 };
+
+        let mut packet = Packet::new(1, 0, 1);
+        packet.set_data_from_vec(C_USER_LOGIN::new().write_to_bytes().unwrap());
+        let reply = client.request(&mut packet, Duration::from_millis(10));
+        let res = async_std::task::block_on(reply.wait());
+        assert!(res.unwrap_err().to_string().contains("rpc timeout!"));
+
+        //超时后才到的回复被rpc吃掉，不会再当成普通消息转发
+        let packet = Packet::build_array_from_server(packet.build_server_bytes()).unwrap();
+        let handler = router.get(&packet[0]).unwrap();
+        let bytes = handle(&mut mgr, handler, &packet[0]);
+        let packet = Packet::build_array_from_server(bytes).unwrap();
+        assert!(client.on_reply(&packet[0]));
+        assert_eq!(client.pending_num(), 0);
+    }
+}
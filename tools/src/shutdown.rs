@@ -0,0 +1,48 @@
+use crate::TOKIO_RT;
+use log::info;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+///进程是否处于停服排空状态
+static DRAINING: AtomicBool = AtomicBool::new(false);
+
+///是否正在停服排空，排空期间不再接收新的登录、房间等请求
+pub fn is_draining() -> bool {
+    DRAINING.load(Ordering::SeqCst)
+}
+
+///进入停服排空状态，返回之前是否已经在排空
+pub fn set_draining() -> bool {
+    DRAINING.swap(true, Ordering::SeqCst)
+}
+
+///注册退出信号(SIGTERM/SIGINT)处理
+///收到信号后进入排空状态并执行f，f执行完毕后退出进程
+pub fn on_signal<F>(f: F)
+where
+    F: FnOnce() + Send + 'static,
+{
+    let m = move || {
+        TOKIO_RT.block_on(wait_signal());
+        info!("收到退出信号，开始停服排空!");
+        set_draining();
+        f();
+        info!("停服排空完毕，退出进程!");
+        std::process::exit(0);
+    };
+    std::thread::spawn(m);
+}
+
+#[cfg(unix)]
+async fn wait_signal() {
+    use tokio::signal::unix::{signal, SignalKind};
+    let mut term = signal(SignalKind::terminate()).unwrap();
+    tokio::select! {
+        _ = term.recv() => {}
+        _ = tokio::signal::ctrl_c() => {}
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait_signal() {
+    let _ = tokio::signal::ctrl_c().await;
+}
@@ -644,7 +644,7 @@ fn new_tcp_client(address: &str) -> anyhow::Result<TcpStream> {
 }
 
 ///set tcp params
-fn set_tream_param(ts: &TcpStream) -> anyhow::Result<()> {
+pub(crate) fn set_tream_param(ts: &TcpStream) -> anyhow::Result<()> {
     //No package, direct send
     ts.set_nodelay(true)?;
     //ts.set_read_timeout(Some(Duration::from_millis(50)))
@@ -662,7 +662,7 @@ fn set_tream_param(ts: &TcpStream) -> anyhow::Result<()> {
 }
 
 ///New TCP connection (for client)
-fn connect(address: &str) -> std::io::Result<TcpStream> {
+pub(crate) fn connect(address: &str) -> std::io::Result<TcpStream> {
     let ts = TcpStream::connect(address);
     ts
 }
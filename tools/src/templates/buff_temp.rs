@@ -1,5 +1,6 @@
 use crate::templates::template::{Template, TemplateMgrTrait};
 use std::collections::HashMap;
+use std::sync::Arc;
 
 #[derive(serde::Serialize, serde::Deserialize, Debug, Default, Clone)]
 pub struct BuffTemp {
@@ -20,12 +21,12 @@ impl Template for BuffTemp {}
 
 #[derive(Debug, Default, Clone)]
 pub struct BuffTempMgr {
-    pub temps: HashMap<u32, BuffTemp>, //key:id value:BuffTemp
+    pub temps: HashMap<u32, Arc<BuffTemp>>, //key:id value:BuffTemp,战斗里的buff持有Arc
 }
 
 impl BuffTempMgr {
     #[warn(unreachable_code)]
-    pub fn get_temp(&self, id: &u32) -> anyhow::Result<&BuffTemp> {
+    pub fn get_temp(&self, id: &u32) -> anyhow::Result<&Arc<BuffTemp>> {
         let res = self.temps.get(id);
         if res.is_none() {
             let str = format!("BuffTemp is none for id:{}", id);
@@ -36,7 +37,7 @@ impl BuffTempMgr {
 
     pub fn init(&mut self, t: Vec<BuffTemp>) {
         for tt in t {
-            self.temps.insert(tt.id, tt);
+            self.temps.insert(tt.id, Arc::new(tt));
         }
     }
 }
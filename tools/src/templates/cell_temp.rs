@@ -1,7 +1,26 @@
 use crate::templates::template::{Template, TemplateMgrTrait};
 use anyhow::Result;
+use num_enum::IntoPrimitive;
+use num_enum::TryFromPrimitive;
 use std::collections::{HashMap, HashSet};
 
+///元素类型
+#[derive(Debug, Clone, Copy, Eq, PartialEq, TryFromPrimitive, IntoPrimitive)]
+#[repr(u8)]
+pub enum ElementType {
+    Nature = 1, //生命元素
+    Earth = 2,  //土元素
+    Water = 3,  //水元素
+    Fire = 4,   //火元素
+}
+
+impl ElementType {
+    pub fn into_u8(self) -> u8 {
+        let res: u8 = self.into();
+        res
+    }
+}
+
 #[derive(serde::Serialize, serde::Deserialize, Debug, Default, Clone)]
 pub struct CellTemp {
     //配置id
@@ -42,12 +42,18 @@ impl LeagueTempMgr {
         }
     }
 
+    ///积分能达到的最高段位
     pub fn get_league_by_score(&self, score: i32) -> anyhow::Result<&LeagueTemp> {
-        let mut res_temp = None;
+        let mut res_temp: Option<&LeagueTemp> = None;
         for temp in self.temps.values() {
             if score < temp.score {
                 continue;
             }
+            if let Some(res) = res_temp {
+                if res.score >= temp.score {
+                    continue;
+                }
+            }
             res_temp = Some(temp);
         }
         if let None = res_temp {
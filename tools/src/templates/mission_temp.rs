@@ -1,5 +1,6 @@
 use crate::templates::template::{Template, TemplateMgrTrait};
 use std::collections::HashMap;
+use std::sync::Arc;
 
 //任务模版
 #[derive(serde::Serialize, serde::Deserialize, Debug, Default, Clone)]
@@ -21,14 +22,14 @@ impl Template for MissionTemp {}
 
 #[derive(Debug, Default, Clone)]
 pub struct MissionTempMgr {
-    pub temps: HashMap<u32, MissionTemp>, //key:id value:itemtemp
-    pub no_condition_mission: Vec<MissionTemp>, //没有条件的任务
-    pub condition_mission: Vec<MissionTemp>, //有条件的任务
+    pub temps: HashMap<u32, Arc<MissionTemp>>, //key:id value:itemtemp,战斗里的任务持有Arc
+    pub no_condition_mission: Vec<Arc<MissionTemp>>, //没有条件的任务
+    pub condition_mission: Vec<Arc<MissionTemp>>, //有条件的任务
 }
 
 impl MissionTempMgr {
     #[warn(unreachable_code)]
-    pub fn get_temp(&self, id: &u32) -> anyhow::Result<&MissionTemp> {
+    pub fn get_temp(&self, id: &u32) -> anyhow::Result<&Arc<MissionTemp>> {
         let res = self.temps.get(id);
         if res.is_none() {
             let str = format!("ItemTemp is none for id:{}", id);
@@ -39,6 +40,7 @@ impl MissionTempMgr {
 
     pub fn init(&mut self, t: Vec<MissionTemp>) {
         for tt in t {
+            let tt = Arc::new(tt);
             self.temps.insert(tt.id, tt.clone());
             if tt.appear_condition == 0 {
                 self.no_condition_mission.push(tt.clone());
@@ -48,11 +50,11 @@ impl MissionTempMgr {
         }
     }
 
-    pub fn condition_mission(&self) -> &[MissionTemp] {
+    pub fn condition_mission(&self) -> &[Arc<MissionTemp>] {
         &self.condition_mission
     }
 
-    pub fn no_condition_mission(&self) -> &[MissionTemp] {
+    pub fn no_condition_mission(&self) -> &[Arc<MissionTemp>] {
         &self.no_condition_mission
     }
 }
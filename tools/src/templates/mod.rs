@@ -19,6 +19,8 @@ pub mod skill_temp;
 pub mod soul_temp;
 pub mod summary_award_temp;
 pub mod template;
+pub mod template_check;
+pub mod template_export;
 pub mod template_name_constants;
 pub mod tile_map_temp;
 pub mod world_boss_temp;
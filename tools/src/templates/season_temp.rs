@@ -7,6 +7,8 @@ use std::collections::HashMap;
 pub struct SeasonTemp {
     pub id: u32,     //赛季id
     pub element: u8, //赛季元素
+    #[serde(default)]
+    pub keep_time: u64, //赛季持续时间(毫秒)
 }
 
 impl Template for SeasonTemp {}
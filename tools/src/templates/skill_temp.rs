@@ -1,5 +1,6 @@
 use crate::templates::template::{Template, TemplateMgrTrait};
 use std::collections::HashMap;
+use std::sync::Arc;
 
 #[derive(serde::Serialize, serde::Deserialize, Debug, Default, Clone)]
 pub struct SkillTemp {
@@ -23,13 +24,13 @@ impl Template for SkillTemp {}
 
 #[derive(Debug, Default, Clone)]
 pub struct SkillTempMgr {
-    pub temps: HashMap<u32, SkillTemp>, //key:id value:celltemp
+    pub temps: HashMap<u32, Arc<SkillTemp>>, //key:id value:celltemp,战斗里的技能持有Arc
     pub lock_skills: Vec<u32>,
 }
 
 impl SkillTempMgr {
     #[warn(unreachable_code)]
-    pub fn get_temp(&self, id: &u32) -> anyhow::Result<&SkillTemp> {
+    pub fn get_temp(&self, id: &u32) -> anyhow::Result<&Arc<SkillTemp>> {
         let res = self.temps.get(id);
         if res.is_none() {
             let str = format!("SkillTemp is none for id:{}", id);
@@ -45,7 +46,7 @@ impl SkillTempMgr {
             if tt.id == 321 {
                 self.lock_skills.push(id);
             }
-            self.temps.insert(id, tt);
+            self.temps.insert(id, Arc::new(tt));
         }
     }
 }
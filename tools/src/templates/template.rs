@@ -1,40 +1,42 @@
-use crate::templates::battle_limit_time_temp::{BattleLimitTimeTemp, BattleLimitTimeTempMgr};
-use crate::templates::buff_temp::{BuffTemp, BuffTempMgr};
-use crate::templates::cell_temp::{CellTemp, CellTempMgr};
-use crate::templates::character_temp::{CharacterTemp, CharacterTempMgr};
-use crate::templates::constant_temp::{ConstantTemp, ConstantTempMgr};
-use crate::templates::emoji_temp::{EmojiTemp, EmojiTempMgr};
-use crate::templates::grade_frame_temp::{GradeFrameTemp, GradeFrameTempMgr};
-use crate::templates::item_temp::{ItemTemp, ItemTempMgr};
-use crate::templates::league_temp::{LeagueTemp, LeagueTempMgr};
-use crate::templates::punish_temp::{PunishTemp, PunishTempMgr};
-use crate::templates::robot_temp::{RobotTemp, RobotTempMgr};
-use crate::templates::season_temp::{SeasonTemp, SeasonTempMgr};
-use crate::templates::skill_judge_temp::{SkillJudgeTemp, SkillJudgeTempMgr};
-use crate::templates::skill_scope_temp::{SkillScopeTemp, SkillScopeTempMgr};
-use crate::templates::skill_temp::{SkillTemp, SkillTempMgr};
-use crate::templates::soul_temp::{SoulTemp, SoulTempMgr};
-use crate::templates::summary_award_temp::{SummaryAwardTemp, SummaryAwardTempMgr};
+use crate::templates::battle_limit_time_temp::BattleLimitTimeTempMgr;
+use crate::templates::buff_temp::BuffTempMgr;
+use crate::templates::cell_temp::CellTempMgr;
+use crate::templates::character_temp::CharacterTempMgr;
+use crate::templates::constant_temp::ConstantTempMgr;
+use crate::templates::emoji_temp::EmojiTempMgr;
+use crate::templates::grade_frame_temp::GradeFrameTempMgr;
+use crate::templates::item_temp::ItemTempMgr;
+use crate::templates::league_temp::LeagueTempMgr;
+use crate::templates::punish_temp::PunishTempMgr;
+use crate::templates::robot_temp::RobotTempMgr;
+use crate::templates::season_temp::SeasonTempMgr;
+use crate::templates::skill_judge_temp::SkillJudgeTempMgr;
+use crate::templates::skill_scope_temp::SkillScopeTempMgr;
+use crate::templates::skill_temp::SkillTempMgr;
+use crate::templates::soul_temp::SoulTempMgr;
+use crate::templates::summary_award_temp::SummaryAwardTempMgr;
+use crate::templates::template_check::CheckReport;
 use crate::templates::template_name_constants::{
     BATTLE_LIMIT_TIME, BUFF, CELL_TEMPLATE, CHARACTER_TEMPLATE, CONSTANT_TEMPLATE, EMOJI_TEMPLATE,
     GRADE_FRAME, ITEM_TEMPLATE, LEAGUE, PUNISH, ROBOT, SEASON, SKILL_JUDGE_TEMPLATE,
     SKILL_SCOPE_TEMPLATE, SKILL_TEMPLATE, SOUL, SUMMARY_AWARD, TILE_MAP_TEMPLATE,
     WORLD_CELL_TEMPLATE,
 };
-use crate::templates::tile_map_temp::{TileMapTemp, TileMapTempMgr};
-use crate::templates::world_cell_temp::{WorldCellTemp, WorldCellTempMgr};
-use log::error;
-use std::borrow::BorrowMut;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use crate::templates::tile_map_temp::TileMapTempMgr;
+use crate::templates::world_cell_temp::WorldCellTempMgr;
+use arc_swap::ArcSwap;
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
 use std::path::Path;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
 
-use super::market_temp::MarketTemp;
 use super::market_temp::MarketTempMgr;
-use super::merchandise_temp::{MerchandiseTemp, MerchandiseTempMgr};
-use super::mission_temp::{MissionTemp, MissionTempMgr};
+use super::merchandise_temp::MerchandiseTempMgr;
+use super::mission_temp::MissionTempMgr;
 use super::template_name_constants::{MARKET, MERCHANDISE, MISSION, WORLD_BOSS};
-use super::world_boss_temp::{WorldBossTemp, WorldBossTempMgr};
+use super::world_boss_temp::WorldBossTempMgr;
 
 pub trait Template {}
 
@@ -69,6 +71,7 @@ pub struct TemplatesMgr {
     merchandise_temp_mgr: MerchandiseTempMgr,           //商品配置
     mission_temp_mgr: MissionTempMgr,                   //任务配置
     worldboss_temp_mgr: WorldBossTempMgr,               //worldboss
+    files: HashMap<String, u32>,                        //已加载的文件 key:文件名 value:内容crc32
 }
 
 impl TemplatesMgr {
@@ -76,39 +79,58 @@ impl TemplatesMgr {
         self.constant_temp_mgr();
     }
 
-    pub fn reload_temps(&self, path: &str) -> anyhow::Result<()> {
-        let mgr_ptr = self as *const TemplatesMgr as *mut TemplatesMgr;
-        unsafe {
-            let mgr_mut = mgr_ptr.as_mut().unwrap();
-            mgr_mut.character_temp_mgr.clear();
-            mgr_mut.tile_map_temp_mgr.clear();
-            mgr_mut.emoji_temp_mgr.clear();
-            mgr_mut.constant_temp_mgr.clear();
-            mgr_mut.world_cell_temp_mgr.clear();
-            mgr_mut.cell_temp_mgr.clear();
-            mgr_mut.skill_temp_mgr.clear();
-            mgr_mut.item_temp_mgr.clear();
-            mgr_mut.skill_scope_temp_mgr.clear();
-            mgr_mut.buff_temp_mgr.clear();
-            mgr_mut.skill_judge_temp_mgr.clear();
-            mgr_mut.season_temp_mgr.clear();
-            mgr_mut.robot_temp_mgr.clear();
-            mgr_mut.league_temp_mgr.clear();
-            mgr_mut.summary_award_temp_mgr.clear();
-            mgr_mut.battle_limit_time_temp_mgr.clear();
-            mgr_mut.punish_temp_mgr.clear();
-            mgr_mut.grade_frame_temp_mgr.clear();
-            mgr_mut.soul_temp_mgr.clear();
-            mgr_mut.market_temp_mgr.clear();
-            mgr_mut.merchandise_temp_mgr.clear();
-            mgr_mut.mission_temp_mgr.clear();
-            mgr_mut.worldboss_temp_mgr.clear();
-            let res = read_templates_from_dir(path, mgr_mut);
-            if let Err(e) = res {
-                error!("{:?}", e);
-                return Ok(());
+    ///从目录读取所有配置表，任何一个文件解析失败都返回错误
+    pub fn load<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let mut temps_mgr = TemplatesMgr::default();
+        read_templates_from_dir(path, &mut temps_mgr)?;
+        Ok(temps_mgr)
+    }
+
+    ///所有配置表，校验用
+    fn tables(&self) -> Vec<(&'static str, &dyn TemplateMgrTrait)> {
+        vec![
+            (CHARACTER_TEMPLATE, &self.character_temp_mgr),
+            (TILE_MAP_TEMPLATE, &self.tile_map_temp_mgr),
+            (EMOJI_TEMPLATE, &self.emoji_temp_mgr),
+            (CONSTANT_TEMPLATE, &self.constant_temp_mgr),
+            (WORLD_CELL_TEMPLATE, &self.world_cell_temp_mgr),
+            (CELL_TEMPLATE, &self.cell_temp_mgr),
+            (SKILL_TEMPLATE, &self.skill_temp_mgr),
+            (ITEM_TEMPLATE, &self.item_temp_mgr),
+            (SKILL_SCOPE_TEMPLATE, &self.skill_scope_temp_mgr),
+            (BUFF, &self.buff_temp_mgr),
+            (SKILL_JUDGE_TEMPLATE, &self.skill_judge_temp_mgr),
+            (SEASON, &self.season_temp_mgr),
+            (ROBOT, &self.robot_temp_mgr),
+            (LEAGUE, &self.league_temp_mgr),
+            (SUMMARY_AWARD, &self.summary_award_temp_mgr),
+            (BATTLE_LIMIT_TIME, &self.battle_limit_time_temp_mgr),
+            (PUNISH, &self.punish_temp_mgr),
+            (GRADE_FRAME, &self.grade_frame_temp_mgr),
+            (SOUL, &self.soul_temp_mgr),
+            (MARKET, &self.market_temp_mgr),
+            (MERCHANDISE, &self.merchandise_temp_mgr),
+            (MISSION, &self.mission_temp_mgr),
+            (WORLD_BOSS, &self.worldboss_temp_mgr),
+        ]
+    }
+
+    ///校验表之间的引用、枚举值和数值范围
+    pub fn check(&self) -> CheckReport {
+        crate::templates::template_check::check(self)
+    }
+
+    ///校验新版本，旧版本有数据的表新版本不能变成空表，表之间的引用也要校验通过
+    pub fn validate(&self, old: &TemplatesMgr) -> anyhow::Result<()> {
+        for ((name, new), (_, old)) in self.tables().into_iter().zip(old.tables()) {
+            if new.is_empty() && !old.is_empty() {
+                anyhow::bail!("配置表{}热更后为空!", name);
             }
         }
+        let report = self.check();
+        if !report.is_ok() {
+            anyhow::bail!("{}", report);
+        }
         Ok(())
     }
 
@@ -228,10 +250,111 @@ impl TemplatesMgr {
     }
 }
 
-pub fn init_temps_mgr(path: &str) -> TemplatesMgr {
-    let mut temps_mgr = TemplatesMgr::default();
-    read_templates_from_dir(path, temps_mgr.borrow_mut()).unwrap();
-    temps_mgr
+///热更结果
+#[derive(Debug, Default, Clone)]
+pub struct ReloadReport {
+    pub version: u32,         //热更后的版本号
+    pub added: Vec<String>,   //新增的文件
+    pub changed: Vec<String>, //内容有变化的文件
+    pub removed: Vec<String>, //删除的文件
+}
+
+impl ReloadReport {
+    fn diff(old: &TemplatesMgr, new: &TemplatesMgr) -> Self {
+        let mut report = ReloadReport::default();
+        for (name, crc) in new.files.iter() {
+            match old.files.get(name) {
+                None => report.added.push(name.clone()),
+                Some(old_crc) if old_crc != crc => report.changed.push(name.clone()),
+                Some(_) => {}
+            }
+        }
+        for name in old.files.keys() {
+            if !new.files.contains_key(name) {
+                report.removed.push(name.clone());
+            }
+        }
+        report.added.sort();
+        report.changed.sort();
+        report.removed.sort();
+        report
+    }
+
+    ///没有任何文件变化
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.changed.is_empty() && self.removed.is_empty()
+    }
+}
+
+impl Display for ReloadReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "version:{},added:{:?},changed:{:?},removed:{:?}",
+            self.version, self.added, self.changed, self.removed
+        )
+    }
+}
+
+///可热更的配置表
+///热更时在旁边完整构建一个新版本并校验，全部成功后原子替换，失败的话旧版本原样保留
+///旧版本在最后一个持有者用完后释放，战斗里的技能、buff等持有各自配置的Arc，不受热更影响
+///同一段逻辑需要前后一致的数据时先load()一次再用，不要多次通过TEMPLATES取
+pub struct Templates {
+    path: String,                   //配置表目录
+    current: ArcSwap<TemplatesMgr>, //当前版本
+    version: AtomicU32,             //版本号，每次热更成功加1
+    reload_lock: Mutex<()>,         //防止同时热更
+}
+
+impl Templates {
+    pub fn new(path: &str, temps_mgr: TemplatesMgr) -> Self {
+        Templates {
+            path: path.to_owned(),
+            current: ArcSwap::from_pointee(temps_mgr),
+            version: AtomicU32::new(0),
+            reload_lock: Mutex::new(()),
+        }
+    }
+
+    ///当前版本
+    pub fn load(&self) -> Arc<TemplatesMgr> {
+        self.current.load_full()
+    }
+
+    pub fn version(&self) -> u32 {
+        self.version.load(Ordering::Acquire)
+    }
+
+    ///重新读取配置表目录，解析和校验都通过才替换，返回变化的文件
+    pub fn reload(&self) -> anyhow::Result<ReloadReport> {
+        //锁里只有读文件和校验，上次热更panic了也不影响这次
+        let _lock = self.reload_lock.lock().unwrap_or_else(|e| e.into_inner());
+        let old = self.load();
+        let new = TemplatesMgr::load(self.path.as_str())?;
+        new.validate(&old)?;
+        let mut report = ReloadReport::diff(&old, &new);
+        if report.is_empty() {
+            report.version = self.version();
+            return Ok(report);
+        }
+        self.current.store(Arc::new(new));
+        report.version = self.version.fetch_add(1, Ordering::AcqRel) + 1;
+        Ok(report)
+    }
+}
+
+///启动时加载并校验配置表，失败直接panic
+pub fn init_temps_mgr(path: &str) -> Templates {
+    let temps_mgr = match TemplatesMgr::load(path) {
+        Ok(temps_mgr) => temps_mgr,
+        Err(e) => panic!("加载配置表失败!path:{},{:?}", path, e),
+    };
+    let report = temps_mgr.check();
+    if !report.is_ok() {
+        panic!("path:{},{}", path, report);
+    }
+    Templates::new(path, temps_mgr)
 }
 
 ///读取配置文件
@@ -239,99 +362,162 @@ fn read_templates_from_dir<P: AsRef<Path>>(
     path: P,
     temps_mgr: &mut TemplatesMgr,
 ) -> anyhow::Result<()> {
-    // Open the file in read-only mode with buffer.
     let result = std::fs::read_dir(path)?;
     for f in result {
-        let file = f.unwrap();
-        let name = file.file_name();
-        if name.eq(".DS_Store") {
+        let file = f?;
+        let file_name = file.file_name();
+        let file_name = match file_name.to_str() {
+            Some(file_name) => file_name.to_owned(),
+            None => anyhow::bail!("配置文件名不是utf8!{:?}", file_name),
+        };
+        if file_name.eq(".DS_Store") {
             continue;
         }
-        let mut str = String::new();
-        str.push_str(file.path().parent().unwrap().to_str().unwrap());
-        str.push_str("/");
-        str.push_str(name.to_str().unwrap());
-        let file = File::open(str)?;
-        let mut reader = BufReader::new(file);
-        let mut context = String::new();
-        reader.read_line(&mut context)?;
-        let mut name = name.to_str().unwrap().to_string();
+        let context = std::fs::read_to_string(file.path())?;
+        let mut name = file_name.clone();
         let beta_offset = name.find('.').unwrap_or(name.len());
         name.replace_range(beta_offset.., "");
-        init_temps(temps_mgr, name, context.as_str());
+        if init_temps(temps_mgr, name, context.as_str())? {
+            let crc = crc32fast::hash(context.as_bytes());
+            temps_mgr.files.insert(file_name, crc);
+        }
     }
     Ok(())
 }
 
-fn init_temps(temps_mgr: &mut TemplatesMgr, name: String, context: &str) {
+fn parse_temps<T: DeserializeOwned>(name: &str, context: &str) -> anyhow::Result<Vec<T>> {
+    match serde_json::from_str(context) {
+        Ok(v) => Ok(v),
+        Err(e) => anyhow::bail!("配置文件解析失败!name:{},{:?}", name, e),
+    }
+}
+
+///初始化一张配置表，不认识的文件返回false
+fn init_temps(temps_mgr: &mut TemplatesMgr, name: String, context: &str) -> anyhow::Result<bool> {
     if name.eq_ignore_ascii_case(TILE_MAP_TEMPLATE) {
-        let v: Vec<TileMapTemp> = serde_json::from_str(context).unwrap();
-        temps_mgr.tile_map_temp_mgr.init(v);
+        temps_mgr
+            .tile_map_temp_mgr
+            .init(parse_temps(&name, context)?);
     } else if name.eq_ignore_ascii_case(CHARACTER_TEMPLATE) {
-        let v: Vec<CharacterTemp> = serde_json::from_str(context).unwrap();
-        temps_mgr.character_temp_mgr.init(v);
+        temps_mgr
+            .character_temp_mgr
+            .init(parse_temps(&name, context)?);
     } else if name.eq_ignore_ascii_case(EMOJI_TEMPLATE) {
-        let v: Vec<EmojiTemp> = serde_json::from_str(context).unwrap();
-        temps_mgr.emoji_temp_mgr.init(v);
+        temps_mgr.emoji_temp_mgr.init(parse_temps(&name, context)?);
     } else if name.eq_ignore_ascii_case(CONSTANT_TEMPLATE) {
-        let v: Vec<ConstantTemp> = serde_json::from_str(context).unwrap();
-        temps_mgr.constant_temp_mgr.init(v);
+        temps_mgr
+            .constant_temp_mgr
+            .init(parse_temps(&name, context)?);
     } else if name.eq_ignore_ascii_case(WORLD_CELL_TEMPLATE) {
-        let v: Vec<WorldCellTemp> = serde_json::from_str(context).unwrap();
-        temps_mgr.world_cell_temp_mgr.init(v);
+        temps_mgr
+            .world_cell_temp_mgr
+            .init(parse_temps(&name, context)?);
     } else if name.eq_ignore_ascii_case(CELL_TEMPLATE) {
-        let v: Vec<CellTemp> = serde_json::from_str(context).unwrap();
-        temps_mgr.cell_temp_mgr.init(v);
+        temps_mgr.cell_temp_mgr.init(parse_temps(&name, context)?);
     } else if name.eq_ignore_ascii_case(SKILL_TEMPLATE) {
-        let v: Vec<SkillTemp> = serde_json::from_str(context).unwrap();
-        temps_mgr.skill_temp_mgr.init(v);
+        temps_mgr.skill_temp_mgr.init(parse_temps(&name, context)?);
     } else if name.eq_ignore_ascii_case(SKILL_SCOPE_TEMPLATE) {
-        let v: Vec<SkillScopeTemp> = serde_json::from_str(context).unwrap();
-        temps_mgr.skill_scope_temp_mgr.init(v);
+        temps_mgr
+            .skill_scope_temp_mgr
+            .init(parse_temps(&name, context)?);
     } else if name.eq_ignore_ascii_case(ITEM_TEMPLATE) {
-        let v: Vec<ItemTemp> = serde_json::from_str(context).unwrap();
-        temps_mgr.item_temp_mgr.init(v);
+        temps_mgr.item_temp_mgr.init(parse_temps(&name, context)?);
     } else if name.eq_ignore_ascii_case(SKILL_JUDGE_TEMPLATE) {
-        let v: Vec<SkillJudgeTemp> = serde_json::from_str(context).unwrap();
-        temps_mgr.skill_judge_temp_mgr.init(v);
+        temps_mgr
+            .skill_judge_temp_mgr
+            .init(parse_temps(&name, context)?);
     } else if name.eq_ignore_ascii_case(BUFF) {
-        let v: Vec<BuffTemp> = serde_json::from_str(context).unwrap();
-        temps_mgr.buff_temp_mgr.init(v);
+        temps_mgr.buff_temp_mgr.init(parse_temps(&name, context)?);
     } else if name.eq_ignore_ascii_case(SEASON) {
-        let v: Vec<SeasonTemp> = serde_json::from_str(context).unwrap();
-        temps_mgr.season_temp_mgr.init(v);
+        temps_mgr.season_temp_mgr.init(parse_temps(&name, context)?);
     } else if name.eq_ignore_ascii_case(ROBOT) {
-        let v: Vec<RobotTemp> = serde_json::from_str(context).unwrap();
-        temps_mgr.robot_temp_mgr.init(v);
+        temps_mgr.robot_temp_mgr.init(parse_temps(&name, context)?);
     } else if name.eq_ignore_ascii_case(LEAGUE) {
-        let v: Vec<LeagueTemp> = serde_json::from_str(context).unwrap();
-        temps_mgr.league_temp_mgr.init(v);
+        temps_mgr.league_temp_mgr.init(parse_temps(&name, context)?);
     } else if name.eq_ignore_ascii_case(SUMMARY_AWARD) {
-        let v: Vec<SummaryAwardTemp> = serde_json::from_str(context).unwrap();
-        temps_mgr.summary_award_temp_mgr.init(v);
+        temps_mgr
+            .summary_award_temp_mgr
+            .init(parse_temps(&name, context)?);
     } else if name.eq_ignore_ascii_case(BATTLE_LIMIT_TIME) {
-        let v: Vec<BattleLimitTimeTemp> = serde_json::from_str(context).unwrap();
-        temps_mgr.battle_limit_time_temp_mgr.init(v);
+        temps_mgr
+            .battle_limit_time_temp_mgr
+            .init(parse_temps(&name, context)?);
     } else if name.eq_ignore_ascii_case(PUNISH) {
-        let v: Vec<PunishTemp> = serde_json::from_str(context).unwrap();
-        temps_mgr.punish_temp_mgr.init(v);
+        temps_mgr.punish_temp_mgr.init(parse_temps(&name, context)?);
     } else if name.eq_ignore_ascii_case(GRADE_FRAME) {
-        let v: Vec<GradeFrameTemp> = serde_json::from_str(context).unwrap();
-        temps_mgr.grade_frame_temp_mgr.init(v);
+        temps_mgr
+            .grade_frame_temp_mgr
+            .init(parse_temps(&name, context)?);
     } else if name.eq_ignore_ascii_case(SOUL) {
-        let v: Vec<SoulTemp> = serde_json::from_str(context).unwrap();
-        temps_mgr.soul_temp_mgr.init(v);
+        temps_mgr.soul_temp_mgr.init(parse_temps(&name, context)?);
     } else if name.eq_ignore_ascii_case(MARKET) {
-        let v: Vec<MarketTemp> = serde_json::from_str(context).unwrap();
-        temps_mgr.market_temp_mgr.init(v);
+        temps_mgr.market_temp_mgr.init(parse_temps(&name, context)?);
     } else if name.eq_ignore_ascii_case(MERCHANDISE) {
-        let v: Vec<MerchandiseTemp> = serde_json::from_str(context).unwrap();
-        temps_mgr.merchandise_temp_mgr.init(v);
+        temps_mgr
+            .merchandise_temp_mgr
+            .init(parse_temps(&name, context)?);
     } else if name.eq_ignore_ascii_case(MISSION) {
-        let v: Vec<MissionTemp> = serde_json::from_str(context).unwrap();
-        temps_mgr.mission_temp_mgr.init(v);
+        temps_mgr
+            .mission_temp_mgr
+            .init(parse_temps(&name, context)?);
     } else if name.eq_ignore_ascii_case(WORLD_BOSS) {
-        let v: Vec<WorldBossTemp> = serde_json::from_str(context).unwrap();
-        temps_mgr.worldboss_temp_mgr.init(v);
+        temps_mgr
+            .worldboss_temp_mgr
+            .init(parse_temps(&name, context)?);
+    } else {
+        return Ok(false);
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn punish_time(temps_mgr: &TemplatesMgr) -> i64 {
+        temps_mgr
+            .punish_temp_mgr()
+            .get_temp(&1)
+            .unwrap()
+            .punish_time
+    }
+
+    #[test]
+    fn reload_keeps_old_version_on_error() {
+        let dir = std::env::temp_dir().join(format!("templates_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let punish = dir.join("Punish.json");
+        std::fs::write(&punish, r#"[{"id":1,"punish_time":10}]"#).unwrap();
+        let temps = init_temps_mgr(dir.to_str().unwrap());
+        let old = temps.load();
+        assert_eq!(punish_time(&temps.load()), 10);
+
+        //解析失败保留旧版本
+        std::fs::write(&punish, r#"[{"id":1,"#).unwrap();
+        assert!(temps.reload().is_err());
+        assert_eq!(temps.version(), 0);
+        assert_eq!(punish_time(&temps.load()), 10);
+
+        //表被清空也不替换
+        std::fs::write(&punish, "[]").unwrap();
+        assert!(temps.reload().is_err());
+
+        std::fs::write(&punish, r#"[{"id":1,"punish_time":20}]"#).unwrap();
+        std::fs::write(dir.join("Emoji.json"), "[]").unwrap();
+        let report = temps.reload().unwrap();
+        assert_eq!(report.version, 1);
+        assert_eq!(report.changed, vec!["Punish.json".to_owned()]);
+        assert_eq!(report.added, vec!["Emoji.json".to_owned()]);
+        assert_eq!(punish_time(&temps.load()), 20);
+        //旧版本还在用的仍然有效，用完就释放
+        assert_eq!(punish_time(&old), 10);
+        let weak = Arc::downgrade(&old);
+        std::mem::drop(old);
+        assert!(weak.upgrade().is_none());
+
+        //没有变化不升版本
+        assert!(temps.reload().unwrap().is_empty());
+        assert_eq!(temps.version(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
     }
 }
@@ -0,0 +1,411 @@
+use crate::templates::cell_temp::ElementType;
+use crate::templates::character_temp::TransformInheritType;
+use crate::templates::merchandise_temp::RoomType;
+use crate::templates::template::TemplatesMgr;
+use crate::templates::template_name_constants::{
+    BUFF, CELL_TEMPLATE, CHARACTER_TEMPLATE, ITEM_TEMPLATE, LEAGUE, MARKET, MERCHANDISE, ROBOT,
+    SEASON, SKILL_JUDGE_TEMPLATE, SKILL_SCOPE_TEMPLATE, SKILL_TEMPLATE, TILE_MAP_TEMPLATE,
+    WORLD_BOSS, WORLD_CELL_TEMPLATE,
+};
+use crate::templates::tile_map_temp::MapCellType;
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::fmt::{Display, Formatter};
+use std::hash::Hash;
+
+///地图格子数量(6x5)
+pub const MAP_SIZE: usize = 30;
+
+///一条校验错误
+#[derive(Debug, Clone)]
+pub struct CheckError {
+    pub table: &'static str, //配置表
+    pub id: String,          //出错的配置id
+    pub field: &'static str, //出错的字段
+    pub message: String,     //错误描述
+}
+
+impl Display for CheckError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}[id:{}].{}: {}",
+            self.table, self.id, self.field, self.message
+        )
+    }
+}
+
+///配置表校验结果
+#[derive(Debug, Default, Clone)]
+pub struct CheckReport {
+    pub errors: Vec<CheckError>,           //所有错误
+    missing_tables: HashSet<&'static str>, //被引用但是没有数据的表,只报一次
+}
+
+impl CheckReport {
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    fn push<I: Display>(
+        &mut self,
+        table: &'static str,
+        id: I,
+        field: &'static str,
+        message: String,
+    ) {
+        self.errors.push(CheckError {
+            table,
+            id: id.to_string(),
+            field,
+            message,
+        });
+    }
+
+    ///检查外键，key为0表示不引用
+    fn check_ref<I: Display, V>(
+        &mut self,
+        table: &'static str,
+        id: I,
+        field: &'static str,
+        key: u32,
+        target: &'static str,
+        temps: &HashMap<u32, V>,
+    ) {
+        if key == 0 || temps.contains_key(&key) {
+            return;
+        }
+        if temps.is_empty() {
+            if self.missing_tables.insert(target) {
+                let message = format!("引用的配置表{}没有数据", target);
+                self.push(table, id, field, message);
+            }
+            return;
+        }
+        let message = format!("{}中没有id:{}", target, key);
+        self.push(table, id, field, message);
+    }
+
+    ///检查枚举值，能转成对应的枚举才算有效
+    fn check_enum<I: Display, E: TryFrom<u8>>(
+        &mut self,
+        table: &'static str,
+        id: I,
+        field: &'static str,
+        value: u32,
+        name: &str,
+    ) {
+        if u8::try_from(value).is_ok_and(|x| E::try_from(x).is_ok()) {
+            return;
+        }
+        let message = format!("{}不是有效的{}", value, name);
+        self.push(table, id, field, message);
+    }
+}
+
+impl Display for CheckReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.is_ok() {
+            return write!(f, "配置表校验通过!");
+        }
+        writeln!(f, "配置表校验失败,共{}个错误:", self.errors.len())?;
+        let mut table = "";
+        for error in self.errors.iter() {
+            if error.table != table {
+                table = error.table;
+                writeln!(f, "[{}]", table)?;
+            }
+            writeln!(f, "    {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+///按id排序，保证报告顺序稳定
+fn sorted<K: Ord + Copy + Hash, V>(temps: &HashMap<K, V>) -> Vec<(K, &V)> {
+    let mut v: Vec<(K, &V)> = temps.iter().map(|(k, v)| (*k, v)).collect();
+    v.sort_by_key(|x| x.0);
+    v
+}
+
+///校验所有配置表之间的引用、枚举值和数值范围
+pub fn check(temps_mgr: &TemplatesMgr) -> CheckReport {
+    let mut report = CheckReport::default();
+    check_character(temps_mgr, &mut report);
+    check_skill(temps_mgr, &mut report);
+    check_buff(temps_mgr, &mut report);
+    check_cell(temps_mgr, &mut report);
+    check_tile_map(temps_mgr, &mut report);
+    check_item(temps_mgr, &mut report);
+    check_merchandise(temps_mgr, &mut report);
+    check_robot(temps_mgr, &mut report);
+    check_world_boss(temps_mgr, &mut report);
+    check_season(temps_mgr, &mut report);
+    check_league(temps_mgr, &mut report);
+    report
+}
+
+fn check_character(temps_mgr: &TemplatesMgr, report: &mut CheckReport) {
+    let characters = &temps_mgr.character_temp_mgr().temps;
+    let skills = &temps_mgr.skill_temp_mgr().temps;
+    let buffs = &temps_mgr.buff_temp_mgr().temps;
+    let t = CHARACTER_TEMPLATE;
+    for (id, temp) in sorted(characters) {
+        for skill_id in temp.skills.iter().flat_map(|x| x.group.iter()) {
+            report.check_ref(t, id, "skills", *skill_id, SKILL_TEMPLATE, skills);
+        }
+        for skill_id in temp.lock_skills.iter().flat_map(|x| x.group.iter()) {
+            report.check_ref(t, id, "lock_skills", *skill_id, SKILL_TEMPLATE, skills);
+        }
+        for buff_id in temp.passive_buff.iter() {
+            report.check_ref(t, id, "passive_buff", *buff_id, BUFF, buffs);
+        }
+        report.check_ref(t, id, "pl_cter_id", temp.pl_cter_id, t, characters);
+        let element = temp.element as u32;
+        report.check_enum::<_, ElementType>(t, id, "element", element, "元素类型");
+        if temp.hp <= 0 {
+            report.push(t, id, "hp", format!("{}必须大于0", temp.hp));
+        }
+        if temp.start_energy > temp.max_energy {
+            let message = format!(
+                "start_energy:{}大于max_energy:{}",
+                temp.start_energy, temp.max_energy
+            );
+            report.push(t, id, "start_energy", message);
+        }
+        for value in temp.transform_inherit.iter() {
+            let field = "transform_inherit";
+            let value = *value as u32;
+            report.check_enum::<_, TransformInheritType>(t, id, field, value, "变身继承类型");
+        }
+    }
+}
+
+fn check_skill(temps_mgr: &TemplatesMgr, report: &mut CheckReport) {
+    let scopes = &temps_mgr.skill_scope_temp_mgr().temps;
+    let judges = &temps_mgr.skill_judge_temp_mgr().temps;
+    let buffs = &temps_mgr.buff_temp_mgr().temps;
+    let t = SKILL_TEMPLATE;
+    for (id, temp) in sorted(&temps_mgr.skill_temp_mgr().temps) {
+        report.check_ref(t, id, "scope", temp.scope, SKILL_SCOPE_TEMPLATE, scopes);
+        let judge = temp.skill_judge as u32;
+        report.check_ref(t, id, "skill_judge", judge, SKILL_JUDGE_TEMPLATE, judges);
+        report.check_ref(t, id, "buff", temp.buff, BUFF, buffs);
+    }
+}
+
+fn check_buff(temps_mgr: &TemplatesMgr, report: &mut CheckReport) {
+    let scopes = &temps_mgr.skill_scope_temp_mgr().temps;
+    for (id, temp) in sorted(&temps_mgr.buff_temp_mgr().temps) {
+        report.check_ref(BUFF, id, "scope", temp.scope, SKILL_SCOPE_TEMPLATE, scopes);
+    }
+}
+
+fn check_cell(temps_mgr: &TemplatesMgr, report: &mut CheckReport) {
+    let buffs = &temps_mgr.buff_temp_mgr().temps;
+    let t = CELL_TEMPLATE;
+    for (id, temp) in sorted(&temps_mgr.cell_temp_mgr().temps) {
+        for buff_id in temp.buff.iter() {
+            report.check_ref(t, id, "buff", *buff_id, BUFF, buffs);
+        }
+        let element = temp.element as u32;
+        report.check_enum::<_, ElementType>(t, id, "element", element, "元素类型");
+    }
+    let t = WORLD_CELL_TEMPLATE;
+    for (id, temp) in sorted(&temps_mgr.world_cell_temp_mgr().temps) {
+        for buff_id in temp.buff.iter() {
+            report.check_ref(t, id, "buff", *buff_id, BUFF, buffs);
+        }
+    }
+}
+
+fn check_tile_map(temps_mgr: &TemplatesMgr, report: &mut CheckReport) {
+    let world_cells = &temps_mgr.world_cell_temp_mgr().temps;
+    let markets = &temps_mgr.market_temp_mgr().temps;
+    let seasons = &temps_mgr.season_temp_mgr().temps;
+    let rare_map = &temps_mgr.cell_temp_mgr().rare_map;
+    let t = TILE_MAP_TEMPLATE;
+    for (id, temp) in sorted(&temps_mgr.tile_map_temp_mgr().temps) {
+        if temp.map.len() != MAP_SIZE {
+            let message = format!("格子数量{}不等于{}", temp.map.len(), MAP_SIZE);
+            report.push(t, id, "map", message);
+        }
+        for cell_type in temp.map.iter() {
+            report.check_enum::<_, MapCellType>(t, id, "map", *cell_type, "地图格子类型");
+        }
+        //每次成对放块，数量按偶数算
+        let valid_count = temp
+            .map
+            .iter()
+            .filter(|x| **x == MapCellType::Valid.into_u32())
+            .count() as u32;
+        let mut rare_count = 0;
+        for cell_rare in temp.cell_rare.iter() {
+            rare_count += cell_rare.count + cell_rare.count % 2;
+            if !rare_map.contains_key(&cell_rare.rare) {
+                let message = format!("Cell中没有稀有度为{}的块", cell_rare.rare);
+                report.push(t, id, "cell_rare", message);
+            }
+        }
+        if rare_count > valid_count {
+            let message = format!("需要放{}个块,可用格子只有{}个", rare_count, valid_count);
+            report.push(t, id, "cell_rare", message);
+        }
+        report.check_ref(
+            t,
+            id,
+            "world_cell",
+            temp.world_cell,
+            WORLD_CELL_TEMPLATE,
+            world_cells,
+        );
+        if temp.world_cell != 0 && temp.world_cell_index >= temp.map.len() {
+            let message = format!("{}超出地图范围", temp.world_cell_index);
+            report.push(t, id, "world_cell_index", message);
+        }
+        report.check_ref(t, id, "market_id", temp.market_id, MARKET, markets);
+        if temp.market_id != 0 && temp.market_index >= temp.map.len() {
+            let message = format!("{}超出地图范围", temp.market_index);
+            report.push(t, id, "market_index", message);
+        }
+        //小于等于0表示不限赛季
+        if temp.season_id > 0 {
+            let season_id = temp.season_id as u32;
+            report.check_ref(t, id, "season_id", season_id, SEASON, seasons);
+        }
+    }
+}
+
+fn check_item(temps_mgr: &TemplatesMgr, report: &mut CheckReport) {
+    let skills = &temps_mgr.skill_temp_mgr().temps;
+    for (id, temp) in sorted(&temps_mgr.item_temp_mgr().temps) {
+        let skill_id = temp.trigger_skill;
+        report.check_ref(
+            ITEM_TEMPLATE,
+            id,
+            "trigger_skill",
+            skill_id,
+            SKILL_TEMPLATE,
+            skills,
+        );
+    }
+}
+
+fn check_merchandise(temps_mgr: &TemplatesMgr, report: &mut CheckReport) {
+    let t = MERCHANDISE;
+    for (id, temp) in sorted(&temps_mgr.merchandise_temp_mgr().temps) {
+        for room_type in temp.room_type.iter() {
+            //RoomType::None不能配
+            if RoomType::try_from(*room_type).map_or(true, |x| x == RoomType::None) {
+                let message = format!("{}不是有效的房间类型", room_type);
+                report.push(t, id, "room_type", message);
+            }
+        }
+        if temp.price < 0 {
+            report.push(t, id, "price", format!("{}不能小于0", temp.price));
+        }
+    }
+}
+
+fn check_robot(temps_mgr: &TemplatesMgr, report: &mut CheckReport) {
+    let characters = &temps_mgr.character_temp_mgr().temps;
+    let skills = &temps_mgr.skill_temp_mgr().temps;
+    let merchandises = &temps_mgr.merchandise_temp_mgr().temps;
+    let t = ROBOT;
+    for (id, temp) in sorted(&temps_mgr.robot_temp_mgr().temps) {
+        report.check_ref(
+            t,
+            id,
+            "cter_id",
+            temp.cter_id,
+            CHARACTER_TEMPLATE,
+            characters,
+        );
+        for skill_id in temp.skills.iter() {
+            report.check_ref(t, id, "skills", *skill_id, SKILL_TEMPLATE, skills);
+        }
+        for merchandise_id in temp.merchandises.iter() {
+            report.check_ref(
+                t,
+                id,
+                "merchandises",
+                *merchandise_id,
+                MERCHANDISE,
+                merchandises,
+            );
+        }
+    }
+}
+
+fn check_world_boss(temps_mgr: &TemplatesMgr, report: &mut CheckReport) {
+    let characters = &temps_mgr.character_temp_mgr().temps;
+    let robots = &temps_mgr.robot_temp_mgr().temps;
+    let tile_maps = &temps_mgr.tile_map_temp_mgr().temps;
+    let t = WORLD_BOSS;
+    for (id, temp) in sorted(&temps_mgr.worldboss_temp_mgr().temps) {
+        report.check_ref(
+            t,
+            id,
+            "cter_id",
+            temp.cter_id,
+            CHARACTER_TEMPLATE,
+            characters,
+        );
+        report.check_ref(t, id, "robot_id", temp.robot_id, ROBOT, robots);
+        for map_id in temp.map_ids.iter() {
+            report.check_ref(t, id, "map_ids", *map_id, TILE_MAP_TEMPLATE, tile_maps);
+        }
+    }
+}
+
+fn check_season(temps_mgr: &TemplatesMgr, report: &mut CheckReport) {
+    for (id, temp) in sorted(&temps_mgr.season_temp_mgr().temps) {
+        let element = temp.element as u32;
+        report.check_enum::<_, ElementType>(SEASON, id, "element", element, "元素类型");
+    }
+}
+
+///段位积分要随段位递增
+fn check_league(temps_mgr: &TemplatesMgr, report: &mut CheckReport) {
+    let mut last_score = None;
+    for (id, temp) in sorted(&temps_mgr.league_temp_mgr().temps) {
+        if let Some(last_score) = last_score {
+            if temp.score < last_score {
+                let message = format!("{}小于上一个段位的{}", temp.score, last_score);
+                report.push(LEAGUE, id, "score", message);
+            }
+        }
+        last_score = Some(temp.score);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_dangling_refs_and_ranges() {
+        let dir = std::env::temp_dir().join(format!("template_check_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let item = r#"[{"id":1,"trigger_skill":5},{"id":2,"trigger_skill":0}]"#;
+        std::fs::write(dir.join("Item.json"), item).unwrap();
+        let league = r#"[{"id":1,"score":100},{"id":2,"score":50}]"#;
+        std::fs::write(dir.join("League.json"), league).unwrap();
+        let merchandise = r#"[{"id":1,"price":10,"effect_type":1,"effect_value":1,
+            "room_type":[0,2,5],"character_type":[],"other_condition":0,"turn_limit_buy_times":1}]"#;
+        std::fs::write(dir.join("Merchandise.json"), merchandise).unwrap();
+        let season = r#"[{"id":1,"element":4},{"id":2,"element":5}]"#;
+        std::fs::write(dir.join("Season.json"), season).unwrap();
+        let temps_mgr = TemplatesMgr::load(&dir).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let report = check(&temps_mgr);
+        assert_eq!(report.errors.len(), 5);
+        let str = report.to_string();
+        assert!(str.contains("Item[id:1].trigger_skill"));
+        assert!(str.contains("League[id:2].score"));
+        assert!(str.contains("Merchandise[id:1].room_type: 0不是有效的房间类型"));
+        assert!(str.contains("Merchandise[id:1].room_type: 5不是有效的房间类型"));
+        assert!(str.contains("Season[id:2].element: 5不是有效的元素类型"));
+    }
+}
@@ -0,0 +1,700 @@
+use crate::excel::{Sheet, SheetRow};
+use crate::templates::battle_limit_time_temp::BattleLimitTimeTemp;
+use crate::templates::buff_temp::BuffTemp;
+use crate::templates::cell_temp::CellTemp;
+use crate::templates::character_temp::CharacterTemp;
+use crate::templates::constant_temp::ConstantTemp;
+use crate::templates::emoji_temp::EmojiTemp;
+use crate::templates::grade_frame_temp::GradeFrameTemp;
+use crate::templates::item_temp::ItemTemp;
+use crate::templates::league_temp::LeagueTemp;
+use crate::templates::market_temp::MarketTemp;
+use crate::templates::merchandise_temp::MerchandiseTemp;
+use crate::templates::mission_temp::MissionTemp;
+use crate::templates::punish_temp::PunishTemp;
+use crate::templates::robot_temp::RobotTemp;
+use crate::templates::season_temp::SeasonTemp;
+use crate::templates::skill_judge_temp::SkillJudgeTemp;
+use crate::templates::skill_scope_temp::SkillScopeTemp;
+use crate::templates::skill_temp::SkillTemp;
+use crate::templates::soul_temp::SoulTemp;
+use crate::templates::summary_award_temp::SummaryAwardTemp;
+use crate::templates::template::TemplatesMgr;
+use crate::templates::template_check::CheckReport;
+use crate::templates::template_name_constants::{
+    BATTLE_LIMIT_TIME, BUFF, CELL_TEMPLATE, CHARACTER_TEMPLATE, CONSTANT_TEMPLATE, EMOJI_TEMPLATE,
+    GRADE_FRAME, ITEM_TEMPLATE, LEAGUE, MARKET, MERCHANDISE, MISSION, PUNISH, ROBOT, SEASON,
+    SKILL_JUDGE_TEMPLATE, SKILL_SCOPE_TEMPLATE, SKILL_TEMPLATE, SOUL, SUMMARY_AWARD,
+    TILE_MAP_TEMPLATE, WORLD_BOSS, WORLD_CELL_TEMPLATE,
+};
+use crate::templates::tile_map_temp::TileMapTemp;
+use crate::templates::world_boss_temp::WorldBossTemp;
+use crate::templates::world_cell_temp::WorldCellTemp;
+use calamine::DataType;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value as JsonValue};
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+
+///一格或者一行导出失败
+#[derive(Debug, Clone)]
+pub struct ExportError {
+    pub row: usize,      //excel行号,0表示表头
+    pub column: String,  //列名
+    pub message: String, //错误描述
+}
+
+impl Display for ExportError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.row == 0 {
+            return write!(f, "表头.{}: {}", self.column, self.message);
+        }
+        write!(f, "第{}行.{}: {}", self.row, self.column, self.message)
+    }
+}
+
+///和当前json的差异，按id比较
+#[derive(Debug, Clone, Default)]
+pub struct TableDiff {
+    pub added: Vec<String>,                  //新增的id
+    pub removed: Vec<String>,                //删除的id
+    pub changed: Vec<(String, Vec<String>)>, //修改的id和字段
+}
+
+impl TableDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl Display for TableDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            return write!(f, "没有变化");
+        }
+        let changed: Vec<String> = self
+            .changed
+            .iter()
+            .map(|(id, fields)| format!("{}({})", id, fields.join(",")))
+            .collect();
+        write!(
+            f,
+            "新增:{:?} 删除:{:?} 修改:{:?}",
+            self.added, self.removed, changed
+        )
+    }
+}
+
+///一张表的导出结果
+#[derive(Debug, Clone)]
+pub struct TableExport {
+    pub table: &'static str,         //配置表名字
+    pub rows: Vec<JsonValue>,        //导出的数据
+    pub errors: Vec<ExportError>,    //导出失败的格子和行
+    pub unused_columns: Vec<String>, //服务器结构体里没有的列，原样导出
+    pub diff: TableDiff,             //和当前json的差异
+}
+
+impl TableExport {
+    fn new(table: &'static str) -> Self {
+        TableExport {
+            table,
+            rows: Vec::new(),
+            errors: Vec::new(),
+            unused_columns: Vec::new(),
+            diff: TableDiff::default(),
+        }
+    }
+
+    fn push_error(&mut self, row: usize, column: &str, message: String) {
+        self.errors.push(ExportError {
+            row,
+            column: column.to_owned(),
+            message,
+        });
+    }
+}
+
+///整个目录的导出结果
+#[derive(Debug, Default)]
+pub struct ExportReport {
+    pub tables: Vec<TableExport>,   //导出的表
+    pub skipped: Vec<String>,       //不认识的excel
+    pub written: Vec<String>,       //写出的json文件
+    pub check: Option<CheckReport>, //写出后整个目录的引用校验
+}
+
+impl ExportReport {
+    pub fn is_ok(&self) -> bool {
+        let check_ok = match self.check.as_ref() {
+            Some(check) => check.is_ok(),
+            None => true,
+        };
+        check_ok && self.tables.iter().all(|x| x.errors.is_empty())
+    }
+}
+
+impl Display for ExportReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for table in self.tables.iter() {
+            writeln!(f, "[{}] {}行,{}", table.table, table.rows.len(), table.diff)?;
+            if !table.unused_columns.is_empty() {
+                writeln!(f, "    服务器不使用的列:{:?}", table.unused_columns)?;
+            }
+            for error in table.errors.iter() {
+                writeln!(f, "    {}", error)?;
+            }
+        }
+        if !self.skipped.is_empty() {
+            writeln!(f, "跳过不认识的excel:{:?}", self.skipped)?;
+        }
+        if !self.written.is_empty() {
+            writeln!(f, "写出json:{:?}", self.written)?;
+        }
+        if let Some(check) = self.check.as_ref() {
+            writeln!(f, "{}", check)?;
+        }
+        Ok(())
+    }
+}
+
+///把xlsx目录导出成配置表json，导出前和json_dir里当前的文件比较
+///导出的表和json_dir里其他的表合在一起做引用校验，有任何错误就一个文件都不写
+///dry_run只比较和校验，不写
+pub fn export_dir<P: AsRef<Path>>(
+    xlsx_dir: P,
+    json_dir: P,
+    dry_run: bool,
+) -> anyhow::Result<ExportReport> {
+    let json_dir = json_dir.as_ref();
+    let mut report = ExportReport::default();
+    let mut paths = Vec::new();
+    for f in std::fs::read_dir(xlsx_dir)? {
+        let path = f?.path();
+        let file_name = path.file_name().and_then(|x| x.to_str()).unwrap_or("");
+        //~$开头的是excel打开时的临时文件
+        if file_name.starts_with("~$") || !file_name.ends_with(".xlsx") {
+            continue;
+        }
+        paths.push(path);
+    }
+    paths.sort();
+    for path in paths {
+        let name = path.file_stem().and_then(|x| x.to_str()).unwrap_or("");
+        let path_str = path.to_str().unwrap_or("");
+        let sheet = crate::excel::read_sheet(path_str)?;
+        let export = export_sheet(name, &sheet);
+        if export.is_none() {
+            report.skipped.push(name.to_owned());
+            continue;
+        }
+        let mut export = export.unwrap();
+        let old = read_json(json_dir.join(format!("{}.json", export.table)))?;
+        export.diff = diff(&old, &export.rows);
+        report.tables.push(export);
+    }
+    if !report.is_ok() {
+        return Ok(report);
+    }
+    report.check = Some(check_staging(json_dir, &report.tables)?);
+    if dry_run || !report.is_ok() {
+        return Ok(report);
+    }
+    for export in report.tables.iter() {
+        if export.diff.is_empty() {
+            continue;
+        }
+        let file_name = format!("{}.json", export.table);
+        //先写临时文件再改名，热更不会读到写了一半的文件
+        //临时文件以.开头，read_templates_from_dir不认识，会跳过
+        let tmp = json_dir.join(format!(".{}.tmp", file_name));
+        std::fs::write(&tmp, serde_json::to_string(&export.rows)?)?;
+        std::fs::rename(&tmp, json_dir.join(file_name.as_str()))?;
+        report.written.push(file_name);
+    }
+    Ok(report)
+}
+
+///在临时目录里合成导出后的完整配置表目录，加载并做引用校验
+fn check_staging(json_dir: &Path, tables: &[TableExport]) -> anyhow::Result<CheckReport> {
+    let staging = std::env::temp_dir().join(format!("export_templates_{}", std::process::id()));
+    if staging.exists() {
+        std::fs::remove_dir_all(&staging)?;
+    }
+    std::fs::create_dir_all(&staging)?;
+    for f in std::fs::read_dir(json_dir)? {
+        let path = f?.path();
+        if !path.is_file() {
+            continue;
+        }
+        if let Some(file_name) = path.file_name() {
+            std::fs::copy(&path, staging.join(file_name))?;
+        }
+    }
+    for export in tables.iter() {
+        let path = staging.join(format!("{}.json", export.table));
+        std::fs::write(path, serde_json::to_string(&export.rows)?)?;
+    }
+    let res = TemplatesMgr::load(&staging);
+    std::fs::remove_dir_all(&staging)?;
+    Ok(res?.check())
+}
+
+///读取当前的json，没有就当空表
+fn read_json<P: AsRef<Path>>(path: P) -> anyhow::Result<Vec<JsonValue>> {
+    let path = path.as_ref();
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let context = std::fs::read_to_string(path)?;
+    match serde_json::from_str(context.as_str()) {
+        Ok(v) => Ok(v),
+        Err(e) => anyhow::bail!("当前json解析失败!path:{:?},{:?}", path, e),
+    }
+}
+
+///按表名导出一个sheet，不认识的表返回None
+pub fn export_sheet(name: &str, sheet: &Sheet) -> Option<TableExport> {
+    let export = if name.eq_ignore_ascii_case(TILE_MAP_TEMPLATE) {
+        export::<TileMapTemp>(TILE_MAP_TEMPLATE, sheet)
+    } else if name.eq_ignore_ascii_case(CHARACTER_TEMPLATE) {
+        export::<CharacterTemp>(CHARACTER_TEMPLATE, sheet)
+    } else if name.eq_ignore_ascii_case(EMOJI_TEMPLATE) {
+        export::<EmojiTemp>(EMOJI_TEMPLATE, sheet)
+    } else if name.eq_ignore_ascii_case(CONSTANT_TEMPLATE) {
+        //常量表是key-value格式，第1列是key，第3列是value
+        export::<ConstantTemp>(CONSTANT_TEMPLATE, &key_value_sheet(sheet))
+    } else if name.eq_ignore_ascii_case(WORLD_CELL_TEMPLATE) {
+        export::<WorldCellTemp>(WORLD_CELL_TEMPLATE, sheet)
+    } else if name.eq_ignore_ascii_case(CELL_TEMPLATE) {
+        export::<CellTemp>(CELL_TEMPLATE, sheet)
+    } else if name.eq_ignore_ascii_case(SKILL_TEMPLATE) {
+        export::<SkillTemp>(SKILL_TEMPLATE, sheet)
+    } else if name.eq_ignore_ascii_case(SKILL_SCOPE_TEMPLATE) {
+        export::<SkillScopeTemp>(SKILL_SCOPE_TEMPLATE, sheet)
+    } else if name.eq_ignore_ascii_case(ITEM_TEMPLATE) {
+        export::<ItemTemp>(ITEM_TEMPLATE, sheet)
+    } else if name.eq_ignore_ascii_case(SKILL_JUDGE_TEMPLATE) {
+        export::<SkillJudgeTemp>(SKILL_JUDGE_TEMPLATE, sheet)
+    } else if name.eq_ignore_ascii_case(BUFF) {
+        export::<BuffTemp>(BUFF, sheet)
+    } else if name.eq_ignore_ascii_case(SEASON) {
+        export::<SeasonTemp>(SEASON, sheet)
+    } else if name.eq_ignore_ascii_case(ROBOT) {
+        export::<RobotTemp>(ROBOT, sheet)
+    } else if name.eq_ignore_ascii_case(LEAGUE) {
+        export::<LeagueTemp>(LEAGUE, sheet)
+    } else if name.eq_ignore_ascii_case(SUMMARY_AWARD) {
+        export::<SummaryAwardTemp>(SUMMARY_AWARD, sheet)
+    } else if name.eq_ignore_ascii_case(BATTLE_LIMIT_TIME) {
+        export::<BattleLimitTimeTemp>(BATTLE_LIMIT_TIME, sheet)
+    } else if name.eq_ignore_ascii_case(PUNISH) {
+        export::<PunishTemp>(PUNISH, sheet)
+    } else if name.eq_ignore_ascii_case(GRADE_FRAME) {
+        export::<GradeFrameTemp>(GRADE_FRAME, sheet)
+    } else if name.eq_ignore_ascii_case(SOUL) {
+        export::<SoulTemp>(SOUL, sheet)
+    } else if name.eq_ignore_ascii_case(MARKET) {
+        export::<MarketTemp>(MARKET, sheet)
+    } else if name.eq_ignore_ascii_case(MERCHANDISE) {
+        export::<MerchandiseTemp>(MERCHANDISE, sheet)
+    } else if name.eq_ignore_ascii_case(MISSION) {
+        export::<MissionTemp>(MISSION, sheet)
+    } else if name.eq_ignore_ascii_case(WORLD_BOSS) {
+        export::<WorldBossTemp>(WORLD_BOSS, sheet)
+    } else {
+        return None;
+    };
+    Some(export)
+}
+
+///key-value格式的sheet转成id、value两列，同excel::read_disc
+fn key_value_sheet(sheet: &Sheet) -> Sheet {
+    let rows = sheet
+        .rows
+        .iter()
+        .map(|x| {
+            let key = x.cells.first().cloned().unwrap_or_default();
+            let value = x.cells.get(2).cloned().unwrap_or_default();
+            SheetRow {
+                row: x.row,
+                cells: vec![key, value],
+            }
+        })
+        .collect();
+    Sheet {
+        fields: vec!["id".to_owned(), "value".to_owned()],
+        rows,
+    }
+}
+
+///按结构体导出一个sheet
+///列的类型以结构体默认值序列化出来的json类型为准，每一行都要能反序列化成结构体
+fn export<T: Default + Serialize + DeserializeOwned>(
+    table: &'static str,
+    sheet: &Sheet,
+) -> TableExport {
+    let mut export = TableExport::new(table);
+    let base = match serde_json::to_value(T::default()) {
+        Ok(JsonValue::Object(base)) => base,
+        _ => Map::new(),
+    };
+    //结构体的字段必须有对应的列，有serde(default)的除外
+    for key in base.keys() {
+        if sheet.fields.contains(key) {
+            continue;
+        }
+        let mut value = base.clone();
+        value.remove(key);
+        if serde_json::from_value::<T>(JsonValue::Object(value)).is_err() {
+            export.push_error(0, key, "缺少这一列".to_owned());
+        }
+    }
+    for field in sheet.fields.iter() {
+        if !base.contains_key(field) {
+            export.unused_columns.push(field.clone());
+        }
+    }
+    if !export.errors.is_empty() {
+        return export;
+    }
+    let mut ids = HashSet::new();
+    for row in sheet.rows.iter() {
+        if row.cells.iter().all(|x| x.is_empty()) {
+            continue;
+        }
+        let object = row_to_json(&mut export, &base, &sheet.fields, row);
+        if object.is_none() {
+            continue;
+        }
+        let object = object.unwrap();
+        if serde_json::from_value::<T>(JsonValue::Object(object.clone())).is_err() {
+            locate_error::<T>(&mut export, &base, &object, row.row);
+            continue;
+        }
+        let id = row_id(&object, export.rows.len());
+        if !ids.insert(id.clone()) {
+            export.push_error(row.row, "id", format!("id:{}重复", id));
+            continue;
+        }
+        export.rows.push(JsonValue::Object(object));
+    }
+    export
+}
+
+///一行转成json对象，有格子转换失败返回None
+fn row_to_json(
+    export: &mut TableExport,
+    base: &Map<String, JsonValue>,
+    fields: &[String],
+    row: &SheetRow,
+) -> Option<Map<String, JsonValue>> {
+    let mut object = Map::new();
+    let mut ok = true;
+    for (index, field) in fields.iter().enumerate() {
+        let cell = row.cells.get(index).cloned().unwrap_or_default();
+        let expect = base.get(field);
+        //空格子用结构体默认值，结构体里没有的列就不导出
+        if cell.is_empty() {
+            if let Some(default) = expect {
+                object.insert(field.clone(), default.clone());
+            }
+            continue;
+        }
+        match cell_to_json(&cell, expect) {
+            Ok(value) => {
+                object.insert(field.clone(), value);
+            }
+            Err(e) => {
+                export.push_error(row.row, field, e);
+                ok = false;
+            }
+        }
+    }
+    if !ok {
+        return None;
+    }
+    Some(object)
+}
+
+///整行反序列化失败时逐列替换到默认值上，找出是哪一列不对
+fn locate_error<T: DeserializeOwned>(
+    export: &mut TableExport,
+    base: &Map<String, JsonValue>,
+    object: &Map<String, JsonValue>,
+    row: usize,
+) {
+    let mut found = false;
+    for (key, value) in object.iter() {
+        if !base.contains_key(key) {
+            continue;
+        }
+        let mut test = base.clone();
+        test.insert(key.clone(), value.clone());
+        if let Err(e) = serde_json::from_value::<T>(JsonValue::Object(test)) {
+            export.push_error(row, key, e.to_string());
+            found = true;
+        }
+    }
+    if !found {
+        let res = serde_json::from_value::<T>(JsonValue::Object(object.clone()));
+        if let Err(e) = res {
+            export.push_error(row, "", e.to_string());
+        }
+    }
+}
+
+///格子转json，expect是结构体里这个字段的默认值，用来确定类型，None表示结构体里没有这个字段
+///数组写法:1,2,3 二维数组:1,2;3,4 对象写法:key:value,key:value 也可以直接写json
+fn cell_to_json(cell: &DataType, expect: Option<&JsonValue>) -> Result<JsonValue, String> {
+    let text = cell_text(cell);
+    match expect {
+        Some(JsonValue::Number(_)) => match cell {
+            DataType::Int(_) | DataType::Float(_) => Ok(number(cell)),
+            DataType::Bool(b) => Ok(JsonValue::from(*b as i64)),
+            _ => match parse_scalar(text.as_str()) {
+                JsonValue::Number(n) => Ok(JsonValue::Number(n)),
+                _ => Err(format!("{:?}不是数字", text)),
+            },
+        },
+        Some(JsonValue::String(_)) => Ok(JsonValue::String(text)),
+        Some(JsonValue::Bool(_)) => match text.as_str() {
+            "1" | "true" | "TRUE" => Ok(JsonValue::Bool(true)),
+            "0" | "false" | "FALSE" => Ok(JsonValue::Bool(false)),
+            _ => Err(format!("{:?}不是bool", text)),
+        },
+        Some(JsonValue::Array(_)) => {
+            if text.starts_with('[') {
+                return parse_json(text.as_str());
+            }
+            Ok(parse_list(text.as_str()))
+        }
+        Some(JsonValue::Object(_)) => {
+            if text.starts_with('{') {
+                return parse_json(text.as_str());
+            }
+            parse_map(text.as_str())
+        }
+        _ => match cell {
+            DataType::Int(_) | DataType::Float(_) => Ok(number(cell)),
+            DataType::Bool(b) => Ok(JsonValue::Bool(*b)),
+            _ if text.starts_with('[') || text.starts_with('{') => parse_json(text.as_str()),
+            _ => Ok(JsonValue::String(text)),
+        },
+    }
+}
+
+///格子的文本，整数的浮点数去掉小数点
+fn cell_text(cell: &DataType) -> String {
+    match cell {
+        DataType::Float(f) if f.fract() == 0.0 => (*f as i64).to_string(),
+        DataType::String(s) => s.trim().to_owned(),
+        _ => cell.to_string(),
+    }
+}
+
+fn number(cell: &DataType) -> JsonValue {
+    match cell {
+        DataType::Int(i) => JsonValue::from(*i),
+        DataType::Float(f) if f.fract() == 0.0 => JsonValue::from(*f as i64),
+        DataType::Float(f) => JsonValue::from(*f),
+        _ => JsonValue::Null,
+    }
+}
+
+fn parse_json(text: &str) -> Result<JsonValue, String> {
+    match serde_json::from_str(text) {
+        Ok(value) => Ok(value),
+        Err(e) => Err(format!("{:?}不是合法的json!{}", text, e)),
+    }
+}
+
+///数字和bool按json解析，其他的当字符串
+fn parse_scalar(text: &str) -> JsonValue {
+    let text = text.trim();
+    match serde_json::from_str::<JsonValue>(text) {
+        Ok(value) if value.is_number() || value.is_boolean() => value,
+        _ => JsonValue::String(text.to_owned()),
+    }
+}
+
+fn parse_list(text: &str) -> JsonValue {
+    if text.contains(';') {
+        let v: Vec<JsonValue> = text
+            .split(';')
+            .filter(|x| !x.trim().is_empty())
+            .map(parse_list)
+            .collect();
+        return JsonValue::Array(v);
+    }
+    let v: Vec<JsonValue> = text
+        .split(',')
+        .filter(|x| !x.trim().is_empty())
+        .map(parse_scalar)
+        .collect();
+    JsonValue::Array(v)
+}
+
+fn parse_map(text: &str) -> Result<JsonValue, String> {
+    let mut map = Map::new();
+    for pair in text.split(',').filter(|x| !x.trim().is_empty()) {
+        let v: Vec<&str> = pair.splitn(2, ':').collect();
+        if v.len() != 2 {
+            return Err(format!("{:?}不是key:value格式", pair));
+        }
+        map.insert(v[0].trim().to_owned(), parse_scalar(v[1]));
+    }
+    Ok(JsonValue::Object(map))
+}
+
+///行的唯一标识，有id用id，没有用行的序号
+fn row_id(object: &Map<String, JsonValue>, index: usize) -> String {
+    match object.get("id") {
+        Some(JsonValue::String(id)) => id.clone(),
+        Some(id) => id.to_string(),
+        None => format!("#{}", index + 1),
+    }
+}
+
+///和当前json按id比较
+pub fn diff(old: &[JsonValue], new: &[JsonValue]) -> TableDiff {
+    let mut diff = TableDiff::default();
+    let to_map = |rows: &[JsonValue]| {
+        let mut v = Vec::new();
+        for (index, row) in rows.iter().enumerate() {
+            if let JsonValue::Object(object) = row {
+                v.push((row_id(object, index), object.clone()));
+            }
+        }
+        v
+    };
+    let old = to_map(old);
+    let new = to_map(new);
+    for (id, object) in new.iter() {
+        let old_object = old.iter().find(|x| &x.0 == id);
+        if old_object.is_none() {
+            diff.added.push(id.clone());
+            continue;
+        }
+        let old_object = &old_object.unwrap().1;
+        let mut fields: Vec<String> = object
+            .iter()
+            .filter(|(key, value)| old_object.get(key.as_str()) != Some(value))
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in old_object.keys() {
+            if !object.contains_key(key) {
+                fields.push(key.clone());
+            }
+        }
+        if !fields.is_empty() {
+            diff.changed.push((id.clone(), fields));
+        }
+    }
+    for (id, _) in old.iter() {
+        if !new.iter().any(|x| &x.0 == id) {
+            diff.removed.push(id.clone());
+        }
+    }
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sheet(fields: &[&str], rows: Vec<Vec<DataType>>) -> Sheet {
+        Sheet {
+            fields: fields.iter().map(|x| x.to_string()).collect(),
+            rows: rows
+                .into_iter()
+                .enumerate()
+                .map(|(index, cells)| SheetRow {
+                    row: index + 5,
+                    cells,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn export_and_diff() {
+        let s = |x: &str| DataType::String(x.to_owned());
+        let rows = vec![
+            vec![
+                DataType::Float(1.0),
+                s("1,2"),
+                s("0"),
+                s("1,2;3"),
+                s("client"),
+            ],
+            vec![
+                DataType::Int(2),
+                s("3"),
+                s("x"),
+                s("[{\"group\":[4]}]"),
+                s(""),
+            ],
+        ];
+        let fields = ["id", "passive_buff", "hp", "skills", "client_show"];
+        let export = export_sheet("character", &sheet(&fields, rows));
+        let export = export.unwrap();
+        //缺少必须的列
+        assert_eq!(export.table, CHARACTER_TEMPLATE);
+        assert!(export.errors.iter().all(|x| x.row == 0));
+        assert!(export.errors.iter().any(|x| x.column == "attack"));
+
+        let rows = vec![
+            vec![DataType::Float(1.0), s("5")],
+            vec![DataType::Int(2), s("x")],
+            vec![DataType::Empty, DataType::Empty],
+            vec![DataType::Int(3), s("7"), s("note")],
+            vec![DataType::Int(4), DataType::Int(-1)],
+        ];
+        let export = export_sheet("Item", &sheet(&["id", "trigger_skill", "desc"], rows));
+        let export = export.unwrap();
+        assert_eq!(export.unused_columns, vec!["desc".to_owned()]);
+        assert_eq!(export.errors.len(), 2);
+        assert_eq!(export.errors[0].row, 6);
+        assert_eq!(export.errors[0].column, "trigger_skill");
+        //数字转换成功但是反序列化失败的，也要定位到列
+        assert_eq!(export.errors[1].row, 9);
+        assert_eq!(export.errors[1].column, "trigger_skill");
+        assert_eq!(export.rows.len(), 2);
+        assert_eq!(export.rows[1]["desc"], "note");
+
+        let old: Vec<JsonValue> =
+            serde_json::from_str(r#"[{"id":1,"trigger_skill":4},{"id":9,"trigger_skill":1}]"#)
+                .unwrap();
+        let diff = diff(&old, &export.rows);
+        assert_eq!(diff.added, vec!["3".to_owned()]);
+        assert_eq!(diff.removed, vec!["9".to_owned()]);
+        assert_eq!(
+            diff.changed,
+            vec![("1".to_owned(), vec!["trigger_skill".to_owned()])]
+        );
+    }
+
+    #[test]
+    fn cell_syntax() {
+        let s = |x: &str| DataType::String(x.to_owned());
+        let list = JsonValue::Array(Vec::new());
+        let map = JsonValue::Object(Map::new());
+        assert_eq!(
+            cell_to_json(&s("1,2;3"), Some(&list)).unwrap(),
+            serde_json::json!([[1, 2], [3]])
+        );
+        assert_eq!(
+            cell_to_json(&s("a:1,b:x"), Some(&map)).unwrap(),
+            serde_json::json!({"a":1,"b":"x"})
+        );
+        let str = JsonValue::String(String::new());
+        assert_eq!(
+            cell_to_json(&DataType::Float(120000.0), Some(&str)).unwrap(),
+            "120000"
+        );
+        assert!(cell_to_json(&s("[1,"), Some(&list)).is_err());
+    }
+}
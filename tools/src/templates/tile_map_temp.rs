@@ -1,7 +1,33 @@
 use crate::templates::template::{Template, TemplateMgrTrait};
 use anyhow::Result;
+use num_enum::IntoPrimitive;
+use num_enum::TryFromPrimitive;
 use std::collections::HashMap;
 
+///地图格子类型
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, TryFromPrimitive, IntoPrimitive)]
+#[repr(u8)]
+pub enum MapCellType {
+    #[default]
+    InValid = 0,
+    UnUse = 1,
+    Valid = 2,
+    WorldCell = 3,
+    MarketCell = 4,
+}
+
+impl MapCellType {
+    pub fn into_u8(self) -> u8 {
+        let res: u8 = self.into();
+        res
+    }
+
+    pub fn into_u32(self) -> u32 {
+        let res = self.into_u8();
+        res as u32
+    }
+}
+
 #[derive(serde::Serialize, serde::Deserialize, Debug, Default, Clone)]
 pub struct TileMapTemp {
     pub id: u32,
@@ -74,17 +74,17 @@ pub mod bytebuf {
         }
 
         pub fn push_u32(&mut self, i: u32) {
-            let value: [u8; 4] = i.to_ne_bytes();
+            let value: [u8; 4] = i.to_le_bytes();
             self.bytes.extend_from_slice(value.as_ref());
         }
 
         pub fn push_u16(&mut self, i: u16) {
-            let value: [u8; 2] = i.to_ne_bytes();
+            let value: [u8; 2] = i.to_le_bytes();
             self.bytes.extend_from_slice(value.as_ref());
         }
 
         pub fn push_u64(&mut self, i: u64) {
-            let value: [u8; 8] = i.to_ne_bytes();
+            let value: [u8; 8] = i.to_le_bytes();
             self.bytes.extend_from_slice(value.as_ref());
         }
 
@@ -119,7 +119,7 @@ pub mod bytebuf {
             self.index += 4;
             let mut bytes = [0; 4];
             bytes.copy_from_slice(buf_bytes);
-            Ok(u32::from_ne_bytes(bytes))
+            Ok(u32::from_le_bytes(bytes))
         }
 
         ///读取两个字节并拼成一个u16
@@ -131,7 +131,7 @@ pub mod bytebuf {
             self.index += 2;
             let mut bytes = [0; 2];
             bytes.copy_from_slice(buf_bytes);
-            Ok(u16::from_ne_bytes(bytes))
+            Ok(u16::from_le_bytes(bytes))
         }
 
         ///读取8个字节并拼成一个u64
@@ -143,7 +143,7 @@ pub mod bytebuf {
             self.index += 8;
             let mut bytes = [0; 8];
             bytes.copy_from_slice(buf_bytes);
-            Ok(u64::from_ne_bytes(bytes))
+            Ok(u64::from_le_bytes(bytes))
         }
 
         ///读取1个字节并拼成一个u8
@@ -171,10 +171,46 @@ pub mod bytebuf {
 }
 
 ///数据包封装，用户封装传输的数据包
+///所有整数统一按小端序读写
+///客户端包头有两个版本:
+///v1(16字节):cmd(u32)|len(u32)|0(u32)|0(u32)
+///v2(20字节):magic(u16)|version(u8)|flags(u8)|cmd(u32)|len(u32)|seq(u32)|crc32(u32)
+///len都是包含包头的总长度，v2的crc32只校验body，flags没有FLAG_CRC时为0
+///服务端内部通信包头(23字节):cmd(u32)|len(u32)|user_id(u32)|is_client(u8)|is_broad(u8)|flags(u8)|server_token(u32)|rpc_id(u32)
+///rpc_id为0表示不是rpc请求，rpc回复带上请求的rpc_id和FLAG_RPC_REPLY
+///body超过压缩阈值时压缩，flags带上FLAG_COMPRESS，crc32校验的是压缩后的body
 pub mod packet {
+    use std::borrow::Cow;
     use std::collections::VecDeque;
 
+    use crate::compress::{self, CompressType};
     use crate::util::bytebuf::ByteBuf;
+
+    ///v1客户端包头长度
+    pub const CLIENT_HEAD_LEN_V1: u32 = 16;
+    ///v2客户端包头长度
+    pub const CLIENT_HEAD_LEN_V2: u32 = 20;
+    ///服务端内部通信包头长度
+    pub const SERVER_HEAD_LEN: u32 = 23;
+    ///v2包头魔数，小端序写出来是"GP"，cmd都小于65536，v1包头第3、4个字节一定为0，不会混淆
+    pub const PACKET_MAGIC: u16 = 0x5047;
+    ///旧版本协议
+    pub const PROTOCOL_VERSION_V1: u8 = 1;
+    ///带序列号和校验的新版本协议
+    pub const PROTOCOL_VERSION_V2: u8 = 2;
+    ///flags:body带crc32校验
+    pub const FLAG_CRC: u8 = 0x01;
+    ///flags:body已压缩，登录包带上表示客户端支持压缩
+    pub const FLAG_COMPRESS: u8 = 0x02;
+    ///flags:body已加密
+    pub const FLAG_ENCRYPT: u8 = 0x04;
+    ///flags:和FLAG_COMPRESS一起使用，表示压缩算法是lz4，没有则是zstd
+    pub const FLAG_LZ4: u8 = 0x08;
+    ///flags:服务端之间的rpc回复
+    pub const FLAG_RPC_REPLY: u8 = 0x10;
+    ///flags:和FLAG_RPC_REPLY一起使用，表示处理失败，body是错误信息
+    pub const FLAG_RPC_ERROR: u8 = 0x20;
+
     #[derive(Debug, Default, Clone)]
     pub struct PacketDes {
         cmd: u32,
@@ -183,6 +219,10 @@ pub mod packet {
         is_broad: bool,    //是否需要广播
         is_client: bool,   //是否客户端
         server_token: u32, //服务器对应的token
+        version: u8,       //客户端包头版本
+        flags: u8,         //包头标志位
+        seq: u32,          //客户端包序列号
+        rpc_id: u32,       //rpc请求id,0表示不是rpc
     }
 
     #[derive(Debug, Default, Clone)]
@@ -200,10 +240,72 @@ pub mod packet {
                 is_broad: false,
                 is_client: true,
                 server_token: 0,
+                version: PROTOCOL_VERSION_V1,
+                flags: 0,
+                seq: 0,
+                rpc_id: 0,
             }
         }
     }
 
+    ///客户端连接协商好的包头格式，登录时按客户端发来的包头版本确定，之后收发都用这个版本
+    ///登录包flags带FLAG_COMPRESS的客户端，发给它的大包才会压缩
+    #[derive(Debug, Default, Clone, Copy)]
+    pub struct ClientFrame {
+        pub version: u8,   //协议版本
+        pub flags: u8,     //发给客户端时使用的标志位
+        pub recv_seq: u32, //最后收到的序列号
+        pub send_seq: u32, //最后发出的序列号
+    }
+
+    impl ClientFrame {
+        ///根据登录包创建
+        pub fn new(login_packet: &Packet) -> Self {
+            ClientFrame {
+                version: login_packet.get_version(),
+                flags: login_packet.get_flags() & (FLAG_CRC | FLAG_COMPRESS),
+                recv_seq: 0,
+                send_seq: 0,
+            }
+        }
+
+        ///校验收到的包，版本必须和登录时一致，v2的序列号必须递增(防重放)
+        pub fn check_recv(&mut self, packet: &Packet) -> anyhow::Result<()> {
+            if packet.get_version() != self.version {
+                anyhow::bail!(
+                    "packet version is not match!negotiated:{},packet:{},cmd:{}",
+                    self.version,
+                    packet.get_version(),
+                    packet.get_cmd()
+                )
+            }
+            if self.version < PROTOCOL_VERSION_V2 {
+                return Ok(());
+            }
+            if packet.get_seq() <= self.recv_seq {
+                anyhow::bail!(
+                    "replay packet!last_seq:{},seq:{},cmd:{}",
+                    self.recv_seq,
+                    packet.get_seq(),
+                    packet.get_cmd()
+                )
+            }
+            self.recv_seq = packet.get_seq();
+            Ok(())
+        }
+
+        ///按协商好的版本转换成发给客户端的byte数组
+        pub fn build_client_bytes(&mut self, packet: &mut Packet) -> Vec<u8> {
+            packet.set_version(self.version);
+            if self.version >= PROTOCOL_VERSION_V2 {
+                self.send_seq = self.send_seq.wrapping_add(1);
+                packet.set_seq(self.send_seq);
+                packet.set_flags(self.flags);
+            }
+            packet.build_client_bytes()
+        }
+    }
+
     unsafe impl Send for Packet {}
 
     unsafe impl Sync for Packet {}
@@ -243,6 +345,179 @@ pub mod packet {
             self.packet_des.is_client = is_client;
         }
 
+        ///获得客户端包头版本
+        pub fn get_version(&self) -> u8 {
+            self.packet_des.version
+        }
+
+        pub fn set_version(&mut self, version: u8) {
+            self.packet_des.version = version;
+        }
+
+        ///获得包头标志位
+        pub fn get_flags(&self) -> u8 {
+            self.packet_des.flags
+        }
+
+        pub fn set_flags(&mut self, flags: u8) {
+            self.packet_des.flags = flags;
+        }
+
+        ///获得客户端包序列号
+        pub fn get_seq(&self) -> u32 {
+            self.packet_des.seq
+        }
+
+        pub fn set_seq(&mut self, seq: u32) {
+            self.packet_des.seq = seq;
+        }
+
+        ///获得rpc请求id
+        pub fn get_rpc_id(&self) -> u32 {
+            self.packet_des.rpc_id
+        }
+
+        pub fn set_rpc_id(&mut self, rpc_id: u32) {
+            self.packet_des.rpc_id = rpc_id;
+        }
+
+        ///是否是rpc回复
+        pub fn is_rpc_reply(&self) -> bool {
+            self.get_rpc_id() > 0 && self.get_flags() & FLAG_RPC_REPLY != 0
+        }
+
+        ///判断客户端数据包的包头版本
+        pub fn client_version(bytes: &[u8]) -> u8 {
+            if bytes.len() >= 3
+                && u16::from_le_bytes([bytes[0], bytes[1]]) == PACKET_MAGIC
+                && bytes[2] == PROTOCOL_VERSION_V2
+            {
+                return PROTOCOL_VERSION_V2;
+            }
+            PROTOCOL_VERSION_V1
+        }
+
+        ///读取客户端包头，兼容v1和v2，返回包、body长度和crc32
+        fn read_client_head(bb: &mut ByteBuf) -> anyhow::Result<(Packet, u32, u32)> {
+            let version = Packet::client_version(&bb.bytes()[bb.index()..]);
+            let packet;
+            let body_size;
+            let mut crc = 0;
+            if version == PROTOCOL_VERSION_V2 {
+                bb.read_u16()?;
+                bb.read_u8()?;
+                let flags = bb.read_u8()?;
+                let cmd = bb.read_u32()?;
+                let len = bb.read_u32()?;
+                let seq = bb.read_u32()?;
+                crc = bb.read_u32()?;
+                if len < CLIENT_HEAD_LEN_V2 {
+                    anyhow::bail!("client packet len is wrong!cmd:{},len:{}", cmd, len)
+                }
+                let mut p = Packet::new(cmd, len, 0);
+                p.set_version(version);
+                p.set_flags(flags);
+                p.set_seq(seq);
+                packet = p;
+                body_size = len - CLIENT_HEAD_LEN_V2;
+            } else {
+                let cmd = bb.read_u32()?;
+                let len = bb.read_u32()?;
+                bb.read_u32()?;
+                bb.read_u32()?;
+                if len < CLIENT_HEAD_LEN_V1 {
+                    anyhow::bail!("client packet len is wrong!cmd:{},len:{}", cmd, len)
+                }
+                packet = Packet::new(cmd, len, 0);
+                body_size = len - CLIENT_HEAD_LEN_V1;
+            }
+            Ok((packet, body_size, crc))
+        }
+
+        ///校验v2包的标志位和crc32，压缩过的body解压
+        fn check_client_body(packet: &mut Packet, crc: u32) -> anyhow::Result<()> {
+            if packet.get_version() < PROTOCOL_VERSION_V2 {
+                return Ok(());
+            }
+            let flags = packet.get_flags();
+            if flags & FLAG_ENCRYPT != 0 {
+                anyhow::bail!(
+                    "unsupported packet flags:{},cmd:{}",
+                    flags,
+                    packet.get_cmd()
+                )
+            }
+            if flags & FLAG_CRC != 0 {
+                let body_crc = crc32fast::hash(packet.get_data());
+                if body_crc != crc {
+                    anyhow::bail!(
+                        "packet crc32 is wrong!cmd:{},crc:{},body_crc:{}",
+                        packet.get_cmd(),
+                        crc,
+                        body_crc
+                    )
+                }
+            }
+            Packet::decompress_body(packet, compress::MAX_CLIENT_DECOMPRESS_SIZE)
+        }
+
+        ///flags带FLAG_COMPRESS则解压body，解压完去掉压缩标志，解压后超过max_size字节则返回错误
+        fn decompress_body(packet: &mut Packet, max_size: usize) -> anyhow::Result<()> {
+            let flags = packet.get_flags();
+            if flags & FLAG_COMPRESS == 0 {
+                return Ok(());
+            }
+            let mut compress_type = CompressType::Zstd;
+            if flags & FLAG_LZ4 != 0 {
+                compress_type = CompressType::Lz4;
+            }
+            let bytes = compress::decompress(compress_type, packet.get_data(), max_size)?;
+            packet.set_data_from_vec(bytes);
+            packet.set_flags(flags & !(FLAG_COMPRESS | FLAG_LZ4));
+            Ok(())
+        }
+
+        ///body超过压缩阈值则压缩，返回带上压缩标志的flags和要写出去的body
+        fn compress_body(&self, flags: u8) -> (u8, Cow<'_, [u8]>) {
+            let flags = flags & !(FLAG_COMPRESS | FLAG_LZ4);
+            match compress::try_compress(self.get_data()) {
+                Some((CompressType::Zstd, bytes)) => (flags | FLAG_COMPRESS, Cow::Owned(bytes)),
+                Some((CompressType::Lz4, bytes)) => {
+                    (flags | FLAG_COMPRESS | FLAG_LZ4, Cow::Owned(bytes))
+                }
+                None => (flags, Cow::Borrowed(self.get_data())),
+            }
+        }
+
+        ///写客户端包头和body，按包上的版本写v1或者v2，v2并且客户端支持才压缩
+        fn write_client(&self, bb: &mut ByteBuf) {
+            if self.get_version() < PROTOCOL_VERSION_V2 {
+                bb.push_u32(self.get_cmd());
+                bb.push_u32(CLIENT_HEAD_LEN_V1 + self.get_data().len() as u32);
+                bb.push_u32(0);
+                bb.push_u32(0);
+                bb.push_array(self.get_data());
+                return;
+            }
+            let (flags, body) = if self.get_flags() & FLAG_COMPRESS != 0 {
+                self.compress_body(self.get_flags())
+            } else {
+                (self.get_flags(), Cow::Borrowed(self.get_data()))
+            };
+            let mut crc = 0;
+            if flags & FLAG_CRC != 0 {
+                crc = crc32fast::hash(&body);
+            }
+            bb.push_u16(PACKET_MAGIC);
+            bb.push(PROTOCOL_VERSION_V2);
+            bb.push(flags);
+            bb.push_u32(self.get_cmd());
+            bb.push_u32(CLIENT_HEAD_LEN_V2 + body.len() as u32);
+            bb.push_u32(self.get_seq());
+            bb.push_u32(crc);
+            bb.push_array(&body);
+        }
+
         ///解析tcp流数据，可能饱含多个数据包，循环解析
         pub fn build_array_from_server(bytes: Vec<u8>) -> anyhow::Result<VecDeque<Packet>> {
             let mut bb = ByteBuf::from(bytes);
@@ -256,17 +531,25 @@ pub mod packet {
                 let user_id = bb.read_u32()?;
                 let is_client = bb.read_u8()? != 0;
                 let is_broad = bb.read_u8()? != 0;
+                let flags = bb.read_u8()?;
                 let server_token = bb.read_u32()?;
-                let body_size = len - 18;
+                let rpc_id = bb.read_u32()?;
+                if len < SERVER_HEAD_LEN {
+                    anyhow::bail!("server packet len is wrong!cmd:{},len:{}", cmd, len)
+                }
+                let body_size = len - SERVER_HEAD_LEN;
                 let mut packet = Packet::new(cmd, len, user_id);
                 packet.set_user_id(user_id);
                 packet.set_cmd(cmd);
                 packet.set_is_client(is_client);
                 packet.set_is_broad(is_broad);
                 packet.set_server_token(server_token);
+                packet.set_rpc_id(rpc_id);
+                packet.set_flags(flags);
                 if body_size > 0 {
                     packet.set_data(bb.read_bytes_size(body_size as usize)?);
                 }
+                Packet::decompress_body(&mut packet, compress::MAX_SERVER_DECOMPRESS_SIZE)?;
                 v.push_back(packet);
             }
         }
@@ -278,17 +561,13 @@ pub mod packet {
                 if bb.index() == bb.get_len() {
                     return Ok(v);
                 }
-                let cmd = bb.read_u32()?;
-                let len = bb.read_u32()?;
-                bb.read_u32()?;
-                bb.read_u32()?;
-                let body_size = len - 16;
-                let mut packet = Packet::new(cmd, len, 0);
+                let (mut packet, body_size, crc) = Packet::read_client_head(&mut bb)?;
                 packet.set_is_client(true);
                 packet.set_is_broad(false);
                 if body_size > 0 {
                     packet.set_data(bb.read_bytes_size(body_size as usize)?);
                 }
+                Packet::check_client_body(&mut packet, crc)?;
                 v.push(packet);
             }
         }
@@ -301,36 +580,34 @@ pub mod packet {
             let user_id = bb.read_u32()?;
             let is_client = bb.read_u8()? != 0;
             let is_broad = bb.read_u8()? != 0;
+            let flags = bb.read_u8()?;
             let server_token = bb.read_u32()?;
+            let rpc_id = bb.read_u32()?;
             let mut packet = Packet::new(cmd, 0, user_id);
             packet.set_is_client(is_client);
             packet.set_is_broad(is_broad);
             packet.set_server_token(server_token);
+            packet.set_rpc_id(rpc_id);
+            packet.set_flags(flags);
             packet.set_data(bb.read_bytes_size(len as usize)?);
+            Packet::decompress_body(&mut packet, compress::MAX_SERVER_DECOMPRESS_SIZE)?;
             Ok(packet)
         }
 
         ///bytebuf转换成packet,只能用于服务端进程内部通信！
         pub fn from_only_client(bytes: Vec<u8>) -> anyhow::Result<Packet> {
             let mut bb = ByteBuf::from(bytes);
-            let cmd = bb.read_u32()?;
-            let len = bb.read_u32()?;
-            bb.read_u32()?;
-            bb.read_u32()?;
-            let mut packet = Packet::new(cmd, len, 0);
+            let (mut packet, _, crc) = Packet::read_client_head(&mut bb)?;
             packet.set_is_client(true);
             packet.set_is_broad(false);
             packet.set_data(bb.read_bytes());
+            Packet::check_client_body(&mut packet, crc)?;
             Ok(packet)
         }
 
         pub fn all_to_client_vec(&self) -> Vec<u8> {
             let mut bb = ByteBuf::new();
-            bb.push_u32(self.get_cmd());
-            bb.push_u32(self.get_len());
-            bb.push_u32(0);
-            bb.push_u32(0);
-            bb.push_array(self.get_data());
+            self.write_client(&mut bb);
             bb.into_bytes()
         }
 
@@ -378,7 +655,11 @@ pub mod packet {
         }
 
         pub fn cal_len(&mut self) {
-            let len = 16 + self.get_data().len();
+            let mut head_len = CLIENT_HEAD_LEN_V1;
+            if self.get_version() >= PROTOCOL_VERSION_V2 {
+                head_len = CLIENT_HEAD_LEN_V2;
+            }
+            let len = head_len as usize + self.get_data().len();
             self.set_len(len as u32);
         }
 
@@ -386,11 +667,7 @@ pub mod packet {
         pub fn to_client_bytebuf(&mut self) -> ByteBuf {
             self.cal_len();
             let mut bb = ByteBuf::new();
-            bb.push_u32(self.get_cmd());
-            bb.push_u32(self.get_len());
-            bb.push_u32(0);
-            bb.push_u32(0);
-            bb.push_array(self.get_data());
+            self.write_client(&mut bb);
             bb
         }
 
@@ -402,16 +679,20 @@ pub mod packet {
             self.packet_des.is_broad
         }
 
-        ///转换成bytebuf
+        ///转换成bytebuf，服务端之间只带压缩和rpc标志
         pub fn to_server_bytebuf(&self) -> ByteBuf {
+            let (flags, body) =
+                self.compress_body(self.get_flags() & (FLAG_RPC_REPLY | FLAG_RPC_ERROR));
             let mut bb = ByteBuf::new();
             bb.push_u32(self.get_cmd());
-            bb.push_u32(18 + self.get_data().len() as u32);
+            bb.push_u32(SERVER_HEAD_LEN + body.len() as u32);
             bb.push_u32(self.get_user_id());
             bb.push(self.is_client() as u8);
             bb.push(self.is_broad() as u8);
+            bb.push(flags);
             bb.push_u32(self.get_server_token());
-            bb.push_array(self.get_data());
+            bb.push_u32(self.get_rpc_id());
+            bb.push_array(&body);
             bb
         }
 
@@ -500,3 +781,76 @@ pub fn is_today(time: i64) -> bool {
     }
     false
 }
+
+#[cfg(test)]
+mod tests {
+    use super::packet::*;
+
+    #[test]
+    fn client_packet_v1_v2() {
+        let _config = crate::compress::test_config(0, "zstd");
+        //v1
+        let mut packet = Packet::new(10001, 0, 0);
+        packet.set_data(&[1, 2, 3]);
+        let bytes = packet.build_client_bytes();
+        assert_eq!(bytes.len(), CLIENT_HEAD_LEN_V1 as usize + 3);
+        assert_eq!(
+            Packet::client_version(bytes.as_slice()),
+            PROTOCOL_VERSION_V1
+        );
+        let v = Packet::build_array_from_client(bytes).unwrap();
+        assert_eq!(v[0].get_cmd(), 10001);
+        assert_eq!(v[0].get_data(), &[1, 2, 3]);
+
+        //v2,带crc
+        let mut login = Packet::new(1001, 0, 0);
+        login.set_version(PROTOCOL_VERSION_V2);
+        login.set_flags(FLAG_CRC);
+        login.set_seq(1);
+        let mut frame = ClientFrame::new(&login);
+        let mut packet = Packet::new(10001, 0, 0);
+        packet.set_data(&[1, 2, 3]);
+        let mut bytes = frame.build_client_bytes(&mut packet);
+        assert_eq!(bytes.len(), CLIENT_HEAD_LEN_V2 as usize + 3);
+        let v = Packet::build_array_from_client(bytes.clone()).unwrap();
+        assert_eq!(v[0].get_seq(), 1);
+        assert_eq!(v[0].get_data(), &[1, 2, 3]);
+        assert!(frame.check_recv(&v[0]).is_ok());
+        //重放
+        assert!(frame.check_recv(&v[0]).is_err());
+        //body被篡改
+        let len = bytes.len();
+        bytes[len - 1] = 4;
+        assert!(Packet::build_array_from_client(bytes).is_err());
+    }
+
+    #[test]
+    fn compress_packet() {
+        let _config = crate::compress::test_config(64, "lz4");
+        let data = vec![7_u8; 1024];
+        //服务端之间
+        let mut packet = Packet::new(20001, 0, 1);
+        packet.set_data(data.as_slice());
+        let bytes = packet.build_server_bytes();
+        assert!(bytes.len() < data.len());
+        let v = Packet::build_array_from_server(bytes).unwrap();
+        assert_eq!(v[0].get_data(), data.as_slice());
+        assert_eq!(v[0].get_flags() & FLAG_COMPRESS, 0);
+
+        //客户端没声明支持压缩就不压缩
+        crate::compress::init(64, "zstd");
+        let mut login = Packet::new(1001, 0, 0);
+        login.set_version(PROTOCOL_VERSION_V2);
+        let mut frame = ClientFrame::new(&login);
+        packet.set_data(data.as_slice());
+        let bytes = frame.build_client_bytes(&mut packet);
+        assert_eq!(bytes.len(), CLIENT_HEAD_LEN_V2 as usize + data.len());
+
+        login.set_flags(FLAG_CRC | FLAG_COMPRESS);
+        let mut frame = ClientFrame::new(&login);
+        let bytes = frame.build_client_bytes(&mut packet);
+        assert!(bytes.len() < data.len());
+        let v = Packet::build_array_from_client(bytes).unwrap();
+        assert_eq!(v[0].get_data(), data.as_slice());
+    }
+}